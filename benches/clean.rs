@@ -0,0 +1,78 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use docx_cleaner::{Cleaner, Config};
+use docx_rs::*;
+
+#[path = "../tests/support/mod.rs"]
+mod support;
+
+use support::Contamination;
+
+// About 1MB of text with nothing to clean, the common case
+const CLEAN_TEXT_BYTES: usize = 1 << 20;
+
+// The documents of clean_package, from parsing the XML to the counted report
+const PARAGRAPHS: usize = 5000;
+
+fn bench_clean(c: &mut Criterion) {
+    let buf = support::dissertation();
+    let docx = read_docx(&buf).unwrap();
+    let cleaner = Cleaner::new(Config::builtin());
+    let single_thread = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()
+        .unwrap();
+
+    let mut group = c.benchmark_group("clean_docx");
+    group.sample_size(20);
+    group.bench_function("parallel", |b| {
+        b.iter_batched_ref(
+            || docx.clone(),
+            |docx| cleaner.clean_docx(docx),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("single thread", |b| {
+        b.iter_batched_ref(
+            || docx.clone(),
+            |docx| single_thread.install(|| cleaner.clean_docx(docx)),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+
+    let clean = support::clean_model(CLEAN_TEXT_BYTES);
+    let mut group = c.benchmark_group("clean_docx_nothing_to_clean");
+    group.sample_size(20);
+    group.bench_function("1MB", |b| {
+        b.iter_batched_ref(
+            || clean.clone(),
+            |docx| cleaner.clean_docx(docx),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("clean_package");
+    group.sample_size(10);
+    group.bench_function("parallel", |b| {
+        b.iter(|| cleaner.clean_package(&buf).unwrap())
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("clean_package_5000_paragraphs");
+    group.sample_size(20);
+    for (name, contamination) in [
+        ("nothing to clean", Contamination::None),
+        ("1% of the runs", Contamination::OneIn(100)),
+        ("every run", Contamination::Every),
+    ] {
+        let buf = support::synthetic_document(PARAGRAPHS, contamination);
+        group.bench_function(name, |b| {
+            b.iter(|| cleaner.clean_package(&buf).unwrap().into_bytes().unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_clean);
+criterion_main!(benches);
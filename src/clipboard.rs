@@ -0,0 +1,46 @@
+// The text clipboard of --clipboard. Only plain text is read and written back, so what else the
+// clipboard holds, like the formatting of text copied from a browser, is replaced along with it;
+// the caller leaves the clipboard alone when there is nothing to clean. Without the "clipboard"
+// feature both fail, though main refuses --clipboard before it gets that far.
+#[cfg(feature = "clipboard")]
+use arboard::{Clipboard, Error};
+
+#[cfg(feature = "clipboard")]
+fn open() -> Result<Clipboard, String> {
+    Clipboard::new().map_err(|e| format!("The clipboard could not be opened: {}", e))
+}
+
+/// The text on the clipboard, or why there is none to clean
+#[cfg(feature = "clipboard")]
+pub fn read_text() -> Result<String, String> {
+    match open()?.get_text() {
+        Ok(text) => Ok(text),
+        // Empty, or an image, files or other data without a text form
+        Err(Error::ContentNotAvailable) => Err(
+            "The clipboard holds no text; copy some text and try again, as only plain text can \
+             be cleaned"
+                .to_string(),
+        ),
+        Err(e) => Err(format!("The clipboard could not be read: {}", e)),
+    }
+}
+
+/// Puts the text on the clipboard in place of what was there. On X11 the clipboard belongs to
+/// the program that set it, and arboard hands the text over to the clipboard manager when it is
+/// dropped, so it stays available once the process has exited.
+#[cfg(feature = "clipboard")]
+pub fn write_text(text: &str) -> Result<(), String> {
+    open()?
+        .set_text(text)
+        .map_err(|e| format!("The cleaned text could not be put on the clipboard: {}", e))
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn read_text() -> Result<String, String> {
+    Err("This build has no clipboard support".to_string())
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn write_text(_text: &str) -> Result<(), String> {
+    Err("This build has no clipboard support".to_string())
+}
@@ -0,0 +1,155 @@
+// The compare subcommand: whether a resubmitted document still has the characters found in the
+// flagged one. Both are scanned like scan does, and the findings are compared per character and
+// per part; a location is matched by its part, paragraph, run and character, so text added or
+// removed before it makes it show up on both sides.
+use super::{replacement_line, scan_file, CompareArgs, EXIT_CHARACTERS_FOUND};
+use docx_cleaner::{CleanReport, Cleaner, Config, Replacement};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+/// Prints the comparison of the two documents; returns 0 when B has nothing left to clean
+pub fn compare(args: &CompareArgs, mut config: Config) -> i32 {
+    config.record_locations = true;
+    let cleaner = Cleaner::new(config);
+    // Failures are reported like those of a batch, naming the document
+    let scan = |path: &Path| {
+        scan_file(path, &cleaner).map_err(|error| {
+            log::error!("{}: {}", path.display(), error);
+            error.exit_code()
+        })
+    };
+    let (a, b) = match (scan(&args.a), scan(&args.b)) {
+        (Ok(a), Ok(b)) => (a, b),
+        (Err(code), Ok(_)) | (Ok(_), Err(code)) => return code,
+        (Err(a), Err(b)) => return a.max(b),
+    };
+    let config = cleaner.config();
+
+    let mut comparison = format!("A: {}\nB: {}\n", args.a.display(), args.b.display());
+    comparison.push_str(&character_table(config, &a, &b));
+    comparison.push_str(&part_table(&a.replacements, &b.replacements));
+    let (only_a, only_b) = unmatched(&a.replacements, &b.replacements);
+    comparison.push_str(&locations("A", config, &only_a));
+    comparison.push_str(&locations("B", config, &only_b));
+    print!("{}", comparison);
+    if b.total_removed() > 0 {
+        EXIT_CHARACTERS_FOUND
+    } else {
+        0
+    }
+}
+
+// e.g. "U+200B ZERO WIDTH SPACE   12   0   -12", with a line for the totals
+fn character_table(config: &Config, a: &CleanReport, b: &CleanReport) -> String {
+    let characters: BTreeSet<char> = a.chars.keys().chain(b.chars.keys()).copied().collect();
+    let rows: Vec<(String, usize, usize)> = characters
+        .into_iter()
+        .map(|c| {
+            let label = format!("U+{:04X} {}", c as u32, config.name(c));
+            let count = |report: &CleanReport| report.chars.get(&c).copied().unwrap_or(0);
+            (label, count(a), count(b))
+        })
+        .chain([("Total".to_string(), a.total_removed(), b.total_removed())])
+        .collect();
+    table("Character", &rows)
+}
+
+// The findings per part of the package, e.g. word/document.xml or word/footnotes.xml
+fn part_table(a: &[Replacement], b: &[Replacement]) -> String {
+    let mut parts: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    for replacement in a {
+        parts.entry(part_name(replacement)).or_default().0 += 1;
+    }
+    for replacement in b {
+        parts.entry(part_name(replacement)).or_default().1 += 1;
+    }
+    if parts.is_empty() {
+        return String::new();
+    }
+    let rows: Vec<(String, usize, usize)> = parts
+        .into_iter()
+        .map(|(part, (a, b))| (part.to_string(), a, b))
+        .collect();
+    table("Part", &rows)
+}
+
+// A text file has no parts
+fn part_name(replacement: &Replacement) -> &str {
+    if replacement.path.is_empty() {
+        "text"
+    } else {
+        &replacement.path
+    }
+}
+
+fn table(heading: &str, rows: &[(String, usize, usize)]) -> String {
+    let width = rows
+        .iter()
+        .map(|(label, _, _)| label.chars().count())
+        .chain([heading.len()])
+        .max()
+        .unwrap_or(0);
+    let mut table = format!(
+        "\n{:<width$}  {:>6}  {:>6}  {:>6}\n",
+        heading, "A", "B", "Change"
+    );
+    for (label, a, b) in rows {
+        let change = *b as i64 - *a as i64;
+        let change = if change > 0 {
+            format!("+{}", change)
+        } else {
+            change.to_string()
+        };
+        table.push_str(&format!(
+            "{:<width$}  {:>6}  {:>6}  {:>6}\n",
+            label, a, b, change
+        ));
+    }
+    table
+}
+
+// The locations of each document without a match in the other, in document order
+fn unmatched<'a>(
+    a: &'a [Replacement],
+    b: &'a [Replacement],
+) -> (Vec<&'a Replacement>, Vec<&'a Replacement>) {
+    (unmatched_in(a, b), unmatched_in(b, a))
+}
+
+// The same location can hold the same character more than once, so they are matched one by one
+fn unmatched_in<'a>(these: &'a [Replacement], others: &[Replacement]) -> Vec<&'a Replacement> {
+    let mut others_left: HashMap<_, usize> = HashMap::new();
+    for replacement in others {
+        *others_left.entry(key(replacement)).or_default() += 1;
+    }
+    these
+        .iter()
+        .filter(|replacement| match others_left.get_mut(&key(replacement)) {
+            Some(left) if *left > 0 => {
+                *left -= 1;
+                false
+            }
+            _ => true,
+        })
+        .collect()
+}
+
+fn key(replacement: &Replacement) -> (&str, Option<usize>, Option<usize>, char) {
+    (
+        replacement.path.as_str(),
+        replacement.paragraph,
+        replacement.run,
+        replacement.character,
+    )
+}
+
+fn locations(side: &str, config: &Config, replacements: &[&Replacement]) -> String {
+    if replacements.is_empty() {
+        return format!("\nOnly in {}: none\n", side);
+    }
+    let mut locations = format!("\nOnly in {} ({}):\n", side, replacements.len());
+    for replacement in replacements {
+        locations.push_str(&replacement_line(config, replacement));
+    }
+    locations
+}
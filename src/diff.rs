@@ -0,0 +1,137 @@
+// The --diff of a cleaned document: the paragraphs cleaning changed, before and after, as a
+// unified diff without context lines, since the paragraphs around a change stay as they were.
+// The before side writes the characters cleaning is after as ⟨U+XXXX⟩, so a line that only lost
+// a zero width space does not look like the line that replaces it, and a long paragraph is cut
+// down to the stretch around its changes.
+use docx_cleaner::{CleanedDocument, Cleaner, Error};
+use similar::{capture_diff_slices, group_diff_ops, Algorithm, DiffTag};
+use std::ops::Range;
+use std::path::Path;
+
+// A paragraph longer than this is shown from shortly before its first change, and cut off after
+// this many characters
+const MAX_WIDTH: usize = 160;
+
+// How many unchanged characters are kept before and after the changes of a long paragraph
+const CONTEXT: usize = 40;
+
+/// The diff of each part of the document that cleaning changed; empty when no text changed
+pub fn document_diff(
+    cleaner: &Cleaner,
+    input_path: &Path,
+    cleaned: &CleanedDocument,
+) -> Result<String, Error> {
+    let mut diff = String::new();
+    for part in cleaned.changed_parts()? {
+        let label = format!("{}: {}", input_path.display(), part.path);
+        diff.push_str(&unified(cleaner, &label, &part.before, &part.after));
+    }
+    Ok(diff)
+}
+
+/// The diff of a text file, line by line
+pub fn text_diff(cleaner: &Cleaner, input_path: &Path, before: &str, after: &str) -> String {
+    let lines = |text: &str| text.lines().map(str::to_string).collect::<Vec<_>>();
+    let label = input_path.display().to_string();
+    unified(cleaner, &label, &lines(before), &lines(after))
+}
+
+fn unified(cleaner: &Cleaner, label: &str, before: &[String], after: &[String]) -> String {
+    let groups = group_diff_ops(capture_diff_slices(Algorithm::Myers, before, after), 0);
+    if groups.is_empty() {
+        return String::new();
+    }
+    let mut diff = format!("--- {} (before)\n+++ {} (after)\n", label, label);
+    for group in groups {
+        let (Some(first), Some(last)) = (group.first(), group.last()) else {
+            continue;
+        };
+        diff.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(first.old_range().start..last.old_range().end),
+            hunk_range(first.new_range().start..last.new_range().end)
+        ));
+        for op in &group {
+            let (tag, old, new) = op.as_tag_tuple();
+            if tag == DiffTag::Equal {
+                continue;
+            }
+            let removed: Vec<Vec<char>> = before[old].iter().map(|p| p.chars().collect()).collect();
+            let added: Vec<Vec<char>> = after[new].iter().map(|p| p.chars().collect()).collect();
+            // A paragraph and the one it became are cut down to the same stretch
+            let windows: Vec<(Range<usize>, Range<usize>)> = removed
+                .iter()
+                .zip(&added)
+                .map(|(removed, added)| windows(removed, added))
+                .collect();
+            for (i, paragraph) in removed.iter().enumerate() {
+                let window = windows.get(i).map_or(0..paragraph.len(), |w| w.0.clone());
+                let shown = shown(paragraph, window, |text| cleaner.render(text));
+                diff.push_str(&format!("-{}\n", shown));
+            }
+            for (i, paragraph) in added.iter().enumerate() {
+                let window = windows.get(i).map_or(0..paragraph.len(), |w| w.1.clone());
+                diff.push_str(&format!("+{}\n", shown(paragraph, window, str::to_string)));
+            }
+        }
+    }
+    diff
+}
+
+// e.g. "12" for one line, "12,3" for three, counted from 1, and "11,0" for none after line 11
+fn hunk_range(range: Range<usize>) -> String {
+    match range.len() {
+        1 => (range.start + 1).to_string(),
+        0 => format!("{},0", range.start),
+        len => format!("{},{}", range.start + 1, len),
+    }
+}
+
+// The stretches of a changed paragraph and of what it became that are shown: all of a short
+// one, or what lies between their common start and end with some of those around it
+fn windows(before: &[char], after: &[char]) -> (Range<usize>, Range<usize>) {
+    if before.len().max(after.len()) <= MAX_WIDTH {
+        return (0..before.len(), 0..after.len());
+    }
+    let prefix = before.iter().zip(after).take_while(|(b, a)| b == a).count();
+    let suffix = before[prefix..]
+        .iter()
+        .rev()
+        .zip(after[prefix..].iter().rev())
+        .take_while(|(b, a)| b == a)
+        .count();
+    // The cuts move to the nearest space toward the changes, so no word is cut in half
+    let mut start = prefix.saturating_sub(CONTEXT);
+    if start > 0 {
+        start = (start..prefix)
+            .find(|&i| before[i].is_whitespace())
+            .map_or(start, |i| i + 1);
+    }
+    let end = |paragraph: &[char]| {
+        let changed = paragraph.len() - suffix;
+        let end = (changed + CONTEXT).min(paragraph.len());
+        if end == paragraph.len() {
+            return end;
+        }
+        (changed..end)
+            .rev()
+            .find(|&i| paragraph[i].is_whitespace())
+            .unwrap_or(end)
+    };
+    (start..end(before), start..end(after))
+}
+
+// The stretch of the paragraph, at most MAX_WIDTH characters of it, with … where text is left out
+fn shown(paragraph: &[char], window: Range<usize>, render: impl Fn(&str) -> String) -> String {
+    let end = window.end.min(window.start + MAX_WIDTH);
+    let text: String = paragraph[window.start..end].iter().collect();
+    let mut shown = String::new();
+    if window.start > 0 {
+        shown.push('…');
+    }
+    shown.push_str(&render(&text));
+    if end < paragraph.len() {
+        shown.push('…');
+    }
+    shown
+}
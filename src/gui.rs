@@ -0,0 +1,165 @@
+// The dialogs of the GUI mode, which runs when the executable is started without input paths,
+// e.g. by double-clicking it. Without the "gui" feature src/headless.rs takes their place.
+use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+use std::path::{Path, PathBuf};
+
+const SAVE_REPORT: &str = "Save report\u{2026}";
+const OPEN_FOLDER: &str = "Open output folder";
+const CLOSE: &str = "Close";
+
+/// How the results dialog was left
+pub enum ResultsChoice {
+    SaveReport,
+    OpenFolder,
+    Close,
+}
+
+pub fn show_error(title: &str, message: &str) {
+    MessageDialog::new()
+        .set_level(MessageLevel::Error)
+        .set_title(title)
+        .set_description(message)
+        .set_buttons(MessageButtons::Ok)
+        .show();
+}
+
+pub fn show_info(title: &str, message: &str) {
+    MessageDialog::new()
+        .set_level(MessageLevel::Info)
+        .set_title(title)
+        .set_description(message)
+        .set_buttons(MessageButtons::Ok)
+        .show();
+}
+
+// A warning with Yes and No; true for Yes
+pub fn ask_yes_no(title: &str, message: &str) -> bool {
+    let result = MessageDialog::new()
+        .set_level(MessageLevel::Warning)
+        .set_title(title)
+        .set_description(message)
+        .set_buttons(MessageButtons::YesNo)
+        .show();
+    result == MessageDialogResult::Yes
+}
+
+// A question with OK and Cancel; true for OK
+pub fn ask_ok_cancel(title: &str, message: &str) -> bool {
+    let result = MessageDialog::new()
+        .set_level(MessageLevel::Info)
+        .set_title(title)
+        .set_description(message)
+        .set_buttons(MessageButtons::OkCancel)
+        .show();
+    result == MessageDialogResult::Ok
+}
+
+// A question with three answers, returning the index of the one chosen; closing the dialog
+// chooses the last
+pub fn ask_choice(title: &str, message: &str, choices: [&str; 3]) -> usize {
+    let [first, second, last] = choices.map(String::from);
+    let result = MessageDialog::new()
+        .set_level(MessageLevel::Info)
+        .set_title(title)
+        .set_description(message)
+        .set_buttons(MessageButtons::YesNoCancelCustom(
+            first.clone(),
+            second.clone(),
+            last,
+        ))
+        .show();
+    match result {
+        MessageDialogResult::Custom(label) if label == first => 0,
+        MessageDialogResult::Custom(label) if label == second => 1,
+        // Some platforms give the custom buttons back by their position
+        MessageDialogResult::Yes => 0,
+        MessageDialogResult::No => 1,
+        _ => 2,
+    }
+}
+
+// The results of a run with "Save report…" and, when the copies went to a folder, "Open output
+// folder". Esc closes it like Close. The window of src/results_window.rs shows all of them where
+// there is one; a message dialog only the first lines.
+pub fn show_results(title: &str, message: &str, folder: bool) -> ResultsChoice {
+    #[cfg(all(windows, feature = "results-window"))]
+    if let Some(choice) = crate::results_window::show(title, message, folder) {
+        return choice;
+    }
+    let message = crate::dialog_text(message);
+    let buttons = if folder {
+        MessageButtons::YesNoCancelCustom(SAVE_REPORT.into(), OPEN_FOLDER.into(), CLOSE.into())
+    } else {
+        MessageButtons::OkCancelCustom(SAVE_REPORT.into(), CLOSE.into())
+    };
+    let result = MessageDialog::new()
+        .set_level(MessageLevel::Info)
+        .set_title(title)
+        .set_description(&message)
+        .set_buttons(buttons)
+        .show();
+    match result {
+        MessageDialogResult::Custom(label) if label == SAVE_REPORT => ResultsChoice::SaveReport,
+        MessageDialogResult::Custom(label) if label == OPEN_FOLDER => ResultsChoice::OpenFolder,
+        // Some platforms give the custom buttons back by their position
+        MessageDialogResult::Yes | MessageDialogResult::Ok => ResultsChoice::SaveReport,
+        MessageDialogResult::No => ResultsChoice::OpenFolder,
+        _ => ResultsChoice::Close,
+    }
+}
+
+// Shows the folder in the file manager of the platform
+pub fn open_folder(folder: &Path) -> std::io::Result<()> {
+    let program = if cfg!(windows) {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    std::process::Command::new(program)
+        .arg(folder)
+        .spawn()
+        .map(|_| ())
+}
+
+// None when the dialog is cancelled
+pub fn pick_save_path(
+    title: &str,
+    file_name: &str,
+    directory: Option<&Path>,
+    filters: &[(&str, &[&str])],
+) -> Option<PathBuf> {
+    let mut dialog = FileDialog::new().set_title(title).set_file_name(file_name);
+    if let Some(directory) = directory {
+        dialog = dialog.set_directory(directory);
+    }
+    for (name, extensions) in filters {
+        dialog = dialog.add_filter(*name, extensions);
+    }
+    dialog.save_file()
+}
+
+// The filters are (name, extensions); None when the picker is cancelled
+pub fn pick_files(
+    title: &str,
+    filters: &[(&str, &[&str])],
+    directory: Option<&Path>,
+) -> Option<Vec<PathBuf>> {
+    let mut dialog = FileDialog::new().set_title(title);
+    if let Some(directory) = directory {
+        dialog = dialog.set_directory(directory);
+    }
+    for (name, extensions) in filters {
+        dialog = dialog.add_filter(*name, extensions);
+    }
+    dialog.pick_files()
+}
+
+pub fn pick_folder(title: &str, directory: Option<&Path>) -> Option<PathBuf> {
+    let mut dialog = FileDialog::new().set_title(title);
+    if let Some(directory) = directory {
+        dialog = dialog.set_directory(directory);
+    }
+    dialog.pick_folder()
+}
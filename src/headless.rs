@@ -0,0 +1,62 @@
+// Stands in for src/gui.rs without the "gui" feature, e.g. for a server or a container. Nothing
+// here opens a window: messages go to standard error, questions are declined and pickers return
+// nothing. Neither the GUI mode nor the panic dialog is entered in this build in the first place.
+use std::path::{Path, PathBuf};
+
+// Nothing is shown, so nothing but Close is chosen
+#[allow(dead_code)]
+pub enum ResultsChoice {
+    SaveReport,
+    OpenFolder,
+    Close,
+}
+
+pub fn show_error(title: &str, message: &str) {
+    eprintln!("{}: {}", title, message);
+}
+
+pub fn show_info(title: &str, message: &str) {
+    eprintln!("{}: {}", title, message);
+}
+
+pub fn show_results(title: &str, message: &str, _folder: bool) -> ResultsChoice {
+    eprintln!("{}: {}", title, message);
+    ResultsChoice::Close
+}
+
+pub fn open_folder(_folder: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+pub fn pick_save_path(
+    _title: &str,
+    _file_name: &str,
+    _directory: Option<&Path>,
+    _filters: &[(&str, &[&str])],
+) -> Option<PathBuf> {
+    None
+}
+
+pub fn ask_yes_no(_title: &str, _message: &str) -> bool {
+    false
+}
+
+pub fn ask_ok_cancel(_title: &str, _message: &str) -> bool {
+    false
+}
+
+pub fn ask_choice(_title: &str, _message: &str, _choices: [&str; 3]) -> usize {
+    2
+}
+
+pub fn pick_files(
+    _title: &str,
+    _filters: &[(&str, &[&str])],
+    _directory: Option<&Path>,
+) -> Option<Vec<PathBuf>> {
+    None
+}
+
+pub fn pick_folder(_title: &str, _directory: Option<&Path>) -> Option<PathBuf> {
+    None
+}
@@ -0,0 +1,6340 @@
+// Library side of docx-cleaner: the configurable character list and the cleaning passes over a
+// .docx package. The command line and GUI front end lives in main.rs.
+use docx_rs::*;
+use once_cell::sync::Lazy;
+use quick_xml::errors::IllFormedError;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use rayon::prelude::*;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::io::{Cursor, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use unicode_normalization::{is_nfc_quick, is_nfkc_quick, IsNormalized, UnicodeNormalization};
+use unicode_properties::{UnicodeEmoji, UnicodeGeneralCategory};
+use regex::Regex;
+
+pub use docx_rs::Docx;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub use unicode_properties::GeneralCategory;
+
+static CONFIG_STR: &str = include_str!("config.json");
+static HOMOGLYPHS_STR: &str = include_str!("homoglyphs.json");
+static TYPOGRAPHY_STR: &str = include_str!("typography.json");
+
+/// The characters to remove, each with its display name and replacement. Starts from the
+/// embedded config.json and the chosen Profile, with the user config (--config or the platform
+/// config directory) merged on top and the --categories characters added last.
+#[derive(Clone)]
+pub struct Config {
+    chars: HashMap<char, (String, String)>,
+    // Characters the user config explicitly keeps, which --categories must not add back
+    kept: HashSet<char>,
+    // Listed characters that are left in the text and only reported ("report", --keep-bidi)
+    reported: HashSet<char>,
+    // Every source that set the rule of a character, with what it made of it, in order
+    origins: HashMap<char, Vec<(Origin, RuleAction)>>,
+    // Characters the user chose to keep in --interactive, left in the text like the reported ones
+    declined: HashSet<char>,
+    /// With --strict, joiners inside emoji sequences are removed as well
+    pub strict: bool,
+    /// With --math-strict, the invisible operators of equations (U+2061 to U+2064) are removed
+    /// from them like the rest of the zero width characters, instead of only being reported
+    pub math_strict: bool,
+    pub collapse_spaces: SpaceCollapse,
+    /// With --trim-around-breaks, a single space at the end of the text before a line break and
+    /// at the start of the text after it is removed, as Word shows it as dead space at the edge
+    /// of the line. Preformatted paragraphs keep theirs.
+    pub trim_around_breaks: bool,
+    /// Paragraph styles whose spacing is left alone, e.g. code blocks with aligned columns
+    pub preformatted_styles: Vec<String>,
+    /// What happens to no-break spaces (U+00A0): the "nbsp" config setting, overridden by --nbsp.
+    /// A user config entry for U+00A0 takes precedence over it.
+    pub nbsp: NbspMode,
+    // The "nbsp_patterns" setting: with NbspMode::Patterns, a no-break space inside a match of
+    // one of these is left in the text
+    nbsp_patterns: Vec<Regex>,
+    /// With --report, clean_package lists every replacement with its location and context
+    pub record_locations: bool,
+    /// With --track-changes, clean_package marks each changed run as a tracked deletion of the
+    /// original run followed by an insertion of the cleaned one, instead of editing it in place
+    pub track_changes: Option<Revision>,
+    /// With --annotate, clean_package adds a Word comment on each replaced character in the main
+    /// document, attributed like a tracked change
+    pub annotate: Option<Revision>,
+    /// The most comments --annotate adds to a document, so that heavily affected documents do
+    /// not drown in them
+    pub max_comments: usize,
+    /// With --highlight, clean_package gives the text around each replacement this Word
+    /// highlight color, e.g. "yellow", splitting runs so that nothing else is highlighted
+    pub highlight: Option<String>,
+    /// With --collapse-cell-spaces, the spaces inside spreadsheet strings are collapsed like
+    /// collapse_spaces does in documents; the spaces at their edges are always kept
+    pub collapse_cell_spaces: bool,
+    /// With --normalize, the text of each run is normalized after the replacements; field codes
+    /// are left alone like the rest of the cleaning does
+    pub normalize: Option<Normalization>,
+    /// With --homoglyphs, Cyrillic and Greek letters that look like Latin ones are replaced with
+    /// those in otherwise Latin words, after the replacements and before Config::normalize
+    pub homoglyphs: Option<Homoglyphs>,
+    /// With --remove-hidden, clean_package finds the runs of Word documents formatted as hidden
+    /// text and removes or only reports them
+    pub hidden_text: Option<HiddenText>,
+    /// With --remove-invisible-formatting, the runs of Word documents that InvisibleRun reports
+    /// are removed like the hidden ones, instead of only being reported
+    pub remove_invisible_formatting: bool,
+    /// With --scrub-metadata, clean_package clears the kinds of identifying metadata it selects
+    pub scrub_metadata: Option<MetadataScrub>,
+    /// With --prune-empty, clean_package removes the runs of Word documents whose text cleaning
+    /// removed entirely, and the paragraphs of the body left without any text that way.
+    /// Paragraphs that were empty already are kept, as are all of them with Config::annotate or
+    /// Config::track_changes.
+    pub prune_empty: bool,
+    /// With --merge-runs, adjacent runs that hold nothing but text and have the same formatting
+    /// are merged into one, so that text split up by the removed characters is whole again
+    pub merge_runs: bool,
+    /// With --visualize, each character that would be removed or replaced is replaced with a
+    /// readable token of this format instead, e.g. "[{short}]" for [ZWSP], so the document shows
+    /// what was hidden in it; see Config::token. Spaces are not collapsed then.
+    pub visualize: Option<String>,
+    // The user config file that was merged in, None for the built-in list alone
+    source: Option<PathBuf>,
+    // The name of the Profile the config was built with
+    profile: String,
+}
+
+/// The profile of Config::load, which leaves the built-in list as it is
+pub const DEFAULT_PROFILE: &str = "standard";
+
+/// The default of Config::max_comments
+pub const DEFAULT_MAX_COMMENTS: usize = 500;
+
+/// The default format of Config::visualize
+pub const DEFAULT_VISUALIZE_FORMAT: &str = "[{short}]";
+
+/// Who and when the tracked changes of Config::track_changes are attributed to
+#[derive(Clone, Debug)]
+pub struct Revision {
+    pub author: String,
+    /// In UTC, e.g. 2024-05-01T09:30:00Z
+    pub date: String,
+}
+
+impl Revision {
+    /// Attributed to docx-cleaner at the current time, or at SOURCE_DATE_EPOCH when it is set, so
+    /// that two runs write the same document
+    pub fn now() -> Self {
+        let source_date = std::env::var("SOURCE_DATE_EPOCH")
+            .ok()
+            .and_then(|seconds| seconds.trim().parse().ok());
+        Revision {
+            author: "docx-cleaner".to_string(),
+            date: source_date.map_or_else(timestamp, format_timestamp),
+        }
+    }
+}
+
+/// The current time in UTC, e.g. 2024-05-01T09:30:00Z
+pub fn timestamp() -> String {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    format_timestamp(seconds)
+}
+
+// Seconds since the Unix epoch as an ISO 8601 timestamp in UTC, with the days turned into a
+// date of the proleptic Gregorian calendar
+fn format_timestamp(seconds: u64) -> String {
+    let days = seconds / 86400 + 719468;
+    let era = days / 146097;
+    let day_of_era = days % 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    let time = seconds % 86400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// What happens to runs of consecutive spaces after the replacements: the "collapse_spaces"
+/// config setting (true, false or "whitespace"), overridden by --no-collapse-spaces and
+/// --collapse-whitespace
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpaceCollapse {
+    Off,
+    Spaces,
+    /// Also folds stretches mixing spaces with no-break, thin and other space separators
+    Whitespace,
+}
+
+/// What happens to no-break spaces: the "nbsp" config setting ("replace", "keep" or
+/// "patterns"), overridden by --nbsp
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NbspMode {
+    /// Replaced with a plain space, before spaces are collapsed
+    Replace,
+    Keep,
+    /// Kept where one of the nbsp_patterns matches around it, e.g. between a number and its
+    /// unit or before French punctuation, and replaced everywhere else
+    Patterns,
+}
+
+/// Parses the modes of the "nbsp" setting and --nbsp
+pub fn parse_nbsp_mode(name: &str) -> Result<NbspMode, String> {
+    match name {
+        "replace" => Ok(NbspMode::Replace),
+        "keep" => Ok(NbspMode::Keep),
+        "patterns" => Ok(NbspMode::Patterns),
+        _ => Err(format!(
+            "unknown no-break space mode '{}', expected replace, keep or patterns",
+            name
+        )),
+    }
+}
+
+/// The syntax of a user config: JSON (with comments) like the embedded config.json, or TOML with
+/// the characters as [[rule]] tables
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// Guessed from the extension: .toml is TOML, anything else JSON
+    pub fn of(path: &Path) -> Self {
+        match path.extension().and_then(OsStr::to_str) {
+            Some(extension) if extension.eq_ignore_ascii_case("toml") => ConfigFormat::Toml,
+            _ => ConfigFormat::Json,
+        }
+    }
+}
+
+/// Parses the formats accepted by --config-format
+pub fn parse_config_format(name: &str) -> Result<ConfigFormat, String> {
+    match name.to_ascii_lowercase().as_str() {
+        "json" => Ok(ConfigFormat::Json),
+        "toml" => Ok(ConfigFormat::Toml),
+        _ => Err(format!(
+            "unknown config format '{}', expected json or toml",
+            name
+        )),
+    }
+}
+
+// The value of the "nbsp" setting for a mode
+fn nbsp_mode_name(mode: NbspMode) -> &'static str {
+    match mode {
+        NbspMode::Replace => "replace",
+        NbspMode::Keep => "keep",
+        NbspMode::Patterns => "patterns",
+    }
+}
+
+// The value of the "collapse_spaces" setting, as JSON
+fn collapse_spaces_json(collapse: SpaceCollapse) -> &'static str {
+    match collapse {
+        SpaceCollapse::Off => "false",
+        SpaceCollapse::Spaces => "true",
+        SpaceCollapse::Whitespace => "\"whitespace\"",
+    }
+}
+
+/// The character of Config::nbsp, counted apart from the listed characters
+pub const NO_BREAK_SPACE: char = '\u{A0}';
+
+/// The Unicode normalization form of --normalize, applied to each text after the replacements
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Normalization {
+    /// Composes characters with their combining marks, e.g. "e" and U+0301 into "é"
+    Nfc,
+    /// Also folds compatibility characters into their plain forms, e.g. the "ﬁ" ligature into
+    /// "fi", superscript digits into digits and full-width letters into ASCII; this is lossy
+    Nfkc,
+}
+
+/// What --remove-hidden does with runs formatted as hidden text (w:vanish), which Word does not
+/// show or print but which is still copied with the text around it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HiddenText {
+    /// Taken out of the document, or marked as deleted with --track-changes. Hidden runs inside
+    /// a field, such as the bookmarks and page numbers of a table of contents, are only reported.
+    Remove,
+    /// Left in the document and only counted
+    Report,
+}
+
+/// The font size, in half points, up to which a run counts as InvisibleFormatting::Tiny
+pub const TINY_FONT_SIZE: u32 = 2;
+
+/// Why the text of a run cannot be seen, see InvisibleRun
+#[derive(Clone, Debug, PartialEq)]
+pub enum InvisibleFormatting {
+    /// The text has the color, as hex RGB, of the highlight or shading behind it, or of the page
+    SameColor(String),
+    /// The font size is at most TINY_FONT_SIZE half points
+    Tiny(u32),
+}
+
+/// A run of a Word document whose direct formatting hides its text, e.g. white text on a white
+/// page. Colors given by the theme are not resolved, see CleanReport::theme_colored_runs.
+#[derive(Clone, Debug)]
+pub struct InvisibleRun {
+    /// Where it is, like the location of a Replacement
+    pub path: String,
+    pub part: &'static str,
+    pub paragraph: usize,
+    pub run: usize,
+    pub formatting: InvisibleFormatting,
+    /// The text of the run, written like the snippet of a Replacement
+    pub snippet: String,
+    /// Whether Config::remove_invisible_formatting took it out, or would have when only checking
+    pub removed: bool,
+}
+
+/// The metadata of a package that --scrub-metadata clears, by kind
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MetadataScrub {
+    /// The creator and last editor of the core properties
+    pub author: bool,
+    /// The Company of the app properties
+    pub company: bool,
+    /// The revision number of the core properties, reset to 1
+    pub revision: bool,
+    /// The TotalTime of the app properties, the minutes spent editing, reset to 0
+    pub time: bool,
+    /// The Template of the app properties, which may be a path on the author's computer
+    pub template: bool,
+    /// The w:rsid* attributes that tie each paragraph and run to an editing session, and the
+    /// list of sessions in the settings
+    pub rsids: bool,
+    /// The preview image of the first page
+    pub thumbnail: bool,
+}
+
+/// The names --scrub-metadata accepts, in the order of MetadataScrub
+pub const METADATA_KINDS: [&str; 7] = [
+    "author",
+    "company",
+    "revision",
+    "time",
+    "template",
+    "rsids",
+    "thumbnail",
+];
+
+impl MetadataScrub {
+    pub fn all() -> Self {
+        MetadataScrub {
+            author: true,
+            company: true,
+            revision: true,
+            time: true,
+            template: true,
+            rsids: true,
+            thumbnail: true,
+        }
+    }
+}
+
+/// Parses the comma-separated kinds of --scrub-metadata, e.g. "author,rsids", or "all"
+pub fn parse_metadata_scrub(list: &str) -> Result<MetadataScrub, String> {
+    let mut scrub = MetadataScrub::default();
+    for name in list.split(',').map(str::trim) {
+        match name {
+            "all" => scrub = MetadataScrub::all(),
+            "author" => scrub.author = true,
+            "company" => scrub.company = true,
+            "revision" => scrub.revision = true,
+            "time" => scrub.time = true,
+            "template" => scrub.template = true,
+            "rsids" => scrub.rsids = true,
+            "thumbnail" => scrub.thumbnail = true,
+            _ => {
+                return Err(format!(
+                    "unknown kind of metadata '{}', expected all or some of {}",
+                    name,
+                    METADATA_KINDS.join(", ")
+                ))
+            }
+        }
+    }
+    Ok(scrub)
+}
+
+/// Parses the modes accepted by --remove-hidden
+pub fn parse_hidden_text(name: &str) -> Result<HiddenText, String> {
+    match name {
+        "remove" => Ok(HiddenText::Remove),
+        "report" => Ok(HiddenText::Report),
+        _ => Err(format!(
+            "unknown hidden text mode '{}', expected remove or report",
+            name
+        )),
+    }
+}
+
+/// Parses the form names accepted by --normalize
+pub fn parse_normalization(name: &str) -> Result<Normalization, String> {
+    match name.to_ascii_lowercase().as_str() {
+        "nfc" => Ok(Normalization::Nfc),
+        "nfkc" => Ok(Normalization::Nfkc),
+        _ => Err(format!(
+            "unknown normalization form '{}', expected nfc or nfkc",
+            name
+        )),
+    }
+}
+
+/// The lookalike letters of --homoglyphs and the Latin letters they stand for. Starts from the
+/// embedded homoglyphs.json, with the user table (--homoglyph-table or the platform config
+/// directory) merged on top.
+#[derive(Clone)]
+pub struct Homoglyphs {
+    letters: HashMap<char, (String, char)>,
+    // The user table that was merged in, None for the built-in table alone
+    source: Option<PathBuf>,
+}
+
+impl Homoglyphs {
+    pub fn builtin() -> Self {
+        let mut homoglyphs = Homoglyphs {
+            letters: HashMap::new(),
+            source: None,
+        };
+        homoglyphs
+            .merge(HOMOGLYPHS_STR)
+            .expect("Failed to parse homoglyphs.json");
+        homoglyphs
+    }
+
+    /// The built-in table with the user table, if any, merged on top
+    pub fn load(user_table: Option<&Path>) -> Result<Self, String> {
+        let mut homoglyphs = Homoglyphs::builtin();
+        if let Some(path) = user_table
+            .map(Path::to_path_buf)
+            .or_else(discover_user_homoglyphs)
+        {
+            let table_str = std::fs::read_to_string(&path).map_err(|e| {
+                format!("Failed to read homoglyph table '{}': {}", path.display(), e)
+            })?;
+            homoglyphs
+                .merge(&table_str)
+                .map_err(|e| format!("Invalid homoglyph table '{}': {}", path.display(), e))?;
+            homoglyphs.source = Some(path);
+        }
+        Ok(homoglyphs)
+    }
+
+    // Each entry maps a lookalike to [NAME, Latin letter], or takes a built-in one off the table
+    // with "keep"
+    fn merge(&mut self, table_str: &str) -> Result<(), String> {
+        let ConfigEntries(entries) =
+            serde_json::from_str(&strip_jsonc(table_str)).map_err(|e| e.to_string())?;
+        let single = |s: &str| {
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(c),
+                _ => None,
+            }
+        };
+        let mut seen = HashSet::new();
+        for (k, v) in entries {
+            let v = v.into_value();
+            let (c, end) = parse_config_key(&k)?;
+            if c != end || k.contains("..") {
+                return Err(format!("key {:?} must be a single character or U+XXXX", k));
+            }
+            if !seen.insert(c) {
+                return Err(format!("key {:?} appears more than once", k));
+            }
+            if is_latin_letter(c) {
+                return Err(format!("key {:?} is a Latin letter itself", k));
+            }
+            if v.as_str() == Some("keep") {
+                if self.letters.remove(&c).is_none() {
+                    return Err(format!(
+                        "key {:?} is set to \"keep\" but is not in the built-in table",
+                        k
+                    ));
+                }
+                continue;
+            }
+            let entry = v.as_array().and_then(|entry| match entry.as_slice() {
+                [name, latin] => Some((name.as_str()?, single(latin.as_str()?)?)),
+                _ => None,
+            });
+            let Some((name, latin)) = entry.filter(|&(_, latin)| is_latin_letter(latin)) else {
+                return Err(format!(
+                    "entry {:?} must be [NAME, Latin letter] or \"keep\"",
+                    k
+                ));
+            };
+            self.letters.insert(c, (name.to_string(), latin));
+        }
+        Ok(())
+    }
+
+    /// The Latin letter a lookalike stands for, None for any other character
+    pub fn latin(&self, c: char) -> Option<char> {
+        self.letters.get(&c).map(|&(_, latin)| latin)
+    }
+
+    /// The display name of a lookalike
+    pub fn name(&self, c: char) -> &str {
+        self.letters
+            .get(&c)
+            .map(|(name, _)| name.as_str())
+            .unwrap_or("UNKNOWN")
+    }
+
+    /// The user table merged on top of the built-in one, if there was one
+    pub fn source(&self) -> Option<&Path> {
+        self.source.as_deref()
+    }
+}
+
+// Letters of the Latin script: ASCII, Latin-1, the extended blocks and the full-width forms
+fn is_latin_letter(c: char) -> bool {
+    c.is_ascii_alphabetic()
+        || c.is_alphabetic()
+            && matches!(c,
+                '\u{00C0}'..='\u{024F}'
+                | '\u{1E00}'..='\u{1EFF}'
+                | '\u{2C60}'..='\u{2C7F}'
+                | '\u{A720}'..='\u{A7FF}'
+                | '\u{FF21}'..='\u{FF3A}'
+                | '\u{FF41}'..='\u{FF5A}')
+}
+
+/// Where a listed character comes from: the embedded config.json, the profile or the user config
+/// (which may also only have changed a built-in entry), --categories or the typography.json of
+/// --ascii-punctuation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Origin {
+    Builtin,
+    Profile,
+    User,
+    Category,
+    Typography,
+}
+
+/// What the rules do with a character, as the "action" of a config entry
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RuleAction {
+    Delete,
+    /// With the replacement of the entry, e.g. a plain space for an en space
+    Replace,
+    /// Left in the text but counted, e.g. to audit no-break spaces before removing them; the
+    /// older "report" means the same
+    Flag,
+    /// Taken off the list, which also keeps it from --categories
+    Keep,
+}
+
+impl RuleAction {
+    /// The name in the config files, e.g. "flag"
+    pub fn name(self) -> &'static str {
+        match self {
+            RuleAction::Delete => "delete",
+            RuleAction::Replace => "replace",
+            RuleAction::Flag => "flag",
+            RuleAction::Keep => "keep",
+        }
+    }
+}
+
+const DEFAULT_PREFORMATTED_STYLES: [&str; 3] = ["Code", "SourceCode", "HTMLPreformatted"];
+
+// Where NbspMode::Patterns keeps a no-break space: between a number and a unit, as a thousands
+// separator, after a section or paragraph sign and inside French quotes and punctuation
+const DEFAULT_NBSP_PATTERNS: [&str; 5] = [
+    r"\d\x{A0}(?:[kcmµn]?[gmlLsVAW]|[kMGT]?(?:B|Hz)|h|min|%|‰|°[CF]?|€|\$|£)(?:\W|$)",
+    r"\d\x{A0}\d{3}(?:\D|$)",
+    r"[§¶]\x{A0}",
+    r"\x{A0}[;:!?»]",
+    r"«\x{A0}",
+];
+
+// Compiled once, as every Config starts out with them
+static DEFAULT_NBSP_RES: Lazy<Vec<Regex>> = Lazy::new(|| {
+    DEFAULT_NBSP_PATTERNS
+        .iter()
+        .map(|pattern| Regex::new(pattern).unwrap())
+        .collect()
+});
+
+// A parsed config file: its character entries and the settings it changes
+#[derive(Clone)]
+struct ConfigFile {
+    entries: Vec<(char, ConfigEntry)>,
+    // The key of the file each character comes from, for naming it in problems
+    keys: HashMap<char, String>,
+    // What makes the file unusable, each naming the key at fault, and what was accepted but
+    // looks like a mistake, e.g. a key given twice
+    problems: Vec<String>,
+    warnings: Vec<String>,
+    collapse_spaces: Option<SpaceCollapse>,
+    preformatted_styles: Option<Vec<String>>,
+    nbsp: Option<NbspMode>,
+    nbsp_patterns: Option<Vec<Regex>>,
+    // The "profiles" of the file in file order
+    profiles: Vec<Profile>,
+    // The settings only a profile has: its description and whether it adds the punctuation of
+    // typography.json and the homoglyph table
+    description: Option<String>,
+    typography: bool,
+    homoglyphs: bool,
+}
+
+/// A named set of changes to the built-in list, from the "profiles" of the embedded config.json
+/// or of the user config, where a profile of the same name takes the place of the built-in one.
+/// It has the schema of a config file, plus "description", "typography" (the punctuation of
+/// --ascii-punctuation) and "homoglyphs" (the table of --homoglyphs).
+#[derive(Clone)]
+pub struct Profile {
+    pub name: String,
+    file: ConfigFile,
+}
+
+impl Profile {
+    pub fn description(&self) -> &str {
+        self.file.description.as_deref().unwrap_or_default()
+    }
+
+    /// What the profile changes, e.g. ["keeps 139 built-in characters", "nbsp: keep"]; empty
+    /// when it leaves the built-in list as it is
+    pub fn contents(&self) -> Vec<String> {
+        let builtin = Config::builtin();
+        let listed = |c: &char| builtin.chars.contains_key(c);
+        let file = &self.file;
+        let count = |keep: bool, report: bool| {
+            file.entries
+                .iter()
+                .filter(|(c, entry)| match entry {
+                    ConfigEntry::Remove(..) => !keep && !report,
+                    ConfigEntry::Keep { .. } => keep && listed(c),
+                    ConfigEntry::Report { .. } => report && listed(c),
+                })
+                .count()
+        };
+        let mut contents = Vec::new();
+        for (count, what) in [
+            (count(true, false), "keeps {} built-in characters"),
+            (count(false, true), "only reports {} built-in characters"),
+            (count(false, false), "removes or replaces {} characters"),
+        ] {
+            if count > 0 {
+                contents.push(what.replace("{}", &count.to_string()));
+            }
+        }
+        if let Some(collapse_spaces) = file.collapse_spaces {
+            contents.push(format!(
+                "collapse_spaces: {}",
+                collapse_spaces_json(collapse_spaces)
+            ));
+        }
+        if let Some(nbsp) = file.nbsp {
+            contents.push(format!("nbsp: {}", nbsp_mode_name(nbsp)));
+        }
+        if let Some(patterns) = &file.nbsp_patterns {
+            contents.push(format!("{} nbsp_patterns", patterns.len()));
+        }
+        if let Some(styles) = &file.preformatted_styles {
+            contents.push(format!("preformatted_styles: {}", styles.join(", ")));
+        }
+        if file.typography {
+            contents.push("ASCII punctuation".to_string());
+        }
+        if file.homoglyphs {
+            contents.push("homoglyphs".to_string());
+        }
+        contents
+    }
+}
+
+// A single config entry: [NAME, replacement] adds or overrides a character, "keep" takes a
+// built-in character off the list and "flag" (or "report") leaves it in the text but still
+// counts it. Keeping or flagging a whole range only affects the listed characters.
+#[derive(Clone)]
+enum ConfigEntry {
+    Remove(String, String),
+    Keep {
+        in_range: bool,
+    },
+    // With a name, a character that is not listed yet is added to be flagged
+    Report {
+        in_range: bool,
+        name: Option<String>,
+    },
+}
+
+// The entries of a config file in file order. Unlike a map this keeps repeated keys, so they
+// can be reported instead of silently overriding each other.
+struct ConfigEntries(Vec<(String, ConfigValue)>);
+
+// The value of an entry: objects, i.e. the profiles and each of them, keep their order as well
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum ConfigValue {
+    Object(ConfigEntries),
+    Other(serde_json::Value),
+}
+
+impl ConfigValue {
+    // The value where the order does not matter; repeated keys of an object keep the last value
+    fn into_value(self) -> serde_json::Value {
+        match self {
+            ConfigValue::Object(ConfigEntries(entries)) => serde_json::Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k, v.into_value()))
+                    .collect(),
+            ),
+            ConfigValue::Other(v) => v,
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for ConfigEntries {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for EntriesVisitor {
+            type Value = ConfigEntries;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("an object mapping characters to [NAME, replacement]")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> Result<Self::Value, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(ConfigEntries(entries))
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor)
+    }
+}
+
+impl Config {
+    pub fn builtin() -> Self {
+        let mut config = Config {
+            chars: HashMap::new(),
+            kept: HashSet::new(),
+            origins: HashMap::new(),
+            reported: HashSet::new(),
+            declined: HashSet::new(),
+            strict: false,
+            math_strict: false,
+            collapse_spaces: SpaceCollapse::Spaces,
+            trim_around_breaks: false,
+            preformatted_styles: DEFAULT_PREFORMATTED_STYLES.map(String::from).to_vec(),
+            nbsp: NbspMode::Replace,
+            nbsp_patterns: DEFAULT_NBSP_RES.clone(),
+            record_locations: false,
+            track_changes: None,
+            annotate: None,
+            max_comments: DEFAULT_MAX_COMMENTS,
+            highlight: None,
+            collapse_cell_spaces: false,
+            normalize: None,
+            homoglyphs: None,
+            hidden_text: None,
+            remove_invisible_formatting: false,
+            scrub_metadata: None,
+            prune_empty: false,
+            merge_runs: false,
+            visualize: None,
+            source: None,
+            profile: DEFAULT_PROFILE.to_string(),
+        };
+        let file = parse_builtin(CONFIG_STR, "config.json");
+        config
+            .merge(file, Origin::Builtin)
+            .expect("Failed to parse config.json");
+        config
+    }
+
+    /// The built-in config with the user config, if any, merged on top
+    pub fn load(
+        user_config: Option<&Path>,
+        categories: &[GeneralCategory],
+    ) -> Result<Self, String> {
+        Config::load_profile(user_config, None, DEFAULT_PROFILE, categories)
+    }
+
+    /// The built-in config with the named profile and then the user config, if any, merged on
+    /// top; the user config is read as JSON or TOML by its extension unless a format is given
+    pub fn load_profile(
+        user_config: Option<&Path>,
+        format: Option<ConfigFormat>,
+        profile: &str,
+        categories: &[GeneralCategory],
+    ) -> Result<Self, String> {
+        let user_file = read_user_config(user_config, format)?;
+        Config::compose(user_file, profile, categories)
+    }
+
+    /// The built-in config with the named built-in profile, leaving out the user config, e.g. to
+    /// go on with the defaults when the user config is invalid
+    pub fn builtin_profile(profile: &str, categories: &[GeneralCategory]) -> Result<Self, String> {
+        Config::compose(None, profile, categories)
+    }
+
+    fn compose(
+        user_file: Option<(PathBuf, ConfigFile)>,
+        profile: &str,
+        categories: &[GeneralCategory],
+    ) -> Result<Self, String> {
+        let profiles = Config::merged_profiles(user_file.as_ref().map(|(_, file)| file));
+        let Some(profile) = profiles.iter().find(|p| p.name == profile).cloned() else {
+            let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+            return Err(format!(
+                "Unknown profile '{}'; the profiles are {}",
+                profile,
+                names.join(", ")
+            ));
+        };
+        let mut config = Config::builtin();
+        config
+            .merge(profile.file.clone(), Origin::Profile)
+            .map_err(|problems| {
+                invalid_config(&format!("Profile '{}'", profile.name), &problems)
+            })?;
+        config.profile = profile.name;
+        // Before the user config, so it can keep or flag the characters they add
+        config.add_categories(categories);
+        let mut keys = HashMap::new();
+        let mut problems = Vec::new();
+        if let Some((path, mut file)) = user_file {
+            keys.clone_from(&file.keys);
+            problems = std::mem::take(&mut file.problems);
+            problems.extend(config.merge(file, Origin::User).err().unwrap_or_default());
+            config.source = Some(path);
+        }
+        // After the user config, which may change some of the punctuation
+        if profile.file.typography {
+            config.add_typography();
+        }
+        problems.extend(config.check_replacements(&keys));
+        if let (Some(path), false) = (config.source(), problems.is_empty()) {
+            let source = format!("Invalid config file '{}'", path.display());
+            return Err(invalid_config(&source, &problems));
+        }
+        if profile.file.homoglyphs {
+            config.homoglyphs = Some(Homoglyphs::load(None)?);
+        }
+        Ok(config)
+    }
+
+    /// The profiles of the embedded config.json, with those of the user config (--config or the
+    /// platform config directory) taking the place of a built-in one of the same name or added
+    /// after them
+    pub fn profiles(
+        user_config: Option<&Path>,
+        format: Option<ConfigFormat>,
+    ) -> Result<Vec<Profile>, String> {
+        let user_file = read_user_config(user_config, format)?;
+        if let Some((path, file)) = user_file
+            .as_ref()
+            .filter(|(_, file)| !file.problems.is_empty())
+        {
+            let source = format!("Invalid config file '{}'", path.display());
+            return Err(invalid_config(&source, &file.problems));
+        }
+        Ok(Config::merged_profiles(
+            user_file.as_ref().map(|(_, file)| file),
+        ))
+    }
+
+    /// The profiles of the embedded config.json alone
+    pub fn builtin_profiles() -> Vec<Profile> {
+        Config::merged_profiles(None)
+    }
+
+    fn merged_profiles(user_file: Option<&ConfigFile>) -> Vec<Profile> {
+        let mut profiles = parse_builtin(CONFIG_STR, "config.json").profiles;
+        for profile in user_file.into_iter().flat_map(|file| &file.profiles) {
+            match profiles.iter_mut().find(|p| p.name == profile.name) {
+                Some(builtin) => *builtin = profile.clone(),
+                None => profiles.push(profile.clone()),
+            }
+        }
+        profiles
+    }
+
+    /// The built-in config with a user config given as JSON merged on top, for where there are
+    /// no files to read it from, e.g. in the browser
+    pub fn from_json(user_config: &str) -> Result<Self, String> {
+        let mut config = Config::builtin();
+        let mut file = parse_config(user_config).map_err(|e| format!("Invalid config: {}", e))?;
+        for warning in &file.warnings {
+            log::warn!("Config: {}", warning);
+        }
+        let keys = file.keys.clone();
+        let mut problems = std::mem::take(&mut file.problems);
+        problems.extend(config.merge(file, Origin::User).err().unwrap_or_default());
+        problems.extend(config.check_replacements(&keys));
+        if !problems.is_empty() {
+            return Err(invalid_config("Invalid config", &problems));
+        }
+        Ok(config)
+    }
+
+    // Characters already configured keep their replacement, the others are deleted
+    fn add_categories(&mut self, categories: &[GeneralCategory]) {
+        if categories.is_empty() {
+            return;
+        }
+        for c in '\0'..=char::MAX {
+            let category = c.general_category();
+            if !categories.contains(&category)
+                || self.chars.contains_key(&c)
+                || self.kept.contains(&c)
+            {
+                continue;
+            }
+            // Named when reported, see Config::name
+            self.chars.insert(c, (String::new(), String::new()));
+            self.record(c, Origin::Category);
+        }
+    }
+
+    /// Adds the typographic punctuation of the embedded typography.json, e.g. curly quotes, dashes
+    /// and the ellipsis, replacing the built-in entries for them. Characters the user config
+    /// lists or keeps stay as they are, so it can change e.g. what em dashes become.
+    pub fn add_typography(&mut self) {
+        let file = parse_builtin(TYPOGRAPHY_STR, "typography.json");
+        for (c, entry) in file.entries {
+            if self.kept.contains(&c) || self.origin(c) == Origin::User {
+                continue;
+            }
+            if let ConfigEntry::Remove(description, replacement) = entry {
+                self.reported.remove(&c);
+                self.chars.insert(c, (description, replacement));
+                self.record(c, Origin::Typography);
+            }
+        }
+    }
+
+    fn merge(&mut self, file: ConfigFile, origin: Origin) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+        if let Some(collapse_spaces) = file.collapse_spaces {
+            self.collapse_spaces = collapse_spaces;
+        }
+        if let Some(preformatted_styles) = file.preformatted_styles {
+            self.preformatted_styles = preformatted_styles;
+        }
+        if let Some(nbsp) = file.nbsp {
+            self.nbsp = nbsp;
+        }
+        if let Some(nbsp_patterns) = file.nbsp_patterns {
+            self.nbsp_patterns = nbsp_patterns;
+        }
+        for (c, entry) in file.entries {
+            match entry {
+                ConfigEntry::Remove(description, replacement) => {
+                    self.kept.remove(&c);
+                    self.reported.remove(&c);
+                    self.chars.insert(c, (description, replacement));
+                    self.record(c, origin);
+                }
+                ConfigEntry::Keep { in_range } => {
+                    self.kept.insert(c);
+                    self.reported.remove(&c);
+                    if self.chars.remove(&c).is_some() || in_range {
+                        self.record(c, origin);
+                    } else {
+                        problems.push(format!(
+                            "key {:?} is set to \"keep\" but is not in the built-in list",
+                            file.keys[&c]
+                        ));
+                    }
+                }
+                ConfigEntry::Report { in_range, name } => {
+                    if let Some(name) = name {
+                        let (_, replacement) = self.chars.remove(&c).unwrap_or_default();
+                        self.chars.insert(c, (name, replacement));
+                    }
+                    if self.chars.contains_key(&c) {
+                        self.kept.remove(&c);
+                        self.reported.insert(c);
+                        self.record(c, origin);
+                    } else if !in_range {
+                        problems.push(format!(
+                            "key {:?} is flagged but is not in the built-in list; give it a \"name\" to add it",
+                            file.keys[&c]
+                        ));
+                    }
+                }
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
+    // Replacements are not cleaned again, so a replacement holding a character that is removed
+    // itself would leave that character in the text. Only pairs involving the user config, whose
+    // keys name the culprit, are checked: the built-in list turns the line and paragraph
+    // separators into line feeds on purpose.
+    fn check_replacements(&self, keys: &HashMap<char, String>) -> Vec<String> {
+        let removed = |c: &char| self.chars.contains_key(c) && !self.reported.contains(c);
+        let mut problems = Vec::new();
+        for c in self.listed().into_iter().filter(removed) {
+            let replacement = self.replacement(c);
+            let Some(inner) = replacement.chars().find(|inner| {
+                removed(inner)
+                    && (self.origin(c) == Origin::User || self.origin(*inner) == Origin::User)
+            }) else {
+                continue;
+            };
+            let problem = if self.origin(c) == Origin::User {
+                format!(
+                    "the replacement {:?} of key {:?} contains U+{:04X} {}, which is removed itself",
+                    replacement,
+                    keys[&c],
+                    inner as u32,
+                    self.name(inner)
+                )
+            } else {
+                format!(
+                    "key {:?} removes U+{:04X}, which the replacement of U+{:04X} {} puts back",
+                    keys[&inner],
+                    inner as u32,
+                    c as u32,
+                    self.name(c)
+                )
+            };
+            problems.push(problem);
+        }
+        problems
+    }
+
+    /// Same schema as config.json, sorted by character, with everything outside printable ASCII
+    /// escaped so invisible characters stay readable
+    pub fn to_json(&self) -> String {
+        let mut chars: Vec<_> = self.chars.iter().collect();
+        chars.sort_by_key(|(c, _)| **c);
+        let entries: Vec<String> = chars
+            .into_iter()
+            .map(|(c, (_, replacement))| {
+                // Named, since a flag may add a character the built-in list does not have
+                if self.reported.contains(c) {
+                    return format!(
+                        "    \"{}\": {{\"name\": \"{}\", \"action\": \"flag\"}}",
+                        escape_json(&c.to_string()),
+                        escape_json(&self.name(*c))
+                    );
+                }
+                format!(
+                    "    \"{}\": [\"{}\", \"{}\"]",
+                    escape_json(&c.to_string()),
+                    escape_json(&self.name(*c)),
+                    escape_json(replacement)
+                )
+            })
+            .collect();
+        let collapse_spaces = collapse_spaces_json(self.collapse_spaces);
+        let preformatted_styles: Vec<String> = self
+            .preformatted_styles
+            .iter()
+            .map(|style| format!("\"{}\"", escape_json(style)))
+            .collect();
+        let nbsp = nbsp_mode_name(self.nbsp);
+        let nbsp_patterns: Vec<String> = self
+            .nbsp_patterns
+            .iter()
+            .map(|pattern| format!("\"{}\"", escape_json(pattern.as_str())))
+            .collect();
+        format!(
+            "{{\n    \"collapse_spaces\": {},\n    \"preformatted_styles\": [{}],\n    \"nbsp\": \"{}\",\n    \"nbsp_patterns\": [{}],\n{}\n}}",
+            collapse_spaces,
+            preformatted_styles.join(", "),
+            nbsp,
+            nbsp_patterns.join(", "),
+            entries.join(",\n")
+        )
+    }
+
+    /// Leaves the directional formatting characters in the text and only reports them
+    pub fn report_bidi_controls(&mut self) {
+        for c in BIDI_CONTROLS {
+            if self.chars.contains_key(&c) {
+                self.reported.insert(c);
+            }
+        }
+    }
+
+    /// The display name of a character: the one the config gives it, or else its Unicode name
+    pub fn name(&self, c: char) -> Cow<'_, str> {
+        match self.chars.get(&c) {
+            Some((name, _)) if !name.is_empty() => Cow::Borrowed(name),
+            None if c == NO_BREAK_SPACE => Cow::Borrowed("NO-BREAK SPACE"),
+            _ => Cow::Owned(unlisted_name(c)),
+        }
+    }
+
+    /// The token of Config::visualize for a character: the format with {short} replaced by the
+    /// Unicode abbreviation of the character, e.g. ZWSP, or by U+XXXX for one without, {code} by
+    /// U+XXXX and {name} by its name
+    pub fn token(&self, format: &str, c: char) -> String {
+        let code = format!("U+{:04X}", c as u32);
+        format
+            .replace("{short}", abbreviation(c).unwrap_or(&code))
+            .replace("{name}", &self.name(c))
+            .replace("{code}", &code)
+    }
+
+    /// How runs of spaces are collapsed: never with Config::visualize, whose tokens show the
+    /// text as it was
+    pub fn space_collapse(&self) -> SpaceCollapse {
+        match self.visualize {
+            Some(_) => SpaceCollapse::Off,
+            None => self.collapse_spaces,
+        }
+    }
+
+    /// What a listed character is replaced with, empty when it is deleted
+    pub fn replacement(&self, c: char) -> &str {
+        match self.chars.get(&c) {
+            Some((_, replacement)) => replacement,
+            None if c == NO_BREAK_SPACE => " ",
+            None => "",
+        }
+    }
+
+    /// Every listed character, in code point order
+    pub fn listed(&self) -> Vec<char> {
+        let mut listed: Vec<char> = self.chars.keys().copied().collect();
+        listed.sort_unstable();
+        listed
+    }
+
+    /// Where a listed character comes from
+    pub fn origin(&self, c: char) -> Origin {
+        self.history(c)
+            .last()
+            .map_or(Origin::Builtin, |(origin, _)| *origin)
+    }
+
+    /// Every source that set the rule of a character, with the action it set, in the order they
+    /// were merged, e.g. deleted by the built-in list and then kept by the user config; empty
+    /// when no rule mentions the character
+    pub fn history(&self, c: char) -> &[(Origin, RuleAction)] {
+        self.origins.get(&c).map_or(&[], Vec::as_slice)
+    }
+
+    fn record(&mut self, c: char, origin: Origin) {
+        let action = self
+            .action(c)
+            .expect("Recorded characters are listed or kept");
+        self.origins.entry(c).or_default().push((origin, action));
+    }
+
+    /// Whether a listed character is left in the text and only reported
+    pub fn is_reported(&self, c: char) -> bool {
+        self.reported.contains(&c)
+    }
+
+    /// What the rules do with a character, None when they leave it alone because it is not
+    /// listed
+    pub fn action(&self, c: char) -> Option<RuleAction> {
+        if self.kept.contains(&c) {
+            return Some(RuleAction::Keep);
+        }
+        let (_, replacement) = self.chars.get(&c)?;
+        Some(if self.reported.contains(&c) {
+            RuleAction::Flag
+        } else if replacement.is_empty() {
+            RuleAction::Delete
+        } else {
+            RuleAction::Replace
+        })
+    }
+
+    /// Leaves a character in the text that the user chose to keep, e.g. the no-break spaces of
+    /// one document; its occurrences are counted in CleanReport::declined
+    pub fn decline(&mut self, c: char) {
+        self.declined.insert(c);
+    }
+
+    /// The user config file merged on top of the built-in list, if there was one
+    pub fn source(&self) -> Option<&Path> {
+        self.source.as_deref()
+    }
+
+    /// The name of the profile the config was built with, DEFAULT_PROFILE unless one was chosen
+    pub fn profile(&self) -> &str {
+        &self.profile
+    }
+}
+
+// The user config (--config or the platform config directory) with its path, None without one.
+// Its problems are left for the caller, so they are reported together with those of merging it.
+// Without a format the extension decides.
+fn read_user_config(
+    user_config: Option<&Path>,
+    format: Option<ConfigFormat>,
+) -> Result<Option<(PathBuf, ConfigFile)>, String> {
+    let Some(path) = user_config
+        .map(Path::to_path_buf)
+        .or_else(discover_user_config)
+    else {
+        return Ok(None);
+    };
+    let config_str = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read config file '{}': {}", path.display(), e))?;
+    let file = match format.unwrap_or_else(|| ConfigFormat::of(&path)) {
+        ConfigFormat::Json => parse_config(&config_str),
+        ConfigFormat::Toml => parse_toml_config(&config_str),
+    }
+    .map_err(|e| format!("Invalid config file '{}': {}", path.display(), e))?;
+    for warning in &file.warnings {
+        log::warn!("Config file '{}': {}", path.display(), warning);
+    }
+    Ok(Some((path, file)))
+}
+
+// What happens to a listed character when it is found in the text
+enum Action {
+    // An empty replacement deletes the character
+    Replace(String),
+    Report,
+    // Left in the text because the user declined to remove it
+    Declined,
+    // A no-break space with NbspMode::Patterns: replaced with a space unless a pattern matches
+    NoBreakSpace,
+}
+
+// The character list of a config compiled into a single lookup, built once per Cleaner so the
+// text is matched against it without going through the names and the reported set
+struct Rules {
+    actions: HashMap<char, Action>,
+    // The characters the user config keeps, which are not suspicious either
+    kept: HashSet<char>,
+    nbsp_patterns: Vec<Regex>,
+    // The listed characters again, for telling most characters apart without hashing them: a
+    // table for ASCII and sorted, merged ranges for everything else
+    ascii: [bool; 128],
+    ranges: Vec<(char, char)>,
+}
+
+impl Rules {
+    fn new(config: &Config) -> Self {
+        let mut actions: HashMap<char, Action> = config
+            .chars
+            .iter()
+            .map(|(&c, (_, replacement))| {
+                let action = if config.reported.contains(&c) {
+                    Action::Report
+                } else {
+                    Action::Replace(replacement.clone())
+                };
+                (c, action)
+            })
+            .collect();
+        let nbsp = match config.nbsp {
+            NbspMode::Replace => Some(Action::Replace(" ".to_string())),
+            NbspMode::Patterns => Some(Action::NoBreakSpace),
+            NbspMode::Keep => None,
+        };
+        if let Some(action) = nbsp {
+            actions.entry(NO_BREAK_SPACE).or_insert(action);
+        }
+        for c in &config.declined {
+            if let Some(action) = actions.get_mut(c) {
+                *action = Action::Declined;
+            }
+        }
+
+        let mut listed: Vec<char> = actions.keys().copied().collect();
+        listed.sort_unstable();
+        let mut ascii = [false; 128];
+        let mut ranges: Vec<(char, char)> = Vec::new();
+        for c in listed {
+            if c.is_ascii() {
+                ascii[c as usize] = true;
+            } else if let Some(last) = ranges
+                .last_mut()
+                .filter(|(_, end)| *end as u32 + 1 == c as u32)
+            {
+                last.1 = c;
+            } else {
+                ranges.push((c, c));
+            }
+        }
+        Rules {
+            actions,
+            kept: config.kept.clone(),
+            nbsp_patterns: config.nbsp_patterns.clone(),
+            ascii,
+            ranges,
+        }
+    }
+
+    // The byte offsets of the no-break spaces in the text that one of the nbsp_patterns matches
+    // around
+    fn patterned_spaces(&self, text: &str) -> HashSet<usize> {
+        self.nbsp_patterns
+            .iter()
+            .flat_map(|pattern| pattern.find_iter(text))
+            .flat_map(|found| {
+                found
+                    .as_str()
+                    .match_indices(NO_BREAK_SPACE)
+                    .map(move |(i, _)| found.start() + i)
+            })
+            .collect()
+    }
+
+    // Whether the text is ASCII without a listed character, like most runs of a document: nothing
+    // in it is replaced, suspicious, a lookalike or unnormalized, which one pass over its bytes
+    // tells without decoding a character
+    fn is_plain(&self, text: &str) -> bool {
+        text.bytes()
+            .all(|b| b.is_ascii() && !self.ascii[b as usize])
+    }
+
+    fn is_listed(&self, c: char) -> bool {
+        if c.is_ascii() {
+            return self.ascii[c as usize];
+        }
+        let i = self.ranges.partition_point(|&(_, end)| end < c);
+        self.ranges.get(i).is_some_and(|&(start, _)| start <= c)
+    }
+
+    // Listed format characters that are deleted without a replacement, e.g. U+200B
+    fn is_zero_width(&self, c: char) -> bool {
+        matches!(self.action(c), Some(Action::Replace(replacement)) if replacement.is_empty())
+            && c.general_category() == GeneralCategory::Format
+    }
+
+    // Format characters, private use characters and unassigned code points that are neither
+    // listed nor kept: what the list may be missing, e.g. a new way of watermarking text
+    fn is_suspicious(&self, c: char) -> bool {
+        !c.is_ascii()
+            && matches!(
+                c.general_category(),
+                GeneralCategory::Format | GeneralCategory::PrivateUse | GeneralCategory::Unassigned
+            )
+            && !self.is_listed(c)
+            && !self.kept.contains(&c)
+    }
+
+    fn action(&self, c: char) -> Option<&Action> {
+        if !self.is_listed(c) {
+            return None;
+        }
+        self.actions.get(&c)
+    }
+}
+
+// Expects an object mapping keys to [NAME, replacement] or "keep", where a key is a single
+// character, U+XXXX or an inclusive range U+XXXX..U+YYYY. Ranges are expanded, and a single
+// character listed explicitly wins over a range containing it. The "collapse_spaces" and
+// "preformatted_styles" keys are settings rather than characters, and "profiles" maps the name
+// of each Profile to an object of the same kind.
+fn parse_config(config_str: &str) -> Result<ConfigFile, String> {
+    let ConfigEntries(json_entries) =
+        serde_json::from_str(&strip_jsonc(config_str)).map_err(|e| e.to_string())?;
+    Ok(parse_entries(json_entries, false))
+}
+
+/// The problems of the embedded config.json and typography.json, which the built-in config
+/// takes to be valid; empty unless the binary was built from broken files
+pub fn builtin_problems() -> Vec<String> {
+    let mut problems = Vec::new();
+    for (name, config_str) in [
+        ("config.json", CONFIG_STR),
+        ("typography.json", TYPOGRAPHY_STR),
+    ] {
+        match parse_config(config_str) {
+            Ok(file) => problems.extend(file.problems.iter().map(|p| format!("{}: {}", name, p))),
+            Err(e) => problems.push(format!("{}: {}", name, e)),
+        }
+    }
+    problems
+}
+
+// One of the embedded files, which has no problems
+fn parse_builtin(config_str: &str, name: &str) -> ConfigFile {
+    let file =
+        parse_config(config_str).unwrap_or_else(|e| panic!("Failed to parse {}: {}", name, e));
+    assert!(
+        file.problems.is_empty(),
+        "Failed to parse {}: {:?}",
+        name,
+        file.problems
+    );
+    file
+}
+
+// A TOML config has the settings of config.json as they are, and a [[rule]] table for each
+// character, e.g.
+//
+//     [[rule]]
+//     char = "U+00A0..U+00A1"
+//     name = "NO-BREAK SPACE"
+//     action = "replace"
+//     replacement = " "
+//
+// where "char" is a key of config.json and "action" is "delete", "replace", "flag" or "keep";
+// without an action a rule with a replacement replaces and one without deletes. The profiles are
+// [profiles.NAME] tables with [[profiles.NAME.rule]] tables of their own. It is turned into the
+// entries of the equivalent JSON, so both go through the same checks.
+fn parse_toml_config(config_str: &str) -> Result<ConfigFile, String> {
+    let document: toml_edit::DocumentMut = config_str
+        .parse()
+        .map_err(|e: toml_edit::TomlError| e.to_string())?;
+    let mut problems = Vec::new();
+    let entries = toml_entries(document.as_table(), false, &mut problems);
+    let mut file = parse_entries(entries, false);
+    problems.append(&mut file.problems);
+    file.problems = problems;
+    Ok(file)
+}
+
+// The entries of a TOML table as those of the equivalent JSON object, with the problems of the
+// [[rule]] tables added to problems
+fn toml_entries(
+    table: &toml_edit::Table,
+    in_profile: bool,
+    problems: &mut Vec<String>,
+) -> Vec<(String, ConfigValue)> {
+    let mut entries = Vec::new();
+    for (key, item) in table.iter() {
+        if key == "rule" {
+            let rules = match toml_json(item) {
+                serde_json::Value::Array(rules) if rules.iter().all(|rule| rule.is_object()) => {
+                    rules
+                }
+                _ => {
+                    problems.push(format!("{:?} must be a list of [[rule]] tables", key));
+                    continue;
+                }
+            };
+            for (n, rule) in rules.into_iter().enumerate() {
+                match toml_rule(rule) {
+                    Ok(entry) => entries.push(entry),
+                    Err(problem) => problems.push(format!("rule {}: {}", n + 1, problem)),
+                }
+            }
+            continue;
+        }
+        let value = match item {
+            toml_edit::Item::Table(profiles) if key == "profiles" && !in_profile => {
+                let mut named = Vec::new();
+                for (name, profile) in profiles.iter() {
+                    let value = match profile.as_table() {
+                        Some(profile) => {
+                            let mut profile_problems = Vec::new();
+                            let entries = toml_entries(profile, true, &mut profile_problems);
+                            problems.extend(
+                                profile_problems
+                                    .into_iter()
+                                    .map(|problem| format!("profile {:?}: {}", name, problem)),
+                            );
+                            ConfigValue::Object(ConfigEntries(entries))
+                        }
+                        None => ConfigValue::Other(toml_json(profile)),
+                    };
+                    named.push((name.to_string(), value));
+                }
+                ConfigValue::Object(ConfigEntries(named))
+            }
+            item => ConfigValue::Other(toml_json(item)),
+        };
+        entries.push((key.to_string(), value));
+    }
+    entries
+}
+
+// A [[rule]] table as the key of config.json and its value
+fn toml_rule(rule: serde_json::Value) -> Result<(String, ConfigValue), String> {
+    let mut rule = match rule {
+        serde_json::Value::Object(rule) => rule,
+        _ => serde_json::Map::new(),
+    };
+    let Some(serde_json::Value::String(key)) = rule.remove("char") else {
+        return Err("\"char\" must be a character, U+XXXX or U+XXXX..U+YYYY".to_string());
+    };
+    Ok((key, ConfigValue::Other(serde_json::Value::Object(rule))))
+}
+
+// An entry written as an object with an explicit action, e.g. {"name": "NO-BREAK SPACE",
+// "action": "flag"}. Without an action an entry with a replacement replaces and one without
+// deletes. Flagging with a name also flags a character that is not listed yet.
+fn rule_entry(key: &str, rule: &serde_json::Value, in_range: bool) -> Result<ConfigEntry, String> {
+    let field = |name: &str| rule.get(name);
+    if let Some(unknown) = rule
+        .as_object()
+        .into_iter()
+        .flat_map(|fields| fields.keys())
+        .find(|field| !matches!(field.as_str(), "name" | "action" | "replacement"))
+    {
+        return Err(format!(
+            "unknown field {:?} in {:?}, expected name, action or replacement",
+            unknown, key
+        ));
+    }
+    let text = |name: &str| match field(name) {
+        None => Ok(None),
+        Some(serde_json::Value::String(text)) => Ok(Some(text.clone())),
+        Some(_) => Err(format!("{:?} of {:?} must be a string", name, key)),
+    };
+    let name = text("name")?;
+    let replacement = text("replacement")?;
+    let action = text("action")?;
+    let description = || name.clone().unwrap_or_default();
+    match (action.as_deref(), replacement) {
+        (Some(action @ ("keep" | "flag" | "report")), Some(_)) => Err(format!(
+            "{:?} is set to {:?} and cannot have a replacement",
+            key, action
+        )),
+        (Some("keep"), None) => Ok(ConfigEntry::Keep { in_range }),
+        (Some("flag" | "report"), None) => Ok(ConfigEntry::Report {
+            in_range,
+            name: name.clone(),
+        }),
+        (Some("delete"), Some(_)) => Err(format!(
+            "{:?} is deleted and cannot have a replacement",
+            key
+        )),
+        (Some("delete") | None, None) => Ok(ConfigEntry::Remove(description(), String::new())),
+        (Some("replace"), None) => Err(format!("{:?} is replaced but has no \"replacement\"", key)),
+        (Some("replace") | None, Some(replacement)) => {
+            Ok(ConfigEntry::Remove(description(), replacement))
+        }
+        (Some(action), _) => Err(format!(
+            "action {:?} of {:?} must be \"delete\", \"replace\", \"flag\" or \"keep\"",
+            action, key
+        )),
+    }
+}
+
+// A TOML value as the JSON value it stands for; dates become strings
+fn toml_json(item: &toml_edit::Item) -> serde_json::Value {
+    use toml_edit::{Item, Value};
+    let value = |value: &Value| match value {
+        Value::String(s) => serde_json::json!(s.value()),
+        Value::Integer(i) => serde_json::json!(i.value()),
+        Value::Float(f) => serde_json::json!(f.value()),
+        Value::Boolean(b) => serde_json::json!(b.value()),
+        Value::Datetime(d) => serde_json::json!(d.value().to_string()),
+        Value::Array(array) => serde_json::Value::Array(
+            array
+                .iter()
+                .map(|v| toml_json(&Item::Value(v.clone())))
+                .collect(),
+        ),
+        Value::InlineTable(table) => serde_json::Value::Object(
+            table
+                .iter()
+                .map(|(k, v)| (k.to_string(), toml_json(&Item::Value(v.clone()))))
+                .collect(),
+        ),
+    };
+    match item {
+        Item::None => serde_json::Value::Null,
+        Item::Value(v) => value(v),
+        Item::Table(table) => serde_json::Value::Object(
+            table
+                .iter()
+                .map(|(k, v)| (k.to_string(), toml_json(v)))
+                .collect(),
+        ),
+        Item::ArrayOfTables(tables) => serde_json::Value::Array(
+            tables
+                .iter()
+                .map(|table| toml_json(&Item::Table(table.clone())))
+                .collect(),
+        ),
+    }
+}
+
+// The entries of a config file or, with in_profile, of one of its profiles. Every problem is
+// collected rather than stopping at the first, so a file can be fixed in one go; a key given
+// twice is only a warning and the last one is used.
+fn parse_entries(json_entries: Vec<(String, ConfigValue)>, in_profile: bool) -> ConfigFile {
+    let mut ranges: Vec<(String, char, char)> = Vec::new();
+    let mut entries: Vec<(String, char, char, bool, ConfigEntry)> = Vec::new();
+    let mut problems = Vec::new();
+    let mut warnings = Vec::new();
+    let mut collapse_spaces = None;
+    let mut preformatted_styles = None;
+    let mut nbsp = None;
+    let mut nbsp_patterns = None;
+    let mut profiles: Option<Vec<Profile>> = None;
+    let mut description = None;
+    let mut typography = None;
+    let mut homoglyphs = None;
+    let mut settings_seen = HashSet::new();
+    for (k, v) in json_entries {
+        let v = match v {
+            ConfigValue::Object(ConfigEntries(entries)) if k == "profiles" => {
+                if in_profile {
+                    problems.push(format!("profiles cannot have {:?} of their own", k));
+                    continue;
+                }
+                if profiles.is_some() {
+                    warnings.push(format!("key {:?} appears more than once", k));
+                }
+                let mut parsed: Vec<Profile> = Vec::new();
+                for (name, profile) in entries {
+                    let ConfigValue::Object(ConfigEntries(entries)) = profile else {
+                        problems.push(format!("profile {:?} must be an object", name));
+                        continue;
+                    };
+                    let mut file = parse_entries(entries, true);
+                    problems.extend(
+                        file.problems
+                            .drain(..)
+                            .map(|problem| format!("profile {:?}: {}", name, problem)),
+                    );
+                    warnings.extend(
+                        file.warnings
+                            .drain(..)
+                            .map(|warning| format!("profile {:?}: {}", name, warning)),
+                    );
+                    if parsed.iter().any(|p| p.name == name) {
+                        warnings.push(format!("profile {:?} appears more than once", name));
+                        parsed.retain(|p| p.name != name);
+                    }
+                    parsed.push(Profile { name, file });
+                }
+                profiles = Some(parsed);
+                continue;
+            }
+            v => v.into_value(),
+        };
+        if k == "profiles" {
+            problems.push(format!("setting {:?} must map names to objects", k));
+            continue;
+        }
+        let is_setting = matches!(
+            k.as_str(),
+            "description"
+                | "typography"
+                | "homoglyphs"
+                | "nbsp"
+                | "nbsp_patterns"
+                | "preformatted_styles"
+                | "collapse_spaces"
+        );
+        if is_setting && !settings_seen.insert(k.clone()) {
+            warnings.push(format!("key {:?} appears more than once", k));
+        }
+        if matches!(k.as_str(), "description" | "typography" | "homoglyphs") {
+            if !in_profile {
+                problems.push(format!("setting {:?} is only allowed in a profile", k));
+                continue;
+            }
+            match (k.as_str(), v) {
+                ("description", serde_json::Value::String(text)) => description = Some(text),
+                ("typography", serde_json::Value::Bool(on)) => typography = Some(on),
+                ("homoglyphs", serde_json::Value::Bool(on)) => homoglyphs = Some(on),
+                ("description", _) => problems.push(format!("setting {:?} must be a string", k)),
+                _ => problems.push(format!("setting {:?} must be true or false", k)),
+            }
+            continue;
+        }
+        if k == "nbsp" {
+            match v.as_str().and_then(|mode| parse_nbsp_mode(mode).ok()) {
+                Some(mode) => nbsp = Some(mode),
+                None => problems.push(format!(
+                    "setting {:?} must be \"replace\", \"keep\" or \"patterns\"",
+                    k
+                )),
+            }
+            continue;
+        }
+        if k == "nbsp_patterns" {
+            let Some(patterns) = v.as_array().and_then(|patterns| {
+                patterns
+                    .iter()
+                    .map(|pattern| pattern.as_str())
+                    .collect::<Option<Vec<_>>>()
+            }) else {
+                problems.push(format!(
+                    "setting {:?} must be a list of regular expressions",
+                    k
+                ));
+                continue;
+            };
+            let mut compiled = Vec::new();
+            for pattern in patterns {
+                match Regex::new(pattern) {
+                    Ok(regex) => compiled.push(regex),
+                    Err(e) => problems.push(format!(
+                        "pattern {:?} in {:?} is invalid: {}",
+                        pattern, k, e
+                    )),
+                }
+            }
+            nbsp_patterns = Some(compiled);
+            continue;
+        }
+        if k == "preformatted_styles" {
+            let Some(styles) = v.as_array().and_then(|styles| {
+                styles
+                    .iter()
+                    .map(|style| style.as_str().map(str::to_string))
+                    .collect::<Option<Vec<_>>>()
+            }) else {
+                problems.push(format!("setting {:?} must be a list of style ids", k));
+                continue;
+            };
+            preformatted_styles = Some(styles);
+            continue;
+        }
+        if k == "collapse_spaces" {
+            match v {
+                serde_json::Value::Bool(false) => collapse_spaces = Some(SpaceCollapse::Off),
+                serde_json::Value::Bool(true) => collapse_spaces = Some(SpaceCollapse::Spaces),
+                serde_json::Value::String(s) if s == "whitespace" => {
+                    collapse_spaces = Some(SpaceCollapse::Whitespace)
+                }
+                _ => problems.push(format!(
+                    "setting {:?} must be true, false or \"whitespace\"",
+                    k
+                )),
+            }
+            continue;
+        }
+        let (start, end) = match parse_config_key(&k) {
+            Ok(bounds) => bounds,
+            Err(problem) => {
+                problems.push(problem);
+                continue;
+            }
+        };
+        let in_range = start != end || k.contains("..");
+        // The same character or range again replaces the earlier entry
+        if entries
+            .iter()
+            .any(|(_, s, e, r, _)| (*s, *e, *r) == (start, end, in_range))
+        {
+            warnings.push(format!("key {:?} appears more than once", k));
+            entries.retain(|(_, s, e, r, _)| (*s, *e, *r) != (start, end, in_range));
+            ranges.retain(|(_, s, e)| (*s, *e) != (start, end));
+        }
+        if in_range {
+            if let Some((other, _, _)) = ranges
+                .iter()
+                .find(|(_, other_start, other_end)| start <= *other_end && *other_start <= end)
+            {
+                problems.push(format!("range {:?} overlaps range {:?}", k, other));
+                continue;
+            }
+        }
+
+        let entry = if v.is_object() {
+            match rule_entry(&k, &v, in_range) {
+                Ok(entry) => entry,
+                Err(problem) => {
+                    problems.push(problem);
+                    continue;
+                }
+            }
+        } else if v.as_str() == Some("keep") {
+            ConfigEntry::Keep { in_range }
+        } else if matches!(v.as_str(), Some("flag" | "report")) {
+            ConfigEntry::Report {
+                in_range,
+                name: None,
+            }
+        } else {
+            let Some(entry) = v
+                .as_array()
+                .filter(|entry| entry.iter().all(|v| v.is_string()))
+            else {
+                problems.push(format!(
+                    "entry {:?} must be a list of strings [NAME, replacement], \"keep\", \"flag\" or an object with an \"action\"",
+                    k
+                ));
+                continue;
+            };
+            let description = entry
+                .first()
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+                .unwrap_or_default();
+            // Without a replacement the character is deleted
+            let replacement = entry
+                .get(1)
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string();
+            ConfigEntry::Remove(description, replacement)
+        };
+        if in_range {
+            ranges.push((k.clone(), start, end));
+        }
+        entries.push((k, start, end, in_range, entry));
+    }
+
+    // Ranges go first so the explicit entries merged after them take precedence
+    entries.sort_by_key(|(_, _, _, in_range, _)| !in_range);
+    let mut expanded = Vec::new();
+    let mut keys = HashMap::new();
+    for (key, start, end, _, entry) in entries {
+        for c in start..=end {
+            expanded.push((c, entry.clone()));
+            keys.insert(c, key.clone());
+        }
+    }
+    ConfigFile {
+        entries: expanded,
+        keys,
+        problems,
+        warnings,
+        collapse_spaces,
+        preformatted_styles,
+        nbsp,
+        nbsp_patterns,
+        profiles: profiles.unwrap_or_default(),
+        description,
+        typography: typography.unwrap_or(false),
+        homoglyphs: homoglyphs.unwrap_or(false),
+    }
+}
+
+// A config that could not be used: a single problem follows the source on its line, several
+// are listed one per line so all of them are seen at once
+fn invalid_config(source: &str, problems: &[String]) -> String {
+    match problems {
+        [problem] => format!("{}: {}", source, problem),
+        _ => format!(
+            "{} has {} problems:\n  {}",
+            source,
+            problems.len(),
+            problems.join("\n  ")
+        ),
+    }
+}
+
+// The config files may have // and /* */ comments and a comma after the last entry of an object
+// or a list, as in JSONC. They are blanked out with spaces rather than removed, so the line and
+// column serde_json reports for an error are those of the file as written.
+fn strip_jsonc(json: &str) -> String {
+    let mut bytes = json.as_bytes().to_vec();
+    let blank = |bytes: &mut [u8]| {
+        for byte in bytes.iter_mut().filter(|byte| **byte != b'\n') {
+            *byte = b' ';
+        }
+    };
+    // The comma after the last value, until another value follows it, and the last byte that
+    // was not whitespace or a comment, so a comma without a value before it is left as an error
+    let mut comma = None;
+    let mut previous = b' ';
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        match byte {
+            b'"' => {
+                comma = None;
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                let end = bytes[i..]
+                    .iter()
+                    .position(|&byte| byte == b'\n')
+                    .map_or(bytes.len(), |n| i + n);
+                blank(&mut bytes[i..end]);
+                i = end;
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = bytes[i + 2..]
+                    .windows(2)
+                    .position(|pair| pair == b"*/")
+                    .map_or(bytes.len(), |n| i + 2 + n + 2);
+                blank(&mut bytes[i..end]);
+                i = end;
+                continue;
+            }
+            b',' => comma = (!matches!(previous, b'[' | b'{' | b',')).then_some(i),
+            b'}' | b']' => {
+                if let Some(comma) = comma.take() {
+                    bytes[comma] = b' ';
+                }
+            }
+            byte if byte.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            _ => comma = None,
+        }
+        previous = byte;
+        i += 1;
+    }
+    // Comments are blanked whole, so no character is cut in half
+    String::from_utf8(bytes).expect("Blanked comments keep the text UTF-8")
+}
+
+// Returns the first and last character of a config key; both are the same for a single character
+fn parse_config_key(key: &str) -> Result<(char, char), String> {
+    let mut key_chars = key.chars();
+    if let (Some(c), None) = (key_chars.next(), key_chars.next()) {
+        return Ok((c, c));
+    }
+
+    let code_point = |s: &str| {
+        s.strip_prefix("U+")
+            .filter(|hex| !hex.is_empty() && hex.len() <= 6)
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+    };
+    let (start, end) = match key.split_once("..") {
+        Some((start, end)) => (code_point(start), code_point(end)),
+        None => (code_point(key), code_point(key)),
+    };
+    let (Some(start), Some(end)) = (start, end) else {
+        return Err(format!(
+            "key {:?} must be a single character, U+XXXX or U+XXXX..U+YYYY",
+            key
+        ));
+    };
+    if end < start {
+        return Err(format!("range {:?} ends before it starts", key));
+    }
+    if start <= 0xDFFF && 0xD800 <= end {
+        return Err(format!(
+            "key {:?} includes surrogate code points (U+D800..U+DFFF)",
+            key
+        ));
+    }
+    match (char::from_u32(start), char::from_u32(end)) {
+        (Some(start), Some(end)) => Ok((start, end)),
+        _ => Err(format!("key {:?} is beyond U+10FFFF", key)),
+    }
+}
+
+// Directional formatting characters, which mixed right-to-left documents rely on for ordering
+const BIDI_CONTROLS: [char; 12] = [
+    '\u{061C}', '\u{200E}', '\u{200F}', '\u{202A}', '\u{202B}', '\u{202C}', '\u{202D}', '\u{202E}',
+    '\u{2066}', '\u{2067}', '\u{2068}', '\u{2069}',
+];
+
+const CATEGORY_NAMES: [(&str, GeneralCategory); 30] = [
+    ("Lu", GeneralCategory::UppercaseLetter),
+    ("Ll", GeneralCategory::LowercaseLetter),
+    ("Lt", GeneralCategory::TitlecaseLetter),
+    ("Lm", GeneralCategory::ModifierLetter),
+    ("Lo", GeneralCategory::OtherLetter),
+    ("Mn", GeneralCategory::NonspacingMark),
+    ("Mc", GeneralCategory::SpacingMark),
+    ("Me", GeneralCategory::EnclosingMark),
+    ("Nd", GeneralCategory::DecimalNumber),
+    ("Nl", GeneralCategory::LetterNumber),
+    ("No", GeneralCategory::OtherNumber),
+    ("Pc", GeneralCategory::ConnectorPunctuation),
+    ("Pd", GeneralCategory::DashPunctuation),
+    ("Ps", GeneralCategory::OpenPunctuation),
+    ("Pe", GeneralCategory::ClosePunctuation),
+    ("Pi", GeneralCategory::InitialPunctuation),
+    ("Pf", GeneralCategory::FinalPunctuation),
+    ("Po", GeneralCategory::OtherPunctuation),
+    ("Sm", GeneralCategory::MathSymbol),
+    ("Sc", GeneralCategory::CurrencySymbol),
+    ("Sk", GeneralCategory::ModifierSymbol),
+    ("So", GeneralCategory::OtherSymbol),
+    ("Zs", GeneralCategory::SpaceSeparator),
+    ("Zl", GeneralCategory::LineSeparator),
+    ("Zp", GeneralCategory::ParagraphSeparator),
+    ("Cc", GeneralCategory::Control),
+    ("Cf", GeneralCategory::Format),
+    ("Cs", GeneralCategory::Surrogate),
+    ("Co", GeneralCategory::PrivateUse),
+    ("Cn", GeneralCategory::Unassigned),
+];
+
+/// Parses the two-letter general category abbreviations accepted by --categories
+pub fn parse_category(name: &str) -> Result<GeneralCategory, String> {
+    CATEGORY_NAMES
+        .iter()
+        .find(|(abbreviation, _)| *abbreviation == name)
+        .map(|(_, category)| *category)
+        .ok_or_else(|| format!("unknown Unicode general category '{}'", name))
+}
+
+/// The two-letter abbreviation of a general category, as --categories takes it, e.g. "Cf"
+pub fn category_name(category: GeneralCategory) -> &'static str {
+    CATEGORY_NAMES
+        .iter()
+        .find(|(_, c)| *c == category)
+        .map(|(abbreviation, _)| *abbreviation)
+        .unwrap()
+}
+
+/// Parses a single character or U+XXXX, like the keys of the config
+pub fn parse_character(text: &str) -> Result<char, String> {
+    match parse_config_key(text) {
+        Ok((c, end)) if c == end && !text.contains("..") => Ok(c),
+        _ => Err(format!(
+            "'{}' is neither a single character nor U+XXXX",
+            text
+        )),
+    }
+}
+
+// The abbreviations Unicode gives the invisible characters and spaces in NameAliases.txt, for the
+// tokens of Config::visualize
+const ABBREVIATIONS: [(char, &str); 39] = [
+    ('\u{0009}', "TAB"),
+    ('\u{000A}', "LF"),
+    ('\u{000D}', "CR"),
+    ('\u{00A0}', "NBSP"),
+    ('\u{00AD}', "SHY"),
+    ('\u{034F}', "CGJ"),
+    ('\u{061C}', "ALM"),
+    ('\u{180E}', "MVS"),
+    ('\u{2000}', "NQSP"),
+    ('\u{2001}', "MQSP"),
+    ('\u{2002}', "ENSP"),
+    ('\u{2003}', "EMSP"),
+    ('\u{2004}', "3/MSP"),
+    ('\u{2005}', "4/MSP"),
+    ('\u{2006}', "6/MSP"),
+    ('\u{2007}', "FSP"),
+    ('\u{2008}', "PSP"),
+    ('\u{2009}', "THSP"),
+    ('\u{200A}', "HSP"),
+    ('\u{200B}', "ZWSP"),
+    ('\u{200C}', "ZWNJ"),
+    ('\u{200D}', "ZWJ"),
+    ('\u{200E}', "LRM"),
+    ('\u{200F}', "RLM"),
+    ('\u{202A}', "LRE"),
+    ('\u{202B}', "RLE"),
+    ('\u{202C}', "PDF"),
+    ('\u{202D}', "LRO"),
+    ('\u{202E}', "RLO"),
+    ('\u{202F}', "NNBSP"),
+    ('\u{205F}', "MMSP"),
+    ('\u{2060}', "WJ"),
+    ('\u{2066}', "LRI"),
+    ('\u{2067}', "RLI"),
+    ('\u{2068}', "FSI"),
+    ('\u{2069}', "PDI"),
+    ('\u{3000}', "IDSP"),
+    ('\u{FE0F}', "VS16"),
+    ('\u{FEFF}', "ZWNBSP"),
+];
+
+fn abbreviation(c: char) -> Option<&'static str> {
+    ABBREVIATIONS
+        .iter()
+        .find(|(listed, _)| *listed == c)
+        .map(|(_, abbreviation)| *abbreviation)
+}
+
+/// The name of a character the config does not name, e.g. a suspicious one: its Unicode name,
+/// e.g. "TAG LATIN SMALL LETTER A", or "<unassigned>" and "<private use>" for code points
+/// without one. Only the reports look names up, so cleaning never pays for the table.
+pub fn unlisted_name(c: char) -> String {
+    match c.general_category() {
+        GeneralCategory::Unassigned => "<unassigned>".to_string(),
+        GeneralCategory::PrivateUse => "<private use>".to_string(),
+        _ => unicode_name(c).unwrap_or_else(|| kind_name(c)),
+    }
+}
+
+#[cfg(feature = "names")]
+fn unicode_name(c: char) -> Option<String> {
+    unicode_names2::name(c).map(|name| name.to_string())
+}
+
+// A build without the "names" feature has no table
+#[cfg(not(feature = "names"))]
+fn unicode_name(_: char) -> Option<String> {
+    None
+}
+
+// What kind of character it is, for those without a Unicode name like the controls, or all of
+// them without the "names" feature, e.g. "TAG 'a'" or "FORMAT CHARACTER (category Cf)"
+fn kind_name(c: char) -> String {
+    let category = c.general_category();
+    let kind = match (c as u32, category) {
+        (0xE0020..=0xE007E, _) => {
+            return format!("TAG '{}'", char::from((c as u32 - 0xE0000) as u8));
+        }
+        (_, GeneralCategory::Format) => "FORMAT CHARACTER",
+        (_, GeneralCategory::Control) => "CONTROL CHARACTER",
+        _ => "CHARACTER",
+    };
+    format!("{} (category {})", kind, category_name(category))
+}
+
+// e.g. %APPDATA%\docx-cleaner\config.json on Windows, ~/.config/docx-cleaner/config.json on Linux,
+// or config.toml in the same place when there is no config.json
+fn discover_user_config() -> Option<PathBuf> {
+    let dir = dirs::config_dir()?.join("docx-cleaner");
+    ["config.json", "config.toml"]
+        .into_iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists())
+}
+
+// The homoglyph table next to the user config, e.g. ~/.config/docx-cleaner/homoglyphs.json
+fn discover_user_homoglyphs() -> Option<PathBuf> {
+    dirs::config_dir()
+        .map(|dir| dir.join("docx-cleaner").join("homoglyphs.json"))
+        .filter(|path| path.exists())
+}
+
+fn escape_json(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            _ => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    escaped.push_str(&format!("\\u{:04X}", unit));
+                }
+            }
+        }
+    }
+    escaped
+}
+
+/// Failures while cleaning a package
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("The selected file is not a valid .docx document ({0})")]
+    InvalidDocx(String),
+    #[error("The file is empty; it may not have finished downloading or copying")]
+    Empty,
+    #[error("The file is incomplete, e.g. from an interrupted download or copy; get a fresh copy and try again")]
+    Truncated,
+    #[error(
+        "This is a legacy .doc file (Office 97-2003); open it in Word and save it as .docx first"
+    )]
+    LegacyFormat,
+    #[error("This document is password-protected; remove the password in Word and try again")]
+    Encrypted,
+    #[error("The input is not UTF-8 text ({0}); save it as UTF-8 and try again")]
+    InvalidText(std::str::Utf8Error),
+    #[error("The document part {0} is damaged and could not be cleaned ({1})")]
+    InvalidPart(String, quick_xml::Error),
+    #[error("The cleaned document could not be assembled: {0}")]
+    Pack(zip::result::ZipError),
+    #[error("The file could not be read: {0}")]
+    Read(std::io::Error),
+}
+
+/// What a cleaning pass found, so callers can render their own statistics
+#[derive(Clone, Debug, Default)]
+pub struct CleanReport {
+    /// Removed or replaced occurrences per character
+    pub chars: HashMap<char, usize>,
+    /// Occurrences of characters that were found but left in the text ("flag", --keep-bidi)
+    pub kept: HashMap<char, usize>,
+    /// Occurrences of characters left in the text because of Config::decline
+    pub declined: HashMap<char, usize>,
+    /// Occurrences of unlisted characters that look like they should not be in the text, see
+    /// is_suspicious; they are left in it
+    pub suspicious: HashMap<char, usize>,
+    /// The context of the first occurrence of each suspicious character, written like the
+    /// context of a Replacement
+    pub suspicious_contexts: HashMap<char, String>,
+    /// Runs of multiple spaces that were collapsed to one
+    pub collapsed_stretches: usize,
+    /// Spaces removed by collapsing them
+    pub collapsed_spaces: usize,
+    /// Spaces next to line breaks removed by Config::trim_around_breaks
+    pub break_spaces: usize,
+    /// Runs (or other texts) changed by Config::normalize
+    pub normalized_runs: usize,
+    /// No-break spaces left in the text by NbspMode::Patterns; the replaced ones are counted in
+    /// chars under NO_BREAK_SPACE
+    pub nbsp_kept: usize,
+    /// Lookalike letters replaced with Latin ones by Config::homoglyphs, per lookalike
+    pub homoglyphs: HashMap<char, usize>,
+    /// Runs formatted as hidden text found with Config::hidden_text, and the characters of text
+    /// in them
+    pub hidden_runs: usize,
+    pub hidden_chars: usize,
+    /// The hidden runs that were removed, or would have been when only checking
+    pub hidden_removed: usize,
+    /// The hidden runs left in the document because they are part of a field
+    pub hidden_in_fields: usize,
+    /// Runs whose text color is that of the background, or whose text is tiny
+    pub invisible_runs: Vec<InvisibleRun>,
+    /// The document properties cleared by Config::scrub_metadata with the values they had,
+    /// e.g. ("dc:creator", "Jane Doe"); a removed thumbnail is listed as ("thumbnail", its path)
+    pub scrubbed: Vec<(String, String)>,
+    /// The w:rsid* attributes, and the sessions listed in the settings, that
+    /// Config::scrub_metadata removed
+    pub rsids_removed: usize,
+    /// The runs and paragraphs removed by Config::prune_empty
+    pub pruned_runs: usize,
+    pub pruned_paragraphs: usize,
+    /// The runs that Config::merge_runs merged into the run before them
+    pub merged_runs: usize,
+    /// Runs with text whose color, or the color behind it, comes from the theme, so that they
+    /// could not be checked for InvisibleFormatting::SameColor
+    pub theme_colored_runs: usize,
+    /// Replacements per document part, keyed by the names in PARTS
+    pub parts: HashMap<&'static str, usize>,
+    /// The occurrences in chars split up by the part they were found in, keyed like parts
+    pub part_chars: HashMap<&'static str, HashMap<char, usize>>,
+    /// Replacements per slide of a presentation, counting its notes, keyed by the slide number
+    pub slides: BTreeMap<usize, usize>,
+    /// Replacements per sheet of a workbook by sheet name, in the order of the workbook. A shared
+    /// string counts for every cell that shows it.
+    pub sheets: Vec<(String, usize)>,
+    /// Every replacement in document order, only listed with Config::record_locations,
+    /// Config::annotate or Config::highlight
+    pub replacements: Vec<Replacement>,
+}
+
+/// A replaced character and where it was found
+#[derive(Clone, Debug)]
+pub struct Replacement {
+    /// The part of the package, e.g. word/header2.xml; empty for a text file
+    pub path: String,
+    /// The kind of part, one of PARTS
+    pub part: &'static str,
+    /// The paragraph in the part and the run in the paragraph, both counted from 1; None for
+    /// the document properties. For a text file the paragraph is the line.
+    pub paragraph: Option<usize>,
+    pub run: Option<usize>,
+    pub character: char,
+    /// Up to CONTEXT_CHARS characters of the original text before and after the character,
+    /// with any invisible characters in them written as ⟨U+XXXX⟩
+    pub before: String,
+    pub after: String,
+    /// The start of the original paragraph, up to SNIPPET_CHARS characters written like the
+    /// context, to find it by in the document. Slides, spreadsheets and OpenDocument texts give
+    /// the text of the run instead, and the document properties that of the property.
+    pub snippet: String,
+    /// The characters of text in the part before this one
+    pub offset: usize,
+    /// How far through the text of its part the character is, from 0 to 1. Page numbers depend
+    /// on the layout, so this is what tells roughly where in the document it is.
+    pub position: f64,
+}
+
+/// How many characters of context a Replacement has on either side
+pub const CONTEXT_CHARS: usize = 20;
+
+/// How many characters of its paragraph a Replacement has as the snippet
+pub const SNIPPET_CHARS: usize = 60;
+
+impl Replacement {
+    /// The context with the character itself in the middle, e.g. "fo⟨U+200B⟩x jumps"
+    pub fn context(&self) -> String {
+        format!(
+            "{}{}{}",
+            self.before,
+            render_invisible(self.character),
+            self.after
+        )
+    }
+}
+
+impl CleanReport {
+    pub fn total_removed(&self) -> usize {
+        self.chars.values().sum()
+    }
+
+    pub fn total_kept(&self) -> usize {
+        self.kept.values().sum()
+    }
+
+    /// Whether cleaning changed the text, or would have when only checking
+    pub fn is_modified(&self) -> bool {
+        self.total_removed() > 0
+            || self.collapsed_spaces > 0
+            || self.break_spaces > 0
+            || self.normalized_runs > 0
+            || !self.homoglyphs.is_empty()
+            || self.hidden_removed > 0
+            || self.invisible_runs.iter().any(|run| run.removed)
+            || !self.scrubbed.is_empty()
+            || self.rsids_removed > 0
+            || self.merged_runs > 0
+    }
+
+    // A removed or replaced occurrence of c in the given part
+    fn count(&mut self, c: char, part: &'static str) {
+        *self.chars.entry(c).or_insert(0) += 1;
+        *self.parts.entry(part).or_insert(0) += 1;
+        *self
+            .part_chars
+            .entry(part)
+            .or_default()
+            .entry(c)
+            .or_insert(0) += 1;
+    }
+
+    /// Adds the counts of another report, e.g. to sum up a batch of documents
+    pub fn merge(&mut self, other: CleanReport) {
+        for (c, count) in other.chars {
+            *self.chars.entry(c).or_insert(0) += count;
+        }
+        for (c, count) in other.kept {
+            *self.kept.entry(c).or_insert(0) += count;
+        }
+        for (c, count) in other.declined {
+            *self.declined.entry(c).or_insert(0) += count;
+        }
+        for (c, count) in other.suspicious {
+            *self.suspicious.entry(c).or_insert(0) += count;
+        }
+        for (c, context) in other.suspicious_contexts {
+            self.suspicious_contexts.entry(c).or_insert(context);
+        }
+        self.collapsed_stretches += other.collapsed_stretches;
+        self.collapsed_spaces += other.collapsed_spaces;
+        self.break_spaces += other.break_spaces;
+        self.normalized_runs += other.normalized_runs;
+        self.nbsp_kept += other.nbsp_kept;
+        self.hidden_runs += other.hidden_runs;
+        self.hidden_chars += other.hidden_chars;
+        self.hidden_removed += other.hidden_removed;
+        self.hidden_in_fields += other.hidden_in_fields;
+        self.invisible_runs.extend(other.invisible_runs);
+        self.theme_colored_runs += other.theme_colored_runs;
+        self.scrubbed.extend(other.scrubbed);
+        self.rsids_removed += other.rsids_removed;
+        self.pruned_runs += other.pruned_runs;
+        self.pruned_paragraphs += other.pruned_paragraphs;
+        self.merged_runs += other.merged_runs;
+        for (c, count) in other.homoglyphs {
+            *self.homoglyphs.entry(c).or_insert(0) += count;
+        }
+        for (part, count) in other.parts {
+            *self.parts.entry(part).or_insert(0) += count;
+        }
+        for (part, chars) in other.part_chars {
+            let counts = self.part_chars.entry(part).or_default();
+            for (c, count) in chars {
+                *counts.entry(c).or_insert(0) += count;
+            }
+        }
+        for (slide, count) in other.slides {
+            *self.slides.entry(slide).or_insert(0) += count;
+        }
+        for (sheet, count) in other.sheets {
+            match self.sheets.iter_mut().find(|(name, _)| *name == sheet) {
+                Some((_, total)) => *total += count,
+                None => self.sheets.push((sheet, count)),
+            }
+        }
+        self.replacements.extend(other.replacements);
+    }
+}
+
+/// Cleans documents with a fixed config. Each call gets its own report, so a cleaner can be
+/// shared between documents.
+pub struct Cleaner {
+    config: Config,
+    rules: Rules,
+}
+
+/// A cleaned package, ready to be written out
+pub struct CleanedDocument<'a> {
+    pub report: CleanReport,
+    original: Source<'a>,
+    // The cleaned XML by part path; parts without changes are not listed
+    parts: HashMap<String, Vec<u8>>,
+    // The parts left out of the cleaned package, e.g. a thumbnail scrubbed by
+    // Config::scrub_metadata
+    removed: Vec<String>,
+}
+
+impl Cleaner {
+    pub fn new(config: Config) -> Self {
+        let rules = Rules::new(&config);
+        Cleaner { config, rules }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The text with the listed characters and anything else invisible written as ⟨U+XXXX⟩,
+    /// like the context of a Replacement
+    pub fn render(&self, text: &str) -> String {
+        render_text(&self.rules, text.chars())
+    }
+
+    /// Cleans the body, headers, footers, comments and text boxes of a document model.
+    /// docx-rs does not model footnotes, endnotes or the document properties; use
+    /// clean_package to clean those as well.
+    pub fn clean_docx(&self, docx: &mut Docx) -> CleanReport {
+        let mut pass = Pass::new(self);
+        pass.clean_document(docx);
+        pass.report
+    }
+
+    /// Cleans every part of a .docx package read from `buf`. The text is cleaned in the XML of
+    /// the parts themselves, so everything else in the package stays exactly as it was.
+    pub fn clean_package<'a>(&self, buf: &'a [u8]) -> Result<CleanedDocument<'a>, Error> {
+        self.clean_source(Source::Bytes(buf))
+    }
+
+    /// Like clean_package for the package in the file at `path`, which is read a part at a time
+    /// as it is needed instead of as a whole, e.g. for a document with large videos. Only the
+    /// XML of the text parts is held in memory; CleanedDocument::write_to copies the rest over
+    /// from the file.
+    pub fn clean_package_file<'a>(&self, path: &'a Path) -> Result<CleanedDocument<'a>, Error> {
+        self.clean_source(Source::File(path))
+    }
+
+    fn clean_source<'a>(&self, source: Source<'a>) -> Result<CleanedDocument<'a>, Error> {
+        let mut pass = Pass::new(self);
+        pass.annotate = self.config.annotate.as_ref();
+        if self.config.record_locations
+            || pass.annotate.is_some()
+            || self.config.highlight.is_some()
+        {
+            pass.positions = Some(Vec::new());
+        }
+        let mut parts = pass.visit_package(source)?;
+        let removed = match self.config.scrub_metadata {
+            Some(scrub) => pass.scrub_metadata(source, &mut parts, scrub)?,
+            None => Vec::new(),
+        };
+        Ok(CleanedDocument {
+            report: pass.report,
+            original: source,
+            parts,
+            removed,
+        })
+    }
+
+    /// Adds the comments of Config::annotate where clean_package would replace characters,
+    /// leaving the text itself as it is, e.g. for --annotate --check
+    pub fn annotate_package<'a>(&self, buf: &'a [u8]) -> Result<CleanedDocument<'a>, Error> {
+        let mut pass = Pass::new(self);
+        pass.annotate = self.config.annotate.as_ref();
+        pass.positions = Some(Vec::new());
+        pass.rewrite = false;
+        let parts = pass.visit_package(Source::Bytes(buf))?;
+        Ok(CleanedDocument {
+            report: pass.report,
+            original: Source::Bytes(buf),
+            parts,
+            removed: Vec::new(),
+        })
+    }
+
+    /// Cleans a plain text or Markdown file read from `buf` line by line, with the characters
+    /// and space collapsing used for documents. Spaces are only collapsed between other
+    /// characters, so indentation is kept, and not at all in Markdown code fences. A byte order
+    /// mark is removed and counted as U+FEFF; the line endings stay as they are.
+    pub fn clean_text(&self, buf: &[u8], markdown: bool) -> Result<CleanedText, Error> {
+        let text = std::str::from_utf8(buf).map_err(Error::InvalidText)?;
+        let mut pass = Pass::new(self);
+        if self.config.record_locations {
+            pass.positions = Some(Vec::new());
+        }
+        let mut cleaned = String::new();
+        pass.clean_part("text", |pass| {
+            cleaned = pass.clean_text_lines(text, markdown)
+        });
+        log::info!("text: {} replaced", pass.report.total_removed());
+        Ok(CleanedText {
+            report: pass.report,
+            text: cleaned,
+        })
+    }
+
+    /// Like scan_package, for the text files of clean_text
+    pub fn scan_text(&self, buf: &[u8], markdown: bool) -> Result<CleanReport, Error> {
+        let text = std::str::from_utf8(buf).map_err(Error::InvalidText)?;
+        let mut pass = Pass::new(self);
+        pass.positions = Some(Vec::new());
+        pass.clean_part("text", |pass| {
+            pass.clean_text_lines(text, markdown);
+        });
+        Ok(pass.report)
+    }
+
+    /// Finds what clean_package would replace and where, listing every replacement in the
+    /// report, without putting together any cleaned XML
+    pub fn scan_package(&self, buf: &[u8]) -> Result<CleanReport, Error> {
+        self.scan_source(Source::Bytes(buf))
+    }
+
+    /// Like scan_package for the package in the file at `path`, read a part at a time like
+    /// clean_package_file does
+    pub fn scan_package_file(&self, path: &Path) -> Result<CleanReport, Error> {
+        self.scan_source(Source::File(path))
+    }
+
+    fn scan_source(&self, source: Source) -> Result<CleanReport, Error> {
+        let mut pass = Pass::new(self);
+        pass.positions = Some(Vec::new());
+        pass.rewrite = false;
+        pass.visit_package(source)?;
+        Ok(pass.report)
+    }
+}
+
+/// Where the cleaned copy of a document goes unless told otherwise: "<stem>_cleaned.<extension>"
+/// next to it, keeping the case of the extension. Files without one, e.g. "Thesis Final" as some
+/// learning platforms download it, or ending in a dot are given ".docx". None for a path without
+/// a file name, like a filesystem root.
+pub fn cleaned_copy_path(input_path: &Path) -> Option<PathBuf> {
+    let stem = input_path.file_stem()?;
+    let extension = input_path
+        .extension()
+        .filter(|extension| !extension.is_empty())
+        .unwrap_or(OsStr::new("docx"));
+    let mut file_name = stem.to_os_string();
+    file_name.push("_cleaned.");
+    file_name.push(extension);
+    Some(input_path.with_file_name(file_name))
+}
+
+/// The first of "<stem>_2.<extension>", "<stem>_3.<extension>" and so on next to `path` that does
+/// not exist yet, for a cleaned copy that should not replace an earlier one
+pub fn numbered_copy_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default();
+    (2..)
+        .map(|number| {
+            let mut file_name = stem.to_os_string();
+            file_name.push(format!("_{}", number));
+            if let Some(extension) = path.extension() {
+                file_name.push(".");
+                file_name.push(extension);
+            }
+            path.with_file_name(file_name)
+        })
+        .find(|numbered| !numbered.exists())
+        .unwrap()
+}
+
+/// A cleaned text file, ready to be written out
+pub struct CleanedText {
+    pub report: CleanReport,
+    text: String,
+}
+
+impl CleanedText {
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.text.into_bytes()
+    }
+}
+
+/// The paragraphs of a part changed by cleaning, before and after, as plain text; the strings of
+/// a spreadsheet and the values of the document properties count as paragraphs
+#[derive(Clone, Debug)]
+pub struct ChangedPart {
+    /// The part of the package, e.g. word/document.xml
+    pub path: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+impl CleanedDocument<'_> {
+    /// The text of each part that cleaning changed, in the order the parts are cleaned, e.g. to
+    /// show a diff. Only the text is compared, so a part whose markup alone changed, as with
+    /// Config::merge_runs, is left out.
+    pub fn changed_parts(&self) -> Result<Vec<ChangedPart>, Error> {
+        let mut archive = self.original.open()?;
+        let text_parts = find_text_parts(&mut archive)?.parts;
+        let mut changed = Vec::new();
+        for (path, _, text) in text_parts {
+            let Some(cleaned) = self.parts.get(&path) else {
+                continue;
+            };
+            let original = read_zip_entry(&mut archive, &path).unwrap_or_default();
+            let paragraphs = |xml: &[u8]| {
+                paragraph_texts(xml, text).map_err(|e| Error::InvalidPart(path.clone(), e))
+            };
+            let before = paragraphs(&original)?;
+            let after = paragraphs(cleaned)?;
+            if before != after {
+                changed.push(ChangedPart {
+                    path,
+                    before,
+                    after,
+                });
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Packs the cleaned document into the bytes of a .docx file, see write_to
+    pub fn into_bytes(self) -> Result<Vec<u8>, Error> {
+        if let (Source::Bytes(buf), true) = (
+            self.original,
+            self.parts.is_empty() && self.removed.is_empty(),
+        ) {
+            return Ok(buf.to_vec());
+        }
+        self.write_to(Cursor::new(Vec::new()))
+            .map(Cursor::into_inner)
+    }
+
+    /// Packs the cleaned document into `writer`, e.g. a file, and hands it back. Only the
+    /// changed parts are written anew, in their place and with their time, every other entry is
+    /// copied over byte for byte. New parts, like the comments of Config::annotate, come last in
+    /// name order and are dated 1980-01-01, so the same input always packs into the same bytes.
+    pub fn write_to<W: Write + Seek>(mut self, mut writer: W) -> Result<W, Error> {
+        let io_error = |e: std::io::Error| Error::Pack(e.into());
+        if self.parts.is_empty() && self.removed.is_empty() {
+            match self.original {
+                Source::Bytes(buf) => writer.write_all(buf).map_err(io_error)?,
+                Source::File(path) => {
+                    let mut file = std::fs::File::open(path).map_err(Error::Read)?;
+                    std::io::copy(&mut file, &mut writer).map_err(io_error)?;
+                }
+            }
+            return Ok(writer);
+        }
+
+        let mut archive = self.original.open()?;
+        let mut writer = zip::ZipWriter::new(writer);
+        for i in 0..archive.len() {
+            let file = archive.by_index(i).map_err(Error::Pack)?;
+            if self.removed.iter().any(|path| path == file.name()) {
+                continue;
+            }
+            let Some(xml) = self.parts.remove(file.name()) else {
+                writer.raw_copy_file(file).map_err(Error::Pack)?;
+                continue;
+            };
+            let compression = match file.compression() {
+                zip::CompressionMethod::Stored => zip::CompressionMethod::Stored,
+                _ => zip::CompressionMethod::Deflated,
+            };
+            let options = zip::write::FileOptions::default()
+                .compression_method(compression)
+                .last_modified_time(file.last_modified());
+            writer
+                .start_file(file.name(), options)
+                .map_err(Error::Pack)?;
+            writer.write_all(&xml).map_err(|e| Error::Pack(e.into()))?;
+        }
+        let mut added: Vec<_> = self.parts.into_iter().collect();
+        added.sort();
+        for (name, xml) in added {
+            let options = zip::write::FileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated)
+                .last_modified_time(zip::DateTime::default());
+            writer.start_file(name, options).map_err(Error::Pack)?;
+            writer.write_all(&xml).map_err(|e| Error::Pack(e.into()))?;
+        }
+        writer.finish().map_err(Error::Pack)
+    }
+}
+
+// A single cleaning call: the config and rules it runs with and the report it fills in
+struct Pass<'a> {
+    config: &'a Config,
+    rules: &'a Rules,
+    report: CleanReport,
+    // The part the replacements are currently counted for, see clean_part
+    part: &'static str,
+    // With Config::record_locations: the replacements of the last replace_chars call, as
+    // (index of the character in the text, character)
+    positions: Option<Vec<(usize, char)>>,
+    // With Config::record_locations: the characters of text in the current part before the
+    // text being located, see clean_part
+    part_offset: usize,
+    // Whether the cleaned XML is put together, or the replacements are only counted
+    rewrite: bool,
+    // With Config::annotate, unless scanning: who the comments are attributed to
+    annotate: Option<&'a Revision>,
+    // The comments added to the part being cleaned, when it is the main document part
+    comments: Option<Comments>,
+}
+
+/// Display order of the parts in the statistics
+pub const PARTS: [&str; 16] = [
+    "body", "header", "footer", "footnote", "endnote", "textbox", "comment", "field", "math",
+    "metadata", "styles", "slide", "notes", "strings", "sheet", "text",
+];
+
+// How the text of a part is found: in the paragraphs of a WordprocessingML part, in the a:t runs
+// of a slide or its notes, in the strings of a spreadsheet, in the paragraphs of an OpenDocument
+// part, or as the values of the listed elements in the document properties
+#[derive(Clone, Copy)]
+enum PartText {
+    Paragraphs,
+    Drawing,
+    Cells,
+    OpenDocument,
+    Elements(&'static [&'static str]),
+}
+
+// An OpenDocument text has no [Content_Types].xml; it starts with a mimetype entry instead and
+// keeps its text in content.xml, and the headers and footers of its page styles in styles.xml
+const OPEN_DOCUMENT_TEXT_MIMETYPE: &[u8] = b"application/vnd.oasis.opendocument.text";
+const OPEN_DOCUMENT_PARTS: [(&str, &str); 2] = [("content.xml", "body"), ("styles.xml", "styles")];
+
+const PRESENTATION_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.presentationml.presentation.main+xml";
+const WORKBOOK_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml";
+const NOTES_SLIDE_RELATIONSHIP: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/notesSlide";
+
+// The parts Config::scrub_metadata looks for
+const CORE_PROPERTIES_RELATIONSHIP: &str =
+    "http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties";
+const APP_PROPERTIES_RELATIONSHIP: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/extended-properties";
+const THUMBNAIL_RELATIONSHIP: &str =
+    "http://schemas.openxmlformats.org/package/2006/relationships/metadata/thumbnail";
+const MAIN_DOCUMENT_RELATIONSHIP: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument";
+const STYLES_RELATIONSHIP: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles";
+const SETTINGS_RELATIONSHIP: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/settings";
+const CONTENT_TYPES_PATH: &str = "[Content_Types].xml";
+const PACKAGE_RELS_PATH: &str = "_rels/.rels";
+
+// The comments part, and what Config::annotate needs to add one to a document without comments
+const COMMENTS_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.comments+xml";
+const COMMENTS_RELATIONSHIP: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/comments";
+const WORDPROCESSINGML_NAMESPACE: &str =
+    "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+const RELATIONSHIPS_NAMESPACE: &str =
+    "http://schemas.openxmlformats.org/package/2006/relationships";
+
+// The parts that are cleaned by content type, with the part their replacements count for;
+// every other part of the package is copied over unchanged, including the macros (vbaProject.bin)
+// of .docm and .dotm files. The main part has a content type of its own in each of .docx, .dotx,
+// .docm and .dotm.
+const TEXT_PARTS: [(&str, &str, PartText); 15] = [
+    (
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml",
+        "body",
+        PartText::Paragraphs,
+    ),
+    (
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.template.main+xml",
+        "body",
+        PartText::Paragraphs,
+    ),
+    (
+        "application/vnd.ms-word.document.macroEnabled.main+xml",
+        "body",
+        PartText::Paragraphs,
+    ),
+    (
+        "application/vnd.ms-word.template.macroEnabledTemplate.main+xml",
+        "body",
+        PartText::Paragraphs,
+    ),
+    (
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.header+xml",
+        "header",
+        PartText::Paragraphs,
+    ),
+    (
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.footer+xml",
+        "footer",
+        PartText::Paragraphs,
+    ),
+    (
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.footnotes+xml",
+        "footnote",
+        PartText::Paragraphs,
+    ),
+    (
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.endnotes+xml",
+        "endnote",
+        PartText::Paragraphs,
+    ),
+    (COMMENTS_CONTENT_TYPE, "comment", PartText::Paragraphs),
+    (
+        "application/vnd.openxmlformats-officedocument.presentationml.slide+xml",
+        "slide",
+        PartText::Drawing,
+    ),
+    (
+        "application/vnd.openxmlformats-officedocument.presentationml.notesSlide+xml",
+        "notes",
+        PartText::Drawing,
+    ),
+    (
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sharedStrings+xml",
+        "strings",
+        PartText::Cells,
+    ),
+    (
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml",
+        "sheet",
+        PartText::Cells,
+    ),
+    (
+        "application/vnd.openxmlformats-package.core-properties+xml",
+        "metadata",
+        PartText::Elements(&CORE_TEXT_ELEMENTS),
+    ),
+    (
+        "application/vnd.openxmlformats-officedocument.custom-properties+xml",
+        "metadata",
+        PartText::Elements(&CUSTOM_TEXT_ELEMENTS),
+    ),
+];
+
+// Only the free-text values of the document properties are cleaned
+const CORE_TEXT_ELEMENTS: [&str; 8] = [
+    "dc:title",
+    "dc:subject",
+    "dc:creator",
+    "cp:keywords",
+    "dc:description",
+    "cp:lastModifiedBy",
+    "cp:category",
+    "cp:contentStatus",
+];
+const CUSTOM_TEXT_ELEMENTS: [&str; 2] = ["vt:lpwstr", "vt:bstr"];
+
+// Run content that neither holds text nor separates the text around it, see clean_paragraph_xml
+const INERT_RUN_ELEMENTS: [&[u8]; 3] = [b"w:rPr", b"w:t", b"w:lastRenderedPageBreak"];
+
+// What a paragraph may hold besides runs to be pruned with Config::prune_empty
+const PRUNABLE_PARAGRAPH_ELEMENTS: [&[u8]; 3] = [b"w:pPr", b"w:r", b"w:proofErr"];
+
+// The run content that ends a line, see Config::trim_around_breaks
+const LINE_BREAKS: [&[u8]; 2] = [b"w:br", b"w:cr"];
+
+// Whether a line break follows the text ending at xml[from] with nothing shown in between, also
+// when it starts the next run
+fn followed_by_break(xml: &[u8], from: usize) -> bool {
+    let mut reader = Reader::from_reader(&xml[from..]);
+    loop {
+        match reader.read_event() {
+            // The reader starts inside the w:t, so it closes elements it has not seen open
+            Err(quick_xml::Error::IllFormed(IllFormedError::UnmatchedEndTag(name)))
+                if name == "w:t" || name == "w:r" => {}
+            Ok(Event::End(e)) if e.name().as_ref() == b"w:r" => {}
+            Ok(Event::Start(e)) if e.name().as_ref() == b"w:r" => {}
+            Ok(Event::Start(e)) if e.name().as_ref() == b"w:rPr" => {
+                if reader.read_to_end(e.name()).is_err() {
+                    return false;
+                }
+            }
+            Ok(Event::Empty(e)) if LINE_BREAKS.contains(&e.name().as_ref()) => return true,
+            Ok(Event::Empty(e)) if INERT_RUN_ELEMENTS.contains(&e.name().as_ref()) => {}
+            Ok(Event::Text(t)) if t.iter().all(u8::is_ascii_whitespace) => {}
+            _ => return false,
+        }
+    }
+}
+
+// The run content that stands for a hyphen character, cleaned like that character
+const HYPHEN_ELEMENTS: [(&[u8], char); 2] = [
+    (b"w:softHyphen", '\u{AD}'),
+    (b"w:noBreakHyphen", '\u{2011}'),
+];
+
+// What a hidden run may hold to be removed with HiddenText::Remove: text and the characters and
+// breaks that go with it, but no drawings, objects, text boxes or field characters
+const REMOVABLE_RUN_ELEMENTS: [&[u8]; 9] = [
+    b"w:rPr",
+    b"w:t",
+    b"w:tab",
+    b"w:br",
+    b"w:cr",
+    b"w:lastRenderedPageBreak",
+    b"w:noBreakHyphen",
+    b"w:softHyphen",
+    b"w:sym",
+];
+
+static SPACE_COLLAPSE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"[ ]{2,}").unwrap());
+static WHITESPACE_COLLAPSE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"[ \p{Zs}]{2,}").unwrap());
+
+impl<'a> Pass<'a> {
+    fn new(cleaner: &'a Cleaner) -> Self {
+        Pass {
+            config: &cleaner.config,
+            rules: &cleaner.rules,
+            report: CleanReport::default(),
+            part: "body",
+            positions: None,
+            part_offset: 0,
+            rewrite: true,
+            annotate: None,
+            comments: None,
+        }
+    }
+
+    // A pass with the same settings and an empty report, for cleaning on another thread
+    fn fork(&self) -> Self {
+        Pass {
+            config: self.config,
+            rules: self.rules,
+            report: CleanReport::default(),
+            part: self.part,
+            positions: self.positions.as_ref().map(|_| Vec::new()),
+            part_offset: 0,
+            rewrite: self.rewrite,
+            annotate: self.annotate,
+            comments: None,
+        }
+    }
+
+    // Cleans the items in parallel, each thread filling a forked report, and merges the reports
+    // into this one. Every item is cleaned on its own, so the order does not matter.
+    fn clean_parallel<T: Send>(
+        &mut self,
+        items: &mut [T],
+        clean: impl Fn(&mut Self, &mut T) + Sync,
+    ) {
+        let this = &*self;
+        let report = items
+            .par_iter_mut()
+            .fold(
+                || this.fork(),
+                |mut pass, item| {
+                    clean(&mut pass, item);
+                    pass
+                },
+            )
+            .map(|pass| pass.report)
+            .reduce(CleanReport::default, |mut report, other| {
+                report.merge(other);
+                report
+            });
+        self.report.merge(report);
+    }
+
+    // Like clean_parallel, for passes that return a result per item, in the order of the items
+    fn map_parallel<T: Sync, R: Send>(
+        &mut self,
+        items: &[T],
+        clean: impl Fn(&mut Self, &T) -> R + Sync,
+    ) -> Vec<R> {
+        let this = &*self;
+        let (reports, results): (Vec<_>, Vec<_>) = items
+            .par_iter()
+            .map(|item| {
+                let mut pass = this.fork();
+                let result = clean(&mut pass, item);
+                (pass.report, result)
+            })
+            .unzip();
+        for report in reports {
+            self.report.merge(report);
+        }
+        results
+    }
+
+    fn clean_document(&mut self, docx: &mut Docx) {
+        self.clean_part("body", |pass| {
+            pass.clean_parallel(&mut docx.document.children, |pass, child| match child {
+                DocumentChild::Paragraph(paragraph) => pass.clean_paragraph(paragraph),
+                DocumentChild::Table(table) => pass.clean_table(table),
+                DocumentChild::StructuredDataTag(sdt) => pass.clean_sdt(sdt),
+                DocumentChild::CommentStart(start) => pass.clean_comment(&mut start.comment),
+                _ => {}
+            });
+        });
+
+        let section = &mut docx.document.section_property;
+        let headers = [
+            &mut section.header,
+            &mut section.first_header,
+            &mut section.even_header,
+        ];
+        for header in headers.into_iter().flatten() {
+            self.clean_part("header", |pass| {
+                pass.clean_parallel(&mut header.children, |pass, child| match child {
+                    HeaderChild::Paragraph(paragraph) => pass.clean_paragraph(paragraph),
+                    HeaderChild::Table(table) => pass.clean_table(table),
+                    HeaderChild::StructuredDataTag(sdt) => pass.clean_sdt(sdt),
+                    _ => {}
+                });
+            });
+        }
+
+        let footers = [
+            &mut section.footer,
+            &mut section.first_footer,
+            &mut section.even_footer,
+        ];
+        for footer in footers.into_iter().flatten() {
+            self.clean_part("footer", |pass| {
+                pass.clean_parallel(&mut footer.children, |pass, child| match child {
+                    FooterChild::Paragraph(paragraph) => pass.clean_paragraph(paragraph),
+                    FooterChild::Table(table) => pass.clean_table(table),
+                    FooterChild::StructuredDataTag(sdt) => pass.clean_sdt(sdt),
+                    _ => {}
+                });
+            });
+        }
+    }
+
+    // Runs a cleaning pass and attributes the replacements it made to the given part. Parts can
+    // be nested (comments are anchored in the body), replacements then only count for the inner
+    // one. Once the length of the text of the part is known, the positions of the replacements
+    // located in it are set.
+    fn clean_part(&mut self, part: &'static str, clean: impl FnOnce(&mut Self)) {
+        let outer = std::mem::replace(&mut self.part, part);
+        let outer_offset = std::mem::take(&mut self.part_offset);
+        let first = self.report.replacements.len();
+        self.report.parts.entry(part).or_insert(0);
+        clean(self);
+        let length = self.part_offset.max(1) as f64;
+        for replacement in &mut self.report.replacements[first..] {
+            replacement.position = replacement.offset as f64 / length;
+        }
+        self.part = outer;
+        self.part_offset = outer_offset;
+    }
+
+    // Cells may contain further tables, so this recurses until only paragraphs are left
+    fn clean_table(&mut self, table: &mut Table) {
+        self.clean_parallel(&mut table.rows, |pass, TableChild::TableRow(row)| {
+            for TableRowChild::TableCell(cell) in &mut row.cells {
+                for content in &mut cell.children {
+                    match content {
+                        TableCellContent::Paragraph(paragraph) => pass.clean_paragraph(paragraph),
+                        TableCellContent::Table(nested) => pass.clean_table(nested),
+                        TableCellContent::StructuredDataTag(sdt) => pass.clean_sdt(sdt),
+                        _ => {}
+                    }
+                }
+            }
+        });
+    }
+
+    // Content controls hold runs (inline) or paragraphs and tables (block level); only the
+    // content is cleaned, the control properties (tag, alias, placeholder) are left alone
+    fn clean_sdt(&mut self, sdt: &mut StructuredDataTag) {
+        for child in &mut sdt.children {
+            match child {
+                StructuredDataTagChild::Run(run) => self.clean_run(run),
+                StructuredDataTagChild::Paragraph(paragraph) => self.clean_paragraph(paragraph),
+                StructuredDataTagChild::Table(table) => self.clean_table(table),
+                StructuredDataTagChild::StructuredDataTag(nested) => self.clean_sdt(nested),
+                _ => {}
+            }
+        }
+    }
+
+    fn clean_paragraph(&mut self, paragraph: &mut Paragraph) {
+        self.clean_paragraph_children(&mut paragraph.children);
+        let style = paragraph.property.style.as_ref().map(|style| &style.val);
+        if !style.is_some_and(|style| self.config.preformatted_styles.contains(style)) {
+            self.collapse_paragraph_spaces(paragraph);
+        }
+        if self.config.merge_runs {
+            self.merge_runs(&mut paragraph.children);
+        }
+    }
+
+    // With Config::merge_runs: appends the text of each run to the run before it when both hold
+    // nothing but text and their formatting is the same. Tabs, breaks, drawings, fields and
+    // anything between the runs keep them apart.
+    fn merge_runs(&mut self, children: &mut Vec<ParagraphChild>) {
+        let is_text = |run: &Run| {
+            run.children
+                .iter()
+                .all(|child| matches!(child, RunChild::Text(_)))
+        };
+        let mut merged: Vec<ParagraphChild> = Vec::with_capacity(children.len());
+        for mut child in children.drain(..) {
+            if let ParagraphChild::Hyperlink(hyperlink) = &mut child {
+                self.merge_runs(&mut hyperlink.children);
+            }
+            if let (Some(ParagraphChild::Run(previous)), ParagraphChild::Run(run)) =
+                (merged.last_mut(), &child)
+            {
+                if is_text(previous) && is_text(run) && previous.run_property == run.run_property {
+                    let text: String = previous
+                        .children
+                        .iter()
+                        .chain(&run.children)
+                        .filter_map(|child| match child {
+                            RunChild::Text(text) => Some(text.text.as_str()),
+                            _ => None,
+                        })
+                        .collect();
+                    previous.children = vec![RunChild::Text(Text::new(text))];
+                    self.report.merged_runs += 1;
+                    continue;
+                }
+            }
+            merged.push(child);
+        }
+        *children = merged;
+    }
+
+    // Collapses the spaces of each run of the paragraph, keeping the leading and trailing spaces
+    // of text marked xml:space="preserve". Word also often splits "word " and " word" into
+    // separate runs, so a double space can straddle a run boundary; the leading spaces of a run
+    // are dropped when the text before it already ends with a preserved space. Run formatting is
+    // left alone.
+    fn collapse_paragraph_spaces(&mut self, paragraph: &mut Paragraph) {
+        let mut contents = Vec::new();
+        collect_run_texts(&mut paragraph.children, &mut contents);
+        let breaks: Vec<bool> = contents
+            .iter()
+            .map(|content| matches!(content, RunContent::Break))
+            .collect();
+        let mut after_space = false;
+        let mut after_break = false;
+        for (i, content) in contents.into_iter().enumerate() {
+            // Tabs, breaks, drawings and the like separate the spaces around them
+            let RunContent::Text(text) = content else {
+                after_space = false;
+                after_break = breaks[i];
+                continue;
+            };
+            let before_break = breaks.get(i + 1) == Some(&true);
+            let collapsed =
+                self.collapse_run_spaces(&text.text, text.preserve_space, &mut after_space);
+            let trimmed = self.trim_around_breaks(collapsed, after_break, before_break);
+            if trimmed != text.text {
+                text.text = trimmed.into_owned();
+            }
+            after_break &= text.text.is_empty();
+        }
+    }
+
+    // With Config::trim_around_breaks: drops a single space from the start of a text that
+    // follows a line break, and from the end of one that a line break follows
+    fn trim_around_breaks<'t>(
+        &mut self,
+        text: Cow<'t, str>,
+        after_break: bool,
+        before_break: bool,
+    ) -> Cow<'t, str> {
+        if !self.config.trim_around_breaks {
+            return text;
+        }
+        let start = usize::from(after_break && text.starts_with(' '));
+        let end = text.len() - usize::from(before_break && text[start..].ends_with(' '));
+        if start == 0 && end == text.len() {
+            return text;
+        }
+        self.report.break_spaces += start + text.len() - end;
+        match text {
+            Cow::Borrowed(text) => Cow::Borrowed(&text[start..end]),
+            Cow::Owned(text) => Cow::Owned(text[start..end].to_string()),
+        }
+    }
+
+    // Collapses the spaces of one run's text in a paragraph. `after_space` tells whether the
+    // text before it ends with a preserved space, and is updated for the text that follows.
+    fn collapse_run_spaces<'t>(
+        &mut self,
+        text: &'t str,
+        preserve_space: bool,
+        after_space: &mut bool,
+    ) -> Cow<'t, str> {
+        let is_space: fn(char) -> bool = match self.config.space_collapse() {
+            SpaceCollapse::Off => return Cow::Borrowed(text),
+            SpaceCollapse::Spaces => |c| c == ' ',
+            SpaceCollapse::Whitespace => {
+                |c| c == ' ' || c.general_category() == GeneralCategory::SpaceSeparator
+            }
+        };
+
+        let mut collapsed = self.collapse_spaces(text, preserve_space);
+        if *after_space {
+            let trimmed = collapsed.trim_start_matches(is_space);
+            let removed = collapsed[..collapsed.len() - trimmed.len()].chars().count();
+            if removed > 0 {
+                self.report.collapsed_stretches += 1;
+                self.report.collapsed_spaces += removed;
+                collapsed = Cow::Owned(trimmed.to_string());
+            }
+        }
+        if !collapsed.is_empty() {
+            *after_space = preserve_space && collapsed.ends_with(is_space);
+        }
+        collapsed
+    }
+
+    fn clean_paragraph_children(&mut self, children: &mut [ParagraphChild]) {
+        for child in children {
+            match child {
+                ParagraphChild::Run(run) => self.clean_run(run),
+                // Only the displayed runs are cleaned, the link target is left as it is
+                ParagraphChild::Hyperlink(hyperlink) => {
+                    self.clean_paragraph_children(&mut hyperlink.children)
+                }
+                ParagraphChild::Insert(insert) => {
+                    for child in &mut insert.children {
+                        match child {
+                            InsertChild::Run(run) => self.clean_run(run),
+                            InsertChild::CommentStart(start) => {
+                                self.clean_comment(&mut start.comment)
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                ParagraphChild::StructuredDataTag(sdt) => self.clean_sdt(sdt),
+                // Deleted text is kept exactly as recorded and not counted: it is no longer part
+                // of the document, and rewriting it would falsify the revision history. Comments
+                // anchored in a deletion are still live comments though.
+                ParagraphChild::Delete(delete) => {
+                    for child in &mut delete.children {
+                        if let DeleteChild::CommentStart(start) = child {
+                            self.clean_comment(&mut start.comment);
+                        }
+                    }
+                }
+                ParagraphChild::CommentStart(start) => self.clean_comment(&mut start.comment),
+                _ => {}
+            }
+        }
+    }
+
+    // docx-rs writes the comments part from the comment stored at each comment range start, so
+    // that is where comments are cleaned; author, date and id are left untouched
+    fn clean_comment(&mut self, comment: &mut Comment) {
+        self.clean_part("comment", |pass| {
+            for child in &mut comment.children {
+                match child {
+                    CommentChild::Paragraph(paragraph) => pass.clean_paragraph(paragraph),
+                    CommentChild::Table(table) => pass.clean_table(table),
+                }
+            }
+        });
+    }
+
+    fn clean_run(&mut self, run: &mut Run) {
+        for child in &mut run.children {
+            match child {
+                // docx-rs reads run text unescaped but writes it out verbatim
+                // Spaces are collapsed per paragraph afterwards, see collapse_paragraph_spaces
+                // Text with nothing to replace or escape is left as it is
+                RunChild::Text(text) => {
+                    let replaced = self.replace_chars(&text.text);
+                    if let Cow::Owned(cleaned) = quick_xml::escape::escape(self.finish(replaced)) {
+                        text.text = cleaned;
+                    }
+                }
+                RunChild::Drawing(drawing) => {
+                    let Some(DrawingData::TextBox(text_box)) = &mut drawing.data else {
+                        continue;
+                    };
+                    self.clean_part("textbox", |pass| {
+                        for content in &mut text_box.children {
+                            match content {
+                                TextBoxContentChild::Paragraph(paragraph) => {
+                                    pass.clean_paragraph(paragraph)
+                                }
+                                TextBoxContentChild::Table(table) => pass.clean_table(table),
+                            }
+                        }
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn clean_text(&mut self, text: &str) -> String {
+        let replaced = self.replace_chars(text);
+        let collapsed = self.collapse_spaces(&replaced, false);
+        self.finish(collapsed).into_owned()
+    }
+
+    // The steps over a whole text once its characters are replaced and its spaces collapsed:
+    // Config::homoglyphs, then Config::normalize. ASCII has no lookalikes and is normalized.
+    fn finish<'t>(&mut self, text: Cow<'t, str>) -> Cow<'t, str> {
+        if text.is_ascii() {
+            return text;
+        }
+        let despoofed = self.despoof(text);
+        self.normalize(despoofed)
+    }
+
+    // Config::homoglyphs, counting the lookalikes it replaces. A word only counts as Latin when
+    // it has a Latin letter and every other letter in it is Latin or a lookalike, so words of
+    // other scripts are left alone even when all their letters are lookalikes, e.g. "сор".
+    fn despoof<'t>(&mut self, text: Cow<'t, str>) -> Cow<'t, str> {
+        let Some(homoglyphs) = &self.config.homoglyphs else {
+            return text;
+        };
+        if !text.chars().any(|c| homoglyphs.latin(c).is_some()) {
+            return text;
+        }
+        let is_word_char = |c: char| {
+            c.is_alphanumeric()
+                || matches!(
+                    c.general_category(),
+                    GeneralCategory::NonspacingMark | GeneralCategory::SpacingMark
+                )
+        };
+        let chars: Vec<char> = text.chars().collect();
+        let mut despoofed = String::with_capacity(text.len());
+        let mut changed = false;
+        let mut start = 0;
+        while start < chars.len() {
+            let is_word = is_word_char(chars[start]);
+            let end = chars[start..]
+                .iter()
+                .position(|&c| is_word_char(c) != is_word)
+                .map_or(chars.len(), |len| start + len);
+            let word = &chars[start..end];
+            let latin = is_word
+                && word.iter().any(|&c| is_latin_letter(c))
+                && word.iter().all(|&c| {
+                    !c.is_alphabetic() || is_latin_letter(c) || homoglyphs.latin(c).is_some()
+                });
+            for &c in word {
+                match homoglyphs.latin(c).filter(|_| latin) {
+                    Some(letter) => {
+                        *self.report.homoglyphs.entry(c).or_insert(0) += 1;
+                        despoofed.push(letter);
+                        changed = true;
+                    }
+                    None => despoofed.push(c),
+                }
+            }
+            start = end;
+        }
+        if !changed {
+            return text;
+        }
+        Cow::Owned(despoofed)
+    }
+
+    // Config::normalize, counting the texts it changes
+    fn normalize<'t>(&mut self, text: Cow<'t, str>) -> Cow<'t, str> {
+        let normalized: String = match self.config.normalize {
+            None => return text,
+            Some(Normalization::Nfc) if is_nfc_quick(text.chars()) == IsNormalized::Yes => {
+                return text
+            }
+            Some(Normalization::Nfkc) if is_nfkc_quick(text.chars()) == IsNormalized::Yes => {
+                return text
+            }
+            Some(Normalization::Nfc) => text.nfc().collect(),
+            Some(Normalization::Nfkc) => text.nfkc().collect(),
+        };
+        if normalized == text {
+            return text;
+        }
+        self.report.normalized_runs += 1;
+        Cow::Owned(normalized)
+    }
+
+    // Returns the text itself when none of its characters are listed, which is the case for
+    // most runs of a document
+    fn replace_chars<'t>(&mut self, text: &'t str) -> Cow<'t, str> {
+        if self.rules.is_plain(text) {
+            return Cow::Borrowed(text);
+        }
+        self.find_suspicious(text);
+        if !text.chars().any(|c| self.rules.is_listed(c)) {
+            return Cow::Borrowed(text);
+        }
+        let chars: Vec<char> = text.chars().collect();
+        let mut cleaned = String::with_capacity(text.len());
+        let mut patterned_spaces = None;
+        for (i, (offset, c)) in text.char_indices().enumerate() {
+            match self.rules.action(c) {
+                Some(_) if !self.config.strict && joins_emoji(&chars, i) => cleaned.push(c),
+                Some(Action::NoBreakSpace)
+                    if patterned_spaces
+                        .get_or_insert_with(|| self.rules.patterned_spaces(text))
+                        .contains(&offset) =>
+                {
+                    self.report.nbsp_kept += 1;
+                    cleaned.push(c);
+                }
+                Some(Action::Report) => {
+                    *self.report.kept.entry(c).or_insert(0) += 1;
+                    cleaned.push(c);
+                }
+                Some(Action::Declined) => {
+                    *self.report.declined.entry(c).or_insert(0) += 1;
+                    cleaned.push(c);
+                }
+                Some(action @ (Action::Replace(_) | Action::NoBreakSpace)) => {
+                    // Increment the counters for this specific character and the current part
+                    self.report.count(c, self.part);
+                    if let Some(positions) = &mut self.positions {
+                        positions.push((i, c));
+                    }
+                    match (&self.config.visualize, action) {
+                        (Some(format), _) => cleaned.push_str(&self.config.token(format, c)),
+                        (None, Action::Replace(replacement)) => cleaned.push_str(replacement),
+                        (None, _) => cleaned.push(' '),
+                    }
+                }
+                None => cleaned.push(c),
+            }
+        }
+        if log::log_enabled!(log::Level::Debug) {
+            let mut found: Vec<char> = chars
+                .iter()
+                .copied()
+                .filter(|&c| self.rules.is_listed(c))
+                .collect();
+            found.sort_unstable();
+            found.dedup();
+            let found: Vec<String> = found
+                .into_iter()
+                .map(|c| format!("U+{:04X} {}", c as u32, self.config.name(c)))
+                .collect();
+            log::debug!("{} run with {}", self.part, found.join(", "));
+            log::trace!("{:?} -> {:?}", text, cleaned);
+        }
+        Cow::Owned(cleaned)
+    }
+
+    // The instruction of a field, e.g. REF _Ref123 \h, only loses the zero-width characters the
+    // config deletes. Anything else may be part of its syntax: a no-break space in a quoted
+    // bookmark name changes the target, and spaces separate the switches. The removals count for
+    // the "field" part.
+    fn clean_field_code<'t>(&mut self, code: &'t str) -> Cow<'t, str> {
+        if !code.chars().any(|c| self.rules.is_zero_width(c)) {
+            return Cow::Borrowed(code);
+        }
+        let mut cleaned = String::with_capacity(code.len());
+        for c in code.chars() {
+            if self.rules.is_zero_width(c) {
+                self.report.count(c, "field");
+            } else {
+                cleaned.push(c);
+            }
+        }
+        Cow::Owned(cleaned)
+    }
+
+    // The text of an equation (m:t) only loses the zero-width characters the config deletes, like
+    // a field instruction, as the rest may be the symbols of the equation. The invisible
+    // operators say that a function is applied or that two terms are multiplied, which
+    // converters to LaTeX or MathML go by, so they are only reported unless Config::math_strict.
+    // The removals count for the "math" part.
+    fn clean_math_text<'t>(&mut self, text: &'t str) -> Cow<'t, str> {
+        if !text.chars().any(|c| self.rules.is_zero_width(c)) {
+            return Cow::Borrowed(text);
+        }
+        let mut cleaned = String::with_capacity(text.len());
+        for c in text.chars() {
+            if !self.rules.is_zero_width(c) {
+                cleaned.push(c);
+            } else if INVISIBLE_OPERATORS.contains(&c) && !self.config.math_strict {
+                *self.report.kept.entry(c).or_insert(0) += 1;
+                cleaned.push(c);
+            } else {
+                self.report.count(c, "math");
+                if let Some(format) = &self.config.visualize {
+                    cleaned.push_str(&self.config.token(format, c));
+                }
+            }
+        }
+        Cow::Owned(cleaned)
+    }
+
+    // Counts the suspicious characters of a text, keeping the context of the first of each
+    fn find_suspicious(&mut self, text: &str) {
+        if !text.chars().any(|c| self.rules.is_suspicious(c)) {
+            return;
+        }
+        let chars: Vec<char> = text.chars().collect();
+        for (i, &c) in chars.iter().enumerate() {
+            if !self.rules.is_suspicious(c) {
+                continue;
+            }
+            *self.report.suspicious.entry(c).or_insert(0) += 1;
+            if !self.report.suspicious_contexts.contains_key(&c) {
+                let before = &chars[i.saturating_sub(CONTEXT_CHARS)..i];
+                let after = &chars[i + 1..chars.len().min(i + 1 + CONTEXT_CHARS)];
+                let context = format!(
+                    "{}{}{}",
+                    self.render_context(before),
+                    render_invisible(c),
+                    self.render_context(after)
+                );
+                self.report.suspicious_contexts.insert(c, context);
+            }
+        }
+    }
+
+    // With `keep_edges` only the stretches between other characters are collapsed, so
+    // significant leading and trailing spaces survive
+    fn collapse_spaces<'t>(&mut self, text: &'t str, keep_edges: bool) -> Cow<'t, str> {
+        let collapse_re = match self.config.space_collapse() {
+            SpaceCollapse::Off => return Cow::Borrowed(text),
+            // Without a double space there is nothing to collapse
+            SpaceCollapse::Spaces if !text.contains("  ") => return Cow::Borrowed(text),
+            SpaceCollapse::Spaces => &SPACE_COLLAPSE_RE,
+            SpaceCollapse::Whitespace => &WHITESPACE_COLLAPSE_RE,
+        };
+        let mut collapsed = String::new();
+        let mut last = 0;
+        for stretch in collapse_re.find_iter(text) {
+            if keep_edges && (stretch.start() == 0 || stretch.end() == text.len()) {
+                continue;
+            }
+            self.report.collapsed_stretches += 1;
+            self.report.collapsed_spaces += stretch.as_str().chars().count() - 1;
+            collapsed.push_str(&text[last..stretch.start()]);
+            collapsed.push(' ');
+            last = stretch.end();
+        }
+        if last == 0 {
+            return Cow::Borrowed(text);
+        }
+        collapsed.push_str(&text[last..]);
+        Cow::Owned(collapsed)
+    }
+}
+
+#[cfg(test)]
+mod collapse_paragraph_spaces_tests {
+    use super::*;
+
+    #[test]
+    fn each_extra_space_is_counted() {
+        let cleaner = Cleaner::new(Config::builtin());
+        let mut pass = Pass::new(&cleaner);
+        let mut paragraph = Paragraph::new().add_run(Run::new().add_text("a   b  c"));
+        pass.collapse_paragraph_spaces(&mut paragraph);
+        assert_eq!(pass.report.collapsed_spaces, 3);
+        assert_eq!(pass.report.collapsed_stretches, 2);
+        let ParagraphChild::Run(run) = &paragraph.children[0] else {
+            panic!("{:?}", paragraph.children);
+        };
+        let RunChild::Text(text) = &run.children[0] else {
+            panic!("{:?}", run.children);
+        };
+        assert_eq!(text.text, "a b c");
+    }
+}
+
+// An item of the displayed run content of a paragraph, see collect_run_texts
+enum RunContent<'a> {
+    Text(&'a mut Text),
+    // A line, page or column break
+    Break,
+    // Tabs, drawings and anything else
+    Other,
+}
+
+// The displayed run content of a paragraph in reading order
+fn collect_run_texts<'a>(children: &'a mut [ParagraphChild], texts: &mut Vec<RunContent<'a>>) {
+    let collect_run = |run: &'a mut Run, texts: &mut Vec<RunContent<'a>>| {
+        for child in &mut run.children {
+            match child {
+                RunChild::Text(text) => texts.push(RunContent::Text(text)),
+                RunChild::Break(_) => texts.push(RunContent::Break),
+                _ => texts.push(RunContent::Other),
+            }
+        }
+    };
+    for child in children {
+        match child {
+            ParagraphChild::Run(run) => collect_run(run, texts),
+            ParagraphChild::Hyperlink(hyperlink) => {
+                collect_run_texts(&mut hyperlink.children, texts)
+            }
+            ParagraphChild::Insert(insert) => {
+                for child in &mut insert.children {
+                    if let InsertChild::Run(run) = child {
+                        collect_run(run, texts);
+                    }
+                }
+            }
+            ParagraphChild::StructuredDataTag(sdt) => {
+                for child in &mut sdt.children {
+                    match child {
+                        StructuredDataTagChild::Run(run) => collect_run(run, texts),
+                        _ => texts.push(RunContent::Other),
+                    }
+                }
+            }
+            // Deleted runs are not displayed and these markers carry no text
+            ParagraphChild::Delete(_)
+            | ParagraphChild::BookmarkStart(_)
+            | ParagraphChild::BookmarkEnd(_)
+            | ParagraphChild::CommentStart(_)
+            | ParagraphChild::CommentEnd(_) => {}
+        }
+    }
+}
+
+// Whether the character at `i` is a zero width joiner or emoji variation selector that is part
+// of an emoji sequence such as 👨‍👩‍👧, 🏳️‍🌈 or 1️⃣, where removing it would break the emoji apart
+fn joins_emoji(chars: &[char], i: usize) -> bool {
+    // Digits, '#' and '*' have the Emoji property too, but are plain text unless a keycap follows
+    let is_emoji = |c: Option<&char>| c.is_some_and(|c| !c.is_ascii() && c.is_emoji_char());
+    let is_keycap = |c: Option<&char>| c.is_some_and(|c| matches!(c, '0'..='9' | '#' | '*'));
+    match chars[i] {
+        '\u{FE0F}' => {
+            i > 0
+                && (is_emoji(chars.get(i - 1))
+                    || is_keycap(chars.get(i - 1)) && chars.get(i + 1) == Some(&'\u{20E3}'))
+        }
+        '\u{200D}' => {
+            let after_emoji = i > 0
+                && (is_emoji(chars.get(i - 1))
+                    || chars[i - 1] == '\u{FE0F}' && joins_emoji(chars, i - 1));
+            after_emoji && is_emoji(chars.get(i + 1))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod joins_emoji_tests {
+    use super::*;
+
+    // The text of a line cleaned with the built-in list
+    fn cleaned(text: &str, strict: bool) -> String {
+        let config = Config {
+            strict,
+            ..Config::builtin()
+        };
+        let cleaned = Cleaner::new(config)
+            .clean_text(text.as_bytes(), false)
+            .unwrap();
+        cleaned.as_str().to_string()
+    }
+
+    #[test]
+    fn family_emoji_keep_their_joiners() {
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        assert_eq!(cleaned(family, false), family);
+    }
+
+    #[test]
+    fn flag_sequences_keep_their_joiners_and_selectors() {
+        let rainbow = "\u{1F3F3}\u{FE0F}\u{200D}\u{1F308}";
+        let pirate = "\u{1F3F4}\u{200D}\u{2620}\u{FE0F}";
+        let germany = "\u{1F1E9}\u{1F1EA}";
+        assert_eq!(cleaned(rainbow, false), rainbow);
+        assert_eq!(cleaned(pirate, false), pirate);
+        assert_eq!(cleaned(germany, false), germany);
+    }
+
+    #[test]
+    fn keycap_sequences_keep_their_selectors() {
+        let keycaps = "1\u{FE0F}\u{20E3} #\u{FE0F}\u{20E3}";
+        assert_eq!(cleaned(keycaps, false), keycaps);
+    }
+
+    #[test]
+    fn joiners_between_letters_are_removed() {
+        assert_eq!(cleaned("ab\u{200D}cd", false), "abcd");
+        assert!(!joins_emoji(&['a', '\u{200D}', 'b'], 1));
+        // Next to an emoji on one side only
+        assert_eq!(cleaned("a\u{200D}\u{1F468}", false), "a\u{1F468}");
+    }
+
+    #[test]
+    fn strict_removes_the_joiners_of_emoji_too() {
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        assert_eq!(cleaned(family, true), "\u{1F468}\u{1F469}\u{1F467}");
+        assert_eq!(cleaned("1\u{FE0F}\u{20E3}", true), "1\u{20E3}");
+    }
+}
+
+// The state of an open paragraph while a part is cleaned as raw XML
+struct ParagraphState {
+    depth: usize,
+    // The depth of the run being read, if any
+    run_depth: Option<usize>,
+    preformatted: bool,
+    // Whether the text so far ends with a preserved space, see collapse_run_spaces
+    after_space: bool,
+    // Inside the ruby text (w:rt) of a phonetic guide: after_space before it, for its base
+    ruby_after_space: Option<bool>,
+    // Whether a line break comes after the text so far, for Config::trim_around_breaks
+    after_break: bool,
+    // Counted from 1 in the part, and the runs in the paragraph so far
+    index: usize,
+    runs: usize,
+    // With Config::record_locations: the original text so far, for the context of the
+    // replacements, and the replacements as (index in the text, run)
+    text: Vec<char>,
+    found: Vec<(usize, usize)>,
+    // With Config::track_changes or Config::annotate: the open run, unless it is edited in
+    // place
+    pending_run: Option<PendingRun>,
+    // With Config::hidden_text: where the open run starts, whether it is hidden and the
+    // characters of text in it, whether it only holds REMOVABLE_RUN_ELEMENTS and whether it is
+    // part of a field
+    run_start: usize,
+    hidden: bool,
+    hidden_chars: usize,
+    removable: bool,
+    in_field: bool,
+    // The shading of the paragraph, and the direct formatting of the open run with what it
+    // makes of its text once its w:rPr ends. The text of the run is kept when it is invisible or
+    // its colors come from the theme.
+    fill: Option<Color>,
+    format: RunFormat,
+    invisible: Option<InvisibleFormatting>,
+    theme_colored: bool,
+    run_text: Vec<char>,
+    // With Config::prune_empty: where the paragraph and the open run start in the cleaned copy
+    // (see Splice::mark), whether they had text and still have any, whether the run only holds
+    // text and whether nothing but pruned runs keeps the paragraph
+    mark: usize,
+    in_body: bool,
+    had_text: bool,
+    has_text: bool,
+    prunable: bool,
+    run_mark: usize,
+    run_had_text: bool,
+    run_has_text: bool,
+    run_bare: bool,
+}
+
+impl ParagraphState {
+    // Whether the open run is going to be removed, as far as is known before it ends
+    fn removing(&self, remove_hidden: bool, remove_invisible: bool) -> bool {
+        let hides =
+            (self.hidden && remove_hidden) || (self.invisible.is_some() && remove_invisible);
+        hides && self.removable && !self.in_field
+    }
+}
+
+// A color of the formatting: hex RGB in upper case, or one of the theme, which is not resolved
+#[derive(Clone, Debug, PartialEq)]
+enum Color {
+    Rgb(String),
+    Theme,
+}
+
+// The direct formatting of a run that can hide its text, see clean_paragraph_xml
+#[derive(Default)]
+struct RunFormat {
+    // None for automatic, which is always readable
+    color: Option<Color>,
+    highlight: Option<Color>,
+    fill: Option<Color>,
+    // In half points
+    size: Option<u32>,
+}
+
+impl RunFormat {
+    // What hides the text in front of the given background, and whether the colors that decide
+    // it come from the theme
+    fn invisible(&self, behind: Option<&Color>) -> (Option<InvisibleFormatting>, bool) {
+        if let Some(size) = self.size.filter(|&size| size <= TINY_FONT_SIZE) {
+            return (Some(InvisibleFormatting::Tiny(size)), false);
+        }
+        let background = self.highlight.as_ref().or(self.fill.as_ref()).or(behind);
+        match (&self.color, background) {
+            (Some(Color::Rgb(color)), Some(Color::Rgb(background))) if color == background => {
+                (Some(InvisibleFormatting::SameColor(color.clone())), false)
+            }
+            (Some(Color::Theme), _) | (Some(_), Some(Color::Theme)) => (None, true),
+            _ => (None, false),
+        }
+    }
+}
+
+// The color of a w:color or w:background
+fn text_color(e: &BytesStart) -> Result<Option<Color>, quick_xml::Error> {
+    if e.try_get_attribute("w:themeColor")?.is_some() {
+        return Ok(Some(Color::Theme));
+    }
+    Ok(e.try_get_attribute("w:val")?
+        .or(e.try_get_attribute("w:color")?)
+        .and_then(|a| rgb(&a.value)))
+}
+
+// The color a w:shd fills with: its pattern color when the pattern is solid
+fn shading_color(e: &BytesStart) -> Result<Option<Color>, quick_xml::Error> {
+    if e.try_get_attribute("w:themeFill")?.is_some() {
+        return Ok(Some(Color::Theme));
+    }
+    let pattern = e.try_get_attribute("w:val")?;
+    if pattern.as_ref().is_some_and(|a| a.value.as_ref() == b"nil") {
+        return Ok(None);
+    }
+    let attribute = match pattern {
+        Some(a) if a.value.as_ref() == b"solid" => "w:color",
+        _ => "w:fill",
+    };
+    Ok(e.try_get_attribute(attribute)?.and_then(|a| rgb(&a.value)))
+}
+
+// Hex RGB in upper case; None for "auto" and anything else that is not a color
+fn rgb(value: &[u8]) -> Option<Color> {
+    let value = std::str::from_utf8(value).ok()?;
+    (value.len() == 6 && value.bytes().all(|b| b.is_ascii_hexdigit()))
+        .then(|| Color::Rgb(value.to_ascii_uppercase()))
+}
+
+// The colors of the named highlights of w:highlight
+fn highlight_color(name: &[u8]) -> Option<Color> {
+    let rgb = match name {
+        b"black" => "000000",
+        b"blue" => "0000FF",
+        b"cyan" => "00FFFF",
+        b"green" => "00FF00",
+        b"magenta" => "FF00FF",
+        b"red" => "FF0000",
+        b"yellow" => "FFFF00",
+        b"white" => "FFFFFF",
+        b"darkBlue" => "000080",
+        b"darkCyan" => "008080",
+        b"darkGreen" => "008000",
+        b"darkMagenta" => "800080",
+        b"darkRed" => "800000",
+        b"darkYellow" => "808000",
+        b"darkGray" => "808080",
+        b"lightGray" => "C0C0C0",
+        _ => return None,
+    };
+    Some(Color::Rgb(rgb.to_string()))
+}
+
+// A run whose changes are spliced in when it ends: as a tracked change, and with the comments
+// on the characters in it around it
+struct PendingRun {
+    // Where the run starts in the XML, and where its start tag ends
+    start: usize,
+    open_end: usize,
+    // Where its w:rPr is in the XML, for the runs it is split into with Config::highlight
+    properties: Option<(usize, usize)>,
+    // The cleaned texts as (start, end, markup) in the XML
+    edits: Vec<(usize, usize, String)>,
+    // Whether the edits become a tracked change
+    tracked: bool,
+    // The ids of the comments anchored to the run
+    comments: Vec<usize>,
+}
+
+// With Config::annotate: the comments added to the main document part
+struct Comments {
+    first_id: usize,
+    // The text of each comment, in the order of their ids
+    texts: Vec<String>,
+}
+
+// The cleaned copy of a part: the original bytes, with cleaned text spliced in where it changed
+struct Splice<'x> {
+    xml: &'x [u8],
+    // Unset when scanning, where nothing is put together
+    rewrite: bool,
+    cleaned: Vec<u8>,
+    // xml[..copied] has been taken over into `cleaned`
+    copied: usize,
+    changed: bool,
+}
+
+impl<'x> Splice<'x> {
+    fn new(xml: &'x [u8], rewrite: bool) -> Self {
+        Splice {
+            xml,
+            rewrite,
+            cleaned: Vec::new(),
+            copied: 0,
+            changed: false,
+        }
+    }
+
+    // Puts the text in place of xml[start..end]
+    fn replace(&mut self, start: usize, end: usize, text: &str) {
+        self.replace_xml(
+            start,
+            end,
+            quick_xml::escape::partial_escape(text).as_bytes(),
+        );
+    }
+
+    // Puts markup in place of xml[start..end]
+    fn replace_xml(&mut self, start: usize, end: usize, markup: &[u8]) {
+        if self.rewrite {
+            self.splice(start, end, markup);
+        }
+    }
+
+    // Adds markup at xml[at], also when only the comments are added and the text is left alone
+    fn insert(&mut self, at: usize, markup: &str) {
+        if !markup.is_empty() {
+            self.splice(at, at, markup.as_bytes());
+        }
+    }
+
+    // How long the cleaned copy is when it reaches xml[at], for cutting it back there with cut
+    fn mark(&mut self, at: usize) -> usize {
+        if self.rewrite {
+            self.cleaned.extend_from_slice(&self.xml[self.copied..at]);
+            self.copied = at;
+        }
+        self.cleaned.len()
+    }
+
+    // Drops the cleaned copy from a mark on, including what was spliced in since, and goes on
+    // copying from xml[at]
+    fn cut(&mut self, mark: usize, at: usize) {
+        if self.rewrite {
+            self.cleaned.truncate(mark);
+            self.copied = at;
+            self.changed = true;
+        }
+    }
+
+    fn splice(&mut self, start: usize, end: usize, markup: &[u8]) {
+        self.cleaned
+            .extend_from_slice(&self.xml[self.copied..start]);
+        self.cleaned.extend_from_slice(markup);
+        self.copied = end;
+        self.changed = true;
+    }
+
+    // Puts in a run that was held back until its end at xml[end], with its comments around it.
+    // Returns the next free id for tracked changes.
+    fn replace_run(
+        &mut self,
+        run: PendingRun,
+        end: usize,
+        revision: Option<&Revision>,
+        id: usize,
+    ) -> usize {
+        self.insert(run.start, &comment_starts(&run.comments));
+        let id = match revision {
+            Some(revision) if run.tracked && !run.edits.is_empty() => {
+                self.replace_tracked(run.start, &run.edits, end, revision, id);
+                id + 2
+            }
+            _ => {
+                for (start, end, markup) in &run.edits {
+                    self.replace_xml(*start, *end, markup.as_bytes());
+                }
+                id
+            }
+        };
+        self.insert(end, &comment_ends(&run.comments));
+        id
+    }
+
+    // Takes out the run in xml[start..end], as a tracked deletion when there is a revision.
+    // Returns the next free id for tracked changes.
+    fn delete_run(
+        &mut self,
+        start: usize,
+        end: usize,
+        revision: Option<&Revision>,
+        id: usize,
+    ) -> usize {
+        let Some(revision) = revision else {
+            self.replace_xml(start, end, b"");
+            return id;
+        };
+        let mut markup =
+            format!("<w:del w:id=\"{}\" {}>", id, revision_attributes(revision)).into_bytes();
+        markup.extend_from_slice(&deleted_run(&self.xml[start..end]));
+        markup.extend_from_slice(b"</w:del>");
+        self.replace_xml(start, end, &markup);
+        id + 1
+    }
+
+    // The run in xml[start..end] as a tracked deletion of the original followed by a tracked
+    // insertion of the cleaned run
+    fn replace_tracked(
+        &mut self,
+        start: usize,
+        edits: &[(usize, usize, String)],
+        end: usize,
+        revision: &Revision,
+        id: usize,
+    ) {
+        let original = &self.xml[start..end];
+        let mut inserted = Vec::new();
+        let mut copied = start;
+        for (start, end, markup) in edits {
+            inserted.extend_from_slice(&self.xml[copied..*start]);
+            inserted.extend_from_slice(markup.as_bytes());
+            copied = *end;
+        }
+        inserted.extend_from_slice(&self.xml[copied..end]);
+
+        let attributes = revision_attributes(revision);
+        let mut markup = format!("<w:del w:id=\"{}\" {}>", id, attributes).into_bytes();
+        markup.extend_from_slice(&deleted_run(original));
+        markup.extend_from_slice(
+            format!("</w:del><w:ins w:id=\"{}\" {}>", id + 1, attributes).as_bytes(),
+        );
+        markup.extend_from_slice(&inserted);
+        markup.extend_from_slice(b"</w:ins>");
+        self.replace_xml(start, end, &markup);
+    }
+
+    // None when nothing was replaced
+    fn finish(mut self) -> Option<Vec<u8>> {
+        if !self.changed {
+            return None;
+        }
+        self.cleaned.extend_from_slice(&self.xml[self.copied..]);
+        Some(self.cleaned)
+    }
+}
+
+// A w:t in the XML, from its start tag to the end of its text
+struct TextAt {
+    start: usize,
+    end: usize,
+    preserve_space: bool,
+}
+
+// The edit that highlights the marked characters of a w:t's cleaned text: the run is closed
+// before each marked stretch and reopened with the highlight, then closed and reopened as it was
+// after it. A run that begins with a marked stretch is rewritten from its start tag on, so that
+// no empty run is left in front of it.
+fn highlight_text(
+    xml: &[u8],
+    run: &PendingRun,
+    text: TextAt,
+    cleaned: &str,
+    marks: &[bool],
+    color: &str,
+) -> (usize, usize, String) {
+    let mut chars: Vec<(char, bool)> = cleaned.chars().zip(marks.iter().copied()).collect();
+    // Without xml:space="preserve" the spaces at the edges are not shown, while the split text is
+    // all preserved
+    if !text.preserve_space {
+        let is_xml_space = |&(c, _): &(char, bool)| matches!(c, ' ' | '\t' | '\r' | '\n');
+        let leading = chars.iter().take_while(|c| is_xml_space(c)).count();
+        chars.drain(..leading);
+        let trailing = chars.iter().rev().take_while(|c| is_xml_space(c)).count();
+        chars.truncate(chars.len() - trailing);
+    }
+
+    let open_tag = String::from_utf8_lossy(&xml[run.start..run.open_end]);
+    let properties = run.properties.map(|(start, end)| &xml[start..end]);
+    let plain = String::from_utf8_lossy(properties.unwrap_or_default());
+    let highlighted = highlighted_properties(properties, color);
+    let content_start = run.properties.map_or(run.open_end, |(_, end)| end);
+    let first_in_run = xml[content_start..text.start]
+        .iter()
+        .all(u8::is_ascii_whitespace);
+    let ends_run = xml[text.end..].starts_with(b"</w:t>")
+        && xml[text.end + 6..]
+            .trim_ascii_start()
+            .starts_with(b"</w:r>");
+
+    let mut stretches: Vec<(String, bool)> = Vec::new();
+    for (c, mark) in chars {
+        match stretches.last_mut() {
+            Some((text, stretch_mark)) if *stretch_mark == mark => text.push(c),
+            _ => stretches.push((c.to_string(), mark)),
+        }
+    }
+
+    let mut markup = String::new();
+    for (i, (text, mark)) in stretches.iter().enumerate() {
+        // The first stretch stays in the run it is in when that is not highlighted
+        let reopen = i > 0 || first_in_run || *mark;
+        if i > 0 {
+            markup.push_str("</w:t></w:r>");
+        } else if reopen && !first_in_run {
+            markup.push_str("</w:r>");
+        }
+        if reopen {
+            markup.push_str(&open_tag);
+            markup.push_str(if *mark { &highlighted } else { &plain });
+        }
+        markup.push_str("<w:t xml:space=\"preserve\">");
+        markup.push_str(&quick_xml::escape::partial_escape(text.as_str()));
+    }
+    // The rest of the run is not highlighted; the original end tag closes the empty w:t
+    if stretches.last().is_some_and(|(_, mark)| *mark) && !ends_run {
+        markup.push_str("</w:t></w:r>");
+        markup.push_str(&open_tag);
+        markup.push_str(&plain);
+        markup.push_str("<w:t xml:space=\"preserve\">");
+    }
+    let start = if first_in_run { run.start } else { text.start };
+    (start, text.end, markup)
+}
+
+// FUNCTION APPLICATION, INVISIBLE TIMES, INVISIBLE SEPARATOR and INVISIBLE PLUS, which
+// clean_math_text keeps in equations
+const INVISIBLE_OPERATORS: std::ops::RangeInclusive<char> = '\u{2061}'..='\u{2064}';
+
+// Run properties that come after w:highlight in a w:rPr
+const AFTER_HIGHLIGHT: [&[u8]; 14] = [
+    b"w:u",
+    b"w:effect",
+    b"w:bdr",
+    b"w:shd",
+    b"w:fitText",
+    b"w:vertAlign",
+    b"w:rtl",
+    b"w:cs",
+    b"w:em",
+    b"w:lang",
+    b"w:eastAsianLayout",
+    b"w:specVanish",
+    b"w:oMath",
+    b"w:rPrChange",
+];
+
+// A run's w:rPr with the highlight color set, in its place in the schema order so that Word
+// accepts it; an existing highlight is replaced
+fn highlighted_properties(properties: Option<&[u8]>, color: &str) -> String {
+    let highlight = format!("<w:highlight w:val=\"{}\"/>", color);
+    let Some(properties) = properties else {
+        return format!("<w:rPr>{}</w:rPr>", highlight);
+    };
+    let mut reader = Reader::from_reader(properties);
+    let mut markup = Vec::new();
+    let mut copied = 0;
+    let mut depth = 0;
+    // Inside an existing w:highlight, which is left out
+    let mut skipping = false;
+    let mut inserted = false;
+    loop {
+        let start = reader.buffer_position() as usize;
+        let Ok(event) = reader.read_event() else {
+            break;
+        };
+        match &event {
+            Event::Start(e) | Event::Empty(e) if depth == 1 => {
+                let name = e.name();
+                let name = name.as_ref();
+                let after = AFTER_HIGHLIGHT.contains(&name) || !name.starts_with(b"w:");
+                if !inserted && after {
+                    markup.extend_from_slice(&properties[copied..start]);
+                    markup.extend_from_slice(highlight.as_bytes());
+                    copied = start;
+                    inserted = true;
+                }
+                if name == b"w:highlight" {
+                    markup.extend_from_slice(&properties[copied..start]);
+                    skipping = matches!(event, Event::Start(_));
+                    copied = reader.buffer_position() as usize;
+                }
+                if matches!(event, Event::Start(_)) {
+                    depth += 1;
+                }
+            }
+            Event::Start(_) => depth += 1,
+            // <w:rPr/>
+            Event::Empty(_) => return format!("<w:rPr>{}</w:rPr>", highlight),
+            Event::End(_) => {
+                depth -= 1;
+                if skipping && depth == 1 {
+                    skipping = false;
+                    copied = reader.buffer_position() as usize;
+                } else if depth == 0 && !inserted {
+                    markup.extend_from_slice(&properties[copied..start]);
+                    markup.extend_from_slice(highlight.as_bytes());
+                    copied = start;
+                    inserted = true;
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    markup.extend_from_slice(&properties[copied..]);
+    String::from_utf8_lossy(&markup).into_owned()
+}
+
+// Where the comments on a run begin, before it
+fn comment_starts(ids: &[usize]) -> String {
+    ids.iter()
+        .map(|id| format!("<w:commentRangeStart w:id=\"{}\"/>", id))
+        .collect()
+}
+
+// Where the comments on a run end, after it, each followed by the run that shows it
+fn comment_ends(ids: &[usize]) -> String {
+    ids.iter()
+        .map(|id| {
+            format!(
+                "<w:commentRangeEnd w:id=\"{0}\"/><w:r><w:commentReference w:id=\"{0}\"/></w:r>",
+                id
+            )
+        })
+        .collect()
+}
+
+// A run as the content of a w:del: its text becomes deleted text
+fn deleted_run(run: &[u8]) -> Vec<u8> {
+    const RENAMED: [(&[u8], &[u8]); 4] = [
+        (b"<w:t", b"<w:delText"),
+        (b"</w:t", b"</w:delText"),
+        (b"<w:instrText", b"<w:delInstrText"),
+        (b"</w:instrText", b"</w:delInstrText"),
+    ];
+    let mut deleted = Vec::with_capacity(run.len());
+    let mut i = 0;
+    'bytes: while i < run.len() {
+        for (name, renamed) in RENAMED {
+            // The name has to end there, so that e.g. <w:tab/> is left alone
+            let ends = |b: &u8| matches!(b, b'>' | b'/' | b' ' | b'\t' | b'\r' | b'\n');
+            if run[i..].starts_with(name) && run.get(i + name.len()).is_some_and(ends) {
+                deleted.extend_from_slice(renamed);
+                i += name.len();
+                continue 'bytes;
+            }
+        }
+        deleted.push(run[i]);
+        i += 1;
+    }
+    deleted
+}
+
+// The author and date attributes of a tracked change
+fn revision_attributes(revision: &Revision) -> String {
+    format!(
+        "w:author=\"{}\" w:date=\"{}\"",
+        quick_xml::escape::escape(revision.author.as_str()),
+        revision.date
+    )
+}
+
+// The highest numeric w:id in a part, so that the ids of new tracked changes do not clash with
+// those of the changes, comments and bookmarks already in it
+fn max_id(xml: &[u8]) -> usize {
+    const ID: &[u8] = b" w:id=\"";
+    let mut max = 0;
+    for (i, window) in xml.windows(ID.len()).enumerate() {
+        if window == ID {
+            let digits = &xml[i + ID.len()..];
+            let len = digits.iter().take_while(|b| b.is_ascii_digit()).count();
+            let id = std::str::from_utf8(&digits[..len])
+                .ok()
+                .and_then(|d| d.parse().ok());
+            max = max.max(id.unwrap_or(0));
+        }
+    }
+    max
+}
+
+// The parts of a package, cleaned as raw XML. Only the text is rewritten; the markup around it
+// is kept byte for byte.
+impl Pass<'_> {
+    // Goes through the text parts of a package, returning the cleaned XML by part path for the
+    // parts that changed
+    fn visit_package(&mut self, source: Source) -> Result<HashMap<String, Vec<u8>>, Error> {
+        let mut archive = source.open()?;
+        let TextParts {
+            parts: text_parts,
+            presentation,
+            workbook,
+        } = find_text_parts(&mut archive)?;
+        let main_path = text_parts
+            .iter()
+            .find(|(_, part, _)| *part == "body")
+            .map(|(path, _, _)| path.as_str());
+        if main_path.is_none() && presentation.is_none() && workbook.is_none() {
+            return Err(Error::InvalidDocx(
+                "the main document part is missing".to_string(),
+            ));
+        }
+        let slides = presentation
+            .map(|path| slide_numbers(&mut archive, &path))
+            .unwrap_or_default();
+        let sheets = workbook
+            .map(|path| listed_parts(&mut archive, &path, b"sheet"))
+            .unwrap_or_default();
+        // Comments are only anchored in the main document, and numbered on from the existing ones
+        let comments_path = text_parts
+            .iter()
+            .find(|(_, part, _)| *part == "comment")
+            .map(|(path, _, _)| path.as_str());
+        let first_comment_id = comments_path
+            .and_then(|path| read_zip_entry(&mut archive, path))
+            .map_or(0, |xml| max_id(&xml) + 1);
+
+        let cleaned = self.map_parallel(&text_parts, |pass, (path, part, text)| {
+            let mut archive = source.open()?;
+            let Some(xml) = read_zip_entry(&mut archive, path) else {
+                return Ok((None, None));
+            };
+            if pass.annotate.is_some() && Some(path.as_str()) == main_path {
+                pass.comments = Some(Comments {
+                    first_id: first_comment_id,
+                    texts: Vec::new(),
+                });
+            }
+            let mut cleaned = Ok(None);
+            pass.clean_part(part, |pass| cleaned = pass.clean_xml_part(&xml, *text));
+            let cleaned = cleaned.map_err(|e| Error::InvalidPart(path.clone(), e))?;
+            // Each part is cleaned with a report of its own
+            for replacement in &mut pass.report.replacements {
+                replacement.path.clone_from(path);
+            }
+            for run in &mut pass.report.invisible_runs {
+                run.path.clone_from(path);
+            }
+            let removed = pass.report.parts.get(part).copied().unwrap_or(0);
+            log::info!("{} ({}): {} replaced", path, part, removed);
+            if let (Some(&slide), true) = (slides.get(path), removed > 0) {
+                pass.report.slides.insert(slide, removed);
+            }
+            if let (Some((_, name)), true) = (sheets.iter().find(|(p, _)| p == path), removed > 0) {
+                pass.report.sheets.push((name.clone(), removed));
+            }
+            Ok((cleaned.map(|xml| (path.clone(), xml)), pass.comments.take()))
+        });
+
+        let mut parts = HashMap::new();
+        let mut comments = None;
+        for part in cleaned {
+            let (cleaned, part_comments) = part?;
+            parts.extend(cleaned);
+            comments = comments.or(part_comments);
+        }
+        if let (Some(comments), Some(revision), Some(main_path)) =
+            (comments, self.annotate, main_path)
+        {
+            if !comments.texts.is_empty() {
+                add_comments(
+                    &mut archive,
+                    &mut parts,
+                    main_path,
+                    comments_path,
+                    &comments,
+                    revision,
+                )?;
+            }
+        }
+        if !sheets.is_empty() {
+            let strings_path = text_parts
+                .iter()
+                .find(|(_, part, _)| *part == "strings")
+                .map(|(path, _, _)| path.as_str());
+            self.count_sheets(&mut archive, &sheets, strings_path);
+        }
+        Ok(parts)
+    }
+
+    // Adds the shared strings shown in each sheet to the counts of its inline strings, and puts
+    // the sheets in the order of the workbook
+    fn count_sheets(
+        &mut self,
+        archive: &mut Archive,
+        sheets: &[(String, String)],
+        strings_path: Option<&str>,
+    ) {
+        let strings = strings_path
+            .and_then(|path| read_zip_entry(archive, path))
+            .map(|xml| self.fork().string_counts(&xml))
+            .unwrap_or_default();
+        let mut inline = std::mem::take(&mut self.report.sheets);
+        for (path, name) in sheets {
+            let mut count = inline
+                .iter()
+                .position(|(sheet, _)| sheet == name)
+                .map_or(0, |i| inline.remove(i).1);
+            if strings.iter().any(|&n| n > 0) {
+                let xml = read_zip_entry(archive, path).unwrap_or_default();
+                count += shared_string_cells(&xml)
+                    .into_iter()
+                    .filter_map(|i| strings.get(i))
+                    .sum::<usize>();
+            }
+            if count > 0 {
+                self.report.sheets.push((name.clone(), count));
+            }
+        }
+    }
+
+    // The characters removed from each shared string, in the order of the strings
+    fn string_counts(&mut self, xml: &[u8]) -> Vec<usize> {
+        self.positions = None;
+        let mut reader = Reader::from_reader(xml);
+        let mut counts = Vec::new();
+        let mut in_phonetic = false;
+        let mut in_text = false;
+        loop {
+            match reader.read_event() {
+                Ok(Event::Start(e)) => match e.local_name().as_ref() {
+                    b"si" => counts.push(0),
+                    b"rPh" => in_phonetic = true,
+                    b"t" => in_text = !in_phonetic,
+                    _ => {}
+                },
+                Ok(Event::Empty(e)) if e.local_name().as_ref() == b"si" => counts.push(0),
+                Ok(Event::End(e)) => match e.local_name().as_ref() {
+                    b"rPh" => in_phonetic = false,
+                    b"t" => in_text = false,
+                    _ => {}
+                },
+                Ok(Event::Text(t)) if in_text => {
+                    let Ok(text) = t.unescape() else { continue };
+                    let before = self.report.total_removed();
+                    self.clean_cell_text(&text);
+                    if let Some(count) = counts.last_mut() {
+                        *count += self.report.total_removed() - before;
+                    }
+                }
+                Ok(Event::Eof) | Err(_) => break,
+                _ => {}
+            }
+        }
+        counts
+    }
+
+    // Returns the cleaned XML of a part, or None when nothing in it changed
+    fn clean_xml_part(
+        &mut self,
+        xml: &[u8],
+        text: PartText,
+    ) -> Result<Option<Vec<u8>>, quick_xml::Error> {
+        match text {
+            PartText::Paragraphs => self.clean_paragraph_xml(xml),
+            PartText::Drawing => self.clean_drawing_xml(xml),
+            PartText::Cells => self.clean_cell_xml(xml),
+            PartText::OpenDocument => self.clean_open_document_xml(xml),
+            PartText::Elements(elements) => self.clean_element_xml(xml, elements),
+        }
+    }
+
+    // Cleans the w:t text of a WordprocessingML part like clean_paragraph cleans the model: the
+    // spaces of a paragraph are collapsed across its runs unless its style is preformatted, and
+    // tabs, breaks, drawings and other run content separate the text around them. Paragraphs
+    // nest when a run holds a text box. Deleted text (w:delText) and the separator notes are left
+    // alone, and field codes only lose their zero-width characters, see clean_field_code.
+    fn clean_paragraph_xml(&mut self, xml: &[u8]) -> Result<Option<Vec<u8>>, quick_xml::Error> {
+        let mut reader = Reader::from_reader(xml);
+        let mut splice = Splice::new(xml, self.rewrite);
+        let mut depth = 0;
+        let mut paragraphs: Vec<ParagraphState> = Vec::new();
+        let mut paragraph_count = 0;
+        // Inside a w:t: whether it is marked xml:space="preserve"
+        let mut text_preserve: Option<bool> = None;
+        // Where the open w:t starts
+        let mut text_start = 0;
+        // Inside a w:instrText, the instruction of a field
+        let mut in_field_code = false;
+        // Inside the m:t of a run of an equation
+        let mut in_math_text = false;
+        // How many fields the position is inside of, for Config::hidden_text
+        let mut field_depth: usize = 0;
+        let remove_hidden = self.config.hidden_text == Some(HiddenText::Remove);
+        let remove_invisible = self.config.remove_invisible_formatting;
+        // The color of the page and the shading of the open table cells, behind the text of the
+        // runs. A page without a w:background is white.
+        let mut page_color = Some(Color::Rgb("FFFFFF".to_string()));
+        let mut cell_fills: Vec<(usize, Option<Color>)> = Vec::new();
+        let mut in_separator = false;
+        let mut deleted_depth = None;
+        // Runs in a tracked insertion are edited in place, as changes cannot be nested in it
+        let mut inserted_depth = None;
+        let revision = self.config.track_changes.as_ref().filter(|_| self.rewrite);
+        let mut revision_id = revision.map_or(0, |_| max_id(xml) + 1);
+        let highlight = self.config.highlight.as_deref().filter(|_| self.rewrite);
+        // Comments would lose their anchors, and tracked changes cannot take out a paragraph
+        // without merging it into the next
+        let prune = self.config.prune_empty && self.comments.is_none();
+        let mut body_depth = None;
+        // The open text boxes, each with the part counted before it
+        let mut text_boxes: Vec<(usize, &'static str)> = Vec::new();
+        // mc:Fallback repeats the content of mc:Choice for older readers, so its text is cleaned
+        // without being counted again; the report is set aside until it ends
+        let mut fallback: Option<(usize, CleanReport)> = None;
+
+        loop {
+            let start = reader.buffer_position() as usize;
+            let event = reader.read_event()?;
+            match &event {
+                Event::Start(e) | Event::Empty(e) => {
+                    let name = e.name();
+                    let name = name.as_ref();
+                    if name == b"w:background" {
+                        page_color = text_color(e)?;
+                    }
+                    if let Some((_, fill)) = cell_fills
+                        .last_mut()
+                        .filter(|(cell, _)| name == b"w:shd" && cell + 2 == depth)
+                    {
+                        *fill = shading_color(e)?;
+                    }
+                    if let Some(paragraph) = paragraphs.last_mut() {
+                        // A section break ends with the paragraph holding it
+                        if (paragraph.depth + 1 == depth
+                            && !PRUNABLE_PARAGRAPH_ELEMENTS.contains(&name))
+                            || name == b"w:sectPr"
+                        {
+                            paragraph.prunable = false;
+                        }
+                        let in_run = paragraph.run_depth.is_some_and(|run| run + 1 == depth);
+                        if in_run && !INERT_RUN_ELEMENTS.contains(&name) {
+                            paragraph.run_bare = false;
+                        }
+                        if in_run && deleted_depth.is_none() {
+                            // An empty w:t holds no text
+                            if name == b"w:t" {
+                                if matches!(event, Event::Start(_)) {
+                                    text_start = start;
+                                    text_preserve = Some(
+                                        e.try_get_attribute("xml:space")?
+                                            .is_some_and(|a| a.value.as_ref() == b"preserve"),
+                                    );
+                                }
+                            } else if !INERT_RUN_ELEMENTS.contains(&name) {
+                                paragraph.after_space = false;
+                                paragraph.after_break = LINE_BREAKS.contains(&name);
+                            }
+                            if name == b"w:instrText" && matches!(event, Event::Start(_)) {
+                                in_field_code = true;
+                            }
+                            if !REMOVABLE_RUN_ELEMENTS.contains(&name) {
+                                paragraph.removable = false;
+                            }
+                            if name == b"w:fldChar" || name == b"w:instrText" {
+                                paragraph.in_field = true;
+                            }
+                            if name == b"w:rPr" {
+                                if let Some(run) = paragraph.pending_run.as_mut() {
+                                    // The end of an open w:rPr is filled in when it closes
+                                    let end = reader.buffer_position() as usize;
+                                    run.properties = Some((start, end));
+                                }
+                            }
+                            // The hyphens Word writes as elements are cleaned like the
+                            // characters they stand for. Deleting one joins the word, so a word
+                            // hyphenated at the end of a line loses no more than the hyphen.
+                            let hyphen =
+                                HYPHEN_ELEMENTS.iter().find(|(element, _)| *element == name);
+                            if let Some(&(_, c)) = hyphen.filter(|_| {
+                                matches!(event, Event::Empty(_))
+                                    && !in_separator
+                                    && !paragraph.removing(remove_hidden, remove_invisible)
+                            }) {
+                                let replaced = self.replace_chars(&c.to_string()).into_owned();
+                                let found = !self.take_positions().is_empty();
+                                if found && fallback.is_none() {
+                                    if let Some(run) = paragraph.pending_run.as_mut() {
+                                        run.comments.extend(self.add_comment(c));
+                                    }
+                                    if self.positions.is_some() {
+                                        paragraph
+                                            .found
+                                            .push((paragraph.text.len(), paragraph.runs));
+                                    }
+                                }
+                                if self.positions.is_some() && fallback.is_none() {
+                                    paragraph.text.push(c);
+                                }
+                                if replaced != c.to_string() {
+                                    let end = reader.buffer_position() as usize;
+                                    let markup = if replaced.is_empty() {
+                                        String::new()
+                                    } else {
+                                        format!(
+                                            "<w:t xml:space=\"preserve\">{}</w:t>",
+                                            quick_xml::escape::partial_escape(&replaced)
+                                        )
+                                    };
+                                    match paragraph.pending_run.as_mut() {
+                                        Some(run) => run.edits.push((start, end, markup)),
+                                        None => splice.replace_xml(start, end, markup.as_bytes()),
+                                    }
+                                }
+                            }
+                        }
+                        let in_properties = paragraph.run_depth.is_some_and(|run| run + 2 == depth);
+                        if name == b"w:vanish"
+                            && in_properties
+                            && deleted_depth.is_none()
+                            && self.config.hidden_text.is_some()
+                        {
+                            paragraph.hidden = e.try_get_attribute("w:val")?.is_none_or(|a| {
+                                !matches!(a.value.as_ref(), b"0" | b"false" | b"off")
+                            });
+                        }
+                        if in_properties && deleted_depth.is_none() {
+                            let format = &mut paragraph.format;
+                            match name {
+                                b"w:color" => format.color = text_color(e)?,
+                                b"w:highlight" => {
+                                    let name = e.try_get_attribute("w:val")?;
+                                    format.highlight =
+                                        name.and_then(|name| highlight_color(&name.value));
+                                }
+                                b"w:shd" => format.fill = shading_color(e)?,
+                                b"w:sz" => {
+                                    let size = e.try_get_attribute("w:val")?;
+                                    format.size = size.and_then(|size| {
+                                        std::str::from_utf8(&size.value).ok()?.parse().ok()
+                                    });
+                                }
+                                _ => {}
+                            }
+                        }
+                        if name == b"w:shd" && paragraph.depth + 2 == depth {
+                            paragraph.fill = shading_color(e)?;
+                        }
+                        if name == b"w:pStyle" && paragraph.depth + 2 == depth {
+                            let style = e.try_get_attribute("w:val")?;
+                            paragraph.preformatted = style.is_some_and(|style| {
+                                self.config
+                                    .preformatted_styles
+                                    .iter()
+                                    .any(|s| s.as_bytes() == style.value.as_ref())
+                            });
+                        }
+                    }
+                    // A complex field runs from its begin to its end character, which may be in
+                    // other paragraphs
+                    if name == b"w:fldChar" {
+                        match e.try_get_attribute("w:fldCharType")? {
+                            Some(a) if a.value.as_ref() == b"begin" => field_depth += 1,
+                            Some(a) if a.value.as_ref() == b"end" => {
+                                field_depth = field_depth.saturating_sub(1)
+                            }
+                            _ => {}
+                        }
+                    }
+                    if matches!(event, Event::Empty(_)) {
+                        continue;
+                    }
+
+                    match name {
+                        b"m:t" if deleted_depth.is_none() => in_math_text = true,
+                        b"w:fldSimple" => field_depth += 1,
+                        b"w:body" => body_depth = Some(depth),
+                        b"w:tc" => cell_fills.push((depth, None)),
+                        b"w:p" => {
+                            paragraph_count += 1;
+                            paragraphs.push(ParagraphState {
+                                depth,
+                                run_depth: None,
+                                preformatted: false,
+                                after_space: false,
+                                ruby_after_space: None,
+                                after_break: false,
+                                index: paragraph_count,
+                                runs: 0,
+                                text: Vec::new(),
+                                found: Vec::new(),
+                                pending_run: None,
+                                run_start: 0,
+                                hidden: false,
+                                hidden_chars: 0,
+                                removable: true,
+                                in_field: false,
+                                fill: None,
+                                format: RunFormat::default(),
+                                invisible: None,
+                                theme_colored: false,
+                                run_text: Vec::new(),
+                                mark: if prune { splice.mark(start) } else { 0 },
+                                in_body: body_depth.is_some_and(|body| body + 1 == depth),
+                                had_text: false,
+                                has_text: false,
+                                prunable: true,
+                                run_mark: 0,
+                                run_had_text: false,
+                                run_has_text: false,
+                                run_bare: true,
+                            });
+                        }
+                        b"w:r" => {
+                            if let Some(paragraph) = paragraphs.last_mut() {
+                                paragraph.run_depth = Some(depth);
+                                paragraph.runs += 1;
+                                paragraph.run_start = start;
+                                paragraph.hidden = false;
+                                paragraph.hidden_chars = 0;
+                                paragraph.removable = true;
+                                paragraph.in_field = field_depth > 0;
+                                paragraph.format = RunFormat::default();
+                                paragraph.invisible = None;
+                                paragraph.theme_colored = false;
+                                paragraph.run_text.clear();
+                                if prune {
+                                    paragraph.run_mark = splice.mark(start);
+                                }
+                                paragraph.run_had_text = false;
+                                paragraph.run_has_text = false;
+                                paragraph.run_bare = true;
+                                let tracked = revision.is_some() && inserted_depth.is_none();
+                                if tracked || self.comments.is_some() || highlight.is_some() {
+                                    paragraph.pending_run = Some(PendingRun {
+                                        start,
+                                        open_end: reader.buffer_position() as usize,
+                                        properties: None,
+                                        edits: Vec::new(),
+                                        tracked,
+                                        comments: Vec::new(),
+                                    });
+                                }
+                            }
+                        }
+                        // The ruby text of a phonetic guide is set above its base, so the spaces
+                        // of each are collapsed on their own and the base goes on from the text
+                        // before it
+                        b"w:rt" => {
+                            if let Some(paragraph) = paragraphs.last_mut() {
+                                paragraph.ruby_after_space =
+                                    Some(std::mem::take(&mut paragraph.after_space));
+                            }
+                        }
+                        b"w:rubyBase" => {
+                            if let Some(paragraph) = paragraphs.last_mut() {
+                                if let Some(after_space) = paragraph.ruby_after_space.take() {
+                                    paragraph.after_space = after_space;
+                                }
+                            }
+                        }
+                        b"w:del" if deleted_depth.is_none() => deleted_depth = Some(depth),
+                        b"w:ins" if inserted_depth.is_none() => inserted_depth = Some(depth),
+                        // separator, continuationSeparator and continuationNotice carry no user
+                        // text
+                        b"w:footnote" | b"w:endnote" => {
+                            in_separator = e
+                                .try_get_attribute("w:type")?
+                                .is_some_and(|a| a.value.as_ref() != b"normal");
+                        }
+                        b"w:txbxContent" => {
+                            // The run holding the text box is edited in place, since the
+                            // changes in the text box are spliced in before the run ends. Its
+                            // comments so far are anchored just before it.
+                            if let Some(run) =
+                                paragraphs.last_mut().and_then(|p| p.pending_run.take())
+                            {
+                                let comments =
+                                    comment_starts(&run.comments) + &comment_ends(&run.comments);
+                                splice.insert(run.start, &comments);
+                                for (start, end, markup) in run.edits {
+                                    splice.replace_xml(start, end, markup.as_bytes());
+                                }
+                            }
+                            text_boxes.push((depth, std::mem::replace(&mut self.part, "textbox")));
+                        }
+                        b"mc:Fallback" if fallback.is_none() => {
+                            fallback = Some((depth, std::mem::take(&mut self.report)));
+                        }
+                        _ => {}
+                    }
+                    depth += 1;
+                }
+                Event::End(e) => {
+                    depth -= 1;
+                    match e.name().as_ref() {
+                        b"w:p" if paragraphs.last().is_some_and(|p| p.depth == depth) => {
+                            if let Some(paragraph) = paragraphs.pop() {
+                                if prune
+                                    && revision.is_none()
+                                    && paragraph.in_body
+                                    && paragraph.prunable
+                                    && paragraph.had_text
+                                    && !paragraph.has_text
+                                {
+                                    splice.cut(paragraph.mark, reader.buffer_position() as usize);
+                                    self.report.pruned_paragraphs += 1;
+                                }
+                                for (i, run) in paragraph.found {
+                                    let replacement = self.locate(
+                                        &paragraph.text,
+                                        i,
+                                        Some(paragraph.index),
+                                        Some(run),
+                                    );
+                                    self.report.replacements.push(replacement);
+                                }
+                                self.part_offset += paragraph.text.len();
+                            }
+                        }
+                        b"w:r" => {
+                            if let Some(paragraph) =
+                                paragraphs.last_mut().filter(|p| p.run_depth == Some(depth))
+                            {
+                                paragraph.run_depth = None;
+                                let end = reader.buffer_position() as usize;
+                                // Runs of spaces are often made small to fine-tune the layout
+                                let visible = paragraph.run_text.iter().any(|c| !c.is_whitespace());
+                                if !visible {
+                                    paragraph.invisible = None;
+                                }
+                                let remove = paragraph.removing(remove_hidden, remove_invisible);
+                                if paragraph.hidden {
+                                    self.report.hidden_runs += 1;
+                                    self.report.hidden_chars += paragraph.hidden_chars;
+                                    if paragraph.in_field {
+                                        self.report.hidden_in_fields += 1;
+                                    } else if remove {
+                                        self.report.hidden_removed += 1;
+                                    }
+                                }
+                                if let Some(formatting) = paragraph.invisible.take() {
+                                    self.report.invisible_runs.push(InvisibleRun {
+                                        path: String::new(),
+                                        part: self.part,
+                                        paragraph: paragraph.index,
+                                        run: paragraph.runs,
+                                        formatting,
+                                        snippet: self.snippet(&paragraph.run_text),
+                                        removed: remove,
+                                    });
+                                } else if paragraph.theme_colored && visible {
+                                    self.report.theme_colored_runs += 1;
+                                }
+                                let emptied = prune
+                                    && paragraph.run_bare
+                                    && paragraph.run_had_text
+                                    && !paragraph.run_has_text;
+                                if emptied && !remove {
+                                    self.report.pruned_runs += 1;
+                                    // Takes back the text spliced into it
+                                    splice.cut(paragraph.run_mark, paragraph.run_start);
+                                } else if !remove && !paragraph.run_bare {
+                                    paragraph.prunable = false;
+                                }
+                                if remove || emptied {
+                                    // Its text was left alone or is gone, so the pending run
+                                    // holds nothing to keep
+                                    paragraph.pending_run = None;
+                                    let revision = revision.filter(|_| inserted_depth.is_none());
+                                    revision_id = splice.delete_run(
+                                        paragraph.run_start,
+                                        end,
+                                        revision,
+                                        revision_id,
+                                    );
+                                }
+                                if let Some(run) = paragraph.pending_run.take() {
+                                    revision_id =
+                                        splice.replace_run(run, end, revision, revision_id);
+                                }
+                            }
+                        }
+                        b"w:rPr" => {
+                            let Some(paragraph) = paragraphs
+                                .last_mut()
+                                .filter(|p| p.run_depth.is_some_and(|run| run + 1 == depth))
+                            else {
+                                continue;
+                            };
+                            let behind = paragraph
+                                .fill
+                                .as_ref()
+                                .or(cell_fills.iter().rev().find_map(|(_, fill)| fill.as_ref()))
+                                .or(page_color.as_ref());
+                            (paragraph.invisible, paragraph.theme_colored) =
+                                paragraph.format.invisible(behind);
+                            let run = paragraph.pending_run.as_mut();
+                            if let Some((_, end)) = run.and_then(|run| run.properties.as_mut()) {
+                                *end = reader.buffer_position() as usize;
+                            }
+                        }
+                        b"w:t" => text_preserve = None,
+                        b"w:instrText" => in_field_code = false,
+                        b"m:t" => in_math_text = false,
+                        b"w:fldSimple" => field_depth = field_depth.saturating_sub(1),
+                        b"w:tc" if cell_fills.last().is_some_and(|(d, _)| *d == depth) => {
+                            cell_fills.pop();
+                        }
+                        b"w:del" if deleted_depth == Some(depth) => deleted_depth = None,
+                        b"w:ins" if inserted_depth == Some(depth) => inserted_depth = None,
+                        b"w:footnote" | b"w:endnote" => in_separator = false,
+                        b"w:txbxContent" if text_boxes.last().is_some_and(|(d, _)| *d == depth) => {
+                            if let Some((_, part)) = text_boxes.pop() {
+                                self.part = part;
+                            }
+                        }
+                        b"mc:Fallback" if fallback.as_ref().is_some_and(|(d, _)| *d == depth) => {
+                            if let Some((_, report)) = fallback.take() {
+                                self.report = report;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                Event::Text(t) if in_field_code && !in_separator => {
+                    let original = t.unescape()?;
+                    let cleaned = self.clean_field_code(&original);
+                    if cleaned != original {
+                        let end = reader.buffer_position() as usize;
+                        match paragraphs.last_mut().and_then(|p| p.pending_run.as_mut()) {
+                            Some(run) => {
+                                let markup = quick_xml::escape::partial_escape(cleaned.as_ref());
+                                run.edits.push((start, end, markup.into_owned()));
+                            }
+                            None => splice.replace(start, end, &cleaned),
+                        }
+                    }
+                }
+                Event::Text(t) if in_math_text && !in_separator => {
+                    let original = t.unescape()?;
+                    let cleaned = self.clean_math_text(&original);
+                    if cleaned != original {
+                        let end = reader.buffer_position() as usize;
+                        splice.replace(start, end, &cleaned);
+                    }
+                }
+                Event::Text(t) if !in_separator => {
+                    let Some(preserve_space) = text_preserve else {
+                        continue;
+                    };
+                    let original = t.unescape()?;
+                    // The text of a run that is going to be removed is not cleaned. Should the run
+                    // turn out to hold more than text, it is kept as it was.
+                    if let Some(paragraph) = paragraphs.last_mut() {
+                        paragraph.had_text = true;
+                        paragraph.run_had_text = true;
+                        if paragraph.hidden {
+                            paragraph.hidden_chars += original.chars().count();
+                        }
+                        if paragraph.invisible.is_some() || paragraph.theme_colored {
+                            paragraph.run_text.extend(original.chars());
+                        }
+                        if paragraph.removing(remove_hidden, remove_invisible) {
+                            continue;
+                        }
+                    }
+                    let replaced = self.replace_chars(&original);
+                    let positions = self.take_positions();
+                    let marked = highlight
+                        .filter(|_| !positions.is_empty())
+                        .map(|_| self.mark_replacements(&original, &positions));
+                    // Fallback content is not counted, so its replacements are not listed or
+                    // commented on either
+                    if let Some(paragraph) = paragraphs.last_mut().filter(|_| fallback.is_none()) {
+                        if let Some(run) = paragraph.pending_run.as_mut() {
+                            for &(_, c) in &positions {
+                                run.comments.extend(self.add_comment(c));
+                            }
+                        }
+                        if self.positions.is_some() {
+                            let offset = paragraph.text.len();
+                            paragraph.found.extend(
+                                positions
+                                    .into_iter()
+                                    .map(|(i, _)| (offset + i, paragraph.runs)),
+                            );
+                            paragraph.text.extend(original.chars());
+                        }
+                    }
+                    let cleaned = match paragraphs.last_mut() {
+                        Some(paragraph) if !paragraph.preformatted => {
+                            let collapsed = self.collapse_run_spaces(
+                                &replaced,
+                                preserve_space,
+                                &mut paragraph.after_space,
+                            );
+                            let before_break = self.config.trim_around_breaks
+                                && followed_by_break(xml, reader.buffer_position() as usize);
+                            let trimmed = self.trim_around_breaks(
+                                collapsed,
+                                paragraph.after_break,
+                                before_break,
+                            );
+                            paragraph.after_break &= trimmed.is_empty();
+                            trimmed
+                        }
+                        _ => Cow::Borrowed(replaced.as_ref()),
+                    };
+                    let cleaned = self.finish(cleaned);
+                    if let Some(paragraph) = paragraphs.last_mut().filter(|_| !cleaned.is_empty()) {
+                        paragraph.has_text = true;
+                        paragraph.run_has_text = true;
+                    }
+                    if cleaned != original {
+                        let end = reader.buffer_position() as usize;
+                        match paragraphs.last_mut().and_then(|p| p.pending_run.as_mut()) {
+                            Some(run) => {
+                                let marks =
+                                    marked.map(|marked| self.mark_collapsed(&marked, &cleaned));
+                                let edit = match (highlight, marks) {
+                                    (Some(color), Some(marks)) if marks.contains(&true) => {
+                                        let text = TextAt {
+                                            start: text_start,
+                                            end,
+                                            preserve_space,
+                                        };
+                                        highlight_text(xml, run, text, &cleaned, &marks, color)
+                                    }
+                                    _ => {
+                                        let markup =
+                                            quick_xml::escape::partial_escape(cleaned.as_ref());
+                                        (start, end, markup.into_owned())
+                                    }
+                                };
+                                run.edits.push(edit);
+                            }
+                            None => splice.replace(start, end, &cleaned),
+                        }
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        // A paragraph in a text box ends before the paragraph holding it; the part is cleaned
+        // with a report of its own, so this puts its replacements in document order
+        self.report
+            .replacements
+            .sort_by_key(|replacement| replacement.paragraph);
+        let cleaned = splice.finish();
+        if !self.config.merge_runs {
+            return Ok(cleaned);
+        }
+        let (merged, count) = merge_runs(cleaned.as_deref().unwrap_or(xml));
+        self.report.merged_runs += count;
+        Ok(merged.filter(|_| self.rewrite).or(cleaned))
+    }
+
+    // Cleans the text of the given elements, e.g. the document properties
+    fn clean_element_xml(
+        &mut self,
+        xml: &[u8],
+        text_elements: &[&str],
+    ) -> Result<Option<Vec<u8>>, quick_xml::Error> {
+        let mut reader = Reader::from_reader(xml);
+        let mut splice = Splice::new(xml, self.rewrite);
+        let mut in_text = false;
+
+        loop {
+            let start = reader.buffer_position() as usize;
+            match reader.read_event()? {
+                Event::Start(e) => in_text = is_text_element(e.name().as_ref(), text_elements),
+                Event::End(_) => in_text = false,
+                Event::Text(t) if in_text => {
+                    let original = t.unescape()?;
+                    let cleaned = self.clean_text(&original);
+                    let positions = self.take_positions();
+                    self.locate_all(&original, positions, None, None);
+                    if cleaned != original {
+                        splice.replace(start, reader.buffer_position() as usize, &cleaned);
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(splice.finish())
+    }
+
+    // Cleans the a:t text of a slide or its notes, each run on its own. The paragraphs and runs
+    // of the locations are counted from the start of the part, across its shapes.
+    fn clean_drawing_xml(&mut self, xml: &[u8]) -> Result<Option<Vec<u8>>, quick_xml::Error> {
+        let mut reader = Reader::from_reader(xml);
+        let mut splice = Splice::new(xml, self.rewrite);
+        let mut paragraph = 0;
+        let mut run = 0;
+        let mut in_text = false;
+
+        loop {
+            let start = reader.buffer_position() as usize;
+            match reader.read_event()? {
+                Event::Start(e) => match e.name().as_ref() {
+                    b"a:p" => {
+                        paragraph += 1;
+                        run = 0;
+                    }
+                    // Fields, like the slide number, hold their text like a run
+                    b"a:r" | b"a:fld" => run += 1,
+                    b"a:t" => in_text = true,
+                    _ => {}
+                },
+                Event::End(_) => in_text = false,
+                Event::Text(t) if in_text => {
+                    let original = t.unescape()?;
+                    let cleaned = self.clean_text(&original);
+                    let positions = self.take_positions();
+                    self.locate_all(&original, positions, Some(paragraph), Some(run));
+                    if cleaned != original {
+                        splice.replace(start, reader.buffer_position() as usize, &cleaned);
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(splice.finish())
+    }
+}
+
+// Metadata
+impl Pass<'_> {
+    // Clears the metadata of Config::scrub_metadata on top of the cleaned parts, so that a part
+    // is written anew when only its metadata changed. Returns the parts to leave out of the
+    // package. OpenDocument texts are left as they are.
+    fn scrub_metadata(
+        &mut self,
+        source: Source,
+        parts: &mut HashMap<String, Vec<u8>>,
+        scrub: MetadataScrub,
+    ) -> Result<Vec<String>, Error> {
+        let mut archive = source.open()?;
+        if archive.by_name(CONTENT_TYPES_PATH).is_err() {
+            return Ok(Vec::new());
+        }
+        let package = relationships(&mut archive, "");
+        let target = |kind: &str| {
+            package
+                .iter()
+                .find(|(_, relationship, _)| relationship == kind)
+                .map(|(_, _, target)| target.clone())
+        };
+
+        let mut core = Vec::new();
+        if scrub.author {
+            core.extend([("dc:creator", ""), ("cp:lastModifiedBy", "")]);
+        }
+        if scrub.revision {
+            core.push(("cp:revision", "1"));
+        }
+        let mut app = Vec::new();
+        if scrub.company {
+            app.push(("Company", ""));
+        }
+        if scrub.time {
+            app.push(("TotalTime", "0"));
+        }
+        if scrub.template {
+            app.push(("Template", ""));
+        }
+        for (path, elements) in [
+            (target(CORE_PROPERTIES_RELATIONSHIP), core),
+            (target(APP_PROPERTIES_RELATIONSHIP), app),
+        ] {
+            if let (Some(path), false) = (path, elements.is_empty()) {
+                edit_part(&mut archive, parts, &path, |xml| {
+                    let (cleared, scrubbed) = clear_elements(xml, &elements);
+                    self.report.scrubbed.extend(scrubbed);
+                    cleared
+                });
+            }
+        }
+
+        if scrub.rsids {
+            let TextParts {
+                parts: text_parts, ..
+            } = find_text_parts(&mut archive)?;
+            let mut paths: Vec<String> = text_parts
+                .into_iter()
+                .filter(|(_, _, text)| matches!(text, PartText::Paragraphs))
+                .map(|(path, _, _)| path)
+                .collect();
+            if let Some(main_path) = target(MAIN_DOCUMENT_RELATIONSHIP) {
+                for (_, relationship, target) in relationships(&mut archive, &main_path) {
+                    if relationship == STYLES_RELATIONSHIP || relationship == SETTINGS_RELATIONSHIP
+                    {
+                        paths.push(target);
+                    }
+                }
+            }
+            for path in paths {
+                edit_part(&mut archive, parts, &path, |xml| {
+                    let (stripped, removed) = strip_rsids(xml);
+                    self.report.rsids_removed += removed;
+                    stripped
+                });
+            }
+        }
+
+        let mut removed = Vec::new();
+        if let Some(path) = target(THUMBNAIL_RELATIONSHIP).filter(|_| scrub.thumbnail) {
+            edit_part(&mut archive, parts, PACKAGE_RELS_PATH, |xml| {
+                remove_elements(xml, b"Relationship", "Type", THUMBNAIL_RELATIONSHIP)
+            });
+            let part_name = format!("/{}", path);
+            edit_part(&mut archive, parts, CONTENT_TYPES_PATH, |xml| {
+                remove_elements(xml, b"Override", "PartName", &part_name)
+            });
+            self.report
+                .scrubbed
+                .push(("thumbnail".to_string(), path.clone()));
+            removed.push(path);
+        }
+        Ok(removed)
+    }
+}
+
+// Spreadsheets
+impl Pass<'_> {
+    // Cleans the strings of a spreadsheet: the shared strings (si) and the inline strings (is) of
+    // a worksheet. Formulas, numbers and the cached results of formulas are left alone, as are
+    // the phonetic readings (rPh) of a string. The strings and the runs of rich text are counted
+    // from 1 for the locations.
+    fn clean_cell_xml(&mut self, xml: &[u8]) -> Result<Option<Vec<u8>>, quick_xml::Error> {
+        let mut reader = Reader::from_reader(xml);
+        let mut splice = Splice::new(xml, self.rewrite);
+        let mut string = 0;
+        let mut run = None;
+        let mut in_string = false;
+        let mut in_phonetic = false;
+        let mut in_text = false;
+
+        loop {
+            let start = reader.buffer_position() as usize;
+            match reader.read_event()? {
+                Event::Start(e) => match e.local_name().as_ref() {
+                    b"si" | b"is" => {
+                        string += 1;
+                        run = None;
+                        in_string = true;
+                    }
+                    b"r" if in_string => run = Some(run.unwrap_or(0) + 1),
+                    b"rPh" => in_phonetic = true,
+                    b"t" => in_text = in_string && !in_phonetic,
+                    _ => {}
+                },
+                Event::Empty(e) if matches!(e.local_name().as_ref(), b"si" | b"is") => string += 1,
+                Event::End(e) => match e.local_name().as_ref() {
+                    b"si" | b"is" => in_string = false,
+                    b"rPh" => in_phonetic = false,
+                    b"t" => in_text = false,
+                    _ => {}
+                },
+                Event::Text(t) if in_text => {
+                    let original = t.unescape()?;
+                    let cleaned = self.clean_cell_text(&original);
+                    let positions = self.take_positions();
+                    self.locate_all(&original, positions, Some(string), run);
+                    if cleaned != original {
+                        splice.replace(start, reader.buffer_position() as usize, &cleaned);
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(splice.finish())
+    }
+
+    // A line feed is a line break in a cell, so each line is cleaned on its own. Spaces can be
+    // meaningful in a cell, e.g. to a lookup, and are only collapsed with collapse_cell_spaces.
+    fn clean_cell_text(&mut self, text: &str) -> String {
+        let mut cleaned = String::with_capacity(text.len());
+        let mut positions = Vec::new();
+        let mut offset = 0;
+        for (n, line) in text.split('\n').enumerate() {
+            if n > 0 {
+                cleaned.push('\n');
+            }
+            let replaced = self.replace_chars(line);
+            let collapsed = if self.config.collapse_cell_spaces {
+                self.collapse_spaces(&replaced, true)
+            } else {
+                Cow::Borrowed(replaced.as_ref())
+            };
+            cleaned.push_str(&self.finish(collapsed));
+            let line_positions = self.take_positions();
+            positions.extend(line_positions.into_iter().map(|(i, c)| (i + offset, c)));
+            offset += line.chars().count() + 1;
+        }
+        if let Some(recorded) = &mut self.positions {
+            *recorded = positions;
+        }
+        cleaned
+    }
+}
+
+// OpenDocument text
+impl Pass<'_> {
+    // Cleans the text of the paragraphs and headings (text:p, text:h) of an OpenDocument part,
+    // spans and links included. Spaces in the text itself are already collapsed by the format: a
+    // run of them is written as a space followed by a text:s element, so collapsing it takes out
+    // the text:s. A text:s after anything but a space stands for spaces of its own and is
+    // shortened to one.
+    fn clean_open_document_xml(&mut self, xml: &[u8]) -> Result<Option<Vec<u8>>, quick_xml::Error> {
+        let mut reader = Reader::from_reader(xml);
+        let mut splice = Splice::new(xml, self.rewrite);
+        let collapse = self.config.space_collapse() != SpaceCollapse::Off;
+        let mut paragraph = 0;
+        // Paragraphs nest in notes and frames
+        let mut depth = 0;
+        let mut after_space = false;
+
+        loop {
+            let start = reader.buffer_position() as usize;
+            match reader.read_event()? {
+                Event::Start(e) if matches!(e.name().as_ref(), b"text:p" | b"text:h") => {
+                    depth += 1;
+                    paragraph += 1;
+                    after_space = false;
+                }
+                Event::End(e) if matches!(e.name().as_ref(), b"text:p" | b"text:h") => {
+                    depth -= 1;
+                    after_space = false;
+                }
+                Event::Empty(e) if depth > 0 => match e.name().as_ref() {
+                    b"text:s" => {
+                        let spaces = e
+                            .try_get_attribute("text:c")
+                            .ok()
+                            .flatten()
+                            .and_then(|c| std::str::from_utf8(&c.value).ok()?.parse().ok())
+                            .unwrap_or(1);
+                        let end = reader.buffer_position() as usize;
+                        if collapse && after_space {
+                            self.report.collapsed_stretches += 1;
+                            self.report.collapsed_spaces += spaces;
+                            splice.replace_xml(start, end, b"");
+                        } else if collapse && spaces > 1 {
+                            self.report.collapsed_stretches += 1;
+                            self.report.collapsed_spaces += spaces - 1;
+                            splice.replace_xml(start, end, b"<text:s/>");
+                        }
+                        after_space = true;
+                    }
+                    b"text:tab" | b"text:line-break" => after_space = false,
+                    _ => {}
+                },
+                Event::Text(t) if depth > 0 => {
+                    let original = t.unescape()?;
+                    let replaced = self.replace_chars(&original);
+                    let cleaned = self.finish(replaced);
+                    let positions = self.take_positions();
+                    self.locate_all(&original, positions, Some(paragraph), None);
+                    if !cleaned.is_empty() {
+                        after_space = cleaned.ends_with(' ');
+                    }
+                    if cleaned != original {
+                        splice.replace(start, reader.buffer_position() as usize, &cleaned);
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(splice.finish())
+    }
+}
+
+// The shared string index of each cell of a worksheet that shows one (t="s"), once per cell
+fn shared_string_cells(xml: &[u8]) -> Vec<usize> {
+    let mut reader = Reader::from_reader(xml);
+    let mut indexes = Vec::new();
+    let mut in_shared_cell = false;
+    let mut in_value = false;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => match e.local_name().as_ref() {
+                b"c" => {
+                    in_shared_cell = e
+                        .try_get_attribute("t")
+                        .ok()
+                        .flatten()
+                        .is_some_and(|t| t.value.as_ref() == b"s")
+                }
+                b"v" => in_value = in_shared_cell,
+                _ => {}
+            },
+            Ok(Event::End(e)) => match e.local_name().as_ref() {
+                b"c" => in_shared_cell = false,
+                b"v" => in_value = false,
+                _ => {}
+            },
+            Ok(Event::Text(t)) if in_value => {
+                let index = std::str::from_utf8(&t)
+                    .ok()
+                    .and_then(|v| v.trim().parse::<usize>().ok());
+                indexes.extend(index);
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    indexes
+}
+
+// Text files, see Cleaner::clean_text
+impl Pass<'_> {
+    fn clean_text_lines(&mut self, text: &str, markdown: bool) -> String {
+        let mut cleaned = String::with_capacity(text.len());
+        let mut text = text;
+        if let Some(rest) = text.strip_prefix(BYTE_ORDER_MARK) {
+            self.report.count(BYTE_ORDER_MARK, self.part);
+            if self.positions.is_some() {
+                let line: Vec<char> = text.lines().next().unwrap_or_default().chars().collect();
+                let replacement = self.locate(&line, 0, Some(1), None);
+                self.report.replacements.push(replacement);
+            }
+            text = rest;
+        }
+
+        let mut in_fence = false;
+        for (i, line) in text.split_inclusive('\n').enumerate() {
+            let content = line.strip_suffix('\n').unwrap_or(line);
+            let content = content.strip_suffix('\r').unwrap_or(content);
+            let ending = &line[content.len()..];
+
+            let replaced = self.replace_chars(content);
+            let positions = self.take_positions();
+            self.locate_all(content, positions, Some(i + 1), None);
+            // Code blocks are left as they are, like the preformatted styles of a document
+            let fence = markdown && is_code_fence(content);
+            let collapsed = if fence || in_fence {
+                Cow::Borrowed(replaced.as_ref())
+            } else {
+                self.collapse_spaces(&replaced, true)
+            };
+            cleaned.push_str(&self.finish(collapsed));
+            in_fence ^= fence;
+            cleaned.push_str(ending);
+        }
+        cleaned
+    }
+}
+
+const BYTE_ORDER_MARK: char = '\u{FEFF}';
+
+// ``` or ~~~, indented by up to three spaces
+fn is_code_fence(line: &str) -> bool {
+    let trimmed = line.trim_start_matches(' ');
+    line.len() - trimmed.len() <= 3 && (trimmed.starts_with("```") || trimmed.starts_with("~~~"))
+}
+
+// Locating replacements for Config::record_locations
+impl Pass<'_> {
+    // The text after replace_chars with the characters Config::highlight marks: what replaced
+    // characters were replaced with and the characters on either side of them
+    fn mark_replacements(&self, original: &str, positions: &[(usize, char)]) -> Vec<(char, bool)> {
+        let replaced: HashSet<usize> = positions.iter().map(|&(i, _)| i).collect();
+        let mut marked = Vec::new();
+        for (i, c) in original.chars().enumerate() {
+            if replaced.contains(&i) {
+                marked.extend(self.config.replacement(c).chars().map(|c| (c, true)));
+            } else {
+                let beside = i > 0 && replaced.contains(&(i - 1)) || replaced.contains(&(i + 1));
+                marked.push((c, beside));
+            }
+        }
+        marked
+    }
+
+    // The marks carried over to the text after collapse_run_spaces, which only takes out spaces:
+    // the other characters keep theirs, and a space is marked when the text around it is
+    fn mark_collapsed(&self, marked: &[(char, bool)], cleaned: &str) -> Vec<bool> {
+        let chars: Vec<char> = cleaned.chars().collect();
+        if chars.len() == marked.len() {
+            return marked.iter().map(|&(_, mark)| mark).collect();
+        }
+        let is_space = |c: char| match self.config.collapse_spaces {
+            SpaceCollapse::Whitespace => {
+                c == ' ' || c.general_category() == GeneralCategory::SpaceSeparator
+            }
+            _ => c == ' ',
+        };
+        let mut others = marked
+            .iter()
+            .filter(|&&(c, _)| !is_space(c))
+            .map(|&(_, mark)| mark);
+        let marks: Vec<Option<bool>> = chars
+            .iter()
+            .map(|&c| if is_space(c) { None } else { others.next() })
+            .collect();
+        (0..marks.len())
+            .map(|i| {
+                marks[i].unwrap_or_else(|| {
+                    let before = marks[..i].iter().rev().find_map(|mark| *mark);
+                    let after = marks[i + 1..].iter().find_map(|mark| *mark);
+                    (before.is_some() || after.is_some())
+                        && before.unwrap_or(true)
+                        && after.unwrap_or(true)
+                })
+            })
+            .collect()
+    }
+
+    // Adds a comment on a replaced character, returning its id; None when no comments are added
+    // to the part or Config::max_comments is reached
+    fn add_comment(&mut self, c: char) -> Option<usize> {
+        let action = if !self.rewrite {
+            "Found"
+        } else if self.config.replacement(c).is_empty() {
+            "Removed"
+        } else {
+            "Replaced"
+        };
+        let text = format!("{} U+{:04X} {}", action, c as u32, self.config.name(c));
+        let comments = self.comments.as_mut()?;
+        if comments.texts.len() >= self.config.max_comments {
+            return None;
+        }
+        comments.texts.push(text);
+        Some(comments.first_id + comments.texts.len() - 1)
+    }
+
+    // The replacements of the last replace_chars call, as (index in the text, character); empty
+    // when locations are not recorded
+    fn take_positions(&mut self) -> Vec<(usize, char)> {
+        self.positions
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    // Lists the replacements of the last replace_chars call on the given text, which then counts
+    // for the offsets of those in the rest of the part
+    fn locate_all(
+        &mut self,
+        original: &str,
+        positions: Vec<(usize, char)>,
+        paragraph: Option<usize>,
+        run: Option<usize>,
+    ) {
+        if self.positions.is_none() {
+            return;
+        }
+        let text: Vec<char> = original.chars().collect();
+        for (i, _) in positions {
+            let replacement = self.locate(&text, i, paragraph, run);
+            self.report.replacements.push(replacement);
+        }
+        self.part_offset += text.len();
+    }
+
+    // The replacement of text[i] in the current part, with the context around it and the
+    // start of the text as its snippet
+    fn locate(
+        &self,
+        text: &[char],
+        i: usize,
+        paragraph: Option<usize>,
+        run: Option<usize>,
+    ) -> Replacement {
+        let before = &text[i.saturating_sub(CONTEXT_CHARS)..i];
+        let after = &text[i + 1..text.len().min(i + 1 + CONTEXT_CHARS)];
+        Replacement {
+            path: String::new(),
+            part: self.part,
+            paragraph,
+            run,
+            character: text[i],
+            before: self.render_context(before),
+            after: self.render_context(after),
+            snippet: self.snippet(text),
+            offset: self.part_offset + i,
+            position: 0.0,
+        }
+    }
+
+    // The start of a paragraph, without the spaces before it, ending in "…" when it is cut off
+    fn snippet(&self, text: &[char]) -> String {
+        let start = text
+            .iter()
+            .position(|c| !c.is_whitespace())
+            .unwrap_or(text.len());
+        let end = text.len().min(start + SNIPPET_CHARS);
+        let mut snippet = self.render_context(&text[start..end]);
+        if end < text.len() {
+            snippet.push('\u{2026}');
+        }
+        snippet
+    }
+
+    // The listed characters are shown as code points, as is anything else that would not be
+    // visible in the report
+    fn render_context(&self, chars: &[char]) -> String {
+        render_text(self.rules, chars.iter().copied())
+    }
+}
+
+fn render_text(rules: &Rules, chars: impl Iterator<Item = char>) -> String {
+    let mut rendered = String::new();
+    for c in chars {
+        if rules.is_listed(c) || is_invisible(c) {
+            rendered.push_str(&render_invisible(c));
+        } else {
+            rendered.push(c);
+        }
+    }
+    rendered
+}
+
+// Controls, format characters, line and paragraph separators and unusual spaces
+fn is_invisible(c: char) -> bool {
+    match c.general_category() {
+        GeneralCategory::Control
+        | GeneralCategory::Format
+        | GeneralCategory::LineSeparator
+        | GeneralCategory::ParagraphSeparator
+        | GeneralCategory::PrivateUse
+        | GeneralCategory::Surrogate
+        | GeneralCategory::Unassigned => true,
+        GeneralCategory::SpaceSeparator => c != ' ',
+        _ => false,
+    }
+}
+
+// e.g. ⟨U+200B⟩
+fn render_invisible(c: char) -> String {
+    format!("\u{27E8}U+{:04X}\u{27E9}", c as u32)
+}
+
+// Adds the comments to the comments part of the package, or to a new comments.xml next to the
+// main document part along with its relationship and content type
+fn add_comments(
+    archive: &mut Archive,
+    parts: &mut HashMap<String, Vec<u8>>,
+    main_path: &str,
+    comments_path: Option<&str>,
+    comments: &Comments,
+    revision: &Revision,
+) -> Result<(), Error> {
+    let mut markup = String::new();
+    for (i, text) in comments.texts.iter().enumerate() {
+        markup.push_str(&format!(
+            "<w:comment w:id=\"{}\" w:author=\"{}\" w:date=\"{}\"><w:p><w:r><w:t>{}</w:t></w:r></w:p></w:comment>",
+            comments.first_id + i,
+            quick_xml::escape::escape(revision.author.as_str()),
+            revision.date,
+            quick_xml::escape::escape(text.as_str())
+        ));
+    }
+
+    if let Some(path) = comments_path {
+        // The comments part may have been cleaned as well
+        let xml = match parts.remove(path) {
+            Some(xml) => xml,
+            None => read_zip_entry(archive, path).unwrap_or_default(),
+        };
+        let xml = insert_before(&xml, "</w:comments>", &markup)
+            .ok_or_else(|| Error::InvalidDocx(format!("{} holds no comments element", path)))?;
+        parts.insert(path.to_string(), xml);
+        return Ok(());
+    }
+
+    let folder = match main_path.rsplit_once('/') {
+        Some((folder, _)) => format!("{}/", folder),
+        None => String::new(),
+    };
+    let path = format!("{}comments.xml", folder);
+    parts.insert(
+        path.clone(),
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <w:comments xmlns:w=\"{}\">{}</w:comments>",
+            WORDPROCESSINGML_NAMESPACE, markup
+        )
+        .into_bytes(),
+    );
+
+    let rels_path = rels_path(main_path);
+    let rels = read_zip_entry(archive, &rels_path).unwrap_or_else(|| {
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <Relationships xmlns=\"{}\"></Relationships>",
+            RELATIONSHIPS_NAMESPACE
+        )
+        .into_bytes()
+    });
+    let id = (1..)
+        .map(|n| format!("rId{}", n))
+        .find(|id| !contains(&rels, format!("Id=\"{}\"", id).as_bytes()))
+        .unwrap_or_default();
+    let relationship = format!(
+        "<Relationship Id=\"{}\" Type=\"{}\" Target=\"comments.xml\"/>",
+        id, COMMENTS_RELATIONSHIP
+    );
+    let rels = insert_before(&rels, "</Relationships>", &relationship)
+        .ok_or_else(|| Error::InvalidDocx(format!("{} holds no relationships", rels_path)))?;
+    parts.insert(rels_path, rels);
+
+    let content_types = read_zip_entry(archive, CONTENT_TYPES_PATH).unwrap_or_default();
+    let content_type = format!(
+        "<Override PartName=\"/{}\" ContentType=\"{}\"/>",
+        path, COMMENTS_CONTENT_TYPE
+    );
+    let content_types = insert_before(&content_types, "</Types>", &content_type)
+        .ok_or_else(|| Error::InvalidDocx(format!("{} is damaged", CONTENT_TYPES_PATH)))?;
+    parts.insert(CONTENT_TYPES_PATH.to_string(), content_types);
+    Ok(())
+}
+
+// The XML with the markup put before its last end tag, None without that end tag
+fn insert_before(xml: &[u8], end_tag: &str, markup: &str) -> Option<Vec<u8>> {
+    let at = xml
+        .windows(end_tag.len())
+        .rposition(|window| window == end_tag.as_bytes())?;
+    let mut inserted = xml[..at].to_vec();
+    inserted.extend_from_slice(markup.as_bytes());
+    inserted.extend_from_slice(&xml[at..]);
+    Some(inserted)
+}
+
+fn contains(xml: &[u8], needle: &[u8]) -> bool {
+    xml.windows(needle.len()).any(|window| window == needle)
+}
+
+// Edits a part as it is after cleaning; `edit` returns None when it leaves the part as it is
+fn edit_part(
+    archive: &mut Archive,
+    parts: &mut HashMap<String, Vec<u8>>,
+    path: &str,
+    edit: impl FnOnce(&[u8]) -> Option<Vec<u8>>,
+) {
+    let edited = match parts.get(path) {
+        Some(xml) => edit(xml),
+        None => match read_zip_entry(archive, path) {
+            Some(xml) => edit(&xml),
+            None => return,
+        },
+    };
+    if let Some(edited) = edited {
+        parts.insert(path.to_string(), edited);
+    }
+}
+
+// Sets the text of the given elements to the given values, returning the XML if anything changed
+// and the elements whose value changed with the value they had
+fn clear_elements(
+    xml: &[u8],
+    elements: &[(&str, &str)],
+) -> (Option<Vec<u8>>, Vec<(String, String)>) {
+    let mut reader = Reader::from_reader(xml);
+    let mut splice = Splice::new(xml, true);
+    let mut cleared = Vec::new();
+    let mut open = None;
+    loop {
+        let start = reader.buffer_position() as usize;
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                open = elements
+                    .iter()
+                    .find(|(name, _)| name.as_bytes() == e.name().as_ref());
+            }
+            Ok(Event::End(_)) => open = None,
+            Ok(Event::Text(t)) => {
+                let (Some((name, value)), Ok(text)) = (open, t.unescape()) else {
+                    continue;
+                };
+                if text != *value {
+                    splice.replace(start, reader.buffer_position() as usize, value);
+                    cleared.push((name.to_string(), text.into_owned()));
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    (splice.finish(), cleared)
+}
+
+// A run of a WordprocessingML part that holds nothing but text, see merge_runs
+struct TextRun {
+    start: usize,
+    // Where its start tag ends and where it ends
+    open_end: usize,
+    end: usize,
+    // Its w:rPr as it is in the XML, empty without one
+    properties: Vec<u8>,
+    // Its text as Word shows it
+    text: String,
+    merged: usize,
+}
+
+// Merges the adjacent runs of a WordprocessingML part that hold nothing but w:t text and have
+// the same w:rPr into the first of them, like Pass::merge_runs does in the model. Returns the XML
+// if anything changed and how many runs were merged into the one before them.
+fn merge_runs(xml: &[u8]) -> (Option<Vec<u8>>, usize) {
+    let mut reader = Reader::from_reader(xml);
+    let mut splice = Splice::new(xml, true);
+    let mut count = 0;
+    let mut depth = 0;
+    // The open runs by depth, until something other than text turns up in them, and the text
+    // run that ended last with the ones merged into it
+    let mut open: Vec<(usize, Option<TextRun>)> = Vec::new();
+    let mut last: Option<TextRun> = None;
+    // Inside a w:t of an open text run: whether it is marked xml:space="preserve"
+    let mut text_preserve: Option<bool> = None;
+    let put_back = |splice: &mut Splice, run: TextRun| {
+        if run.merged == 0 {
+            return;
+        }
+        let space = if run.text.starts_with(char::is_whitespace)
+            || run.text.ends_with(char::is_whitespace)
+        {
+            " xml:space=\"preserve\""
+        } else {
+            ""
+        };
+        let mut markup = xml[run.start..run.open_end].to_vec();
+        markup.extend_from_slice(&run.properties);
+        markup.extend_from_slice(
+            format!(
+                "<w:t{}>{}</w:t></w:r>",
+                space,
+                quick_xml::escape::partial_escape(&run.text)
+            )
+            .as_bytes(),
+        );
+        splice.replace_xml(run.start, run.end, &markup);
+    };
+    loop {
+        let start = reader.buffer_position() as usize;
+        let event = match reader.read_event() {
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(event) => event,
+        };
+        match &event {
+            Event::Start(e) | Event::Empty(e) => {
+                let name = e.name();
+                let name = name.as_ref();
+                let end = reader.buffer_position() as usize;
+                if let Some((run_depth, text_run)) = open.last_mut() {
+                    if *run_depth + 1 == depth {
+                        match text_run.as_mut() {
+                            Some(run) if name == b"w:rPr" && run.properties.is_empty() => {
+                                if matches!(event, Event::Start(_)) {
+                                    let end_tag = e.to_end().into_owned();
+                                    if reader.read_to_end(end_tag.name()).is_err() {
+                                        break;
+                                    }
+                                }
+                                run.properties =
+                                    xml[start..reader.buffer_position() as usize].to_vec();
+                                continue;
+                            }
+                            Some(_) if name == b"w:t" => {
+                                if matches!(event, Event::Start(_)) {
+                                    text_preserve = Some(
+                                        e.try_get_attribute("xml:space")
+                                            .ok()
+                                            .flatten()
+                                            .is_some_and(|a| a.value.as_ref() == b"preserve"),
+                                    );
+                                }
+                            }
+                            _ => *text_run = None,
+                        }
+                    }
+                }
+                if matches!(event, Event::Empty(_)) {
+                    continue;
+                }
+                if name == b"w:r" {
+                    open.push((
+                        depth,
+                        Some(TextRun {
+                            start,
+                            open_end: end,
+                            end,
+                            properties: Vec::new(),
+                            text: String::new(),
+                            merged: 0,
+                        }),
+                    ));
+                }
+                depth += 1;
+            }
+            Event::End(e) => {
+                depth -= 1;
+                match e.name().as_ref() {
+                    b"w:t" => text_preserve = None,
+                    b"w:r"
+                        if open
+                            .last()
+                            .is_some_and(|(run_depth, _)| *run_depth == depth) =>
+                    {
+                        let Some((_, Some(mut run))) = open.pop() else {
+                            continue;
+                        };
+                        run.end = reader.buffer_position() as usize;
+                        match last.as_mut() {
+                            // Only white space between them
+                            Some(previous)
+                                if previous.properties == run.properties
+                                    && xml[previous.end..run.start]
+                                        .iter()
+                                        .all(u8::is_ascii_whitespace) =>
+                            {
+                                previous.text.push_str(&run.text);
+                                previous.end = run.end;
+                                previous.merged += 1;
+                                count += 1;
+                            }
+                            _ => {
+                                if let Some(previous) = last.replace(run) {
+                                    put_back(&mut splice, previous);
+                                }
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::Text(t) => {
+                let (Some(preserve), Some((_, Some(run)))) = (text_preserve, open.last_mut())
+                else {
+                    continue;
+                };
+                let Ok(text) = t.unescape() else {
+                    continue;
+                };
+                // Word drops the white space around text that is not preserved
+                if preserve {
+                    run.text.push_str(&text);
+                } else {
+                    run.text
+                        .push_str(text.trim_matches(|c| matches!(c, ' ' | '\t' | '\r' | '\n')));
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(previous) = last {
+        put_back(&mut splice, previous);
+    }
+    (splice.finish(), count)
+}
+
+// Takes the w:rsid* attributes out of the tags of a WordprocessingML part, and the w:rsids list
+// out of its settings. Returns the XML if anything changed and how many were removed.
+fn strip_rsids(xml: &[u8]) -> (Option<Vec<u8>>, usize) {
+    let mut reader = Reader::from_reader(xml);
+    let mut splice = Splice::new(xml, true);
+    let mut removed = 0;
+    loop {
+        let start = reader.buffer_position() as usize;
+        match reader.read_event() {
+            Ok(Event::Start(e)) if e.name().as_ref() == b"w:rsids" => {
+                let end_tag = e.to_end().into_owned();
+                if reader.read_to_end(end_tag.name()).is_err() {
+                    break;
+                }
+                let end = reader.buffer_position() as usize;
+                // Each session is listed with its w:val
+                removed += xml[start..end]
+                    .windows(b"w:val=".len())
+                    .filter(|window| *window == b"w:val=")
+                    .count();
+                splice.replace_xml(start, end, b"");
+            }
+            Ok(Event::Start(e) | Event::Empty(e)) => {
+                let rsids = e
+                    .attributes()
+                    .flatten()
+                    .filter(|a| a.key.as_ref().starts_with(b"w:rsid"))
+                    .count();
+                if rsids > 0 {
+                    let end = reader.buffer_position() as usize;
+                    splice.replace_xml(start, end, &without_rsids(&xml[start..end]));
+                    removed += rsids;
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    (splice.finish(), removed)
+}
+
+// A start tag without its w:rsid* attributes
+fn without_rsids(tag: &[u8]) -> Vec<u8> {
+    let mut kept = Vec::with_capacity(tag.len());
+    let mut quote = None;
+    let mut i = 0;
+    while i < tag.len() {
+        let b = tag[i];
+        match quote {
+            Some(open) if b == open => quote = None,
+            Some(_) => {}
+            None if b == b'"' || b == b'\'' => quote = Some(b),
+            None if b.is_ascii_whitespace() && tag[i + 1..].starts_with(b"w:rsid") => {
+                // Skips the space before the attribute and the attribute up to its closing quote
+                let value = tag[i..].iter().position(|b| matches!(b, b'"' | b'\''));
+                let close = value.and_then(|value| {
+                    let open = tag[i + value];
+                    let length = tag[i + value + 1..].iter().position(|&b| b == open)?;
+                    Some(i + value + 1 + length)
+                });
+                i = close.map_or(tag.len(), |close| close + 1);
+                continue;
+            }
+            None => {}
+        }
+        kept.push(b);
+        i += 1;
+    }
+    kept
+}
+
+// Takes out the empty elements of the given name whose attribute has the given value, e.g. a
+// Relationship of some type
+fn remove_elements(xml: &[u8], name: &[u8], attribute: &str, value: &str) -> Option<Vec<u8>> {
+    let mut reader = Reader::from_reader(xml);
+    let mut splice = Splice::new(xml, true);
+    loop {
+        let start = reader.buffer_position() as usize;
+        match reader.read_event() {
+            Ok(Event::Empty(e)) if e.name().as_ref() == name => {
+                let matches = e
+                    .try_get_attribute(attribute)
+                    .ok()
+                    .flatten()
+                    .is_some_and(|a| a.value.as_ref() == value.as_bytes());
+                if matches {
+                    splice.replace_xml(start, reader.buffer_position() as usize, b"");
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    splice.finish()
+}
+
+// Where clean_package_file and clean_package read a package from: the file itself, which each
+// reader opens again, or its bytes
+#[derive(Clone, Copy)]
+enum Source<'a> {
+    Bytes(&'a [u8]),
+    File(&'a Path),
+}
+
+trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+// A package read from either kind of Source
+type Archive<'a> = zip::ZipArchive<Box<dyn ReadSeek + 'a>>;
+
+impl<'a> Source<'a> {
+    fn open(self) -> Result<Archive<'a>, Error> {
+        let path = match self {
+            Source::Bytes(buf) => return open_package(buf),
+            Source::File(path) => path,
+        };
+        let file = std::fs::File::open(path).map_err(Error::Read)?;
+        let reader: Box<dyn ReadSeek> = Box::new(std::io::BufReader::new(file));
+        zip::ZipArchive::new(reader).or_else(|_| {
+            // A file that is no package is read as a whole to tell what it is instead
+            let buf = std::fs::read(path).map_err(Error::Read)?;
+            let error = open_package(&buf).err();
+            Err(error.unwrap_or_else(|| {
+                Error::InvalidDocx("the file changed while it was read".to_string())
+            }))
+        })
+    }
+}
+
+// Packages are ZIP archives. Other files are told apart by their first bytes, so the error says
+// what the file is rather than why it is no ZIP archive.
+fn open_package(buf: &[u8]) -> Result<Archive<'_>, Error> {
+    if buf.is_empty() {
+        return Err(Error::Empty);
+    }
+    if buf.starts_with(&COMPOUND_FILE_SIGNATURE) {
+        return Err(if is_encrypted(buf) {
+            Error::Encrypted
+        } else {
+            Error::LegacyFormat
+        });
+    }
+    let reader: Box<dyn ReadSeek> = Box::new(Cursor::new(buf));
+    zip::ZipArchive::new(reader).map_err(|e| {
+        // The end of the central directory is the last record of an archive
+        if buf.starts_with(b"PK\x03\x04") && !contains(buf, b"PK\x05\x06") {
+            Error::Truncated
+        } else if !buf.starts_with(b"PK") {
+            Error::InvalidDocx("the file is not a ZIP package".to_string())
+        } else {
+            Error::InvalidDocx(e.to_string())
+        }
+    })
+}
+
+// Legacy .doc files and password-protected documents are both OLE compound files
+const COMPOUND_FILE_SIGNATURE: [u8; 8] = [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+
+// Encrypted packages (ECMA-376 standard and agile encryption) are compound files with an
+// "EncryptionInfo" stream. Directory entries take 128 bytes each and start with the name in
+// UTF-16, followed at byte 64 by its length in bytes, the terminating zero included.
+fn is_encrypted(buf: &[u8]) -> bool {
+    let name: Vec<u8> = "EncryptionInfo\0"
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    buf.chunks_exact(128)
+        .any(|entry| entry.starts_with(&name) && entry[64..66] == (name.len() as u16).to_le_bytes())
+}
+
+fn read_zip_entry(archive: &mut Archive, path: &str) -> Option<Vec<u8>> {
+    let mut file = archive.by_name(path).ok()?;
+    let mut data = Vec::new();
+    file.read_to_end(&mut data).ok()?;
+    Some(data)
+}
+
+// The text of each paragraph of a part, in the order they end, so a paragraph in a text box
+// comes before the one holding it. Deleted text and field codes are left out, and the hyphens
+// Word writes as elements are taken as the characters they stand for.
+fn paragraph_texts(xml: &[u8], text: PartText) -> Result<Vec<String>, quick_xml::Error> {
+    let (paragraph_elements, text_elements): (&[&str], &[&str]) = match text {
+        PartText::Paragraphs => (&["w:p"], &["w:t", "m:t"]),
+        PartText::Drawing => (&["a:p"], &["a:t"]),
+        PartText::Cells => (&["si", "is"], &["t"]),
+        // All the text of an OpenDocument paragraph is in its spans and links
+        PartText::OpenDocument => (&["text:p", "text:h"], &[]),
+        PartText::Elements(elements) => (elements, elements),
+    };
+    let mut reader = Reader::from_reader(xml);
+    let mut paragraphs = Vec::new();
+    let mut open: Vec<String> = Vec::new();
+    let mut in_text = false;
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                let name = e.name();
+                if is_text_element(name.as_ref(), paragraph_elements) {
+                    open.push(String::new());
+                }
+                in_text |= is_text_element(name.as_ref(), text_elements);
+            }
+            Event::Empty(e) => {
+                let name = e.name();
+                if is_text_element(name.as_ref(), paragraph_elements) {
+                    paragraphs.push(String::new());
+                }
+                let hyphen = HYPHEN_ELEMENTS
+                    .iter()
+                    .find(|(element, _)| *element == name.as_ref());
+                if let (Some(&(_, c)), Some(paragraph)) = (hyphen, open.last_mut()) {
+                    paragraph.push(c);
+                }
+            }
+            Event::End(e) => {
+                let name = e.name();
+                if is_text_element(name.as_ref(), text_elements) {
+                    in_text = false;
+                }
+                if is_text_element(name.as_ref(), paragraph_elements) {
+                    paragraphs.extend(open.pop());
+                }
+            }
+            Event::Text(t) if in_text || text_elements.is_empty() => {
+                if let Some(paragraph) = open.last_mut() {
+                    paragraph.push_str(&t.unescape()?);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(paragraphs)
+}
+
+fn is_text_element(name: &[u8], text_elements: &[&str]) -> bool {
+    text_elements
+        .iter()
+        .any(|element| element.as_bytes() == name)
+}
+
+// The parts of a package to clean as (path, part, how the text is found), with the main part of a
+// presentation or workbook, whose slides and sheets are counted on their own
+struct TextParts {
+    parts: Vec<(String, &'static str, PartText)>,
+    presentation: Option<String>,
+    workbook: Option<String>,
+}
+
+// The parts are found by their content types in [Content_Types].xml
+fn find_text_parts(archive: &mut Archive) -> Result<TextParts, Error> {
+    let Some(xml) = read_zip_entry(archive, CONTENT_TYPES_PATH) else {
+        if read_zip_entry(archive, "mimetype").as_deref() == Some(OPEN_DOCUMENT_TEXT_MIMETYPE) {
+            let parts = OPEN_DOCUMENT_PARTS
+                .iter()
+                .map(|(path, part)| (path.to_string(), *part, PartText::OpenDocument))
+                .collect();
+            return Ok(TextParts {
+                parts,
+                presentation: None,
+                workbook: None,
+            });
+        }
+        return Err(Error::InvalidDocx(format!(
+            "{} is missing",
+            CONTENT_TYPES_PATH
+        )));
+    };
+
+    let mut reader = Reader::from_reader(xml.as_slice());
+    let mut parts = Vec::new();
+    let mut presentation = None;
+    let mut workbook = None;
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| Error::InvalidPart(CONTENT_TYPES_PATH.to_string(), e))?;
+        match event {
+            Event::Empty(e) | Event::Start(e) if e.name().as_ref() == b"Override" => {
+                let attr = |name: &str| {
+                    e.try_get_attribute(name)
+                        .ok()
+                        .flatten()
+                        .and_then(|a| a.unescape_value().ok().map(|v| v.into_owned()))
+                };
+                let (Some(path), Some(content_type)) = (attr("PartName"), attr("ContentType"))
+                else {
+                    continue;
+                };
+                let path = path.trim_start_matches('/').to_string();
+                if content_type == PRESENTATION_CONTENT_TYPE {
+                    presentation = Some(path);
+                } else if content_type == WORKBOOK_CONTENT_TYPE {
+                    workbook = Some(path);
+                } else if let Some((_, part, text)) = TEXT_PARTS
+                    .iter()
+                    .find(|(part_type, _, _)| *part_type == content_type)
+                {
+                    parts.push((path, *part, *text));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(TextParts {
+        parts,
+        presentation,
+        workbook,
+    })
+}
+
+// The number of each slide of a presentation in the order of its slide list, by the path of the
+// slide and of its notes
+fn slide_numbers(archive: &mut Archive, presentation_path: &str) -> HashMap<String, usize> {
+    let slides = listed_parts(archive, presentation_path, b"sldId");
+    let mut numbers = HashMap::new();
+    for (i, (slide, _)) in slides.into_iter().enumerate() {
+        for (_, relationship, notes) in relationships(archive, &slide) {
+            if relationship == NOTES_SLIDE_RELATIONSHIP {
+                numbers.insert(notes, i + 1);
+            }
+        }
+        numbers.insert(slide, i + 1);
+    }
+    numbers
+}
+
+// The parts listed in a main part, e.g. the slides (p:sldId) of a presentation or the sheets of a
+// workbook, as (path, name) in the order of the list; the name is empty where there is none.
+// A damaged list gives the parts found up to the damage.
+fn listed_parts(archive: &mut Archive, main_path: &str, element: &[u8]) -> Vec<(String, String)> {
+    let targets = relationships(archive, main_path);
+    let xml = read_zip_entry(archive, main_path).unwrap_or_default();
+    let mut reader = Reader::from_reader(xml.as_slice());
+    let mut listed = Vec::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Empty(e) | Event::Start(e)) if e.local_name().as_ref() == element => {
+                // r:id, as opposed to the unprefixed id of a slide
+                let id = e
+                    .attributes()
+                    .flatten()
+                    .find(|a| a.key.local_name().as_ref() == b"id" && a.key.prefix().is_some());
+                let target = id.and_then(|id| {
+                    targets
+                        .iter()
+                        .find(|(target_id, _, _)| target_id.as_bytes() == id.value.as_ref())
+                });
+                let name = e
+                    .try_get_attribute("name")
+                    .ok()
+                    .flatten()
+                    .and_then(|a| a.unescape_value().ok().map(|v| v.into_owned()));
+                if let Some((_, _, path)) = target {
+                    listed.push((path.clone(), name.unwrap_or_default()));
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    listed
+}
+
+// The relationships of a part as (id, type, path of the target in the package), leaving out
+// external targets
+fn relationships(archive: &mut Archive, path: &str) -> Vec<(String, String, String)> {
+    let Some(xml) = read_zip_entry(archive, &rels_path(path)) else {
+        return Vec::new();
+    };
+    let mut reader = Reader::from_reader(xml.as_slice());
+    let mut relationships = Vec::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Empty(e) | Event::Start(e)) if e.name().as_ref() == b"Relationship" => {
+                let attr = |name: &str| {
+                    e.try_get_attribute(name)
+                        .ok()
+                        .flatten()
+                        .and_then(|a| a.unescape_value().ok().map(|v| v.into_owned()))
+                };
+                if attr("TargetMode").as_deref() == Some("External") {
+                    continue;
+                }
+                if let (Some(id), Some(kind), Some(target)) =
+                    (attr("Id"), attr("Type"), attr("Target"))
+                {
+                    relationships.push((id, kind, resolve_target(path, &target)));
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    relationships
+}
+
+// e.g. word/_rels/document.xml.rels for word/document.xml
+fn rels_path(path: &str) -> String {
+    match path.rsplit_once('/') {
+        Some((folder, file_name)) => format!("{}/_rels/{}.rels", folder, file_name),
+        None => format!("_rels/{}.rels", path),
+    }
+}
+
+// Targets are relative to the folder of the part, or to the package root with a leading slash
+fn resolve_target(source: &str, target: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    if !target.starts_with('/') {
+        segments.extend(source.split('/'));
+        segments.pop();
+    }
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
@@ -1,88 +1,672 @@
 #![cfg_attr(
-    all(not(debug_assertions), target_os = "windows"),
+    all(not(debug_assertions), target_os = "windows", feature = "gui"),
     windows_subsystem = "windows"
 )]
-use clap::Parser;
-use dashmap::DashMap; // Thread-safe HashMap
-use docx_rs::*;
+use clap::{CommandFactory, Parser};
+use docx_cleaner::{
+    category_name, cleaned_copy_path, numbered_copy_path, parse_category, parse_character,
+    parse_config_format, parse_hidden_text, parse_metadata_scrub, parse_nbsp_mode,
+    parse_normalization, timestamp, unlisted_name, CleanReport, CleanedDocument, CleanedText,
+    Cleaner, Config, ConfigFormat, GeneralCategory, HiddenText, Homoglyphs, InvisibleFormatting,
+    MetadataScrub, NbspMode, Normalization, Origin, Replacement, Revision, RuleAction,
+    SpaceCollapse, DEFAULT_MAX_COMMENTS, DEFAULT_PROFILE, DEFAULT_VISUALIZE_FORMAT, NO_BREAK_SPACE,
+    PARTS,
+};
 use once_cell::sync::Lazy;
-use rfd::FileDialog;
-use rfd::MessageDialog;
-use serde_json;
+use serde_json::{json, Value};
+use std::cell::Cell;
 use std::collections::HashMap;
-use std::collections::HashSet;
-use std::fs::File;
-use std::path::PathBuf;
+use std::io::{BufWriter, Read, Write};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex, OnceLock};
+use unicode_properties::UnicodeGeneralCategory;
+use walkdir::WalkDir;
 use regex::Regex;
 
 #[derive(Parser)]
 #[command(name = "docx-cleaner")]
-#[command(about = "Remove special characters from DOCX files")]
+#[command(about = "Remove special characters from DOCX, PPTX, XLSX and ODT files")]
+#[command(args_conflicts_with_subcommands = true)]
+#[command(
+    after_help = "Without a subcommand the files are cleaned, as with 'clean'.\n\nExit codes: 0 \
+nothing found, 1 characters found or removed (0 with --exit-zero), or suspicious characters \
+found with --suspicious-as-error, 2 invalid arguments or config, \
+3 input missing, unreadable or not a valid document, 4 output could not be written, 5 internal \
+error. With several files the highest code wins."
+)]
 struct Cli {
-    input: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    clean: CleanArgs,
 }
 
-use rfd::MessageLevel;
-use std::panic::PanicHookInfo;
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Write a cleaned copy of each document (the default)
+    Clean(Box<CleanArgs>),
+    /// Report the characters found in each document and where, without writing any files
+    Scan(ScanArgs),
+    /// Compare the characters found in two documents, e.g. a flagged one and its resubmission,
+    /// per character and per part, and list the locations found in only one; exits with 1 if
+    /// the second still has any
+    Compare(CompareArgs),
+    /// List the characters that are removed, replaced or reported with the config
+    ListChars(ListArgs),
+    /// List the profiles of --profile with what each changes in the built-in list
+    ListProfiles(ListProfilesArgs),
+    /// Check that this binary works: clean a generated document holding every character of the
+    /// built-in list and print PASS or FAIL for each stage; exits with 5 if any failed. No files
+    /// are needed or written.
+    Selftest,
+    /// Show what the config does with a character and why: its action, the sources of its rule,
+    /// its category and how a sample text containing it is cleaned
+    Explain(ExplainArgs),
+    /// Add "Clean invisible characters" to the Explorer context menu and Send To menu of the
+    /// current user (Windows only)
+    RegisterShell,
+    /// Remove the Explorer entries added by register-shell (Windows only)
+    UnregisterShell,
+}
 
-const RED_CROSS_MARK: char = '❌';
-static CONFIG_STR: &str = include_str!("../src/config.json");
+#[derive(Clone, clap::Args)]
+struct CleanArgs {
+    /// DOCX, PPTX, XLSX, ODT, plain text or Markdown files, or directories, to clean; a file
+    /// picker is shown when none are given
+    inputs: Vec<PathBuf>,
 
-static CONFIG_DATA: Lazy<HashMap<char, (String, char)>> = Lazy::new(|| {
-    let config_str = CONFIG_STR;
-    let json_map: HashMap<String, Vec<String>> =
-        serde_json::from_str(&config_str).expect("Failed to parse config.json");
+    #[command(flatten)]
+    selection: SelectionArgs,
 
-    json_map
-        .into_iter()
-        .map(|(k, v)| {
-            let key_char = k.chars().next().unwrap();
-            let description = v.get(0).cloned().unwrap_or_else(|| "UNKNOWN".to_string());
-            let replacement_char = v.get(1)
-                .and_then(|s| s.chars().next())
-                .unwrap_or('❌'); // fallback to red cross mark
-            (key_char, (description, replacement_char))
-        })
-        .collect()
-});
+    /// Where to write the cleaned copy: a file path for a single input, or a directory
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Overwrite the cleaned copy or the --output target if it already exists, e.g. from an
+    /// earlier run
+    #[arg(long)]
+    force: bool,
+
+    /// Replace the input with the cleaned document, keeping the original as <name>.docx.bak
+    #[arg(long, conflicts_with = "output", group = "replaces_input")]
+    in_place: bool,
+
+    /// Give the cleaned copy, or the cleaned document with --in-place, the modified time of the
+    /// input, and on Windows its created time as well, e.g. for a document management system that
+    /// syncs by time
+    #[arg(long)]
+    preserve_timestamps: bool,
+
+    /// Write nothing for a file with nothing to clean, instead of a copy of it, and leave it
+    /// without a backup with --in-place
+    #[arg(long, conflicts_with = "check")]
+    no_change_skip: bool,
+
+    /// Stop at the first file that cannot be processed instead of going on with the rest, which
+    /// are left as they are and counted in the summary; in the GUI as well as on the console
+    #[arg(long, conflicts_with = "watch")]
+    stop_on_error: bool,
+
+    /// Read documents a part at a time and write the cleaned copy straight to disk, instead of
+    /// holding the whole input and output in memory; done anyway for documents over 64 MB, which
+    /// are mostly images and videos that are only copied over
+    #[arg(long)]
+    stream: bool,
+
+    /// How many files are cleaned at the same time, one per CPU core by default. Files are still
+    /// cleaned one at a time in the GUI and with --interactive or --stop-on-error.
+    #[arg(long, value_name = "N")]
+    threads: Option<NonZeroUsize>,
+
+    /// Keep cleaning the documents created or changed in this folder and its subdirectories
+    /// until Ctrl-C, then print a summary. Each is cleaned once it has stopped changing, into a
+    /// copy next to it or in --output, replacing the copy of an earlier version; "_cleaned"
+    /// copies and Word lock files are left alone.
+    #[arg(
+        long,
+        value_name = "DIR",
+        conflicts_with_all = [
+            "inputs", "in_place", "check", "interactive", "stdin", "clipboard", "dialogs", "report",
+        ]
+    )]
+    watch: Option<PathBuf>,
+
+    /// Check the files listed in this file, one path per line, or on standard input with "-",
+    /// e.g. in a pre-commit hook: files of other types are left out, nothing is written, and a
+    /// line is printed for each file with something to clean; exits with 1 if there was any.
+    /// Paths given as arguments are checked along with them.
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = [
+            "output", "in_place", "check", "interactive", "stdin", "clipboard", "watch", "dialogs",
+            "annotate",
+        ]
+    )]
+    files_from: Option<PathBuf>,
+
+    /// Clean the files of --files-from in place instead of only checking them, keeping backups
+    /// as --in-place does
+    #[arg(long, requires = "files_from", group = "replaces_input")]
+    fix: bool,
+
+    /// Directory for the --in-place or --fix backups instead of next to the input
+    #[arg(long, requires = "replaces_input")]
+    backup_dir: Option<PathBuf>,
+
+    /// Only report the characters found, without writing anything; exits with 1 if any were found.
+    /// With --annotate, the copy with the comments is still written.
+    #[arg(long)]
+    check: bool,
+
+    /// Ask about each character found in a file before cleaning it, e.g. "Remove 41 × U+00A0
+    /// NO-BREAK SPACE? [Y/n/show]", where "show" lists a few of them in context; the declined
+    /// ones are kept
+    #[arg(long, conflicts_with = "check")]
+    interactive: bool,
+
+    /// Mark each changed run as a tracked change in Word, deleting the original run and
+    /// inserting the cleaned one, so the removals can be reviewed and accepted
+    #[arg(long)]
+    track_changes: bool,
+
+    /// Print each paragraph that cleaning changes before and after, as a unified diff with the
+    /// characters removed written as ⟨U+XXXX⟩ on the before side; with --report it goes into the
+    /// report instead, and in the GUI into the preview of the changes
+    #[arg(long)]
+    diff: bool,
+
+    /// Add a Word comment on each character that is removed or replaced in the document body
+    #[arg(long)]
+    annotate: bool,
+
+    /// The most comments --annotate adds to a document
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_COMMENTS, requires = "annotate")]
+    max_comments: usize,
+
+    /// Highlight the text around each removed or replaced character in this Word highlight
+    /// color, e.g. yellow
+    #[arg(long, value_name = "COLOR", value_parser = parse_highlight)]
+    highlight: Option<String>,
+
+    /// Make the invisible characters visible instead of cleaning them away, e.g. to show on a
+    /// projector what was hidden in a text: each one that would be removed or replaced becomes a
+    /// token like [ZWSP], or [U+2061] for one without a Unicode abbreviation, and spaces are not
+    /// collapsed. The format may use {short}, {code} for U+XXXX and {name}, e.g.
+    /// --visualize="<{name}>".
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = DEFAULT_VISUALIZE_FORMAT,
+        conflicts_with_all = [
+            "in_place", "fix", "check", "interactive", "track_changes", "annotate", "files_from",
+        ]
+    )]
+    visualize: Option<String>,
+
+    /// Clean UTF-8 text from standard input to standard output, with the statistics on standard
+    /// error; exits with 1 if anything was changed. No files are written and no dialogs shown.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "inputs", "output", "in_place", "check", "interactive", "track_changes", "annotate",
+            "highlight", "report", "diff",
+        ]
+    )]
+    stdin: bool,
+
+    /// Clean the text on the clipboard and put the cleaned text back, printing the statistics or
+    /// showing them in a dialog with --dialogs; exits with 1 if anything was changed. Only plain
+    /// text is cleaned, and no files are read besides the config or written.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "inputs", "output", "in_place", "check", "interactive", "track_changes", "annotate",
+            "highlight", "report", "stdin", "log", "diff",
+        ]
+    )]
+    clipboard: bool,
+
+    /// Read standard input as Markdown, leaving the spaces in code blocks as they are
+    #[arg(long, requires = "stdin")]
+    markdown: bool,
+
+    #[command(flatten)]
+    statistics: StatisticsArgs,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    /// Print the merged config that would be used as JSON and exit
+    #[arg(long)]
+    print_effective_config: bool,
 
+    /// Append a line for each cleaned file to the log: the time, the input, the characters
+    /// removed and the cleaned copy
+    #[arg(long)]
+    log: bool,
 
-static UNICODE_CHARS_TO_REMOVE: Lazy<HashSet<char>> =
-    Lazy::new(|| CONFIG_DATA.keys().copied().collect());
+    /// Where to write the log instead of docx-cleaner.log next to the executable, or in the
+    /// platform state or temporary directory when that folder is not writable
+    #[arg(long, value_name = "FILE")]
+    log_file: Option<PathBuf>,
 
-static CHAR_COUNTERS: Lazy<DashMap<char, usize>> = Lazy::new(|| {
-    let map = DashMap::new();
-    for &c in UNICODE_CHARS_TO_REMOVE.iter() {
-        map.insert(c, 0);
+    /// Neither read nor save the folders and options the GUI remembers between runs, e.g. on a
+    /// shared or kiosk computer
+    #[arg(long)]
+    no_settings: bool,
+
+    /// Show the results and errors in dialogs, as when the files are picked, for the given paths
+    /// or the clipboard; the Explorer entries of register-shell use it
+    #[arg(long, conflicts_with = "stdin")]
+    dialogs: bool,
+}
+
+#[derive(clap::Args)]
+struct ScanArgs {
+    /// DOCX, PPTX, XLSX, ODT, plain text or Markdown files, or directories, to scan
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    /// How many files are scanned at the same time, one per CPU core by default
+    #[arg(long, value_name = "N")]
+    threads: Option<NonZeroUsize>,
+
+    #[command(flatten)]
+    statistics: StatisticsArgs,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+}
+
+#[derive(clap::Args)]
+struct CompareArgs {
+    /// The first document, A, e.g. the flagged version
+    a: PathBuf,
+
+    /// The second document, B, e.g. the resubmission
+    b: PathBuf,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+}
+
+// Which files are processed. Directories given as inputs are walked for Word documents and
+// templates, presentations, workbooks, OpenDocument texts, .txt and .md files, including their
+// subdirectories.
+#[derive(Clone, clap::Args)]
+struct SelectionArgs {
+    /// Only process files whose name matches one of these patterns, e.g. "*thesis*.docx"; a
+    /// pattern with a slash is matched against the whole path, e.g. "*/2024/*"
+    #[arg(long)]
+    include: Vec<String>,
+
+    /// Skip files whose name matches one of these patterns, e.g. "*_cleaned.docx"
+    #[arg(long)]
+    exclude: Vec<String>,
+}
+
+impl SelectionArgs {
+    fn selects(&self, path: &Path) -> bool {
+        let matches =
+            |patterns: &[String]| patterns.iter().any(|pattern| glob_matches(pattern, path));
+        (self.include.is_empty() || matches(&self.include)) && !matches(&self.exclude)
     }
-    map
-});
+}
 
-static CHAR_NAMES: Lazy<HashMap<char, String>> = Lazy::new(|| {
-    CONFIG_DATA.iter()
-        .map(|(&k, (desc, _))| (k, desc.clone()))
-        .collect()
-});
+// How the results are printed, for both clean and scan
+#[derive(Clone, clap::Args)]
+struct StatisticsArgs {
+    /// Print nothing besides --json and errors, which still go to standard error
+    #[arg(short, long)]
+    quiet: bool,
 
-// Add a new static for replacement characters
-static REPLACEMENT_CHARS: Lazy<HashMap<char, char>> = Lazy::new(|| {
-    CONFIG_DATA.iter()
-        .map(|(&k, (_, replacement))| (k, *replacement))
-        .collect()
-});
+    /// Log what is cleaned to standard error, or to the log file in the GUI: -v each part of a
+    /// document, -vv each changed run with its characters, -vvv its text before and after
+    #[arg(short, long, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+
+    /// Print the statistics of each file as a JSON object per line, or as a single array with
+    /// --json=array; several files end with an object of all "files" and their "totals". The
+    /// readable statistics move to standard error.
+    #[arg(
+        long,
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "ndjson"
+    )]
+    json: Option<JsonFormat>,
+
+    /// Exit with 0 when characters were found or removed, instead of 1; failures keep their codes
+    #[arg(long)]
+    exit_zero: bool,
+
+    /// Exit with 1 when suspicious characters that are not in the config were found, e.g. to
+    /// make --check fail on them; this takes precedence over --exit-zero
+    #[arg(long)]
+    suspicious_as_error: bool,
+
+    /// Exit with 1 when flagged characters were found, e.g. to make --check fail on them once
+    /// they have been audited long enough; this takes precedence over --exit-zero
+    #[arg(long)]
+    strict_flags: bool,
+
+    /// Print the findings of scan or --check in this format once all files are processed; the
+    /// readable statistics move to standard error
+    #[arg(long, value_enum, value_name = "FORMAT", conflicts_with = "json")]
+    format: Option<OutputFormat>,
+
+    /// Write every replacement with its location and the text around it to this file, as text,
+    /// as JSON or as a self-contained HTML page depending on the extension (.txt, .json or .html)
+    #[arg(long, value_parser = parse_report_path)]
+    report: Option<PathBuf>,
+}
+
+#[derive(clap::Args)]
+struct ListArgs {
+    /// Print the list as JSON
+    #[arg(long)]
+    json: bool,
+
+    #[command(flatten)]
+    characters: CharacterArgs,
+}
+
+#[derive(clap::Args)]
+struct ListProfilesArgs {
+    /// JSON or TOML config whose "profiles" are listed with the built-in ones; defaults to
+    /// docx-cleaner/config.json in the platform config directory if it exists
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Read the config as json or toml whatever its extension
+    #[arg(long, value_name = "FORMAT", value_parser = parse_config_format)]
+    config_format: Option<ConfigFormat>,
+}
 
-pub fn show_error_dialog(title: &str, message: &str) {
-    MessageDialog::new()
-        .set_level(rfd::MessageLevel::Error)
-        .set_title(title)
-        .set_description(message)
-        .set_buttons(rfd::MessageButtons::Ok)
-        .show();
+#[derive(clap::Args)]
+struct ExplainArgs {
+    /// The character itself or its code point, e.g. U+00A0
+    #[arg(value_parser = parse_character)]
+    character: char,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+}
+
+// Which characters are listed
+#[derive(Clone, clap::Args)]
+struct CharacterArgs {
+    /// JSON config merged on top of the built-in list, or TOML when it ends in .toml; defaults
+    /// to docx-cleaner/config.json (or config.toml) in the platform config directory if it
+    /// exists
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Read the config as json or toml whatever its extension
+    #[arg(long, value_name = "FORMAT", value_parser = parse_config_format)]
+    config_format: Option<ConfigFormat>,
+
+    /// Start from a profile of the built-in list instead of the list as it is: minimal (only
+    /// zero-width and format characters), standard (the default) or aggressive (also ASCII
+    /// punctuation, lookalike letters and every kind of space). The config can define more;
+    /// list-profiles shows what each changes.
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Also remove every character in these Unicode general categories, e.g. Cf,Co
+    #[arg(long, value_delimiter = ',', value_parser = parse_category)]
+    categories: Vec<GeneralCategory>,
+
+    /// Also replace typographic punctuation with ASCII: curly quotes and apostrophes with
+    /// straight ones, en and em dashes with "-" and "--" and the ellipsis with "..."; the user
+    /// config can change any of these, e.g. to turn em dashes into "-"
+    #[arg(long)]
+    ascii_punctuation: bool,
+
+    /// Leave directional formatting marks (U+200E, U+200F, U+202A..U+202E, ...) in the text and
+    /// only report them
+    #[arg(long)]
+    keep_bidi: bool,
+
+    // Leaves out the user config, set when the GUI goes on with the built-in list after the user
+    // config turned out to be invalid
+    #[arg(skip)]
+    builtin_only: bool,
+}
+
+// How the text is cleaned
+#[derive(Clone, clap::Args)]
+struct ConfigArgs {
+    #[command(flatten)]
+    characters: CharacterArgs,
+
+    /// Also remove zero width joiners and variation selectors inside emoji sequences
+    #[arg(long)]
+    strict: bool,
+
+    /// Also remove the invisible operators of equations (U+2061 to U+2064), which are only
+    /// reported there by default as they tell a function application or product apart
+    #[arg(long)]
+    math_strict: bool,
+
+    /// Leave runs of multiple spaces as they are
+    #[arg(long)]
+    no_collapse_spaces: bool,
+
+    /// Also collapse runs mixing spaces with no-break, thin and other space characters
+    #[arg(long, conflicts_with = "no_collapse_spaces")]
+    collapse_whitespace: bool,
+
+    /// Remove a single space at the end of the text before a line break and at the start of the
+    /// text after it, which Word shows as dead space at the edge of the line
+    #[arg(long)]
+    trim_around_breaks: bool,
+
+    /// What happens to no-break spaces (U+00A0): replace turns them into plain spaces (the
+    /// default), keep leaves them alone and patterns only keeps them where one of the
+    /// "nbsp_patterns" of the config matches, e.g. between a number and its unit
+    #[arg(long, value_name = "MODE", value_parser = parse_nbsp_mode)]
+    nbsp: Option<NbspMode>,
+
+    /// Also collapse runs of multiple spaces inside spreadsheet cells, which are left as they are
+    /// by default since the spacing of a cell can matter, e.g. to a lookup
+    #[arg(long, conflicts_with = "no_collapse_spaces")]
+    collapse_cell_spaces: bool,
+
+    /// Normalize the text of each run after the characters are removed: nfc composes letters
+    /// with their accents, so that "café" typed either way compares equal; nfkc also folds
+    /// ligatures, superscripts, full-width forms and the like into plain characters, which
+    /// loses that formatting for good
+    #[arg(long, value_name = "FORM", value_parser = parse_normalization)]
+    normalize: Option<Normalization>,
+
+    /// Replace Cyrillic and Greek letters that look like Latin ones (а, е, о, р, ѕ, ...) with
+    /// those, in words that are otherwise Latin; words of other scripts are left alone
+    #[arg(long)]
+    homoglyphs: bool,
+
+    /// JSON table of lookalike letters merged on top of the built-in one; defaults to
+    /// docx-cleaner/homoglyphs.json in the platform config directory if it exists
+    #[arg(long, value_name = "FILE", requires = "homoglyphs")]
+    homoglyph_table: Option<PathBuf>,
+
+    /// Remove runs formatted as hidden text, which Word neither shows nor prints but which is
+    /// copied along with the text around it; --remove-hidden=report only counts them. Hidden
+    /// runs inside a field, like the page numbers of a table of contents, are always kept.
+    #[arg(
+        long,
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "remove",
+        value_parser = parse_hidden_text
+    )]
+    remove_hidden: Option<HiddenText>,
+
+    /// Remove runs whose text cannot be seen because of their formatting: text colored like the
+    /// shading or page behind it, or at most 1pt in size. They are only reported by default.
+    #[arg(long)]
+    remove_invisible_formatting: bool,
+
+    /// Remove the runs of Word documents whose text was removed entirely, and the paragraphs of
+    /// the body left without any text, which would show as stray blank lines. Paragraphs that
+    /// were empty to begin with are kept as spacing.
+    #[arg(long)]
+    prune_empty: bool,
+
+    /// Merge adjacent runs of Word documents that hold nothing but text and have the same
+    /// formatting, so that text split into many runs by the removed characters is whole again.
+    /// Tabs, breaks, drawings and fields keep the runs around them apart.
+    #[arg(long)]
+    merge_runs: bool,
+
+    /// Clear identifying metadata: the author, company, revision number, editing time and
+    /// template of the document properties, the rsid editing-session ids of Word documents and
+    /// the thumbnail. Name the kinds to clear only those, e.g. --scrub-metadata=author,rsids.
+    #[arg(
+        long,
+        value_name = "KINDS",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "all",
+        value_parser = parse_metadata_scrub
+    )]
+    scrub_metadata: Option<MetadataScrub>,
+}
+
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+enum JsonFormat {
+    Ndjson,
+    Array,
+}
+
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+enum OutputFormat {
+    /// A SARIF 2.1.0 log of every finding, as read by code scanning in CI
+    Sarif,
+}
+
+use std::panic::PanicHookInfo;
+
+mod clipboard;
+mod compare;
+mod diff;
+// The dialogs, or their stand-ins on standard error in a build without the "gui" feature
+#[cfg_attr(not(feature = "gui"), path = "headless.rs")]
+mod gui;
+mod progress;
+#[cfg(all(windows, feature = "results-window"))]
+mod results_window;
+mod selftest;
+mod settings;
+mod shell;
+#[cfg(feature = "watch")]
+mod watch;
+
+use progress::{progress_bar, ProgressBar};
+use settings::Settings;
+
+// Exit codes, so scripts can tell the outcomes apart. They rise with the severity, so a batch
+// exits with the highest code of its files.
+const EXIT_CHARACTERS_FOUND: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_INPUT_UNREADABLE: i32 = 3;
+const EXIT_OUTPUT_FAILED: i32 = 4;
+const EXIT_BUG: i32 = 5;
+
+// Ordinary failures, reported with a readable message instead of going through the panic hook
+#[derive(Debug, thiserror::Error)]
+enum CleanError {
+    #[error("{0}")]
+    Config(String),
+    #[error("The file does not exist")]
+    InputMissing,
+    #[error("The file could not be read: {0}")]
+    Read(std::io::Error),
+    #[error(transparent)]
+    Clean(docx_cleaner::Error),
+    #[error("--output '{}' must be an existing directory when several files are given", .0.display())]
+    OutputNotDirectory(PathBuf),
+    #[error("There is no file name in '{}' to name the cleaned copy after; pass --output", .0.display())]
+    OutputName(PathBuf),
+    #[error("Output file '{}' already exists; pass --force to overwrite it", .0.display())]
+    OutputExists(PathBuf),
+    #[error("There is no file name in '{}' to name the backup after", .0.display())]
+    BackupName(PathBuf),
+    #[error("Could not back up the original (is the folder read-only?): {0}")]
+    Backup(std::io::Error),
+    #[error("Could not write output '{}' (is the folder read-only?): {}", .0.display(), .1)]
+    Write(PathBuf, std::io::Error),
+    #[error("Could not replace '{}' because another program has it open; close the file in Word and try again", .0.display())]
+    Locked(PathBuf),
+    #[error("Could not write to standard output: {0}")]
+    Stdout(std::io::Error),
+    #[error("{0}")]
+    Shell(String),
+    #[error("{0}")]
+    ClipboardRead(String),
+    #[error("{0}")]
+    ClipboardWrite(String),
+    #[cfg(feature = "watch")]
+    #[error("{0}")]
+    Watch(String),
+    #[error("Could not read the list of files '{}': {}", .0.display(), .1)]
+    FileList(PathBuf, std::io::Error),
+    #[error("'{}' and '{}' would both be written to '{}'; clean them into different --output folders", .0.display(), .1.display(), .2.display())]
+    OutputCollision(PathBuf, PathBuf, PathBuf),
 }
 
+impl CleanError {
+    fn exit_code(&self) -> i32 {
+        match self {
+            CleanError::Config(_)
+            | CleanError::OutputNotDirectory(_)
+            | CleanError::OutputCollision(..) => EXIT_USAGE,
+            CleanError::Clean(docx_cleaner::Error::Pack(_)) => EXIT_OUTPUT_FAILED,
+            CleanError::InputMissing
+            | CleanError::Read(_)
+            | CleanError::Clean(_)
+            | CleanError::ClipboardRead(_)
+            | CleanError::FileList(..) => EXIT_INPUT_UNREADABLE,
+            #[cfg(feature = "watch")]
+            CleanError::Watch(_) => EXIT_INPUT_UNREADABLE,
+            CleanError::OutputName(_)
+            | CleanError::OutputExists(_)
+            | CleanError::BackupName(_)
+            | CleanError::Backup(_)
+            | CleanError::Write(..)
+            | CleanError::Locked(_)
+            | CleanError::Stdout(_)
+            | CleanError::Shell(_)
+            | CleanError::ClipboardWrite(_) => EXIT_OUTPUT_FAILED,
+        }
+    }
+
+    // The title of the error dialog, naming what the file is where the cleaner could tell
+    fn title(&self) -> &'static str {
+        match self {
+            CleanError::Clean(docx_cleaner::Error::LegacyFormat) => "Legacy .doc File",
+            CleanError::Clean(docx_cleaner::Error::Encrypted) => "Password-Protected Document",
+            CleanError::Clean(docx_cleaner::Error::Empty | docx_cleaner::Error::Truncated) => {
+                "Incomplete File"
+            }
+            _ => "Processing Failed",
+        }
+    }
+}
 
-fn setup_panic_handler() {
-    std::panic::set_hook(Box::new(|panic_info: &PanicHookInfo| {
+// The dialog is left out where nothing else opens one, e.g. in a --stdin filter
+fn setup_panic_handler(show_dialog: bool, log_file: Option<PathBuf>) {
+    std::panic::set_hook(Box::new(move |panic_info: &PanicHookInfo| {
         let message = if let Some(s) = panic_info.payload().downcast_ref::<&str>() {
             s.to_string()
         } else if let Some(s) = panic_info.payload().downcast_ref::<String>() {
@@ -101,198 +685,3360 @@ fn setup_panic_handler() {
 
         // Log to file
         eprintln!("{}", full_message); // only useful in console mode
-        log_panic_to_file(&full_message); // log, just in case
+        let entry = format!("{} {}", timestamp(), full_message);
+        let log_path = append_to_log(log_file.as_deref(), &entry); // log, just in case
 
         // Show in GUI
-        MessageDialog::new()
-            .set_title("Fatal Error")
-            .set_description(&full_message)
-            .set_level(MessageLevel::Error)
-            .set_buttons(rfd::MessageButtons::Ok)
-            .show();
-
-        std::process::exit(1); // Ensure clean exit
+        if show_dialog {
+            let description = match log_path {
+                Some(path) => format!(
+                    "{}\n\nThe details were written to {}",
+                    full_message,
+                    path.display()
+                ),
+                None => full_message,
+            };
+            gui::show_error("Fatal Error", &description);
+        }
+
+        std::process::exit(EXIT_BUG); // Ensure clean exit
     }));
 }
 
-fn log_panic_to_file(log: &str) {
+// The log of panics and, with --log, of the cleaned files. It goes next to the executable where
+// that folder is writable, since a double-clicked exe often runs in C:\Windows\System32, and into
+// the platform state (or local data) directory or the temporary directory otherwise.
+const LOG_NAME: &str = "docx-cleaner.log";
+
+// A log grown past this is moved to <log>.1, replacing the one moved there before
+const LOG_SIZE_LIMIT: u64 = 1024 * 1024;
+
+// Returns the path the entry was appended to
+fn append_to_log(log_file: Option<&Path>, entry: &str) -> Option<PathBuf> {
     use std::fs::OpenOptions;
-    use std::io::Write;
 
-    if let Ok(mut file) = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open("error.log")
-    {
-        let _ = writeln!(file, "{}", log);
-    }
+    let candidates: Vec<PathBuf> = match log_file {
+        Some(path) => vec![path.to_path_buf()],
+        None => [
+            std::env::current_exe()
+                .ok()
+                .and_then(|exe| exe.parent().map(Path::to_path_buf)),
+            dirs::state_dir()
+                .or_else(dirs::data_local_dir)
+                .map(|dir| dir.join("docx-cleaner")),
+            Some(std::env::temp_dir()),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|dir| dir.join(LOG_NAME))
+        .collect(),
+    };
+    candidates.into_iter().find_map(|path| {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        if std::fs::metadata(&path).is_ok_and(|metadata| metadata.len() > LOG_SIZE_LIMIT) {
+            let mut rotated = path.clone().into_os_string();
+            rotated.push(".1");
+            let _ = std::fs::rename(&path, rotated);
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .ok()?;
+        writeln!(file, "{}", entry).ok()?;
+        Some(path)
+    })
 }
 
 fn main() {
     let cli = Cli::parse();
 
-    setup_panic_handler();
+    let command = cli.command.unwrap_or(Command::Clean(Box::new(cli.clean)));
+    let log_file = match &command {
+        Command::Clean(args) => args.log_file.clone(),
+        _ => None,
+    };
+    setup_panic_handler(
+        cfg!(feature = "gui") && !matches!(&command, Command::Clean(args) if args.stdin),
+        log_file.clone(),
+    );
+    if let Command::Clean(args) = &command {
+        // Without --annotate, --check writes nothing
+        if args.check && !args.annotate && (args.output.is_some() || args.in_place) {
+            Cli::command()
+                .error(
+                    clap::error::ErrorKind::ArgumentConflict,
+                    "--output and --in-place cannot be used with --check unless --annotate is given",
+                )
+                .exit();
+        }
+        // The findings are only complete when nothing is cleaned
+        if args.statistics.format.is_some() && !args.check {
+            Cli::command()
+                .error(
+                    clap::error::ErrorKind::MissingRequiredArgument,
+                    "--format requires --check, or the scan subcommand",
+                )
+                .exit();
+        }
+    }
+    // Without input paths the file picker is used and errors are shown as dialogs; a build
+    // without the "gui" feature needs the paths instead
+    let wants_picker = matches!(&command, Command::Clean(args)
+        if args.inputs.is_empty() && !args.print_effective_config && !args.stdin
+            && !args.clipboard && args.watch.is_none() && args.files_from.is_none());
+    if wants_picker && !cfg!(feature = "gui") {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::MissingRequiredArgument,
+                "an input path is required, since this build has no file picker",
+            )
+            .exit();
+    }
+    let clipboard = matches!(&command, Command::Clean(args) if args.clipboard);
+    if clipboard && !cfg!(feature = "clipboard") {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::InvalidValue,
+                "--clipboard is not available, since this build has no clipboard support",
+            )
+            .exit();
+    }
+    let watches = matches!(&command, Command::Clean(args) if args.watch.is_some());
+    if watches && !cfg!(feature = "watch") {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::InvalidValue,
+                "--watch is not available, since this build cannot watch folders",
+            )
+            .exit();
+    }
+    let is_gui_mode = wants_picker
+        || cfg!(feature = "gui") && matches!(&command, Command::Clean(args) if args.dialogs);
+    let statistics = match &command {
+        Command::Clean(args) => Some(&args.statistics),
+        Command::Scan(args) => Some(&args.statistics),
+        Command::ListChars(_)
+        | Command::ListProfiles(_)
+        | Command::Explain(_)
+        | Command::Selftest
+        | Command::RegisterShell
+        | Command::UnregisterShell
+        | Command::Compare(_) => None,
+    };
+    let level = match statistics {
+        Some(statistics) if statistics.quiet => log::LevelFilter::Error,
+        Some(statistics) => match statistics.verbose {
+            0 => log::LevelFilter::Warn,
+            1 => log::LevelFilter::Info,
+            2 => log::LevelFilter::Debug,
+            _ => log::LevelFilter::Trace,
+        },
+        None => log::LevelFilter::Warn,
+    };
+    // The clipboard is cleaned without writing any file, the log included
+    setup_logger(level, is_gui_mode && !clipboard, log_file);
+    let result = match &command {
+        Command::Clean(args) => clean(args, is_gui_mode),
+        Command::Scan(args) => scan(args),
+        Command::Compare(args) => {
+            load_config(&args.config, None).map(|config| compare::compare(args, config))
+        }
+        Command::ListChars(args) => list_chars(args),
+        Command::ListProfiles(args) => list_profiles(args),
+        Command::Explain(args) => explain(args),
+        Command::Selftest => Ok(if selftest::run() { 0 } else { EXIT_BUG }),
+        Command::RegisterShell => shell_entries(shell::register),
+        Command::UnregisterShell => shell_entries(shell::unregister),
+    };
+    match result {
+        Ok(code) => std::process::exit(code),
+        Err(error) => {
+            log::error!("{}", error);
+            if is_gui_mode {
+                gui::show_error(error.title(), &error.to_string());
+            }
+            std::process::exit(error.exit_code())
+        }
+    }
+}
+
+fn load_characters(args: &CharacterArgs) -> Result<Config, CleanError> {
+    let profile = args.profile.as_deref().unwrap_or(DEFAULT_PROFILE);
+    let mut config = if args.builtin_only {
+        Config::builtin_profile(profile, &args.categories)
+    } else {
+        Config::load_profile(
+            args.config.as_deref(),
+            args.config_format,
+            profile,
+            &args.categories,
+        )
+    }
+    .map_err(CleanError::Config)?;
+    if args.ascii_punctuation {
+        config.add_typography();
+    }
+    if args.keep_bidi {
+        config.report_bidi_controls();
+    }
+    Ok(config)
+}
+
+// Without statistics, as for explain, no locations are recorded
+fn load_config(
+    args: &ConfigArgs,
+    statistics: Option<&StatisticsArgs>,
+) -> Result<Config, CleanError> {
+    let mut config = load_characters(&args.characters)?;
+    config.strict = args.strict;
+    config.math_strict = args.math_strict;
+    if args.no_collapse_spaces {
+        config.collapse_spaces = SpaceCollapse::Off;
+    } else if args.collapse_whitespace {
+        config.collapse_spaces = SpaceCollapse::Whitespace;
+    }
+    config.trim_around_breaks = args.trim_around_breaks;
+    if let Some(nbsp) = args.nbsp {
+        config.nbsp = nbsp;
+    }
+    config.collapse_cell_spaces = args.collapse_cell_spaces;
+    config.normalize = args.normalize;
+    config.hidden_text = args.remove_hidden;
+    config.remove_invisible_formatting = args.remove_invisible_formatting;
+    config.prune_empty = args.prune_empty;
+    config.merge_runs = args.merge_runs;
+    config.scrub_metadata = args.scrub_metadata;
+    if args.homoglyphs {
+        config.homoglyphs =
+            Some(Homoglyphs::load(args.homoglyph_table.as_deref()).map_err(CleanError::Config)?);
+    }
+    config.record_locations = statistics.is_some_and(|statistics| {
+        statistics.report.is_some() || statistics.format.is_some() || statistics.json.is_some()
+    });
+    Ok(config)
+}
+
+fn clean(args: &CleanArgs, is_gui_mode: bool) -> Result<i32, CleanError> {
+    let listed_args;
+    let args = match &args.files_from {
+        Some(list) => {
+            listed_args = listed(args, list)?;
+            &listed_args
+        }
+        None => args,
+    };
+    let mut settings = (is_gui_mode && !args.no_settings && !args.clipboard).then(Settings::load);
+    let remembered_args;
+    let args = match &settings {
+        Some(settings) => {
+            remembered_args = remembered(args, settings);
+            &remembered_args
+        }
+        None => args,
+    };
+    // Every problem of an invalid user config is shown in one dialog, which offers to go on
+    // without it
+    let builtin_args;
+    let (mut config, args) = match clean_config(args, is_gui_mode) {
+        Err(CleanError::Config(message)) if is_gui_mode => {
+            log::error!("{}", message);
+            let question = format!("{}\n\nContinue with the built-in defaults?", message);
+            if !gui::ask_yes_no("Invalid config", &question) {
+                return Ok(EXIT_USAGE);
+            }
+            let mut args = args.clone();
+            args.config.characters.builtin_only = true;
+            builtin_args = args;
+            (clean_config(&builtin_args, is_gui_mode)?, &builtin_args)
+        }
+        config => (config?, args),
+    };
+    if args.print_effective_config {
+        println!("{}", config.to_json());
+        return Ok(0);
+    }
+    if args.stdin {
+        return filter_stdin(config, args);
+    }
+    if args.clipboard {
+        return clean_clipboard(config, args, is_gui_mode);
+    }
+    #[cfg(feature = "watch")]
+    if let Some(dir) = &args.watch {
+        if let Some(output) = args.output.as_ref().filter(|output| !output.is_dir()) {
+            return Err(CleanError::OutputNotDirectory(output.clone()));
+        }
+        return watch::watch(dir, &Cleaner::new(config), args);
+    }
 
-    let (input_path, is_gui_mode) = match cli.input {
-        Some(path) => {
-            if !path.exists() {
-                eprintln!("Error: File '{}' does not exist.", path.display());
-                std::process::exit(1)
+    let mut gui_args;
+    let (paths, args) = if is_gui_mode && args.inputs.is_empty() {
+        let filters: [(&str, &[&str]); 5] = [
+            ("Word Documents", &DOCUMENT_EXTENSIONS),
+            ("PowerPoint Presentations", &PRESENTATION_EXTENSIONS),
+            ("Excel Workbooks", &WORKBOOK_EXTENSIONS),
+            ("OpenDocument Text", &OPEN_DOCUMENT_EXTENSIONS),
+            ("Text and Markdown Files", &["txt", "md", "markdown"]),
+        ];
+        // The pickers start where the last run left off, as long as those folders still exist
+        let last_directory = settings
+            .as_ref()
+            .and_then(|settings| settings.last_directory.clone())
+            .filter(|directory| directory.is_dir());
+        let Some(file_paths) = gui::pick_files(
+            "Select the files to process",
+            &filters,
+            last_directory.as_deref(),
+        ) else {
+            // Cancelling the picker is not an error
+            return Ok(0);
+        };
+        let output_folder = settings
+            .as_ref()
+            .and_then(|settings| settings.output_folder.clone())
+            .filter(|folder| folder.is_dir());
+        // The cleaned copies go to the chosen folder, or next to the originals when this dialog
+        // is cancelled; earlier copies in either place are only replaced when confirmed
+        let output = gui::pick_folder(
+            "Choose where to save the cleaned copies (Cancel saves each next to its original)",
+            output_folder.as_deref().or(file_paths[0].parent()),
+        );
+        gui_args = CleanArgs {
+            output,
+            ..args.clone()
+        };
+        let characters = &mut gui_args.config.characters;
+        if characters.profile.is_none() {
+            let last = settings
+                .as_ref()
+                .and_then(|settings| settings.profile.as_deref());
+            let profile = ask_profile(characters, last);
+            characters.profile = Some(profile.to_string());
+            if profile != DEFAULT_PROFILE {
+                config = clean_config(&gui_args, is_gui_mode)?;
             }
-            (path, false)
         }
-        None => {
-            let file_path = FileDialog::new()
-                .add_filter("Word Documents", &["docx"])
-                .set_title("Select a DOCX file to process")
-                .pick_file()
-                .expect("No file selected");
-            (file_path, true)
+        if let Some(settings) = &mut settings {
+            settings.last_directory = file_paths[0].parent().map(Path::to_path_buf);
+            settings.output_folder.clone_from(&gui_args.output);
+            settings.collapse_spaces = !gui_args.config.no_collapse_spaces;
+            settings
+                .config
+                .clone_from(&gui_args.config.characters.config);
+            settings
+                .profile
+                .clone_from(&gui_args.config.characters.profile);
+            settings.save();
         }
+        (file_paths, &gui_args)
+    } else {
+        (args.inputs.clone(), args)
     };
+    let inputs = collect_inputs(&paths, &args.selection);
 
-    let buf = std::fs::read(&input_path).expect("Failed to read DOCX input file.");
-    let mut docx = read_docx(&buf).expect("Failed to parse DOCX.");
+    if let Some(output) = &args.output {
+        if inputs.files.len() > 1 && !output.is_dir() {
+            return Err(CleanError::OutputNotDirectory(output.clone()));
+        }
+    }
 
-    for mut entry in CHAR_COUNTERS.iter_mut() {
-        *entry.value_mut() = 0;
+    if let Some(collision) = output_collision(&inputs.files, args) {
+        return Err(collision);
     }
-    clean_document(&mut docx);
 
-    let output_path = generate_output_path(&input_path);
-    let mut output_file = File::create(&output_path).expect("Failed to create output file.");
-    docx.build()
-        .pack(&mut output_file)
-        .expect("Failed to write DOCX.");
+    let cleaner = Cleaner::new(config);
+    run(
+        &inputs,
+        &args.statistics,
+        &cleaner,
+        Mode::Clean(args),
+        args.threads,
+        is_gui_mode,
+    )
+}
 
-    if is_gui_mode {
-        show_gui_statistics(&output_path);
+// The arguments of --files-from: the listed files of a supported type after those given as
+// arguments, checked, or cleaned in place with --fix. Files that do not exist are kept, so they are
+// reported along with the rest.
+fn listed(args: &CleanArgs, list: &Path) -> Result<CleanArgs, CleanError> {
+    let bytes = if list == Path::new("-") {
+        let mut bytes = Vec::new();
+        std::io::stdin().read_to_end(&mut bytes).map(|_| bytes)
     } else {
-        print_console_statistics(&output_path);
+        std::fs::read(list)
     }
+    .map_err(|e| CleanError::FileList(list.to_path_buf(), e))?;
+    let listed = bytes
+        .split(|&byte| byte == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !line.is_empty())
+        .map(listed_path);
+    let mut inputs: Vec<PathBuf> = args.inputs.iter().cloned().chain(listed).collect();
+    inputs.retain(|path| path.is_dir() || is_supported(path));
+    Ok(CleanArgs {
+        inputs,
+        check: !args.fix,
+        in_place: args.fix,
+        // Only the files that were fixed get a backup
+        no_change_skip: args.fix,
+        ..args.clone()
+    })
 }
 
-fn clean_document(docx: &mut Docx) {
-    // Trivial transformation: remove all spaces
-    for child in &mut docx.document.children {
-        if let DocumentChild::Paragraph(paragraph) = child {
-            clean_paragraph(paragraph);
-        }
+// Git lists paths as they are stored, which on Unix need not be UTF-8
+#[cfg(unix)]
+fn listed_path(line: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(line))
+}
+
+#[cfg(not(unix))]
+fn listed_path(line: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(line).into_owned())
+}
+
+fn clean_config(args: &CleanArgs, is_gui_mode: bool) -> Result<Config, CleanError> {
+    let mut config = load_config(&args.config, Some(&args.statistics))?;
+    // The locations are listed when asking before writing
+    config.record_locations |= is_gui_mode || args.interactive;
+    config.track_changes = args.track_changes.then(Revision::now);
+    config.annotate = args.annotate.then(Revision::now);
+    config.max_comments = args.max_comments;
+    if args.highlight.is_some() && args.check {
+        log::warn!("--highlight is ignored with --check, as the text is not changed");
+    } else {
+        config.highlight.clone_from(&args.highlight);
     }
+    config.visualize.clone_from(&args.visualize);
+    Ok(config)
 }
 
-fn clean_paragraph(paragraph: &mut Paragraph) {
-    for child in &mut paragraph.children {
-        if let ParagraphChild::Run(run) = child {
-            clean_run(run);
-        }
+// The built-in profile picked in the GUI, described as the config has them. Closing the dialog
+// keeps the one of the last run, or the default.
+fn ask_profile(characters: &CharacterArgs, last: Option<&str>) -> &'static str {
+    const CHOICES: [(&str, &str); 3] = [
+        ("Minimal", "minimal"),
+        ("Aggressive", "aggressive"),
+        ("Standard", DEFAULT_PROFILE),
+    ];
+    // An invalid user config was already reported, and the built-in profiles are the ones offered
+    let profiles = Config::profiles(characters.config.as_deref(), characters.config_format)
+        .unwrap_or_else(|_| Config::builtin_profiles());
+    let mut message = "Which characters should be cleaned?\n".to_string();
+    for (label, name) in [CHOICES[0], CHOICES[2], CHOICES[1]] {
+        let description = profiles
+            .iter()
+            .find(|profile| profile.name == name)
+            .map_or("", |profile| profile.description());
+        message.push_str(&format!("\n{}: {}", label, description));
+    }
+    // The last button is the one closing the dialog picks
+    let mut choices = CHOICES;
+    if let Some(i) = choices.iter().position(|(_, name)| Some(*name) == last) {
+        choices[i..].rotate_left(1);
+        let label = choices[2].0;
+        message.push_str(&format!("\n\nClosing this keeps {}, as last time.", label));
     }
+    let choice = gui::ask_choice("Profile", &message, choices.map(|(label, _)| label));
+    choices[choice].1
 }
 
-fn clean_run(run: &mut Run) {
-    for child in &mut run.children {
-        if let RunChild::Text(text) = child {
-            let cleaned: String = text
-                .text
-                .chars()
-                .map(|c| {
-                    if UNICODE_CHARS_TO_REMOVE.contains(&c) {
-                        // Increment the counter for this specific character
-                        if let Some(mut counter) = CHAR_COUNTERS.get_mut(&c) {
-                            *counter += 1;
-                        }
-                        // Use the replacement character from the config instead of RED_CROSS_MARK
-                        REPLACEMENT_CHARS.get(&c).copied().unwrap_or(RED_CROSS_MARK)
-                    } else {
-                        c
-                    }
-                })
-                .collect();
-            let space_collapse_re = Regex::new(r"[ ]{2,}").unwrap();
+// The GUI arguments with what the settings remember filled in where the command line leaves it
+// open
+fn remembered(args: &CleanArgs, settings: &Settings) -> CleanArgs {
+    let mut args = args.clone();
+    let config = &mut args.config;
+    if config.characters.config.is_none() {
+        config.characters.config = settings.config.clone().filter(|path| path.is_file());
+    }
+    if !config.no_collapse_spaces && !config.collapse_whitespace {
+        config.no_collapse_spaces = !settings.collapse_spaces;
+    }
+    args
+}
 
-            let num_space_replacements = space_collapse_re.find_iter(&cleaned).count();
+// Standard output only gets the cleaned text, so it can be piped on or used as a git clean filter
+fn filter_stdin(config: Config, args: &CleanArgs) -> Result<i32, CleanError> {
+    let mut buf = Vec::new();
+    std::io::stdin()
+        .read_to_end(&mut buf)
+        .map_err(CleanError::Read)?;
+    let cleaner = Cleaner::new(config);
+    let config = cleaner.config();
+    let cleaned = cleaner
+        .clean_text(&buf, args.markdown)
+        .map_err(CleanError::Clean)?;
+    let report = cleaned.report.clone();
 
-            if num_space_replacements > 0 {
-                println!(
-                    "Replaced {} stretch(es) of multiple spaces in '{}'",
-                    num_space_replacements, cleaned
-                );
-            }
+    let mut stdout = std::io::stdout().lock();
+    stdout
+        .write_all(&cleaned.into_bytes())
+        .and_then(|()| stdout.flush())
+        .map_err(CleanError::Stdout)?;
+    if !args.statistics.quiet {
+        eprint!("{}", console_statistics(config, &report, None, false));
+    }
+    if args.statistics.json.is_some() {
+        let statistics =
+            json_statistics(config, Path::new("-"), None, &report, &report.replacements);
+        eprintln!("{}", statistics);
+    }
+    let suspicious = args.statistics.suspicious_as_error && !report.suspicious.is_empty();
+    let flagged = args.statistics.strict_flags && report.total_kept() > 0;
+    Ok(
+        if suspicious || flagged || report.is_modified() && !args.statistics.exit_zero {
+            EXIT_CHARACTERS_FOUND
+        } else {
+            0
+        },
+    )
+}
 
-            let collapsed = space_collapse_re.replace_all(&cleaned, " ");
-            text.text = collapsed.into_owned();
-        }
+// The text is only put back when something was cleaned, so the formatting that came with it is
+// kept otherwise
+fn clean_clipboard(config: Config, args: &CleanArgs, is_gui_mode: bool) -> Result<i32, CleanError> {
+    let text = clipboard::read_text().map_err(CleanError::ClipboardRead)?;
+    let cleaner = Cleaner::new(config);
+    let config = cleaner.config();
+    let cleaned = cleaner
+        .clean_text(text.as_bytes(), false)
+        .map_err(CleanError::Clean)?;
+    let report = cleaned.report.clone();
+    let modified = report.is_modified();
+    if modified {
+        clipboard::write_text(cleaned.as_str()).map_err(CleanError::ClipboardWrite)?;
+    }
+
+    let mut statistics = console_statistics(config, &report, None, false);
+    statistics.push_str(if modified {
+        "The cleaned text is on the clipboard\n"
+    } else {
+        "Nothing to clean, the clipboard was left as it was\n"
+    });
+    if is_gui_mode {
+        gui::show_info("Clipboard Cleaned", &dialog_text(statistics.trim_start()));
+    } else if !args.statistics.quiet {
+        print!("{}", statistics);
     }
+    if args.statistics.json.is_some() {
+        let statistics = json_statistics(
+            config,
+            Path::new("clipboard"),
+            None,
+            &report,
+            &report.replacements,
+        );
+        println!("{}", statistics);
+    }
+    let suspicious = args.statistics.suspicious_as_error && !report.suspicious.is_empty();
+    let flagged = args.statistics.strict_flags && report.total_kept() > 0;
+    Ok(
+        if suspicious || flagged || modified && !args.statistics.exit_zero {
+            EXIT_CHARACTERS_FOUND
+        } else {
+            0
+        },
+    )
 }
 
+fn scan(args: &ScanArgs) -> Result<i32, CleanError> {
+    let cleaner = Cleaner::new(load_config(&args.config, Some(&args.statistics))?);
+    let inputs = collect_inputs(&args.inputs, &args.selection);
+    run(
+        &inputs,
+        &args.statistics,
+        &cleaner,
+        Mode::Scan,
+        args.threads,
+        false,
+    )
+}
 
-fn generate_output_path(input_path: &PathBuf) -> PathBuf {
-    let stem = input_path.file_stem().unwrap().to_str().unwrap();
-    let extension = input_path.extension().unwrap().to_str().unwrap();
-    let parent = input_path
-        .parent()
-        .unwrap_or_else(|| std::path::Path::new("."));
+// A table of the effective character list, after the user config and --categories
+fn list_chars(args: &ListArgs) -> Result<i32, CleanError> {
+    let config = load_characters(&args.characters)?;
+    let listing = if args.json {
+        json_character_list(&config)
+    } else {
+        character_table(&config)
+    };
+    // The list is long and often piped into head or grep, which may stop reading early
+    let _ = std::io::stdout().write_all(listing.as_bytes());
+    Ok(0)
+}
 
-    parent.join(format!("{}_cleaned.{}", stem, extension))
+// e.g.
+// minimal     Only the zero-width and format characters; spaces, punctuation and ...
+//             keeps 139 built-in characters, collapse_spaces: false, nbsp: keep
+fn list_profiles(args: &ListProfilesArgs) -> Result<i32, CleanError> {
+    let profiles =
+        Config::profiles(args.config.as_deref(), args.config_format).map_err(CleanError::Config)?;
+    let width = profiles
+        .iter()
+        .map(|profile| profile.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(10);
+    let mut listing = String::new();
+    for profile in &profiles {
+        let default = if profile.name == DEFAULT_PROFILE {
+            " (default)"
+        } else {
+            ""
+        };
+        listing.push_str(&format!(
+            "{:<width$}  {}{}\n",
+            profile.name,
+            profile.description(),
+            default
+        ));
+        let contents = profile.contents();
+        if !contents.is_empty() {
+            listing.push_str(&format!("{:<width$}  {}\n", "", contents.join(", ")));
+        }
+    }
+    print!("{}", listing);
+    Ok(0)
 }
-fn print_console_statistics(output_path: &PathBuf) {
-    println!("\nCharacter Removal Statistics:");
-    println!("============================");
-    let mut total = 0;
 
-    let mut results: Vec<(char, usize)> = CHAR_COUNTERS
+// e.g.
+// U+2002 EN SPACE
+// Category:  Zs
+// Action:    replace with " "
+// Rules:     replace (built-in list)
+// Example:   "one\u{2002}two" becomes "one two"
+fn explain(args: &ExplainArgs) -> Result<i32, CleanError> {
+    let config = load_config(&args.config, None)?;
+    let c = args.character;
+    let category = category_name(c.general_category());
+    let action = config.action(c);
+    let mut explanation = format!("U+{:04X} {}\n", c as u32, config.name(c));
+    explanation.push_str(&format!("{:<10} {}\n", "Category:", category));
+    let action = match action {
+        Some(RuleAction::Replace) => format!("replace with {:?}", config.replacement(c)),
+        Some(RuleAction::Flag) => "flag, left in the text and reported".to_string(),
+        Some(RuleAction::Keep) => "keep, left alone even with --categories".to_string(),
+        Some(RuleAction::Delete) => "delete".to_string(),
+        // No-break spaces are up to the "nbsp" setting unless a rule lists them
+        None if c == NO_BREAK_SPACE => match config.nbsp {
+            NbspMode::Replace => "replace with \" \" (the \"nbsp\" setting)".to_string(),
+            NbspMode::Keep => "none, the \"nbsp\" setting keeps it".to_string(),
+            NbspMode::Patterns => "replace with \" \" except where one of the \"nbsp_patterns\" \
+                                   matches (the \"nbsp\" setting)"
+                .to_string(),
+        },
+        None => "none, it is not targeted".to_string(),
+    };
+    explanation.push_str(&format!("{:<10} {}\n", "Action:", action));
+    let rules: Vec<String> = config
+        .history(c)
         .iter()
-        .map(|entry| (*entry.key(), *entry.value()))
+        .map(|(origin, action)| {
+            format!(
+                "{} ({})",
+                action.name(),
+                origin_description(&config, *origin)
+            )
+        })
         .collect();
-    results.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+    if !rules.is_empty() {
+        explanation.push_str(&format!("{:<10} {}\n", "Rules:", rules.join(", then ")));
+    }
+    let sample = format!("one{}two", c);
+    let cleaner = Cleaner::new(config);
+    let cleaned = cleaner
+        .clean_text(sample.as_bytes(), false)
+        .map_err(CleanError::Clean)?;
+    let cleaned = String::from_utf8(cleaned.into_bytes()).expect("Cleaned text stays UTF-8");
+    let example = if cleaned == sample {
+        format!("{:?} is left as it is", sample)
+    } else {
+        format!("{:?} becomes {:?}", sample, cleaned)
+    };
+    explanation.push_str(&format!("{:<10} {}\n", "Example:", example));
+    if cleaner.config().action(c).is_none() && c != NO_BREAK_SPACE {
+        explanation.push_str(&format!(
+            "\nTo remove it, add \"U+{:04X}\": [\"NAME\", \"\"] to the config, or remove its \
+             whole category with --categories {}\n",
+            c as u32, category
+        ));
+    }
+    print!("{}", explanation);
+    Ok(0)
+}
 
-    for (char, count) in results {
-        if count > 0 {
-            let name = CHAR_NAMES
-                .get(&char)
-                .map(|s| s.as_str())
-                .unwrap_or("UNKNOWN");
-            println!("{} (U+{:04X}) - {}: {}", name, char as u32, char, count);
-            total += count;
-        }
+// Where a rule comes from, for explain
+fn origin_description(config: &Config, origin: Origin) -> String {
+    match origin {
+        Origin::Builtin => "built-in list".to_string(),
+        Origin::Profile => format!("profile '{}'", config.profile()),
+        Origin::User => match config.source() {
+            Some(path) => format!("user config '{}'", path.display()),
+            None => "user config".to_string(),
+        },
+        Origin::Category => "--categories".to_string(),
+        Origin::Typography => "typographic punctuation".to_string(),
     }
+}
 
-    println!("\nTotal characters removed: {}", total);
-    println!("Saved as: {}", output_path.display());
+// The context menu entries cover every document format; text files open in an editor instead
+fn shell_entries(change: fn(&[&str]) -> Result<String, String>) -> Result<i32, CleanError> {
+    let extensions: Vec<&str> = DOCUMENT_EXTENSIONS
+        .iter()
+        .chain(&PRESENTATION_EXTENSIONS)
+        .chain(&WORKBOOK_EXTENSIONS)
+        .chain(&OPEN_DOCUMENT_EXTENSIONS)
+        .copied()
+        .collect();
+    println!("{}", change(&extensions).map_err(CleanError::Shell)?);
+    Ok(0)
 }
 
-fn show_gui_statistics(output_path: &PathBuf) {
-    let mut message = String::from("Character Removal Statistics:\n");
-    message.push_str("============================\n\n");
-    let mut total = 0;
+fn origin_name(origin: Origin) -> &'static str {
+    match origin {
+        Origin::Builtin => "builtin",
+        Origin::Profile => "profile",
+        Origin::User => "user",
+        Origin::Category => "category",
+        Origin::Typography => "typography",
+    }
+}
 
-    let mut results: Vec<(char, usize)> = CHAR_COUNTERS
-        .iter()
-        .map(|entry| (*entry.key(), *entry.value()))
+// e.g. U+00B7      "*"          builtin    MIDDLE DOT
+fn character_table(config: &Config) -> String {
+    let mut table = format!(
+        "{:<11} {:<12} {:<10} {}\n",
+        "CODE POINT", "REPLACEMENT", "SOURCE", "DESCRIPTION"
+    );
+    for c in config.listed() {
+        let replacement = match config.action(c) {
+            Some(RuleAction::Replace) => format!("{:?}", config.replacement(c)),
+            action => format!("<{}>", action.map_or("", RuleAction::name)),
+        };
+        table.push_str(&format!(
+            "{:<11} {:<12} {:<10} {}\n",
+            format!("U+{:04X}", c as u32),
+            replacement,
+            origin_name(config.origin(c)),
+            config.name(c)
+        ));
+    }
+    table
+}
+
+fn json_character_list(config: &Config) -> String {
+    let characters: Vec<Value> = config
+        .listed()
+        .into_iter()
+        .map(|c| {
+            json!({
+                "codepoint": format!("U+{:04X}", c as u32),
+                "name": config.name(c),
+                "action": config.action(c).map(RuleAction::name),
+                "replacement": config.replacement(c),
+                "source": origin_name(config.origin(c)),
+            })
+        })
         .collect();
-    results.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+    let list = json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "config": config_source(config),
+        "profile": config.profile(),
+        "characters": characters,
+    });
+    serde_json::to_string_pretty(&list).unwrap() + "\n"
+}
 
-    for (char, count) in results {
-        if count > 0 {
-            let name = CHAR_NAMES
-                .get(&char)
-                .map(|s| s.as_str())
-                .unwrap_or("UNKNOWN");
-            message.push_str(&format!(
-                "{} (U+{:04X}) - {}: {}\n",
-                name, char as u32, char, count
-            ));
-            total += count;
+// The outcome of a file in a batch, or why it could not be processed
+type FileResult = Result<FileSummary, CleanError>;
+
+// What the batch summary lists of a processed file
+struct FileSummary {
+    removed: usize,
+    spaces_removed: usize,
+    modified: bool,
+}
+
+impl FileSummary {
+    fn findings(&self) -> usize {
+        self.removed + self.spaces_removed
+    }
+}
+
+// The outcome of each file of a run in the order they were processed, with their reports added
+// up for the totals
+struct BatchReport<'a> {
+    files: Vec<(&'a PathBuf, FileResult)>,
+    totals: CleanReport,
+    skipped: usize,
+    // The files left after the failure that ended the run with --stop-on-error
+    stopped: usize,
+}
+
+impl<'a> BatchReport<'a> {
+    fn new() -> Self {
+        BatchReport {
+            files: Vec::new(),
+            totals: CleanReport::default(),
+            skipped: 0,
+            stopped: 0,
         }
     }
 
-    message.push_str(&format!("\nTotal characters removed: {}\n", total));
-    message.push_str(&format!("Saved as: {}", output_path.display()));
+    fn add(&mut self, input_path: &'a PathBuf, report: CleanReport) {
+        let summary = FileSummary {
+            removed: report.total_removed(),
+            spaces_removed: report.collapsed_spaces + report.break_spaces,
+            modified: report.is_modified(),
+        };
+        self.files.push((input_path, Ok(summary)));
+        self.totals.merge(report);
+    }
+
+    fn fail(&mut self, input_path: &'a PathBuf, error: CleanError) {
+        self.files.push((input_path, Err(error)));
+    }
+
+    fn processed(&self) -> impl Iterator<Item = (&'a PathBuf, &FileSummary)> + '_ {
+        self.files
+            .iter()
+            .filter_map(|(path, result)| Some((*path, result.as_ref().ok()?)))
+    }
+
+    fn failed(&self) -> impl Iterator<Item = (&'a PathBuf, &CleanError)> + '_ {
+        self.files
+            .iter()
+            .filter_map(|(path, result)| Some((*path, result.as_ref().err()?)))
+    }
+
+    // The processed files with findings, most first so the worst offenders lead; ties keep the
+    // order they were processed in
+    fn ranked(&self) -> Vec<(&'a PathBuf, &FileSummary)> {
+        let mut ranked: Vec<_> = self
+            .processed()
+            .filter(|(_, summary)| summary.modified || summary.findings() > 0)
+            .collect();
+        ranked.sort_by_key(|(_, summary)| std::cmp::Reverse(summary.findings()));
+        ranked
+    }
+
+    // The files without anything to clean
+    fn clean_files(&self) -> usize {
+        self.processed()
+            .filter(|(_, summary)| !summary.modified && summary.findings() == 0)
+            .count()
+    }
+
+    // The most severe outcome decides the exit code, failures ahead of characters found
+    fn exit_code(&self, args: &StatisticsArgs) -> i32 {
+        let found = self.processed().any(|(_, summary)| summary.removed > 0);
+        let suspicious = args.suspicious_as_error && !self.totals.suspicious.is_empty();
+        let flagged = args.strict_flags && self.totals.total_kept() > 0;
+        let found = if suspicious || flagged || found && !args.exit_zero {
+            EXIT_CHARACTERS_FOUND
+        } else {
+            0
+        };
+        let failed = self.failed().map(|(_, error)| error.exit_code());
+        failed.fold(found, i32::max)
+    }
 
-    let _ok = MessageDialog::new()
-        .set_title("Processing Complete")
-        .set_description(&message)
-        .set_buttons(rfd::MessageButtons::Ok)
-        .set_level(rfd::MessageLevel::Info)
-        .show();
+    // The last --json object of a batch: every file, ranked like the summary, and the totals
+    fn to_json(&self) -> Value {
+        let mut files: Vec<Value> = self
+            .ranked()
+            .into_iter()
+            .map(|(path, summary)| {
+                json!({
+                    "input": path.display().to_string(),
+                    "characters": summary.removed,
+                    "spaces_removed": summary.spaces_removed,
+                    "modified": summary.modified,
+                })
+            })
+            .collect();
+        for (path, summary) in self.processed() {
+            if !summary.modified && summary.findings() == 0 {
+                files.push(json!({
+                    "input": path.display().to_string(),
+                    "characters": 0,
+                    "spaces_removed": 0,
+                    "modified": false,
+                }));
+            }
+        }
+        for (path, error) in self.failed() {
+            files.push(json!({
+                "input": path.display().to_string(),
+                "error": error.to_string(),
+                "exit_code": error.exit_code(),
+            }));
+        }
+        let processed = self.processed().count();
+        json!({
+            "files": files,
+            "totals": {
+                "processed": processed,
+                "modified": self.processed().filter(|(_, summary)| summary.modified).count(),
+                "already_clean": self.clean_files(),
+                "skipped": self.skipped,
+                "failed": self.files.len() - processed,
+                "not_processed": self.stopped,
+                "characters": self.totals.total_removed(),
+                "spaces_removed": self.totals.collapsed_spaces + self.totals.break_spaces,
+            },
+        })
+    }
+}
+
+// What run does with each document: write a cleaned copy (or only check it with --check), or
+// scan it and list where the characters are
+#[derive(Clone, Copy)]
+enum Mode<'a> {
+    Clean(&'a CleanArgs),
+    Scan,
+}
+
+// Returns the exit code once all files are processed; failures that stop the whole run, such as
+// an invalid config, are returned as errors instead
+fn run(
+    inputs: &Inputs,
+    args: &StatisticsArgs,
+    cleaner: &Cleaner,
+    mode: Mode,
+    threads: Option<NonZeroUsize>,
+    is_gui_mode: bool,
+) -> Result<i32, CleanError> {
+    let config = cleaner.config();
+    let check = match mode {
+        Mode::Clean(args) => args.check,
+        Mode::Scan => true,
+    };
+    let mut batch = BatchReport::new();
+    let mut located = Vec::new();
+    let mut diffs = HashMap::new();
+    let mut json = args.json.map(JsonOutput::new);
+    let print_statistics = !is_gui_mode && !args.quiet;
+    let to_stderr = json.is_some() || args.format.is_some();
+    // --files-from prints a line for each file with something to clean and nothing else
+    let brief = matches!(mode, Mode::Clean(args) if args.files_from.is_some());
+    let summarize = !brief && (inputs.files.len() > 1 || inputs.skipped > 0);
+    // Several files picked in the GUI get a single dialog at the end instead of one per file
+    let combined_dialog = is_gui_mode && inputs.files.len() > 1;
+    // The report and copy of a single file, for its statistics dialog once it is done
+    let mut single_file = None;
+    let progress = progress_bar(inputs.files.len(), !print_statistics);
+    log_around(&progress);
+    let interactive = matches!(mode, Mode::Clean(args) if args.interactive);
+    let stop_on_error = matches!(mode, Mode::Clean(args) if args.stop_on_error);
+    // In the GUI the changes are shown before a file is written, unless they were just asked
+    // about one character at a time. Several files can be written without asking again.
+    let write_all = Cell::new(false);
+    let confirm = |input_path: &Path, report: &CleanReport, diff: Option<&str>| {
+        if !is_gui_mode || interactive || write_all.get() {
+            return true;
+        }
+        let preview = preview(config, input_path, report, diff);
+        if !combined_dialog {
+            return confirm_in_dialog(&preview);
+        }
+        match confirm_batch_in_dialog(&preview) {
+            BatchConfirmation::Write => true,
+            BatchConfirmation::WriteAll => {
+                write_all.set(true);
+                true
+            }
+            BatchConfirmation::Skip => false,
+        }
+    };
+    // With --interactive the user picks the characters to keep from what was found
+    let decline = |input_path: &Path, report: &CleanReport| {
+        if is_gui_mode {
+            decline_in_dialogs(config, input_path, report)
+        } else {
+            progress.suspend(|| decline_on_stdin(config, input_path, report))
+        }
+    };
+    // An earlier copy is only replaced with --force; the GUI asks whether to replace it or to
+    // write the new one under a numbered name
+    let choose_output = |output_path: PathBuf| {
+        if is_gui_mode {
+            Ok(ask_to_overwrite(output_path))
+        } else {
+            Err(CleanError::OutputExists(output_path))
+        }
+    };
+    let mut declined = 0;
+    // Without questions to ask, several files are processed at once, each on its own, and taken
+    // here in the order they were given, so the output and the totals are the same whichever
+    // finishes first
+    let parallel = inputs.files.len() > 1 && !is_gui_mode && !interactive && !stop_on_error;
+    let pool = parallel
+        .then(|| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads.map_or(0, NonZeroUsize::get))
+                .build()
+                .ok()
+        })
+        .flatten();
+    let (sender, receiver) = mpsc::channel();
+    let mut finished = HashMap::new();
+    std::thread::scope(|scope| {
+        if let Some(pool) = &pool {
+            let files = &inputs.files;
+            scope.spawn(move || {
+                pool.scope(|tasks| {
+                    for (i, input_path) in files.iter().enumerate() {
+                        let sender = sender.clone();
+                        tasks.spawn(move |_| {
+                            // Nothing is asked, as on the command line without --interactive
+                            let processed = process(
+                                input_path,
+                                mode,
+                                cleaner,
+                                &|_, _, _| true,
+                                &|_, _| Vec::new(),
+                                &|output_path| Err(CleanError::OutputExists(output_path)),
+                            );
+                            let _ = sender.send((i, processed));
+                        });
+                    }
+                })
+            });
+        }
+        for (i, input_path) in inputs.files.iter().enumerate() {
+            progress.set_message(input_path.display().to_string());
+            if print_statistics && summarize {
+                progress
+                    .suspend(|| print_human(&format!("\n{}\n", input_path.display()), to_stderr));
+            }
+            let processed = if pool.is_some() {
+                loop {
+                    if let Some(processed) = finished.remove(&i) {
+                        break processed;
+                    }
+                    // Only when a worker panicked, which the scope passes on
+                    let Ok((done, processed)) = receiver.recv() else {
+                        return;
+                    };
+                    finished.insert(done, processed);
+                }
+            } else {
+                process(
+                    input_path,
+                    mode,
+                    cleaner,
+                    &confirm,
+                    &decline,
+                    &choose_output,
+                )
+            };
+            let Some(processed) = processed.transpose() else {
+                if print_statistics {
+                    let message = format!("No changes written to {}\n", input_path.display());
+                    progress.suspend(|| print_human(&message, to_stderr));
+                }
+                declined += 1;
+                progress.inc(1);
+                continue;
+            };
+            match processed {
+                Ok((mut report, output_path, diff)) => {
+                    let replacements = std::mem::take(&mut report.replacements);
+                    if let Mode::Clean(args) = mode {
+                        if args.log {
+                            let entry = log_entry(input_path, &report, output_path.as_deref());
+                            if append_to_log(args.log_file.as_deref(), &entry).is_none() {
+                                log::warn!("the log could not be written");
+                            }
+                        }
+                    }
+                    if is_gui_mode && !combined_dialog {
+                        single_file = Some((report.clone(), output_path.clone()));
+                    } else if print_statistics && brief {
+                        if let Some(line) = brief_statistics(input_path, &report, check) {
+                            progress.suspend(|| print_human(&line, to_stderr));
+                        }
+                    } else if print_statistics {
+                        let mut statistics =
+                            console_statistics(config, &report, output_path.as_deref(), check);
+                        // --no-change-skip
+                        if output_path.is_none() && !check {
+                            statistics.push_str("Nothing to clean, nothing written\n");
+                        }
+                        if matches!(mode, Mode::Scan) {
+                            statistics.push_str(&location_details(config, &replacements));
+                        }
+                        progress.suspend(|| print_human(&statistics, to_stderr));
+                    }
+                    // The diff is only left out of the output when it goes into the report
+                    match diff.filter(|diff| !diff.is_empty()) {
+                        Some(diff) if args.report.is_some() => {
+                            diffs.insert(input_path.clone(), diff);
+                        }
+                        Some(diff) if !is_gui_mode => {
+                            let diff = if print_statistics {
+                                format!("\n{}", diff)
+                            } else {
+                                diff
+                            };
+                            progress.suspend(|| print_human(&diff, to_stderr));
+                        }
+                        _ => {}
+                    }
+                    if let Some(json) = &mut json {
+                        let statistics = json_statistics(
+                            config,
+                            input_path,
+                            output_path.as_deref(),
+                            &report,
+                            &replacements,
+                        );
+                        progress.suspend(|| json.push(statistics));
+                    }
+                    // The GUI can save the report once the files are done
+                    if args.report.is_some() || args.format.is_some() || is_gui_mode {
+                        located.push((input_path, replacements));
+                    }
+                    batch.add(input_path, report);
+                }
+                Err(error) => {
+                    log::error!("{}: {}", input_path.display(), error);
+                    if let Some(json) = &mut json {
+                        let failure = json_failure(config, input_path, &error);
+                        progress.suspend(|| json.push(failure));
+                    }
+                    batch.fail(input_path, error);
+                    if stop_on_error {
+                        batch.stopped = inputs.files.len() - i - 1;
+                        break;
+                    }
+                }
+            }
+            progress.inc(1);
+        }
+    });
+    progress.finish_and_clear();
+
+    batch.skipped = inputs.skipped + declined;
+    if print_statistics && summarize {
+        print_human(&batch_summary(config, &batch, check), to_stderr);
+    }
+    if is_gui_mode {
+        let outcome = match &single_file {
+            _ if combined_dialog => Outcome::Batch(&batch),
+            Some((report, output_path)) => Outcome::File(report, output_path.as_deref()),
+            None => Outcome::Failed(&batch),
+        };
+        show_outcome(config, outcome, mode, check, &located);
+    }
+    if let Some(mut json) = json {
+        if summarize {
+            json.push(batch.to_json());
+        }
+        json.finish();
+    }
+    if args.format == Some(OutputFormat::Sarif) {
+        println!("{}", sarif_log(config, &located));
+    }
+    if let Some(report_path) = &args.report {
+        write_report(config, report_path, &located, &diffs)
+            .map_err(|e| CleanError::Write(report_path.clone(), e))?;
+    }
+    let exit_code = batch.exit_code(args);
+    // Whatever was printed for a hook counts as found, the spaces as well
+    if brief && !args.exit_zero && batch.processed().any(|(_, summary)| summary.modified) {
+        return Ok(exit_code.max(EXIT_CHARACTERS_FOUND));
+    }
+    Ok(exit_code)
+}
+
+// The files to process once the directories are walked, and how many were left out
+struct Inputs {
+    files: Vec<PathBuf>,
+    skipped: usize,
+}
+
+// Files are taken as given; directories are walked in name order, following symbolic links, for
+// supported files. Entries that cannot be read, such as a directory without permission or a link
+// back to one of its ancestors, are skipped with a warning instead of ending the walk.
+fn collect_inputs(paths: &[PathBuf], selection: &SelectionArgs) -> Inputs {
+    let mut inputs = Inputs {
+        files: Vec::new(),
+        skipped: 0,
+    };
+    let add = |path: PathBuf, inputs: &mut Inputs| {
+        if selection.selects(&path) {
+            inputs.files.push(path);
+        } else {
+            inputs.skipped += 1;
+        }
+    };
+    for path in paths {
+        if !path.is_dir() {
+            add(path.clone(), &mut inputs);
+            continue;
+        }
+        for entry in WalkDir::new(path).follow_links(true).sort_by_file_name() {
+            match entry {
+                Ok(entry) if entry.file_type().is_file() && is_supported(entry.path()) => {
+                    add(entry.into_path(), &mut inputs)
+                }
+                Ok(_) => {}
+                Err(error) => {
+                    log::warn!("skipped {}", error);
+                    inputs.skipped += 1;
+                }
+            }
+        }
+    }
+    inputs
+}
+
+// Documents and templates, with and without macros; the cleaned copy keeps the extension
+const DOCUMENT_EXTENSIONS: [&str; 4] = ["docx", "docm", "dotx", "dotm"];
+const PRESENTATION_EXTENSIONS: [&str; 1] = ["pptx"];
+const WORKBOOK_EXTENSIONS: [&str; 1] = ["xlsx"];
+const OPEN_DOCUMENT_EXTENSIONS: [&str; 1] = ["odt"];
+
+// Word keeps a "~$name.docx" lock file next to an open document, which is not a document itself
+fn is_supported(path: &Path) -> bool {
+    let is_lock_file = path
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with("~$"));
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let is_document = DOCUMENT_EXTENSIONS
+        .iter()
+        .chain(&PRESENTATION_EXTENSIONS)
+        .chain(&WORKBOOK_EXTENSIONS)
+        .chain(&OPEN_DOCUMENT_EXTENSIONS)
+        .any(|known| extension.eq_ignore_ascii_case(known));
+    (is_document || text_kind(path).is_some()) && !is_lock_file
+}
+
+// Some(true) for Markdown and Some(false) for plain text files, which are cleaned line by line;
+// None for anything else, which is read as a document
+fn text_kind(path: &Path) -> Option<bool> {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    if extension.eq_ignore_ascii_case("txt") {
+        Some(false)
+    } else if extension.eq_ignore_ascii_case("md") || extension.eq_ignore_ascii_case("markdown") {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+// `*` matches any run of characters and `?` a single one, ignoring case. Patterns without a
+// slash are matched against the file name only.
+fn glob_matches(pattern: &str, path: &Path) -> bool {
+    let text = if pattern.contains('/') {
+        path.to_string_lossy().replace('\\', "/")
+    } else {
+        path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    };
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // After a `*`: where the pattern continues, and where in the text the `*` match ends
+    let mut star = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            // Let the last `*` take one more character and try again
+            _ => match star {
+                Some((after_star, end)) => {
+                    p = after_star;
+                    t = end + 1;
+                    star = Some((after_star, end + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// How many of the changes a preview lists with their context
+const PREVIEW_CHANGES: usize = 10;
+
+// How many lines of the --diff a preview shows
+const PREVIEW_DIFF_LINES: usize = 30;
+
+// What cleaning a file changes: the counts per character and the first few replacements in
+// their context, with the invisible characters written out
+fn preview(config: &Config, input_path: &Path, report: &CleanReport, diff: Option<&str>) -> String {
+    let mut preview = format!("{}\n\nChanges:\n", input_path.display());
+    for (c, count) in sorted_counts(&report.chars) {
+        preview.push_str(&format!(
+            "{} (U+{:04X}): {}\n",
+            config.name(c),
+            c as u32,
+            count
+        ));
+    }
+    preview.push_str(&space_statistics(report));
+    preview.push_str(&normalization_statistics(report));
+    preview.push_str(&homoglyph_statistics(config, report));
+    // With --diff the changed paragraphs take the place of the first changes
+    if let Some(diff) = diff.filter(|diff| !diff.is_empty()) {
+        let lines: Vec<&str> = diff.lines().collect();
+        preview.push_str("\nDiff:\n");
+        for line in lines.iter().take(PREVIEW_DIFF_LINES) {
+            preview.push_str(&format!("{}\n", line));
+        }
+        if lines.len() > PREVIEW_DIFF_LINES {
+            preview.push_str(&format!(
+                "... and {} more lines\n",
+                lines.len() - PREVIEW_DIFF_LINES
+            ));
+        }
+    } else if !report.replacements.is_empty() {
+        preview.push_str("\nFirst changes:\n");
+        for replacement in report.replacements.iter().take(PREVIEW_CHANGES) {
+            preview.push_str(&replacement_line(config, replacement));
+        }
+        if report.replacements.len() > PREVIEW_CHANGES {
+            preview.push_str(&format!(
+                "... and {} more\n",
+                report.replacements.len() - PREVIEW_CHANGES
+            ));
+        }
+    }
+    preview
+}
+
+// Yes replaces the earlier copy, No writes the new one under the first free numbered name
+fn ask_to_overwrite(output_path: PathBuf) -> PathBuf {
+    let numbered = numbered_copy_path(&output_path);
+    let name = |path: &Path| {
+        path.file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned())
+    };
+    let message = format!(
+        "{} already exists and may hold edits made since it was cleaned.\n\n\
+         Press Yes to replace it, or No to save the new copy as {} instead.",
+        name(&output_path),
+        name(&numbered)
+    );
+    if gui::ask_yes_no("Replace the earlier cleaned copy?", &message) {
+        output_path
+    } else {
+        numbered
+    }
+}
+
+fn confirm_in_dialog(preview: &str) -> bool {
+    let message = format!("{}\nPress OK to write the cleaned copy.", preview);
+    gui::ask_ok_cancel("Write the cleaned copy?", &message)
+}
+
+// The answer to the preview of a file among several
+enum BatchConfirmation {
+    Write,
+    WriteAll,
+    Skip,
+}
+
+// Closing the dialog skips the file, like Cancel does for a single one
+fn confirm_batch_in_dialog(preview: &str) -> BatchConfirmation {
+    let message = format!(
+        "{}\nPress Write to write the cleaned copy, or Write All to write it and the copies of \
+         the remaining files without asking.",
+        preview
+    );
+    match gui::ask_choice(
+        "Write the cleaned copy?",
+        &message,
+        ["Write", "Write All", "Skip"],
+    ) {
+        0 => BatchConfirmation::Write,
+        1 => BatchConfirmation::WriteAll,
+        _ => BatchConfirmation::Skip,
+    }
+}
+
+// How many occurrences of a character "show" lists
+const SHOWN_OCCURRENCES: usize = 5;
+
+fn occurrences(config: &Config, report: &CleanReport, c: char) -> String {
+    let mut occurrences: String = report
+        .replacements
+        .iter()
+        .filter(|replacement| replacement.character == c)
+        .take(SHOWN_OCCURRENCES)
+        .map(|replacement| replacement_line(config, replacement))
+        .collect();
+    let count = report.chars.get(&c).copied().unwrap_or(0);
+    if count > SHOWN_OCCURRENCES {
+        occurrences.push_str(&format!("... and {} more\n", count - SHOWN_OCCURRENCES));
+    }
+    occurrences
+}
+
+// The characters of the file to keep, asked about from the most frequent one. An empty answer
+// removes them like y does, anything else asks again; the end of the input keeps the rest.
+fn decline_on_stdin(config: &Config, input_path: &Path, report: &CleanReport) -> Vec<char> {
+    eprintln!("\n{}", input_path.display());
+    let mut declined = Vec::new();
+    for (c, count) in sorted_counts(&report.chars) {
+        loop {
+            eprint!(
+                "Remove {} \u{d7} U+{:04X} {}? [Y/n/show] ",
+                count,
+                c as u32,
+                config.name(c)
+            );
+            let mut answer = String::new();
+            if !matches!(std::io::stdin().read_line(&mut answer), Ok(read) if read > 0) {
+                eprintln!();
+                declined.push(c);
+                break;
+            }
+            match answer.trim().to_lowercase().as_str() {
+                "" | "y" | "yes" => break,
+                "n" | "no" => {
+                    declined.push(c);
+                    break;
+                }
+                "s" | "show" => eprint!("{}", occurrences(config, report, c)),
+                _ => {}
+            }
+        }
+    }
+    declined
+}
+
+// rfd has no checkboxes, so the GUI asks about one character after the other, with a few of its
+// occurrences in the message
+fn decline_in_dialogs(config: &Config, input_path: &Path, report: &CleanReport) -> Vec<char> {
+    sorted_counts(&report.chars)
+        .into_iter()
+        .filter(|&(c, count)| {
+            let message = format!(
+                "{}\n\n{} \u{d7} U+{:04X} {}, e.g.\n\n{}\n\
+                 Press Yes to remove them, or No to keep them.",
+                input_path.display(),
+                count,
+                c as u32,
+                config.name(c),
+                occurrences(config, report, c)
+            );
+            !gui::ask_yes_no(&format!("Remove {}?", config.name(c)), &message)
+        })
+        .map(|(c, _)| c)
+        .collect()
+}
+
+// Only reads the document, so read-only files can be scanned
+fn scan_file(input_path: &Path, cleaner: &Cleaner) -> Result<CleanReport, CleanError> {
+    if !input_path.exists() {
+        return Err(CleanError::InputMissing);
+    }
+    let buf = read_unless_streamed(input_path, false)?;
+    detect(input_path, buf.as_deref(), cleaner)
+}
+
+// Documents from this size on are streamed as with --stream
+const STREAM_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+// The whole file, or None for a document that is read a part at a time instead
+fn read_unless_streamed(input_path: &Path, stream: bool) -> Result<Option<Vec<u8>>, CleanError> {
+    let streamed = text_kind(input_path).is_none()
+        && (stream
+            || std::fs::metadata(input_path)
+                .is_ok_and(|metadata| metadata.len() >= STREAM_THRESHOLD));
+    if streamed {
+        return Ok(None);
+    }
+    std::fs::read(input_path)
+        .map(Some)
+        .map_err(CleanError::Read)
+}
+
+// What cleaning would replace and where, without cleaning anything: scan, and the questions of
+// --interactive before the document is cleaned
+fn detect(
+    input_path: &Path,
+    buf: Option<&[u8]>,
+    cleaner: &Cleaner,
+) -> Result<CleanReport, CleanError> {
+    match (text_kind(input_path), buf) {
+        (Some(markdown), Some(buf)) => cleaner.scan_text(buf, markdown),
+        (_, Some(buf)) => cleaner.scan_package(buf),
+        (_, None) => cleaner.scan_package_file(input_path),
+    }
+    .map_err(CleanError::Clean)
+}
+
+// Cleans a single document and returns its report with the path of the cleaned copy, or None
+// with --check where the document is only cleaned in memory, and the diff of --diff. Nothing is
+// written when `confirm` turns the changes down, which returns Ok(None). With --interactive,
+// `decline` picks the characters to keep from what was found first. Without --force,
+// `choose_output` decides where a copy goes whose path is taken already, before the document is
+// even read.
+fn process_file(
+    input_path: &Path,
+    args: &CleanArgs,
+    cleaner: &Cleaner,
+    confirm: &dyn Fn(&Path, &CleanReport, Option<&str>) -> bool,
+    decline: &dyn Fn(&Path, &CleanReport) -> Vec<char>,
+    choose_output: &dyn Fn(PathBuf) -> Result<PathBuf, CleanError>,
+) -> Result<Option<Processed>, CleanError> {
+    if !input_path.exists() {
+        return Err(CleanError::InputMissing);
+    }
+    let mut output_path = if args.in_place {
+        input_path.to_path_buf()
+    } else {
+        resolve_output_path(input_path, args.output.as_deref())?
+    };
+    // An earlier copy is found before the document is read, or with --no-change-skip once the
+    // copy is certain to be written
+    let writes_copy = !args.in_place && (!args.check || args.annotate);
+    if writes_copy && !args.force && !args.no_change_skip && output_path.exists() {
+        output_path = choose_output(output_path)?;
+    }
+    // --annotate --check has no streamed form, but leaves the text as it is anyway
+    let buf = read_unless_streamed(input_path, args.stream && !(args.check && args.annotate))?;
+    let declining;
+    let cleaner = if args.interactive {
+        let declined = decline(input_path, &detect(input_path, buf.as_deref(), cleaner)?);
+        let mut config = cleaner.config().clone();
+        for c in declined {
+            config.decline(c);
+        }
+        declining = Cleaner::new(config);
+        &declining
+    } else {
+        cleaner
+    };
+    // Text files have no comments, tracked changes or highlighting, so --annotate, --track-changes
+    // and --highlight only apply to documents
+    let (report, packed, diff) = if let (Some(markdown), Some(buf)) = (text_kind(input_path), &buf)
+    {
+        let cleaned = cleaner
+            .clean_text(buf, markdown)
+            .map_err(CleanError::Clean)?;
+        let diff = args.diff.then(|| {
+            let original = String::from_utf8_lossy(buf);
+            diff::text_diff(cleaner, input_path, &original, cleaned.as_str())
+        });
+        if args.check {
+            return Ok(Some((cleaned.report, None, diff)));
+        }
+        (cleaned.report.clone(), Packed::Text(cleaned), diff)
+    } else {
+        // --annotate --check writes a copy with the comments and the text left as it is
+        let cleaned = match &buf {
+            Some(buf) if args.check && args.annotate => cleaner.annotate_package(buf),
+            Some(buf) => cleaner.clean_package(buf),
+            None => cleaner.clean_package_file(input_path),
+        };
+        let cleaned = cleaned.map_err(CleanError::Clean)?;
+        let diff = if args.diff {
+            Some(diff::document_diff(cleaner, input_path, &cleaned).map_err(CleanError::Clean)?)
+        } else {
+            None
+        };
+        if args.check && !args.annotate {
+            return Ok(Some((cleaned.report, None, diff)));
+        }
+        (cleaned.report.clone(), Packed::Document(cleaned), diff)
+    };
+    if !args.check && report.is_modified() && !confirm(input_path, &report, diff.as_deref()) {
+        return Ok(None);
+    }
+    if args.no_change_skip && !report.is_modified() {
+        return Ok(Some((report, None, diff)));
+    }
+    if args.no_change_skip && writes_copy && !args.force && output_path.exists() {
+        output_path = choose_output(output_path)?;
+    }
+
+    let original = std::fs::metadata(input_path).map_err(CleanError::Read)?;
+    if args.in_place {
+        backup_original(input_path, args.backup_dir.as_deref())?;
+        replace_original(input_path, packed, &original, args.preserve_timestamps)?;
+    } else {
+        write_atomically(&output_path, packed)?;
+        if args.preserve_timestamps {
+            preserve_times(&original, &output_path)
+                .map_err(|e| CleanError::Write(output_path.clone(), e))?;
+        }
+    }
+    Ok(Some((report, Some(output_path), diff)))
+}
+
+// Cleans or scans a single file of a run, see process_file
+fn process(
+    input_path: &Path,
+    mode: Mode,
+    cleaner: &Cleaner,
+    confirm: &dyn Fn(&Path, &CleanReport, Option<&str>) -> bool,
+    decline: &dyn Fn(&Path, &CleanReport) -> Vec<char>,
+    choose_output: &dyn Fn(PathBuf) -> Result<PathBuf, CleanError>,
+) -> Result<Option<Processed>, CleanError> {
+    match mode {
+        Mode::Clean(args) => {
+            process_file(input_path, args, cleaner, confirm, decline, choose_output)
+        }
+        Mode::Scan => scan_file(input_path, cleaner).map(|report| Some((report, None, None))),
+    }
+}
+
+// What process_file returns for a document it did not leave alone: the report, where the cleaned
+// document went and the diff of --diff
+type Processed = (CleanReport, Option<PathBuf>, Option<String>);
+
+// What process_file writes out, once the changes are confirmed
+enum Packed<'a> {
+    Document(CleanedDocument<'a>),
+    Text(CleanedText),
+}
+
+impl Packed<'_> {
+    // A streamed document is only read again while it is written, so a failure to read it shows
+    // up here as well
+    fn write_to(self, file: &mut std::fs::File, path: &Path) -> Result<(), CleanError> {
+        let write_error = |e| CleanError::Write(path.to_path_buf(), e);
+        match self {
+            Packed::Document(cleaned) => {
+                let writer = cleaned
+                    .write_to(BufWriter::new(file))
+                    .map_err(|e| match e {
+                        docx_cleaner::Error::Pack(zip::result::ZipError::Io(e)) => write_error(e),
+                        e => CleanError::Clean(e),
+                    })?;
+                writer
+                    .into_inner()
+                    .map(drop)
+                    .map_err(|e| write_error(e.into_error()))
+            }
+            Packed::Text(cleaned) => file.write_all(&cleaned.into_bytes()).map_err(write_error),
+        }
+    }
+}
+
+// The colors Word offers for highlighting text
+const HIGHLIGHT_COLORS: [&str; 16] = [
+    "yellow",
+    "green",
+    "cyan",
+    "magenta",
+    "blue",
+    "red",
+    "darkBlue",
+    "darkCyan",
+    "darkGreen",
+    "darkMagenta",
+    "darkRed",
+    "darkYellow",
+    "darkGray",
+    "lightGray",
+    "black",
+    "white",
+];
+
+fn parse_highlight(color: &str) -> Result<String, String> {
+    HIGHLIGHT_COLORS
+        .iter()
+        .find(|name| name.eq_ignore_ascii_case(color))
+        .map(|name| name.to_string())
+        .ok_or_else(|| format!("the color must be one of {}", HIGHLIGHT_COLORS.join(", ")))
+}
+
+// The --report format is chosen by the extension
+fn parse_report_path(path: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(path);
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    if ["txt", "json", "html"]
+        .iter()
+        .any(|known| extension.eq_ignore_ascii_case(known))
+    {
+        Ok(path)
+    } else {
+        Err("the report must be a .txt, .json or .html file".to_string())
+    }
+}
+
+// Two inputs that would be written to the same file, e.g. a.docx from two folders cleaned into
+// one --output folder, or the same file given twice with --in-place. Windows and macOS tell
+// names apart without regard to case.
+fn output_collision(files: &[PathBuf], args: &CleanArgs) -> Option<CleanError> {
+    if args.check && !args.annotate {
+        return None;
+    }
+    let mut outputs: HashMap<PathBuf, &PathBuf> = HashMap::new();
+    for input_path in files {
+        let output_path = if args.in_place {
+            input_path.clone()
+        } else {
+            match resolve_output_path(input_path, args.output.as_deref()) {
+                Ok(output_path) => output_path,
+                // A copy that cannot be named fails on its own
+                Err(_) => continue,
+            }
+        };
+        let key = if cfg!(any(windows, target_os = "macos")) {
+            PathBuf::from(output_path.to_string_lossy().to_lowercase())
+        } else {
+            output_path.clone()
+        };
+        if let Some(first) = outputs.insert(key, input_path) {
+            return Some(CleanError::OutputCollision(
+                first.clone(),
+                input_path.clone(),
+                output_path,
+            ));
+        }
+    }
+    None
+}
+
+fn generate_output_path(input_path: &Path) -> Result<PathBuf, CleanError> {
+    cleaned_copy_path(input_path).ok_or_else(|| CleanError::OutputName(input_path.to_path_buf()))
+}
+
+fn backup_original(input_path: &Path, backup_dir: Option<&Path>) -> Result<PathBuf, CleanError> {
+    let mut file_name = input_path
+        .file_name()
+        .ok_or_else(|| CleanError::BackupName(input_path.to_path_buf()))?
+        .to_os_string();
+    file_name.push(".bak");
+    let backup_path = match backup_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir).map_err(CleanError::Backup)?;
+            dir.join(file_name)
+        }
+        None => input_path.with_file_name(file_name),
+    };
+    std::fs::copy(input_path, &backup_path).map_err(CleanError::Backup)?;
+    Ok(backup_path)
+}
+
+// The document is written to a temporary file next to the target and only renamed to it once
+// it is complete, so a full disk or an interrupted write never leaves a truncated document
+// behind. The temporary file is removed when it is dropped without being persisted.
+fn write_atomically(path: &Path, packed: Packed) -> Result<(), CleanError> {
+    let write_error = |e| CleanError::Write(path.to_path_buf(), e);
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut prefix = std::ffi::OsString::from(".");
+    prefix.push(path.file_name().unwrap_or_default());
+    let mut temp = tempfile::Builder::new()
+        .prefix(&prefix)
+        .suffix(".tmp")
+        .tempfile_in(dir)
+        .map_err(write_error)?;
+    packed.write_to(temp.as_file_mut(), path)?;
+    temp.as_file().sync_all().map_err(write_error)?;
+    temp.persist(path).map_err(|e| {
+        if is_locked(&e.error) {
+            CleanError::Locked(path.to_path_buf())
+        } else {
+            write_error(e.error)
+        }
+    })?;
+    Ok(())
+}
+
+// The cleaned document takes the place of the original with its permissions, so a read-only
+// original stays read-only. They are set again even when the write fails.
+fn replace_original(
+    path: &Path,
+    packed: Packed,
+    original: &std::fs::Metadata,
+    preserve_timestamps: bool,
+) -> Result<(), CleanError> {
+    let write_error = |e| CleanError::Write(path.to_path_buf(), e);
+    let permissions = original.permissions();
+    // Windows refuses to rename over a read-only file; elsewhere the folder decides
+    #[cfg(windows)]
+    if permissions.readonly() {
+        let mut writable = permissions.clone();
+        #[allow(clippy::permissions_set_readonly_false)]
+        writable.set_readonly(false);
+        std::fs::set_permissions(path, writable).map_err(write_error)?;
+    }
+    let written = write_atomically(path, packed).and_then(|()| {
+        if preserve_timestamps {
+            preserve_times(original, path).map_err(write_error)?;
+        }
+        Ok(())
+    });
+    std::fs::set_permissions(path, permissions).map_err(write_error)?;
+    written
+}
+
+// The modified and accessed times of the input put on the cleaned file, and the created time on
+// Windows, the only platform where it can be set
+fn preserve_times(input: &std::fs::Metadata, output: &Path) -> std::io::Result<()> {
+    let times = std::fs::FileTimes::new()
+        .set_modified(input.modified()?)
+        .set_accessed(input.accessed()?);
+    #[cfg(windows)]
+    let times = {
+        use std::os::windows::fs::FileTimesExt;
+        match input.created() {
+            Ok(created) => times.set_created(created),
+            Err(_) => times,
+        }
+    };
+    std::fs::File::options()
+        .write(true)
+        .open(output)?
+        .set_times(times)
+}
+
+// Word keeps the documents it has open locked, so the finished copy cannot be renamed over them
+#[cfg(windows)]
+fn is_locked(error: &std::io::Error) -> bool {
+    const ERROR_ACCESS_DENIED: i32 = 5;
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    const ERROR_LOCK_VIOLATION: i32 = 33;
+    matches!(
+        error.raw_os_error(),
+        Some(ERROR_ACCESS_DENIED | ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION)
+    )
+}
+
+#[cfg(not(windows))]
+fn is_locked(_error: &std::io::Error) -> bool {
+    false
+}
+
+// Relative --output paths are taken from the working directory, so the reported path is the one
+// actually written. A directory receives the default "<stem>_cleaned" name.
+fn resolve_output_path(input_path: &Path, output: Option<&Path>) -> Result<PathBuf, CleanError> {
+    let Some(output) = output else {
+        return generate_output_path(input_path);
+    };
+    let output = std::env::current_dir()
+        .map(|cwd| cwd.join(output))
+        .unwrap_or_else(|_| output.to_path_buf());
+    if output.is_dir() {
+        let file_name = generate_output_path(input_path)?;
+        Ok(output.join(file_name.file_name().unwrap()))
+    } else {
+        Ok(output)
+    }
+}
+
+// The characters of each part in the order of PARTS, with a subtotal per part, and their total.
+// Parts without any are left out; no-break spaces and typographic punctuation are listed apart.
+fn characters_by_part(config: &Config, report: &CleanReport) -> (String, usize) {
+    let mut characters = String::new();
+    let mut total = 0;
+    for part in PARTS {
+        let Some(counts) = report.part_chars.get(part) else {
+            continue;
+        };
+        let counts: Vec<(char, usize)> = sorted_counts(counts)
+            .into_iter()
+            .filter(|&(c, _)| c != NO_BREAK_SPACE && config.origin(c) != Origin::Typography)
+            .collect();
+        if counts.is_empty() {
+            continue;
+        }
+        characters.push_str(&format!("{}:\n", part));
+        for &(c, count) in &counts {
+            characters.push_str(&format!(
+                "  {} (U+{:04X}) - {}: {}\n",
+                config.name(c),
+                c as u32,
+                c,
+                count
+            ));
+        }
+        let subtotal: usize = counts.iter().map(|(_, count)| count).sum();
+        characters.push_str(&format!("  Subtotal: {}\n", subtotal));
+        total += subtotal;
+    }
+    (characters, total)
+}
+
+// e.g. "1: 2, 4: 1" for the slides with replacements, their notes included
+fn slide_breakdown(report: &CleanReport) -> String {
+    report
+        .slides
+        .iter()
+        .map(|(slide, count)| format!("{}: {}", slide, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// e.g. "Sheet1: 3, Prices: 1", in the order of the workbook
+fn sheet_breakdown(report: &CleanReport) -> String {
+    report
+        .sheets
+        .iter()
+        .map(|(sheet, count)| format!("{}: {}", sheet, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Diagnostics go through the log crate: errors and warnings, only errors with --quiet, and with
+// -v, -vv and -vvv what the library logs about the parts, runs and texts it cleans. The console
+// gets them on standard error, above the progress bar; the GUI appends them to the log file.
+struct Logger {
+    level: log::LevelFilter,
+    to_file: bool,
+    log_file: Option<PathBuf>,
+    progress: Mutex<Option<ProgressBar>>,
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+fn setup_logger(level: log::LevelFilter, to_file: bool, log_file: Option<PathBuf>) {
+    let logger = LOGGER.get_or_init(|| Logger {
+        level,
+        to_file,
+        log_file,
+        progress: Mutex::new(None),
+    });
+    if log::set_logger(logger).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+// Messages are printed above the progress bar from now on instead of through it
+fn log_around(progress: &ProgressBar) {
+    if let Some(logger) = LOGGER.get() {
+        *logger.progress.lock().unwrap() = Some(progress.clone());
+    }
+}
+
+impl log::Log for Logger {
+    // Only this crate logs, not the crates it uses
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.level && metadata.target().starts_with("docx_cleaner")
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let level = match record.level() {
+            log::Level::Error => "Error",
+            log::Level::Warn => "Warning",
+            log::Level::Info => "Info",
+            log::Level::Debug => "Debug",
+            log::Level::Trace => "Trace",
+        };
+        let message = format!("{}: {}", level, record.args());
+        if self.to_file {
+            append_to_log(
+                self.log_file.as_deref(),
+                &format!("{} {}", timestamp(), message),
+            );
+        } else if let Some(progress) = &*self.progress.lock().unwrap() {
+            progress.suspend(|| eprintln!("{}", message));
+        } else {
+            eprintln!("{}", message);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+// e.g. "2024-05-01T09:30:00Z  thesis.docx  12 characters removed  thesis_cleaned.docx"
+fn log_entry(input_path: &Path, report: &CleanReport, output_path: Option<&Path>) -> String {
+    format!(
+        "{}  {}  {} characters {}  {}",
+        timestamp(),
+        input_path.display(),
+        report.total_removed(),
+        outcome(output_path),
+        output_path.map_or("no copy written".to_string(), |path| path
+            .display()
+            .to_string())
+    )
+}
+
+// The line --files-from prints for a file with something to clean, e.g.
+// "thesis.docx: 3 characters (U+200B ×2, U+00A0 ×1), 2 spaces found"; None for a clean one
+fn brief_statistics(input_path: &Path, report: &CleanReport, check: bool) -> Option<String> {
+    if !report.is_modified() {
+        return None;
+    }
+    let mut findings = Vec::new();
+    if report.total_removed() > 0 {
+        let mut chars: Vec<_> = report.chars.iter().collect();
+        chars.sort();
+        let chars: Vec<String> = chars
+            .into_iter()
+            .map(|(&c, count)| format!("U+{:04X} ×{}", c as u32, count))
+            .collect();
+        findings.push(format!(
+            "{} characters ({})",
+            report.total_removed(),
+            chars.join(", ")
+        ));
+    }
+    let spaces = report.collapsed_spaces + report.break_spaces;
+    if spaces > 0 {
+        findings.push(format!("{} spaces", spaces));
+    }
+    if findings.is_empty() {
+        findings.push("changes".to_string());
+    }
+    let outcome = if check { "found" } else { "removed" };
+    Some(format!(
+        "{}: {} {}\n",
+        input_path.display(),
+        findings.join(", "),
+        outcome
+    ))
+}
+
+// Without an output path the document was only checked, not cleaned
+fn outcome(output_path: Option<&Path>) -> &'static str {
+    if output_path.is_some() {
+        "removed"
+    } else {
+        "found"
+    }
+}
+
+// e.g. "Multiple spaces collapsed: 2 (3 spaces removed)"; empty when there were none
+fn space_statistics(report: &CleanReport) -> String {
+    let mut statistics = String::new();
+    if report.collapsed_stretches > 0 {
+        statistics.push_str(&format!(
+            "Multiple spaces collapsed: {} ({} spaces removed)\n",
+            report.collapsed_stretches, report.collapsed_spaces
+        ));
+    }
+    if report.break_spaces > 0 {
+        statistics.push_str(&format!(
+            "Spaces trimmed around line breaks: {}\n",
+            report.break_spaces
+        ));
+    }
+    statistics
+}
+
+fn normalization_statistics(report: &CleanReport) -> String {
+    if report.normalized_runs == 0 {
+        return String::new();
+    }
+    format!(
+        "Runs changed by normalization: {}\n",
+        report.normalized_runs
+    )
+}
+
+// e.g. "No-break spaces replaced: 4 (2 kept by a pattern)"; empty when there were none
+fn nbsp_statistics(report: &CleanReport, check: bool) -> String {
+    let replaced = report.chars.get(&NO_BREAK_SPACE).copied().unwrap_or(0);
+    if replaced == 0 && report.nbsp_kept == 0 {
+        return String::new();
+    }
+    let outcome = if check { "found" } else { "replaced" };
+    let mut statistics = format!("No-break spaces {}: {}", outcome, replaced);
+    if report.nbsp_kept > 0 {
+        statistics.push_str(&format!(" ({} kept by a pattern)", report.nbsp_kept));
+    }
+    statistics.push('\n');
+    statistics
+}
+
+// The hidden runs of --remove-hidden, listed apart from the characters since whole runs go; empty
+// without it
+fn hidden_statistics(config: &Config, report: &CleanReport, check: bool) -> String {
+    let Some(mode) = config.hidden_text else {
+        return String::new();
+    };
+    let mut statistics = String::from("\nHidden text:\n");
+    statistics.push_str(&format!(
+        "Hidden runs found: {} ({} characters)\n",
+        report.hidden_runs, report.hidden_chars
+    ));
+    if mode == HiddenText::Remove {
+        let outcome = if check { "to remove" } else { "removed" };
+        statistics.push_str(&format!(
+            "Hidden runs {}: {}\n",
+            outcome, report.hidden_removed
+        ));
+    }
+    if report.hidden_in_fields > 0 {
+        statistics.push_str(&format!(
+            "Hidden runs kept in fields: {}\n",
+            report.hidden_in_fields
+        ));
+    }
+    statistics
+}
+
+// e.g. "Empty runs removed: 3, empty paragraphs removed: 1"; empty without --prune-empty
+fn prune_statistics(config: &Config, report: &CleanReport, check: bool) -> String {
+    if !config.prune_empty || report.pruned_runs + report.pruned_paragraphs == 0 {
+        return String::new();
+    }
+    let outcome = if check { "to remove" } else { "removed" };
+    format!(
+        "Empty runs {}: {}, empty paragraphs {}: {}\n",
+        outcome, report.pruned_runs, outcome, report.pruned_paragraphs
+    )
+}
+
+// e.g. "Runs merged: 14"; empty without --merge-runs
+fn merge_statistics(report: &CleanReport, check: bool) -> String {
+    if report.merged_runs == 0 {
+        return String::new();
+    }
+    let outcome = if check { "to merge" } else { "merged" };
+    format!("Runs {}: {}\n", outcome, report.merged_runs)
+}
+
+// What --scrub-metadata cleared, each property with the value it had; empty without it
+fn metadata_statistics(config: &Config, report: &CleanReport, check: bool) -> String {
+    if config.scrub_metadata.is_none() {
+        return String::new();
+    }
+    let outcome = if check { "to scrub" } else { "scrubbed" };
+    let mut statistics = format!("\nMetadata {}:\n", outcome);
+    if report.scrubbed.is_empty() && report.rsids_removed == 0 {
+        statistics.push_str("None\n");
+        return statistics;
+    }
+    for (property, value) in &report.scrubbed {
+        statistics.push_str(&format!("{}: \"{}\"\n", property, value));
+    }
+    if report.rsids_removed > 0 {
+        statistics.push_str(&format!(
+            "Editing session ids (rsid): {}\n",
+            report.rsids_removed
+        ));
+    }
+    statistics
+}
+
+// The runs whose formatting hides their text, each with its text to find it by; empty when there
+// were none
+fn invisible_statistics(report: &CleanReport, check: bool) -> String {
+    if report.invisible_runs.is_empty() && report.theme_colored_runs == 0 {
+        return String::new();
+    }
+    let mut statistics = String::from("\nInvisible formatting:\n");
+    let outcome = if check { "to remove" } else { "removed" };
+    for run in &report.invisible_runs {
+        statistics.push_str(&format!(
+            "{}, paragraph {}, run {} ({}): {}{}\n    in \"{}\"\n",
+            run.part,
+            run.paragraph,
+            run.run,
+            run.path,
+            invisible_reason(&run.formatting),
+            if run.removed {
+                format!(", {}", outcome)
+            } else {
+                String::new()
+            },
+            run.snippet
+        ));
+    }
+    let removed = report
+        .invisible_runs
+        .iter()
+        .filter(|run| run.removed)
+        .count();
+    statistics.push_str(&format!(
+        "Runs with invisible formatting: {}",
+        report.invisible_runs.len()
+    ));
+    if removed > 0 {
+        statistics.push_str(&format!(" ({} {})", removed, outcome));
+    }
+    statistics.push('\n');
+    if report.theme_colored_runs > 0 {
+        statistics.push_str(&format!(
+            "Runs colored by the theme, check these by hand: {}\n",
+            report.theme_colored_runs
+        ));
+    }
+    statistics
+}
+
+// e.g. "text colored FFFFFF like the background" or "font size 0.5pt"
+fn invisible_reason(formatting: &InvisibleFormatting) -> String {
+    match formatting {
+        InvisibleFormatting::SameColor(color) => {
+            format!("text colored {} like the background", color)
+        }
+        InvisibleFormatting::Tiny(size) => format!("font size {}pt", *size as f64 / 2.0),
+    }
+}
+
+// The punctuation of --ascii-punctuation, listed apart from the invisible characters since it is
+// only a cosmetic change; empty when there was none
+fn typography_statistics(config: &Config, report: &CleanReport, check: bool) -> String {
+    let results: Vec<(char, usize)> = sorted_counts(&report.chars)
+        .into_iter()
+        .filter(|&(c, _)| config.origin(c) == Origin::Typography)
+        .collect();
+    if results.is_empty() {
+        return String::new();
+    }
+    let mut statistics = String::from("\nTypographic punctuation:\n");
+    for (char, count) in &results {
+        statistics.push_str(&format!(
+            "{} (U+{:04X}) - {}: {}\n",
+            config.name(*char),
+            *char as u32,
+            char,
+            count
+        ));
+    }
+    let total: usize = results.iter().map(|(_, count)| count).sum();
+    let outcome = if check { "found" } else { "replaced" };
+    statistics.push_str(&format!("Total punctuation {}: {}\n", outcome, total));
+    statistics
+}
+
+// The lookalike letters replaced by --homoglyphs with the Latin letters they became, e.g.
+// "CYRILLIC SMALL LETTER O (U+043E) -> o (U+006F): 2"; empty when there were none
+fn homoglyph_statistics(config: &Config, report: &CleanReport) -> String {
+    let Some(homoglyphs) = config
+        .homoglyphs
+        .as_ref()
+        .filter(|_| !report.homoglyphs.is_empty())
+    else {
+        return String::new();
+    };
+    let mut statistics = String::from("\nLookalike letters:\n");
+    let mut total = 0;
+    for (c, count) in sorted_counts(&report.homoglyphs) {
+        let latin = homoglyphs.latin(c).unwrap_or(c);
+        statistics.push_str(&format!(
+            "{} (U+{:04X}) -> {} (U+{:04X}): {}\n",
+            homoglyphs.name(c),
+            c as u32,
+            latin,
+            latin as u32,
+            count
+        ));
+        total += count;
+    }
+    statistics.push_str(&format!("Total lookalike letters: {}\n", total));
+    statistics
+}
+
+// e.g. "Deleted: 3, replaced: 1" under the total of the characters listed by part, when some of
+// them were replaced rather than deleted
+fn action_statistics(config: &Config, report: &CleanReport) -> String {
+    let (mut deleted, mut replaced) = (0, 0);
+    for (&c, &count) in &report.chars {
+        match config.action(c) {
+            _ if c == NO_BREAK_SPACE || config.origin(c) == Origin::Typography => {}
+            Some(RuleAction::Replace) => replaced += count,
+            _ => deleted += count,
+        }
+    }
+    if replaced == 0 {
+        return String::new();
+    }
+    format!("Deleted: {}, replaced: {}\n", deleted, replaced)
+}
+
+// The characters the config flags, which are counted but left in the text
+fn flagged_statistics(config: &Config, report: &CleanReport) -> String {
+    let flagged = sorted_counts(&report.kept);
+    if flagged.is_empty() {
+        return String::new();
+    }
+    let mut statistics = String::from("\nFlagged (left in the text):\n");
+    for (c, count) in flagged {
+        statistics.push_str(&format!(
+            "{} (U+{:04X}) - {}: {}\n",
+            config.name(c),
+            c as u32,
+            c,
+            count
+        ));
+    }
+    statistics.push_str(&format!(
+        "Total characters flagged: {}\n",
+        report.total_kept()
+    ));
+    statistics
+}
+
+// The characters the user chose to keep, listed apart from the removed ones; empty when the user
+// kept none
+fn kept_statistics(config: &Config, report: &CleanReport) -> String {
+    let mut results: Vec<(char, usize)> = report
+        .declined
+        .iter()
+        .map(|(&c, &count)| (c, count))
+        .filter(|&(_, count)| count > 0)
+        .collect();
+    if results.is_empty() {
+        return String::new();
+    }
+    results.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+
+    let mut statistics = String::from("\nFound but kept:\n");
+    for (char, count) in &results {
+        let name = config.name(*char);
+        statistics.push_str(&format!(
+            "{} (U+{:04X}) - {}: {} (kept by user)\n",
+            name, *char as u32, char, count
+        ));
+    }
+    let total: usize = results.iter().map(|(_, count)| count).sum();
+    statistics.push_str(&format!("Total characters kept: {}\n", total));
+    statistics
+}
+
+// The characters that are not in the config but look like they should be, each with the context
+// of its first occurrence, e.g. for finding new ways of watermarking text
+fn suspicious_statistics(report: &CleanReport) -> String {
+    if report.suspicious.is_empty() {
+        return String::new();
+    }
+    let mut statistics = String::from("\nFound but not configured (left in the text):\n");
+    for (c, count) in sorted_counts(&report.suspicious) {
+        statistics.push_str(&format!(
+            "U+{:04X} {}: {}, e.g. \"{}\"\n",
+            c as u32,
+            unlisted_name(c),
+            count,
+            report.suspicious_contexts[&c]
+        ));
+    }
+    let total: usize = report.suspicious.values().sum();
+    statistics.push_str(&format!("Total suspicious characters: {}\n", total));
+    statistics
+}
+
+// The readable statistics go to standard error with --json, so they never mix with the JSON
+fn print_human(text: &str, to_stderr: bool) {
+    if to_stderr {
+        eprint!("{}", text);
+    } else {
+        print!("{}", text);
+    }
+}
+
+// With --check the characters were only found, even when --annotate wrote a copy
+fn console_statistics(
+    config: &Config,
+    report: &CleanReport,
+    output_path: Option<&Path>,
+    check: bool,
+) -> String {
+    let mut statistics = String::from("\nCharacter Removal Statistics:\n");
+    statistics.push_str("============================\n");
+    let (characters, total) = characters_by_part(config, report);
+    statistics.push_str(&characters);
+
+    let outcome = if check { "found" } else { "removed" };
+    statistics.push_str(&format!("\nTotal characters {}: {}\n", outcome, total));
+    statistics.push_str(&action_statistics(config, report));
+    if !report.slides.is_empty() {
+        statistics.push_str(&format!("By slide: {}\n", slide_breakdown(report)));
+    }
+    if !report.sheets.is_empty() {
+        statistics.push_str(&format!("By sheet: {}\n", sheet_breakdown(report)));
+    }
+    statistics.push_str(&nbsp_statistics(report, check));
+    statistics.push_str(&typography_statistics(config, report, check));
+    statistics.push_str(&space_statistics(report));
+    statistics.push_str(&prune_statistics(config, report, check));
+    statistics.push_str(&merge_statistics(report, check));
+    statistics.push_str(&normalization_statistics(report));
+    statistics.push_str(&homoglyph_statistics(config, report));
+    statistics.push_str(&hidden_statistics(config, report, check));
+    statistics.push_str(&invisible_statistics(report, check));
+    statistics.push_str(&metadata_statistics(config, report, check));
+    statistics.push_str(&flagged_statistics(config, report));
+    statistics.push_str(&kept_statistics(config, report));
+    statistics.push_str(&suspicious_statistics(report));
+    if let Some(output_path) = output_path {
+        statistics.push_str(&format!("Saved as: {}\n", output_path.display()));
+    }
+    statistics
+}
+
+fn batch_summary(config: &Config, batch: &BatchReport, check: bool) -> String {
+    let outcome = if check { "found" } else { "removed" };
+    let mut summary = String::from("\nBatch Summary:\n");
+    summary.push_str("==============\n");
+    let ranked = batch.ranked();
+    if !ranked.is_empty() {
+        summary.push_str(&format!("{:>10}  {:>6}  FILE\n", "CHARACTERS", "SPACES"));
+    }
+    for (input_path, file) in ranked {
+        summary.push_str(&format!(
+            "{:>10}  {:>6}  {}\n",
+            file.removed,
+            file.spaces_removed,
+            input_path.display()
+        ));
+    }
+    match batch.clean_files() {
+        0 => {}
+        1 => summary.push_str("1 file was already clean\n"),
+        clean => summary.push_str(&format!("{} files were already clean\n", clean)),
+    }
+    let mut failed = batch.failed().peekable();
+    if failed.peek().is_some() {
+        summary.push_str("\nFailed:\n");
+    }
+    for (input_path, error) in failed {
+        summary.push_str(&format!("{}: {}\n", input_path.display(), error));
+    }
+    match batch.stopped {
+        0 => {}
+        1 => summary.push_str("Stopped at the first failure; 1 more file was not processed\n"),
+        stopped => summary.push_str(&format!(
+            "Stopped at the first failure; {} more files were not processed\n",
+            stopped
+        )),
+    }
+
+    let counts = &batch.totals.chars;
+    let mut counts: Vec<(char, usize)> = counts
+        .iter()
+        .filter(|(_, &count)| count > 0)
+        .map(|(&c, &count)| (c, count))
+        .collect();
+    counts.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+
+    summary.push('\n');
+    for (char, count) in &counts {
+        let name = config.name(*char);
+        summary.push_str(&format!(
+            "{} (U+{:04X}) - {}: {}\n",
+            name, *char as u32, char, count
+        ));
+    }
+
+    let processed = batch.processed().count();
+    let modified = batch
+        .processed()
+        .filter(|(_, summary)| summary.modified)
+        .count();
+    let total: usize = counts.iter().map(|(_, count)| count).sum();
+    let modified_label = if check {
+        "Files to clean:"
+    } else {
+        "Files modified:"
+    };
+    let rows = [
+        ("Files processed:", processed),
+        (modified_label, modified),
+        ("Files skipped:", batch.skipped),
+        ("Files failed:", batch.files.len() - processed),
+    ];
+    summary.push('\n');
+    for (label, count) in rows {
+        summary.push_str(&format!("{:<26}{}\n", label, count));
+    }
+    summary.push_str(&format!(
+        "{:<26}{}\n",
+        format!("Total characters {}:", outcome),
+        total
+    ));
+    summary
+}
+
+// Collects the --json objects: printed one per line as each file is done, or as a single array
+// once the batch is finished
+struct JsonOutput {
+    format: JsonFormat,
+    collected: Vec<Value>,
+}
+
+impl JsonOutput {
+    fn new(format: JsonFormat) -> Self {
+        JsonOutput {
+            format,
+            collected: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, value: Value) {
+        match self.format {
+            JsonFormat::Ndjson => println!("{}", value),
+            JsonFormat::Array => self.collected.push(value),
+        }
+    }
+
+    fn finish(self) {
+        if self.format == JsonFormat::Array {
+            println!("{}", Value::Array(self.collected));
+        }
+    }
+}
+
+// "builtin", or the path of the user config merged on top of it
+fn config_source(config: &Config) -> String {
+    config
+        .source()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|| "builtin".to_string())
+}
+
+// Most frequent first, ties by code point so the output is stable
+fn sorted_counts(counts: &HashMap<char, usize>) -> Vec<(char, usize)> {
+    let mut counts: Vec<(char, usize)> = counts
+        .iter()
+        .filter(|(_, &count)| count > 0)
+        .map(|(&c, &count)| (c, count))
+        .collect();
+    counts.sort_by_key(|&(c, count)| (std::cmp::Reverse(count), c));
+    counts
+}
+
+// The --json object of a processed file; "modified" is whether the text changed, or would have
+// with --check
+fn json_statistics(
+    config: &Config,
+    input_path: &Path,
+    output_path: Option<&Path>,
+    report: &CleanReport,
+    replacements: &[Replacement],
+) -> Value {
+    let (typography, characters): (Vec<_>, Vec<_>) = sorted_counts(&report.chars)
+        .into_iter()
+        .filter(|&(c, _)| c != NO_BREAK_SPACE)
+        .partition(|&(c, _)| config.origin(c) == Origin::Typography);
+    let characters: Vec<Value> = characters
+        .into_iter()
+        .map(|(c, count)| {
+            json!({
+                "codepoint": format!("U+{:04X}", c as u32),
+                "name": config.name(c),
+                "count": count,
+                "replacement": config.replacement(c),
+            })
+        })
+        .collect();
+    // Split up like the total, every character counted
+    let deleted: usize = report
+        .chars
+        .iter()
+        .filter(|(&c, _)| config.replacement(c).is_empty())
+        .map(|(_, count)| count)
+        .sum();
+    let replaced = report.total_removed() - deleted;
+    let kept: Vec<Value> = sorted_counts(&report.kept)
+        .into_iter()
+        .map(|(c, count)| {
+            json!({
+                "codepoint": format!("U+{:04X}", c as u32),
+                "name": config.name(c),
+                "count": count,
+            })
+        })
+        .collect();
+    let kept_by_user: Vec<Value> = sorted_counts(&report.declined)
+        .into_iter()
+        .map(|(c, count)| {
+            json!({
+                "codepoint": format!("U+{:04X}", c as u32),
+                "name": config.name(c),
+                "count": count,
+            })
+        })
+        .collect();
+    let suspicious: Vec<Value> = sorted_counts(&report.suspicious)
+        .into_iter()
+        .map(|(c, count)| {
+            json!({
+                "codepoint": format!("U+{:04X}", c as u32),
+                "name": unlisted_name(c),
+                "count": count,
+                "context": report.suspicious_contexts[&c],
+            })
+        })
+        .collect();
+    // Every character replaced in a part, the no-break spaces and punctuation included
+    let parts: serde_json::Map<String, Value> = PARTS
+        .iter()
+        .filter_map(|part| {
+            let count = report.parts.get(part).copied().unwrap_or(0);
+            let characters: Vec<Value> = sorted_counts(report.part_chars.get(part)?)
+                .into_iter()
+                .map(|(c, count)| {
+                    json!({
+                        "codepoint": format!("U+{:04X}", c as u32),
+                        "name": config.name(c),
+                        "count": count,
+                    })
+                })
+                .collect();
+            (count > 0).then(|| {
+                let breakdown = json!({ "total": count, "characters": characters });
+                (part.to_string(), breakdown)
+            })
+        })
+        .collect();
+    let mut statistics = json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "config": config_source(config),
+        "profile": config.profile(),
+        "input": input_path.display().to_string(),
+        "output": output_path.map(|path| path.display().to_string()),
+        "characters": characters,
+        "kept": kept,
+        "kept_by_user": kept_by_user,
+        "suspicious": suspicious,
+        "parts": parts,
+        "spaces_collapsed": report.collapsed_stretches,
+        "spaces_removed": report.collapsed_spaces,
+        "break_spaces_removed": report.break_spaces,
+        "normalized_runs": report.normalized_runs,
+        "nbsp_replaced": report.chars.get(&NO_BREAK_SPACE).copied().unwrap_or(0),
+        "nbsp_kept": report.nbsp_kept,
+        "total": report.total_removed(),
+        "deleted": deleted,
+        "replaced": replaced,
+        "flagged": report.total_kept(),
+        "modified": report.is_modified(),
+    });
+    if !replacements.is_empty() {
+        let locations: Vec<Value> = replacements
+            .iter()
+            .map(|replacement| replacement_json(config, replacement))
+            .collect();
+        statistics["locations"] = Value::Array(locations);
+    }
+    // Only with --ascii-punctuation; "total" counts this punctuation as well
+    if !typography.is_empty() {
+        let typography: Vec<Value> = typography
+            .into_iter()
+            .map(|(c, count)| {
+                json!({
+                    "codepoint": format!("U+{:04X}", c as u32),
+                    "name": config.name(c),
+                    "count": count,
+                    "replacement": config.replacement(c),
+                })
+            })
+            .collect();
+        statistics["typography"] = Value::Array(typography);
+    }
+    // Only with --homoglyphs
+    if let Some(homoglyphs) = config.homoglyphs.as_ref() {
+        let replaced: Vec<Value> = sorted_counts(&report.homoglyphs)
+            .into_iter()
+            .map(|(c, count)| {
+                let latin = homoglyphs.latin(c).unwrap_or(c);
+                json!({
+                    "codepoint": format!("U+{:04X}", c as u32),
+                    "name": homoglyphs.name(c),
+                    "latin": format!("U+{:04X}", latin as u32),
+                    "count": count,
+                })
+            })
+            .collect();
+        statistics["homoglyphs"] = Value::Array(replaced);
+    }
+    // Only Word documents have formatting that hides text
+    if !report.invisible_runs.is_empty() || report.theme_colored_runs > 0 {
+        let runs: Vec<Value> = report
+            .invisible_runs
+            .iter()
+            .map(|run| {
+                let (reason, value) = match &run.formatting {
+                    InvisibleFormatting::SameColor(color) => ("color", json!(color)),
+                    InvisibleFormatting::Tiny(size) => ("size", json!(size)),
+                };
+                json!({
+                    "part": run.part,
+                    "path": run.path,
+                    "paragraph": run.paragraph,
+                    "run": run.run,
+                    "reason": reason,
+                    reason: value,
+                    "snippet": run.snippet,
+                    "removed": run.removed,
+                })
+            })
+            .collect();
+        statistics["invisible_formatting"] = json!({
+            "runs": runs,
+            "theme_colored_runs": report.theme_colored_runs,
+        });
+    }
+    // Only with --scrub-metadata
+    if config.scrub_metadata.is_some() {
+        let scrubbed: Vec<Value> = report
+            .scrubbed
+            .iter()
+            .map(|(property, value)| json!({ "property": property, "value": value }))
+            .collect();
+        statistics["metadata"] = json!({
+            "scrubbed": scrubbed,
+            "rsids_removed": report.rsids_removed,
+        });
+    }
+    // Only with --prune-empty
+    if config.prune_empty {
+        statistics["pruned"] = json!({
+            "runs": report.pruned_runs,
+            "paragraphs": report.pruned_paragraphs,
+        });
+    }
+    // Only with --merge-runs
+    if config.merge_runs {
+        statistics["merged_runs"] = json!(report.merged_runs);
+    }
+    // Only with --remove-hidden
+    if config.hidden_text.is_some() {
+        statistics["hidden"] = json!({
+            "runs": report.hidden_runs,
+            "characters": report.hidden_chars,
+            "removed": report.hidden_removed,
+            "in_fields": report.hidden_in_fields,
+        });
+    }
+    // Only presentations have slides
+    if !report.slides.is_empty() {
+        let slides: serde_json::Map<String, Value> = report
+            .slides
+            .iter()
+            .map(|(slide, count)| (slide.to_string(), json!(count)))
+            .collect();
+        statistics["slides"] = Value::Object(slides);
+    }
+    // Only workbooks have sheets, listed in the order of the workbook
+    if !report.sheets.is_empty() {
+        let sheets: Vec<Value> = report
+            .sheets
+            .iter()
+            .map(|(sheet, count)| json!({ "name": sheet, "count": count }))
+            .collect();
+        statistics["sheets"] = Value::Array(sheets);
+    }
+    statistics
+}
+
+// The --json object of a file that could not be processed
+fn json_failure(config: &Config, input_path: &Path, error: &CleanError) -> Value {
+    json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "config": config_source(config),
+        "profile": config.profile(),
+        "input": input_path.display().to_string(),
+        "error": error.to_string(),
+        "exit_code": error.exit_code(),
+    })
+}
+
+// What the GUI shows at the end of a run
+enum Outcome<'a, 'b> {
+    // The statistics of a single file and where its copy went
+    File(&'a CleanReport, Option<&'a Path>),
+    // Why a single file failed
+    Failed(&'a BatchReport<'b>),
+    // A summary of several files
+    Batch(&'a BatchReport<'b>),
+}
+
+// The GUI shows how the run went once every file is done, so that no dialog holds up the files
+// after it: the statistics of a single file or why it failed, or a summary of several files
+// with their failures listed by name
+fn show_outcome(
+    config: &Config,
+    outcome: Outcome,
+    mode: Mode,
+    check: bool,
+    located: &[(&PathBuf, Vec<Replacement>)],
+) {
+    let batch = match outcome {
+        Outcome::File(report, output_path) => {
+            show_gui_statistics(config, report, output_path, located);
+            return;
+        }
+        // Or nothing at all when the single file was declined
+        Outcome::Failed(batch) => {
+            for (input_path, error) in batch.failed() {
+                gui::show_error(
+                    error.title(),
+                    &format!("{}:\n\n{}", input_path.display(), error),
+                );
+            }
+            return;
+        }
+        Outcome::Batch(batch) => batch,
+    };
+    let mut summary = batch_summary(config, batch, check);
+    let mut folder = None;
+    if let Mode::Clean(args) = mode {
+        match &args.output {
+            Some(output) => summary.push_str(&format!("Saved to: {}", output.display())),
+            None => summary.push_str("Saved next to each original"),
+        }
+        folder = args.output.clone().filter(|_| !args.check);
+    }
+    show_results(
+        "Processing Complete",
+        summary.trim_start(),
+        folder.as_deref(),
+        config,
+        located,
+    );
+}
+
+fn show_gui_statistics(
+    config: &Config,
+    report: &CleanReport,
+    output_path: Option<&Path>,
+    files: &[(&PathBuf, Vec<Replacement>)],
+) {
+    let mut message = String::from("Character Removal Statistics:\n");
+    message.push_str("============================\n\n");
+    let (characters, total) = characters_by_part(config, report);
+    message.push_str(&characters);
+
+    message.push_str(&format!(
+        "\nTotal characters {}: {}\n",
+        outcome(output_path),
+        total
+    ));
+    message.push_str(&action_statistics(config, report));
+    if !report.slides.is_empty() {
+        message.push_str(&format!("By slide: {}\n", slide_breakdown(report)));
+    }
+    if !report.sheets.is_empty() {
+        message.push_str(&format!("By sheet: {}\n", sheet_breakdown(report)));
+    }
+    message.push_str(&nbsp_statistics(report, output_path.is_none()));
+    message.push_str(&typography_statistics(
+        config,
+        report,
+        output_path.is_none(),
+    ));
+    message.push_str(&space_statistics(report));
+    message.push_str(&prune_statistics(config, report, output_path.is_none()));
+    message.push_str(&merge_statistics(report, output_path.is_none()));
+    message.push_str(&normalization_statistics(report));
+    message.push_str(&homoglyph_statistics(config, report));
+    message.push_str(&hidden_statistics(config, report, output_path.is_none()));
+    message.push_str(&invisible_statistics(report, output_path.is_none()));
+    message.push_str(&metadata_statistics(config, report, output_path.is_none()));
+    message.push_str(&flagged_statistics(config, report));
+    message.push_str(&kept_statistics(config, report));
+    message.push_str(&suspicious_statistics(report));
+    if let Some(output_path) = output_path {
+        message.push_str(&format!("\nSaved as: {}", output_path.display()));
+    }
+
+    let folder = output_path.and_then(Path::parent);
+    show_results("Processing Complete", &message, folder, config, files);
+}
+
+// The most lines of statistics a message dialog shows; a heavily affected document lists many
+// more, which only fit in the results window or the saved report
+const DIALOG_LINES: usize = 30;
+
+// The statistics cut down to DIALOG_LINES lines, saying how many more there are
+fn dialog_text(statistics: &str) -> String {
+    let lines: Vec<&str> = statistics.trim_end().lines().collect();
+    if lines.len() <= DIALOG_LINES {
+        return format!("{}\n", lines.join("\n"));
+    }
+    format!(
+        "{}\n\u{2026} and {} more lines; save the report to see every change\n",
+        lines[..DIALOG_LINES].join("\n"),
+        lines.len() - DIALOG_LINES
+    )
+}
+
+// The results dialog, until it is closed. "Save report…" writes the replacements of the files
+// like --report, in the format of the extension picked, and can be used more than once.
+fn show_results(
+    title: &str,
+    message: &str,
+    folder: Option<&Path>,
+    config: &Config,
+    files: &[(&PathBuf, Vec<Replacement>)],
+) {
+    const FILTERS: [(&str, &[&str]); 3] = [
+        ("Text report", &["txt"]),
+        ("HTML report", &["html"]),
+        ("JSON report", &["json"]),
+    ];
+    loop {
+        match gui::show_results(title, message, folder.is_some()) {
+            gui::ResultsChoice::SaveReport => {
+                let Some(path) = gui::pick_save_path("Save report", "report.txt", folder, &FILTERS)
+                else {
+                    continue;
+                };
+                if let Err(e) = write_report(config, &path, files, &HashMap::new()) {
+                    let message = format!("{}:\n\n{}", path.display(), e);
+                    gui::show_error("Could not save the report", &message);
+                }
+            }
+            gui::ResultsChoice::OpenFolder => {
+                if let Some(Err(e)) = folder.map(gui::open_folder) {
+                    gui::show_error("Could not open the output folder", &e.to_string());
+                }
+                return;
+            }
+            gui::ResultsChoice::Close => return,
+        }
+    }
+}
+
+// The --report file: the replacements of each processed file, in document order, each with its
+// --diff when there is one
+fn write_report(
+    config: &Config,
+    path: &Path,
+    files: &[(&PathBuf, Vec<Replacement>)],
+    diffs: &HashMap<PathBuf, String>,
+) -> std::io::Result<()> {
+    let extension = path.extension().unwrap_or_default();
+    let contents = if extension.eq_ignore_ascii_case("json") {
+        json_report(config, files, diffs)
+    } else if extension.eq_ignore_ascii_case("html") {
+        html_report(config, files, diffs)
+    } else {
+        text_report(config, files, diffs)
+    };
+    std::fs::write(path, contents)
+}
+
+// e.g. "header, paragraph 3, run 2 (word/header2.xml)", or "line 12, ≈ 40% through the file"
+// in a text file
+fn location(replacement: &Replacement) -> String {
+    if replacement.part == "text" {
+        return format!(
+            "line {}, {}",
+            replacement.paragraph.unwrap_or(1),
+            position(replacement)
+        );
+    }
+    // The paragraphs of a spreadsheet are its strings
+    if matches!(replacement.part, "strings" | "sheet") {
+        let mut location = match replacement.part {
+            "strings" => "shared string".to_string(),
+            _ => "sheet, inline string".to_string(),
+        };
+        location.push_str(&format!(" {}", replacement.paragraph.unwrap_or(1)));
+        if let Some(run) = replacement.run {
+            location.push_str(&format!(", run {}", run));
+        }
+        location.push_str(&format!(" ({})", replacement.path));
+        return location;
+    }
+    let mut location = replacement.part.to_string();
+    if let Some(paragraph) = replacement.paragraph {
+        location.push_str(&format!(", paragraph {}", paragraph));
+    }
+    if let Some(run) = replacement.run {
+        location.push_str(&format!(", run {}", run));
+    }
+    location.push_str(&format!(" ({})", replacement.path));
+    // Only the body runs through the pages; the other parts are short or repeated on them
+    if replacement.part == "body" {
+        location.push_str(&format!(", {}", position(replacement)));
+    }
+    location
+}
+
+// How far through its part a replacement is, e.g. "≈ 37% through the body", which stands in
+// for the page number
+fn position(replacement: &Replacement) -> String {
+    let part = match replacement.part {
+        "text" => "file",
+        part => part,
+    };
+    format!(
+        "\u{2248} {:.0}% through the {}",
+        replacement.position * 100.0,
+        part
+    )
+}
+
+// e.g. "body, paragraph 3, run 1 (word/document.xml), ≈ 37% through the body: ZERO WIDTH SPACE
+// (U+200B): "fo⟨U+200B⟩x"", followed by the start of the paragraph to find it by
+fn replacement_line(config: &Config, replacement: &Replacement) -> String {
+    let c = replacement.character;
+    format!(
+        "{}: {} (U+{:04X}): \"{}\"\n    in \"{}\"\n",
+        location(replacement),
+        config.name(c),
+        c as u32,
+        replacement.context(),
+        replacement.snippet
+    )
+}
+
+// The locations listed by scan; empty when nothing was found
+fn location_details(config: &Config, replacements: &[Replacement]) -> String {
+    if replacements.is_empty() {
+        return String::new();
+    }
+    let mut details = String::from("\nLocations:\n");
+    for replacement in replacements {
+        details.push_str(&replacement_line(config, replacement));
+    }
+    details
+}
+
+fn text_report(
+    config: &Config,
+    files: &[(&PathBuf, Vec<Replacement>)],
+    diffs: &HashMap<PathBuf, String>,
+) -> String {
+    let mut report = format!(
+        "docx-cleaner {} replacement report\nConfig: {}\n",
+        env!("CARGO_PKG_VERSION"),
+        config_source(config)
+    );
+    for (input_path, replacements) in files {
+        report.push_str(&format!(
+            "\n{}: {} replacement(s)\n",
+            input_path.display(),
+            replacements.len()
+        ));
+        for replacement in replacements {
+            report.push_str(&replacement_line(config, replacement));
+        }
+        if let Some(diff) = diffs.get(*input_path) {
+            report.push_str(&format!("\nDiff:\n{}", diff));
+        }
+    }
+    report
+}
+
+// A replacement in the JSON report and the "locations" of --json
+fn replacement_json(config: &Config, replacement: &Replacement) -> Value {
+    let c = replacement.character;
+    json!({
+        "part": replacement.part,
+        "path": replacement.path,
+        "paragraph": replacement.paragraph,
+        "run": replacement.run,
+        "codepoint": format!("U+{:04X}", c as u32),
+        "name": config.name(c),
+        "context": replacement.context(),
+        "snippet": replacement.snippet,
+        "offset": replacement.offset,
+        "percent": (replacement.position * 100.0).round() as u32,
+    })
+}
+
+fn json_report(
+    config: &Config,
+    files: &[(&PathBuf, Vec<Replacement>)],
+    diffs: &HashMap<PathBuf, String>,
+) -> String {
+    let files: Vec<Value> = files
+        .iter()
+        .map(|(input_path, replacements)| {
+            let replacements: Vec<Value> = replacements
+                .iter()
+                .map(|replacement| replacement_json(config, replacement))
+                .collect();
+            let mut file = json!({
+                "input": input_path.display().to_string(),
+                "replacements": replacements,
+            });
+            if let Some(diff) = diffs.get(*input_path) {
+                file["diff"] = json!(diff);
+            }
+            file
+        })
+        .collect();
+    let report = json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "config": config_source(config),
+        "profile": config.profile(),
+        "files": files,
+    });
+    serde_json::to_string_pretty(&report).unwrap() + "\n"
+}
+
+// A SARIF 2.1.0 log with a rule per listed character, e.g. "U+200B", and a result per
+// replacement. Documents have no lines, so a result is located by a logical location like
+// "body/paragraph 14/run 2" in the file; text files get the line as well.
+fn sarif_log(config: &Config, files: &[(&PathBuf, Vec<Replacement>)]) -> String {
+    let mut characters = config.listed();
+    if config.nbsp != NbspMode::Keep {
+        characters.push(NO_BREAK_SPACE);
+    }
+    for (_, replacements) in files {
+        characters.extend(replacements.iter().map(|replacement| replacement.character));
+    }
+    characters.sort_unstable();
+    characters.dedup();
+
+    let rules: Vec<Value> = characters
+        .iter()
+        .map(|&c| {
+            let treatment = if config.is_reported(c) {
+                "reported".to_string()
+            } else {
+                match config.replacement(c) {
+                    "" => "removed".to_string(),
+                    replacement => format!("replaced with {:?}", replacement),
+                }
+            };
+            json!({
+                "id": format!("U+{:04X}", c as u32),
+                "shortDescription": { "text": config.name(c) },
+                "fullDescription": {
+                    "text": format!("{} (U+{:04X}) is {}", config.name(c), c as u32, treatment),
+                },
+                "defaultConfiguration": { "level": "warning" },
+            })
+        })
+        .collect();
+
+    let mut results = Vec::new();
+    for (input_path, replacements) in files {
+        let uri = sarif_uri(input_path);
+        for replacement in replacements {
+            let c = replacement.character;
+            let mut physical = json!({ "artifactLocation": { "uri": uri } });
+            let mut location = json!({});
+            if replacement.part == "text" {
+                physical["region"] = json!({ "startLine": replacement.paragraph.unwrap_or(1) });
+            } else {
+                let mut name = replacement.part.to_string();
+                if let Some(paragraph) = replacement.paragraph {
+                    name.push_str(&format!("/paragraph {}", paragraph));
+                }
+                if let Some(run) = replacement.run {
+                    name.push_str(&format!("/run {}", run));
+                }
+                location["logicalLocations"] = json!([{
+                    "fullyQualifiedName": name,
+                    "kind": "element",
+                }]);
+            }
+            location["physicalLocation"] = physical;
+            results.push(json!({
+                "ruleId": format!("U+{:04X}", c as u32),
+                "ruleIndex": characters.binary_search(&c).unwrap(),
+                "level": "warning",
+                "message": {
+                    "text": format!(
+                        "{} (U+{:04X}) in \"{}\"",
+                        config.name(c),
+                        c as u32,
+                        replacement.context()
+                    ),
+                },
+                "locations": [location],
+            }));
+        }
+    }
+
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "docx-cleaner",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    });
+    serde_json::to_string_pretty(&log).unwrap()
+}
+
+// A path as a relative or file: URI reference, with the characters a URI cannot hold, e.g.
+// spaces, percent-encoded
+fn sarif_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::new();
+    if Path::new(&path).has_root() || path.chars().nth(1) == Some(':') {
+        uri.push_str(if path.starts_with('/') {
+            "file://"
+        } else {
+            "file:///"
+        });
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+// The page of an HTML report up to the summary, which follows with the files
+const HTML_REPORT_HEAD: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>docx-cleaner replacement report</title>
+<style>
+body { font-family: sans-serif; margin: 2em; max-width: 60em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; }
+td.count { text-align: right; }
+.context { font-family: monospace; white-space: pre-wrap; }
+.snippet { color: #555; font-style: italic; }
+.badge { background: #c00; color: #fff; border-radius: 0.3em; padding: 0 0.3em; font-size: 0.85em; }
+</style>
+</head>
+<body>
+"#;
+
+// Like text_report, as a page to share with people who would not read the text or JSON: a
+// summary table of the characters, then the context of each replacement under its paragraph,
+// with the invisible characters shown as red badges
+fn html_report(
+    config: &Config,
+    files: &[(&PathBuf, Vec<Replacement>)],
+    diffs: &HashMap<PathBuf, String>,
+) -> String {
+    let mut counts = HashMap::new();
+    for replacement in files.iter().flat_map(|(_, replacements)| replacements) {
+        *counts.entry(replacement.character).or_insert(0) += 1;
+    }
+    let mut report = String::from(HTML_REPORT_HEAD);
+    report.push_str("<h1>docx-cleaner replacement report</h1>\n");
+    report.push_str(&format!(
+        "<p>Version {}, config: {}</p>\n",
+        env!("CARGO_PKG_VERSION"),
+        escape_html(&config_source(config))
+    ));
+
+    report.push_str("<h2>Summary</h2>\n<table>\n");
+    report.push_str("<tr><th>Character</th><th>Code point</th><th>Count</th></tr>\n");
+    for (c, count) in sorted_counts(&counts) {
+        report.push_str(&format!(
+            "<tr><td>{}</td><td>U+{:04X}</td><td class=\"count\">{}</td></tr>\n",
+            escape_html(&config.name(c)),
+            c as u32,
+            count
+        ));
+    }
+    let total: usize = counts.values().sum();
+    report.push_str(&format!(
+        "<tr><th>Total</th><th></th><th class=\"count\">{}</th></tr>\n</table>\n",
+        total
+    ));
+
+    for (input_path, replacements) in files {
+        report.push_str(&format!(
+            "\n<h2>{}</h2>\n<p>{} replacement(s)</p>\n",
+            escape_html(&input_path.display().to_string()),
+            replacements.len()
+        ));
+        // Replacements in the same paragraph follow each other in document order
+        let paragraphs =
+            replacements.chunk_by(|a, b| a.path == b.path && a.paragraph == b.paragraph);
+        for paragraph in paragraphs {
+            let heading = location(&Replacement {
+                run: None,
+                ..paragraph[0].clone()
+            });
+            report.push_str(&format!(
+                "<h3>{}</h3>\n<p class=\"snippet\"><bdi dir=\"auto\">{}</bdi></p>\n<ul>\n",
+                escape_html(&heading),
+                html_context(config, &paragraph[0].snippet)
+            ));
+            // Characters close together share their context, which is then shown once
+            let mut contexts: Vec<String> = paragraph
+                .iter()
+                .map(|replacement| {
+                    format!(
+                        "{}{}{}",
+                        html_context(config, &replacement.before),
+                        badge(config, replacement.character),
+                        html_context(config, &replacement.after)
+                    )
+                })
+                .collect();
+            contexts.dedup();
+            for context in contexts {
+                report.push_str(&format!(
+                    "<li><bdi class=\"context\" dir=\"auto\">{}</bdi></li>\n",
+                    context
+                ));
+            }
+            report.push_str("</ul>\n");
+        }
+        if let Some(diff) = diffs.get(*input_path) {
+            report.push_str(&format!(
+                "<h3>Diff</h3>\n<pre class=\"context\">{}</pre>\n",
+                escape_html(diff)
+            ));
+        }
+    }
+    report.push_str("</body>\n</html>\n");
+    report
+}
+
+// A character as a red badge, e.g. ⟨U+200B⟩, with its name shown on hover
+fn badge(config: &Config, c: char) -> String {
+    format!(
+        "<span class=\"badge\" title=\"{}\">\u{27E8}U+{:04X}\u{27E9}</span>",
+        escape_html(&config.name(c)),
+        c as u32
+    )
+}
+
+// The context of a replacement, escaped, with the ⟨U+XXXX⟩ the library writes for the invisible
+// characters around it turned into badges as well
+fn html_context(config: &Config, context: &str) -> String {
+    let mut html = String::new();
+    let mut last = 0;
+    for marker in INVISIBLE_MARKER_RE.captures_iter(context) {
+        let whole = marker.get(0).unwrap();
+        let c = u32::from_str_radix(&marker[1], 16)
+            .ok()
+            .and_then(char::from_u32);
+        let Some(c) = c else {
+            continue;
+        };
+        html.push_str(&escape_html(&context[last..whole.start()]));
+        html.push_str(&badge(config, c));
+        last = whole.end();
+    }
+    html.push_str(&escape_html(&context[last..]));
+    html
+}
+
+static INVISIBLE_MARKER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new("\u{27E8}U\\+([0-9A-F]{4,6})\u{27E9}").unwrap());
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
 }
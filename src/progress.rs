@@ -0,0 +1,48 @@
+// The progress bar of a batch on standard error, or a stand-in that shows nothing in a build
+// without the "progress" feature, for which indicatif is left out
+#[cfg(feature = "progress")]
+pub use indicatif::ProgressBar;
+
+/// Progress on standard error while the files are processed: a bar with the files done and the
+/// current file, or only a spinner for a single file. indicatif hides it by itself when standard
+/// error is not a terminal.
+#[cfg(feature = "progress")]
+pub fn progress_bar(files: usize, hidden: bool) -> ProgressBar {
+    use indicatif::ProgressStyle;
+
+    if hidden {
+        return ProgressBar::hidden();
+    }
+    let progress = if files > 1 {
+        ProgressBar::new(files as u64).with_style(
+            ProgressStyle::with_template("{spinner} [{pos}/{len}] {bar:30} {wide_msg}").unwrap(),
+        )
+    } else {
+        ProgressBar::new_spinner()
+            .with_style(ProgressStyle::with_template("{spinner} {wide_msg}").unwrap())
+    };
+    progress.enable_steady_tick(std::time::Duration::from_millis(100));
+    progress
+}
+
+#[cfg(not(feature = "progress"))]
+#[derive(Clone)]
+pub struct ProgressBar;
+
+#[cfg(not(feature = "progress"))]
+impl ProgressBar {
+    pub fn set_message(&self, _message: String) {}
+
+    pub fn inc(&self, _delta: u64) {}
+
+    pub fn suspend<R>(&self, f: impl FnOnce() -> R) -> R {
+        f()
+    }
+
+    pub fn finish_and_clear(&self) {}
+}
+
+#[cfg(not(feature = "progress"))]
+pub fn progress_bar(_files: usize, _hidden: bool) -> ProgressBar {
+    ProgressBar
+}
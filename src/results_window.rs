@@ -0,0 +1,215 @@
+// The results of a GUI run in a window of their own on Windows, with the "results-window"
+// feature: the whole report in a scrollable, selectable text box with "Copy to clipboard",
+// "Save report…", "Open output folder" and "Close" buttons. A message dialog cannot scroll, so
+// it only shows the first lines of a long report. Esc closes the window like Close.
+use crate::gui::ResultsChoice;
+use std::cell::Cell;
+use windows_sys::Win32::Foundation::{HWND, LPARAM, LRESULT, RECT, WPARAM};
+use windows_sys::Win32::Graphics::Gdi::{GetStockObject, COLOR_BTNFACE, DEFAULT_GUI_FONT, HBRUSH};
+use windows_sys::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows_sys::Win32::UI::Controls::{EM_SETLIMITTEXT, EM_SETSEL};
+use windows_sys::Win32::UI::Input::KeyboardAndMouse::{SetFocus, VK_ESCAPE};
+use windows_sys::Win32::UI::WindowsAndMessaging::*;
+
+const CLASS_NAME: &str = "DocxCleanerResults";
+
+const TEXT: i32 = 100;
+const COPY: i32 = 101;
+const SAVE_REPORT: i32 = 102;
+const OPEN_FOLDER: i32 = 103;
+const CLOSE: i32 = 104;
+
+// The buttons along the bottom, left to right
+const BUTTONS: [(i32, &str); 4] = [
+    (COPY, "Copy to clipboard"),
+    (SAVE_REPORT, "Save report\u{2026}"),
+    (OPEN_FOLDER, "Open output folder"),
+    (CLOSE, "Close"),
+];
+
+const MARGIN: i32 = 10;
+const BUTTON_WIDTH: i32 = 140;
+const BUTTON_HEIGHT: i32 = 28;
+
+thread_local! {
+    // How the window was left, set by the window procedure
+    static CHOICE: Cell<Option<ResultsChoice>> = const { Cell::new(None) };
+}
+
+/// Shows the report until the window is closed or a button other than Copy is clicked; None
+/// when the window could not be created, so the caller falls back to a message dialog
+pub fn show(title: &str, message: &str, folder: bool) -> Option<ResultsChoice> {
+    let class_name = wide(CLASS_NAME);
+    // SAFETY: every pointer handed to Windows points to a live, null-terminated UTF-16 buffer or
+    // a struct on the stack, and the window only lives inside this call
+    unsafe {
+        let instance = GetModuleHandleW(std::ptr::null());
+        let class = WNDCLASSW {
+            style: CS_HREDRAW | CS_VREDRAW,
+            lpfnWndProc: Some(window_proc),
+            cbClsExtra: 0,
+            cbWndExtra: 0,
+            hInstance: instance,
+            hIcon: std::ptr::null_mut(),
+            hCursor: LoadCursorW(std::ptr::null_mut(), IDC_ARROW),
+            hbrBackground: (COLOR_BTNFACE + 1) as HBRUSH,
+            lpszMenuName: std::ptr::null(),
+            lpszClassName: class_name.as_ptr(),
+        };
+        // Fails harmlessly when the class is registered from an earlier run of the window
+        RegisterClassW(&class);
+        let window = CreateWindowExW(
+            0,
+            class_name.as_ptr(),
+            wide(title).as_ptr(),
+            WS_OVERLAPPEDWINDOW,
+            CW_USEDEFAULT,
+            CW_USEDEFAULT,
+            720,
+            540,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            instance,
+            std::ptr::null(),
+        );
+        if window.is_null() {
+            return None;
+        }
+        let font = GetStockObject(DEFAULT_GUI_FONT);
+        let edit_class = wide("EDIT");
+        let text = CreateWindowExW(
+            WS_EX_CLIENTEDGE,
+            edit_class.as_ptr(),
+            std::ptr::null(),
+            WS_CHILD
+                | WS_VISIBLE
+                | WS_VSCROLL
+                | WS_HSCROLL
+                | WS_TABSTOP
+                | (ES_MULTILINE | ES_READONLY | ES_AUTOVSCROLL | ES_AUTOHSCROLL) as u32,
+            0,
+            0,
+            0,
+            0,
+            window,
+            TEXT as isize as HMENU,
+            instance,
+            std::ptr::null(),
+        );
+        SendMessageW(text, WM_SETFONT, font as WPARAM, 1);
+        // Without a limit a multiline text box holds about 32,000 characters
+        SendMessageW(text, EM_SETLIMITTEXT, 0, 0);
+        SetWindowTextW(text, wide(&message.replace('\n', "\r\n")).as_ptr());
+
+        let button_class = wide("BUTTON");
+        for (id, label) in BUTTONS {
+            if id == OPEN_FOLDER && !folder {
+                continue;
+            }
+            let style = if id == CLOSE {
+                BS_DEFPUSHBUTTON
+            } else {
+                BS_PUSHBUTTON
+            };
+            let button = CreateWindowExW(
+                0,
+                button_class.as_ptr(),
+                wide(label).as_ptr(),
+                WS_CHILD | WS_VISIBLE | WS_TABSTOP | style as u32,
+                0,
+                0,
+                0,
+                0,
+                window,
+                id as isize as HMENU,
+                instance,
+                std::ptr::null(),
+            );
+            SendMessageW(button, WM_SETFONT, font as WPARAM, 1);
+        }
+        layout(window);
+        ShowWindow(window, SW_SHOW);
+        SetForegroundWindow(window);
+        SetFocus(GetDlgItem(window, CLOSE));
+
+        CHOICE.set(None);
+        let mut message: MSG = std::mem::zeroed();
+        while GetMessageW(&mut message, std::ptr::null_mut(), 0, 0) > 0 {
+            // The text box would take Esc for itself
+            if message.message == WM_KEYDOWN && message.wParam == VK_ESCAPE as WPARAM {
+                DestroyWindow(window);
+                continue;
+            }
+            // Tab between the text and the buttons, and Enter for the default one
+            if IsDialogMessageW(window, &message) == 0 {
+                TranslateMessage(&message);
+                DispatchMessageW(&message);
+            }
+        }
+    }
+    Some(CHOICE.take().unwrap_or(ResultsChoice::Close))
+}
+
+unsafe extern "system" fn window_proc(
+    window: HWND,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match message {
+        WM_SIZE => layout(window),
+        WM_COMMAND => {
+            let choice = match (wparam & 0xFFFF) as i32 {
+                COPY => {
+                    let text = GetDlgItem(window, TEXT);
+                    SendMessageW(text, EM_SETSEL, 0, -1);
+                    SendMessageW(text, WM_COPY, 0, 0);
+                    return 0;
+                }
+                SAVE_REPORT => ResultsChoice::SaveReport,
+                OPEN_FOLDER => ResultsChoice::OpenFolder,
+                CLOSE | IDCANCEL => ResultsChoice::Close,
+                _ => return DefWindowProcW(window, message, wparam, lparam),
+            };
+            CHOICE.set(Some(choice));
+            DestroyWindow(window);
+        }
+        WM_DESTROY => PostQuitMessage(0),
+        _ => return DefWindowProcW(window, message, wparam, lparam),
+    }
+    0
+}
+
+// The text fills the window above a row of buttons
+unsafe fn layout(window: HWND) {
+    let mut client = RECT {
+        left: 0,
+        top: 0,
+        right: 0,
+        bottom: 0,
+    };
+    GetClientRect(window, &mut client);
+    let buttons_top = client.bottom - MARGIN - BUTTON_HEIGHT;
+    MoveWindow(
+        GetDlgItem(window, TEXT),
+        MARGIN,
+        MARGIN,
+        (client.right - 2 * MARGIN).max(0),
+        (buttons_top - 2 * MARGIN).max(0),
+        1,
+    );
+    let mut left = MARGIN;
+    for (id, _) in BUTTONS {
+        let button = GetDlgItem(window, id);
+        if button.is_null() {
+            continue;
+        }
+        MoveWindow(button, left, buttons_top, BUTTON_WIDTH, BUTTON_HEIGHT, 1);
+        left += BUTTON_WIDTH + MARGIN;
+    }
+}
+
+// A null-terminated UTF-16 string for the W functions
+fn wide(text: &str) -> Vec<u16> {
+    text.encode_utf16().chain(Some(0)).collect()
+}
@@ -0,0 +1,148 @@
+// The selftest subcommand: checks that the installed binary can clean a document, e.g. where an
+// antivirus or a missing runtime might get in the way. A document with one of each character the
+// built-in list removes is generated in memory and cleaned with the whole pipeline, so no input
+// is needed and nothing is written.
+use docx_cleaner::{builtin_problems, CleanReport, Cleaner, Config, RuleAction, DEFAULT_PROFILE};
+use docx_rs::{Docx, Paragraph, Run};
+use std::io::{Cursor, Read};
+
+// What a stage passes on to the next, with what is printed after PASS, or why it failed
+type Stage<T> = Result<(T, String), String>;
+
+/// Runs the stages in order and prints PASS or FAIL for each, stopping at the first failure;
+/// returns whether all passed
+pub fn run() -> bool {
+    let passed = stages().is_some();
+    if passed {
+        println!("Self-test passed");
+    } else {
+        println!("Self-test failed");
+    }
+    passed
+}
+
+fn stages() -> Option<()> {
+    let config = print("Embedded config", check_config())?;
+    let targets = removed(&config);
+    let document = print("Test document", build(&targets))?;
+    let (report, cleaned) = print("Clean", clean(config.clone(), &document))?;
+    print("Output", check_output(&config, &targets, &cleaned))?;
+    print("Counters", check_counters(&targets, &report))
+}
+
+fn print<T>(name: &str, stage: Stage<T>) -> Option<T> {
+    match stage {
+        Ok((passed_on, detail)) => {
+            println!("PASS  {}: {}", name, detail);
+            Some(passed_on)
+        }
+        Err(problem) => {
+            println!("FAIL  {}: {}", name, problem);
+            None
+        }
+    }
+}
+
+// Every profile is built as well, since they are only parsed once chosen
+fn check_config() -> Stage<Config> {
+    let problems = builtin_problems();
+    if !problems.is_empty() {
+        return Err(problems.join("; "));
+    }
+    let profiles = Config::builtin_profiles();
+    for profile in &profiles {
+        Config::builtin_profile(&profile.name, &[])?;
+    }
+    let config = Config::builtin_profile(DEFAULT_PROFILE, &[])?;
+    let detail = format!(
+        "{} characters, {} profiles",
+        config.listed().len(),
+        profiles.len()
+    );
+    Ok((config, detail))
+}
+
+// The characters that should be gone from the cleaned document. Line feeds are left out, since
+// docx-rs drops them from the text of a run.
+fn removed(config: &Config) -> Vec<char> {
+    config
+        .listed()
+        .into_iter()
+        .filter(|&c| c != '\n')
+        .filter(|&c| {
+            matches!(
+                config.action(c),
+                Some(RuleAction::Delete | RuleAction::Replace)
+            )
+        })
+        .collect()
+}
+
+// A paragraph for each character, between two letters so nothing else is cleaned around it
+fn build(targets: &[char]) -> Stage<Vec<u8>> {
+    let mut docx = Docx::new();
+    for c in targets {
+        let text = format!("x{}y", c);
+        docx = docx.add_paragraph(Paragraph::new().add_run(Run::new().add_text(text)));
+    }
+    let mut document = Cursor::new(Vec::new());
+    docx.build()
+        .pack(&mut document)
+        .map_err(|e| e.to_string())?;
+    let document = document.into_inner();
+    let detail = format!("{} characters in {} bytes", targets.len(), document.len());
+    Ok((document, detail))
+}
+
+fn clean(config: Config, document: &[u8]) -> Stage<(CleanReport, Vec<u8>)> {
+    let cleaner = Cleaner::new(config);
+    let cleaned = cleaner.clean_package(document).map_err(|e| e.to_string())?;
+    let report = cleaned.report.clone();
+    let cleaned = cleaned.into_bytes().map_err(|e| e.to_string())?;
+    let detail = format!("{} bytes written", cleaned.len());
+    Ok(((report, cleaned), detail))
+}
+
+// Characters that other replacements bring in, like the line feed of the line separator, may be
+// left
+fn check_output(config: &Config, targets: &[char], cleaned: &[u8]) -> Stage<()> {
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(cleaned)).map_err(|e| format!("not a package: {}", e))?;
+    let mut xml = String::new();
+    archive
+        .by_name("word/document.xml")
+        .map_err(|e| e.to_string())?
+        .read_to_string(&mut xml)
+        .map_err(|e| e.to_string())?;
+    let brought_in: String = targets.iter().map(|&c| config.replacement(c)).collect();
+    let left: Vec<String> = targets
+        .iter()
+        .filter(|&&c| xml.contains(c) && !brought_in.contains(c))
+        .map(|&c| format!("U+{:04X}", c as u32))
+        .collect();
+    if !left.is_empty() {
+        return Err(format!("still contains {}", left.join(", ")));
+    }
+    Ok(((), format!("none of the {} characters left", targets.len())))
+}
+
+fn check_counters(targets: &[char], report: &CleanReport) -> Stage<()> {
+    let miscounted: Vec<String> = targets
+        .iter()
+        .filter_map(|c| {
+            let count = report.chars.get(c).copied().unwrap_or(0);
+            (count != 1).then(|| format!("U+{:04X} counted {} times", *c as u32, count))
+        })
+        .collect();
+    if !miscounted.is_empty() {
+        return Err(miscounted.join(", "));
+    }
+    if report.total_removed() != targets.len() {
+        return Err(format!(
+            "{} removed in all instead of {}",
+            report.total_removed(),
+            targets.len()
+        ));
+    }
+    Ok(((), format!("{} removed, once each", report.total_removed())))
+}
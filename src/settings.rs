@@ -0,0 +1,68 @@
+// What the GUI remembers between runs, kept in docx-cleaner/gui-settings.json in the platform
+// config directory. A missing or damaged file gives the defaults and is written anew after the
+// next run; --no-settings neither reads nor writes it.
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// The folder the files were last picked from, where the file picker starts
+    pub last_directory: Option<PathBuf>,
+    /// Whether runs of spaces were collapsed, unless the command line says otherwise
+    pub collapse_spaces: bool,
+    /// The --config of the last run that had one, used while it exists
+    pub config: Option<PathBuf>,
+    /// The profile of the last run, which the profile dialog keeps when it is closed
+    pub profile: Option<String>,
+    /// The folder the cleaned copies last went to, or None when they went next to their
+    /// originals
+    pub output_folder: Option<PathBuf>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            last_directory: None,
+            collapse_spaces: true,
+            config: None,
+            profile: None,
+            output_folder: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        settings_path()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .unwrap_or_default()
+    }
+
+    // Failing to save only loses the settings, so it is not reported beyond the log
+    pub fn save(&self) {
+        let Some(path) = settings_path() else {
+            return;
+        };
+        let saved = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| {
+                let json = serde_json::to_vec_pretty(self).unwrap_or_default();
+                std::fs::write(&path, json)
+            });
+        if let Err(e) = saved {
+            log::warn!(
+                "the GUI settings could not be saved to {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+}
+
+// e.g. ~/.config/docx-cleaner/gui-settings.json on Linux
+fn settings_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("docx-cleaner").join("gui-settings.json"))
+}
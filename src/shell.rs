@@ -0,0 +1,180 @@
+// The Explorer entries of register-shell: "Clean invisible characters" in the context menu of
+// the supported files, a shortcut in the Send To menu and, with the "clipboard" feature, "Clean
+// clipboard text" in the context menu of the desktop and folder backgrounds, all for the current
+// user only, so no administrator rights are needed. Explorer starts one process per file from the context menu
+// but passes all the selected files to a single one through Send To, which cleans them as a
+// batch. Elsewhere the subcommands only say that there is nothing to do.
+#[cfg(windows)]
+use std::path::{Path, PathBuf};
+#[cfg(windows)]
+use std::process::Command;
+
+#[cfg(windows)]
+const MENU_TEXT: &str = "Clean invisible characters";
+
+#[cfg(windows)]
+const CLIPBOARD_MENU_TEXT: &str = "Clean clipboard text";
+
+// Shown when right-clicking the desktop or the empty space of a folder
+#[cfg(windows)]
+const CLIPBOARD_KEY: &str =
+    r"HKCU\Software\Classes\Directory\Background\shell\docx-cleaner-clipboard";
+
+// The registry key of the verb for an extension; under SystemFileAssociations the entry is shown
+// whichever program opens the files
+#[cfg(windows)]
+fn verb_key(extension: &str) -> String {
+    format!(
+        r"HKCU\Software\Classes\SystemFileAssociations\.{}\shell\docx-cleaner",
+        extension
+    )
+}
+
+// %APPDATA%\Microsoft\Windows\SendTo\Clean invisible characters.lnk
+#[cfg(windows)]
+fn send_to_shortcut() -> Result<PathBuf, String> {
+    dirs::data_dir()
+        .map(|dir| {
+            dir.join(r"Microsoft\Windows\SendTo")
+                .join(format!("{}.lnk", MENU_TEXT))
+        })
+        .ok_or_else(|| "The Send To folder could not be found".to_string())
+}
+
+// reg.exe and PowerShell are console programs, which would flash a window from the GUI build
+#[cfg(windows)]
+fn run(program: &str, args: &[&str], env: &[(&str, &Path)]) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let output = Command::new(program)
+        .args(args)
+        .envs(env.iter().map(|(name, value)| (name, value.as_os_str())))
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| format!("Could not run {}: {}", program, e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Adds the context menu entry for each extension and the Send To shortcut, both starting this
+/// executable with --dialogs; registering again updates them, e.g. after the program was moved
+#[cfg(windows)]
+pub fn register(extensions: &[&str]) -> Result<String, String> {
+    let executable = std::env::current_exe()
+        .map_err(|e| format!("The path of this program is unknown: {}", e))?;
+    let path = executable.display().to_string();
+    let command = format!("\"{}\" --dialogs \"%1\"", path);
+    for extension in extensions {
+        let key = verb_key(extension);
+        run("reg", &["add", &key, "/ve", "/d", MENU_TEXT, "/f"], &[])?;
+        run("reg", &["add", &key, "/v", "Icon", "/d", &path, "/f"], &[])?;
+        // Without it the entry disappears when more than 15 files are selected
+        run(
+            "reg",
+            &["add", &key, "/v", "MultiSelectModel", "/d", "Player", "/f"],
+            &[],
+        )?;
+        let command_key = format!(r"{}\command", key);
+        run(
+            "reg",
+            &["add", &command_key, "/ve", "/d", &command, "/f"],
+            &[],
+        )?;
+    }
+    if cfg!(feature = "clipboard") {
+        let command = format!("\"{}\" --clipboard --dialogs", path);
+        run(
+            "reg",
+            &["add", CLIPBOARD_KEY, "/ve", "/d", CLIPBOARD_MENU_TEXT, "/f"],
+            &[],
+        )?;
+        run(
+            "reg",
+            &["add", CLIPBOARD_KEY, "/v", "Icon", "/d", &path, "/f"],
+            &[],
+        )?;
+        let command_key = format!(r"{}\command", CLIPBOARD_KEY);
+        run(
+            "reg",
+            &["add", &command_key, "/ve", "/d", &command, "/f"],
+            &[],
+        )?;
+    }
+    // The paths go through the environment rather than the script, so they need no quoting
+    let shortcut = send_to_shortcut()?;
+    run(
+        "powershell",
+        &[
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            "$link = (New-Object -ComObject WScript.Shell).CreateShortcut($env:DOCX_CLEANER_LINK); \
+             $link.TargetPath = $env:DOCX_CLEANER_EXE; $link.Arguments = '--dialogs'; $link.Save()",
+        ],
+        &[
+            ("DOCX_CLEANER_LINK", &shortcut),
+            ("DOCX_CLEANER_EXE", &executable),
+        ],
+    )?;
+    let clipboard = if cfg!(feature = "clipboard") {
+        format!(
+            ", and \"{}\" to the desktop context menu",
+            CLIPBOARD_MENU_TEXT
+        )
+    } else {
+        String::new()
+    };
+    Ok(format!(
+        "Added \"{}\" to the context menu of .{} files and to the Send To menu{}, \
+         running {}",
+        MENU_TEXT,
+        extensions.join(", ."),
+        clipboard,
+        path
+    ))
+}
+
+/// Removes what register adds; entries that are already gone are skipped
+#[cfg(windows)]
+pub fn unregister(extensions: &[&str]) -> Result<String, String> {
+    for extension in extensions {
+        let key = verb_key(extension);
+        if run("reg", &["query", &key], &[]).is_ok() {
+            run("reg", &["delete", &key, "/f"], &[])?;
+        }
+    }
+    // Also when this build has no clipboard support, since another one may have added it
+    if run("reg", &["query", CLIPBOARD_KEY], &[]).is_ok() {
+        run("reg", &["delete", CLIPBOARD_KEY, "/f"], &[])?;
+    }
+    let shortcut = send_to_shortcut()?;
+    if shortcut.exists() {
+        std::fs::remove_file(&shortcut)
+            .map_err(|e| format!("Could not remove '{}': {}", shortcut.display(), e))?;
+    }
+    Ok(format!(
+        "Removed \"{}\" from the context menu and the Send To menu, and \"{}\" from the \
+         desktop context menu",
+        MENU_TEXT, CLIPBOARD_MENU_TEXT
+    ))
+}
+
+#[cfg(not(windows))]
+pub fn register(_extensions: &[&str]) -> Result<String, String> {
+    Ok("register-shell only adds Explorer entries on Windows; nothing was changed".to_string())
+}
+
+#[cfg(not(windows))]
+pub fn unregister(_extensions: &[&str]) -> Result<String, String> {
+    Ok(
+        "unregister-shell only removes Explorer entries on Windows; nothing was changed"
+            .to_string(),
+    )
+}
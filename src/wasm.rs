@@ -0,0 +1,94 @@
+//! The entry point of the browser build, compiled with `wasm-pack build --no-default-features
+//! --features wasm`. Everything happens on the bytes handed over by the page, so the document
+//! never leaves the browser.
+use crate::{Cleaner, Config, PARTS};
+use serde_json::{json, Value};
+use wasm_bindgen::prelude::*;
+
+/// A cleaned document and its statistics
+#[wasm_bindgen]
+pub struct CleanedOutput {
+    bytes: Vec<u8>,
+    statistics: String,
+}
+
+#[wasm_bindgen]
+impl CleanedOutput {
+    /// The cleaned .docx, to offer as a download
+    #[wasm_bindgen(getter)]
+    pub fn bytes(&self) -> Vec<u8> {
+        self.bytes.clone()
+    }
+
+    /// What was replaced, as JSON: "characters" with a code point, name and count each, the
+    /// counts of the "parts", the "suspicious" characters that were left in, and the "total"
+    #[wasm_bindgen(getter)]
+    pub fn statistics(&self) -> String {
+        self.statistics.clone()
+    }
+}
+
+/// Cleans the bytes of a .docx (or another supported package) with the built-in config, or with
+/// `config_json` merged on top of it like a user config file
+#[wasm_bindgen]
+pub fn clean_docx_bytes(
+    input: &[u8],
+    config_json: Option<String>,
+) -> Result<CleanedOutput, JsValue> {
+    let config = match config_json.as_deref() {
+        Some(json) => Config::from_json(json),
+        None => Ok(Config::builtin()),
+    };
+    let cleaner = Cleaner::new(config.map_err(|e| JsValue::from_str(&e))?);
+    let error = |e: crate::Error| JsValue::from_str(&e.to_string());
+    let cleaned = cleaner.clean_package(input).map_err(error)?;
+    let report = cleaned.report.clone();
+    let bytes = cleaned.into_bytes().map_err(error)?;
+
+    let config = cleaner.config();
+    let mut counts: Vec<(char, usize)> = report.chars.iter().map(|(&c, &n)| (c, n)).collect();
+    counts.sort_by_key(|&(c, count)| (std::cmp::Reverse(count), c));
+    let characters: Vec<Value> = counts
+        .into_iter()
+        .map(|(c, count)| {
+            json!({
+                "codepoint": format!("U+{:04X}", c as u32),
+                "name": config.name(c),
+                "count": count,
+            })
+        })
+        .collect();
+    let parts: serde_json::Map<String, Value> = PARTS
+        .iter()
+        .filter_map(|part| {
+            let count = report.parts.get(part).copied().filter(|&count| count > 0)?;
+            Some((part.to_string(), json!(count)))
+        })
+        .collect();
+    let mut suspicious: Vec<(char, usize)> =
+        report.suspicious.iter().map(|(&c, &n)| (c, n)).collect();
+    suspicious.sort_unstable();
+    let suspicious: Vec<Value> = suspicious
+        .into_iter()
+        .map(|(c, count)| {
+            json!({
+                "codepoint": format!("U+{:04X}", c as u32),
+                "name": crate::unlisted_name(c),
+                "count": count,
+                "context": report.suspicious_contexts[&c],
+            })
+        })
+        .collect();
+    let statistics = json!({
+        "characters": characters,
+        "parts": parts,
+        "suspicious": suspicious,
+        "spaces_removed": report.collapsed_spaces,
+        "total": report.total_removed(),
+        "modified": report.is_modified(),
+    });
+    Ok(CleanedOutput {
+        bytes,
+        statistics: statistics.to_string(),
+    })
+}
@@ -0,0 +1,241 @@
+// The --watch mode: cleans the documents that are created or changed in a folder, e.g. a shared
+// "incoming" folder, until Ctrl-C, and then prints a summary of the session. A file is cleaned
+// like an input path once its events have settled and its size has stopped changing, and one
+// that cannot be read as a document yet, as while it is still being copied, is tried again a few
+// times before the error is reported.
+use super::{
+    append_to_log, batch_summary, is_supported, json_failure, json_statistics, log_entry,
+    print_human, process_file, BatchReport, CleanArgs, CleanError, JsonOutput,
+};
+use docx_cleaner::{CleanReport, Cleaner};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+// How long a file has to go without events before it is cleaned
+const SETTLE: Duration = Duration::from_millis(500);
+
+// How long to wait before trying again a file that could not be read as a document
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+// How often a file is tried before it counts as failed
+const ATTEMPTS: u32 = 5;
+
+// What the loop waits for, from the watcher and the Ctrl-C handler
+enum Signal {
+    Changed(PathBuf),
+    Stop,
+}
+
+// A file waiting to be cleaned: when, its size when it was last looked at, and how often it
+// failed so far
+struct Pending {
+    due: Instant,
+    size: Option<u64>,
+    failures: u32,
+}
+
+/// Cleans the files changed in `dir` and its subdirectories until Ctrl-C; returns the exit code
+/// of the session like a batch of input paths
+pub fn watch(dir: &Path, cleaner: &Cleaner, args: &CleanArgs) -> Result<i32, CleanError> {
+    let watch_error = |e: &dyn std::fmt::Display| {
+        CleanError::Watch(format!("Could not watch '{}': {}", dir.display(), e))
+    };
+    // The events name paths under the folder as it was given to the watcher, so both sides of
+    // the comparison with the output folder are canonical
+    let dir = dir.canonicalize().map_err(|e| watch_error(&e))?;
+    if !dir.is_dir() {
+        return Err(watch_error(&"not a directory"));
+    }
+    let output = args
+        .output
+        .as_deref()
+        .and_then(|output| output.canonicalize().ok());
+
+    let (sender, receiver) = mpsc::channel();
+    let events = sender.clone();
+    let mut watcher =
+        notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                for path in event.paths {
+                    let _ = events.send(Signal::Changed(path));
+                }
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("{}", e),
+        })
+        .map_err(|e| watch_error(&e))?;
+    watcher
+        .watch(&dir, RecursiveMode::Recursive)
+        .map_err(|e| watch_error(&e))?;
+    ctrlc::set_handler(move || {
+        let _ = sender.send(Signal::Stop);
+    })
+    .map_err(|e| watch_error(&e))?;
+
+    let statistics = &args.statistics;
+    let mut json = statistics.json.map(JsonOutput::new);
+    let to_stderr = json.is_some();
+    if !statistics.quiet {
+        let message = format!(
+            "Watching {} for new documents; press Ctrl-C to stop\n",
+            dir.display()
+        );
+        print_human(&message, to_stderr);
+    }
+    let is_watched = |path: &Path| {
+        path.is_file()
+            && is_supported(path)
+            && !is_cleaned_copy(path)
+            && args.selection.selects(path)
+            && !output
+                .as_ref()
+                .is_some_and(|output| path.starts_with(output))
+    };
+
+    let mut pending: HashMap<PathBuf, Pending> = HashMap::new();
+    // The outcome of each file, for its latest version
+    let mut session: Vec<(PathBuf, Result<CleanReport, CleanError>)> = Vec::new();
+    loop {
+        let timeout = pending
+            .values()
+            .map(|file| file.due.saturating_duration_since(Instant::now()))
+            .min()
+            .unwrap_or(Duration::MAX);
+        match receiver.recv_timeout(timeout) {
+            Ok(Signal::Changed(path)) if is_watched(&path) => {
+                let file = pending.entry(path.clone()).or_insert(Pending {
+                    due: Instant::now(),
+                    size: None,
+                    failures: 0,
+                });
+                file.due = Instant::now() + SETTLE;
+                file.size = file_size(&path);
+            }
+            Ok(Signal::Changed(path)) => log::debug!("ignored {}", path.display()),
+            Ok(Signal::Stop) | Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+
+        let now = Instant::now();
+        let due: Vec<PathBuf> = pending
+            .iter()
+            .filter(|(_, file)| file.due <= now)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in due {
+            let Some(mut file) = pending.remove(&path) else {
+                continue;
+            };
+            // Still being written
+            let size = file_size(&path);
+            if size != file.size {
+                file.size = size;
+                file.due = now + SETTLE;
+                pending.insert(path, file);
+                continue;
+            }
+            // An earlier copy is replaced, as it was most likely written for an earlier version
+            // of the same file
+            let result = process_file(
+                &path,
+                args,
+                cleaner,
+                &|_, _, _| true,
+                &|_, _| Vec::new(),
+                &Ok,
+            );
+            match result {
+                Err(error) if is_incomplete(&error) && file.failures + 1 < ATTEMPTS => {
+                    log::info!("{}: {}; trying again", path.display(), error);
+                    file.failures += 1;
+                    file.due = now + RETRY_DELAY;
+                    pending.insert(path, file);
+                }
+                // Moved or deleted before it settled, like the temporary files of some programs
+                Err(CleanError::InputMissing) => {}
+                Ok(None) => {}
+                Ok(Some((report, output_path, diff))) => {
+                    let entry = log_entry(&path, &report, output_path.as_deref());
+                    if !statistics.quiet {
+                        print_human(&format!("{}\n", entry), to_stderr);
+                    }
+                    if let Some(diff) = diff {
+                        print_human(&diff, to_stderr);
+                    }
+                    if args.log && append_to_log(args.log_file.as_deref(), &entry).is_none() {
+                        log::warn!("the log could not be written");
+                    }
+                    if let Some(json) = &mut json {
+                        let config = cleaner.config();
+                        let replacements = &report.replacements;
+                        json.push(json_statistics(
+                            config,
+                            &path,
+                            output_path.as_deref(),
+                            &report,
+                            replacements,
+                        ));
+                    }
+                    session.retain(|(earlier, _)| *earlier != path);
+                    session.push((path, Ok(report)));
+                }
+                Err(error) => {
+                    log::error!("{}: {}", path.display(), error);
+                    if let Some(json) = &mut json {
+                        json.push(json_failure(cleaner.config(), &path, &error));
+                    }
+                    session.retain(|(earlier, _)| *earlier != path);
+                    session.push((path, Err(error)));
+                }
+            }
+        }
+    }
+
+    let (paths, results): (Vec<PathBuf>, Vec<_>) = session.into_iter().unzip();
+    let mut batch = BatchReport::new();
+    for (path, result) in paths.iter().zip(results) {
+        match result {
+            Ok(report) => batch.add(path, report),
+            Err(error) => batch.fail(path, error),
+        }
+    }
+    if !statistics.quiet {
+        print_human(&batch_summary(cleaner.config(), &batch, false), to_stderr);
+    }
+    if let Some(mut json) = json {
+        json.push(batch.to_json());
+        json.finish();
+    }
+    Ok(batch.exit_code(statistics))
+}
+
+// The copies written next to the originals, "<stem>_cleaned.<extension>", and the numbered ones
+// beside earlier copies
+fn is_cleaned_copy(path: &Path) -> bool {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let stem = match stem.rsplit_once('_') {
+        Some((stem, number)) if number.chars().all(|c| c.is_ascii_digit()) => stem,
+        _ => &stem,
+    };
+    stem.ends_with("_cleaned")
+}
+
+// What a document still being copied or saved fails with
+fn is_incomplete(error: &CleanError) -> bool {
+    matches!(
+        error,
+        CleanError::Read(_)
+            | CleanError::Clean(
+                docx_cleaner::Error::Empty
+                    | docx_cleaner::Error::Truncated
+                    | docx_cleaner::Error::InvalidDocx(_)
+            )
+    )
+}
+
+fn file_size(path: &Path) -> Option<u64> {
+    std::fs::metadata(path).ok().map(|metadata| metadata.len())
+}
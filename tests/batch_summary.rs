@@ -0,0 +1,137 @@
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+mod support;
+
+// A folder with a lightly and a badly affected document, two clean ones and a broken one
+fn folder(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("batch-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let files: [(&str, &[u8]); 5] = [
+        ("a.docx", &support::text_package("one\u{200B}")),
+        ("b.docx", &support::text_package("clean")),
+        (
+            "c.docx",
+            &support::text_package("\u{200B}th\u{200B}ree  \u{200D}"),
+        ),
+        ("d.docx", &support::text_package("also clean")),
+        ("e.docx", b"not a document"),
+    ];
+    for (name, contents) in files {
+        std::fs::write(dir.join(name), contents).unwrap();
+    }
+    dir
+}
+
+fn check(dir: &Path, args: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("--check")
+        .args(args)
+        .arg(dir)
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(dir).unwrap();
+    output
+}
+
+#[test]
+fn the_summary_ranks_the_files_by_findings() {
+    let dir = folder("console");
+    let output = check(&dir, &[]);
+    assert_eq!(output.status.code(), Some(3), "{:?}", output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let summary = &stdout[stdout.find("Batch Summary:").unwrap()..];
+    let path = |name: &str| dir.join(name).display().to_string();
+    let expected = format!(
+        "CHARACTERS  SPACES  FILE\n         \
+         3       1  {}\n         \
+         1       0  {}\n\
+         2 files were already clean\n\
+         \n\
+         Failed:\n{}: ",
+        path("c.docx"),
+        path("a.docx"),
+        path("e.docx"),
+    );
+    assert!(summary.contains(&expected), "{summary}");
+    assert!(
+        summary.contains("Files failed:             1\n"),
+        "{summary}"
+    );
+}
+
+#[test]
+fn the_json_ends_with_the_files_and_totals() {
+    let dir = folder("json");
+    let output = check(&dir, &["-q", "--json=array"]);
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let objects = json.as_array().unwrap();
+    assert_eq!(objects.len(), 6);
+
+    let batch = &objects[5];
+    let files = batch["files"].as_array().unwrap();
+    let names: Vec<_> = files
+        .iter()
+        .map(|file| {
+            let input = file["input"].as_str().unwrap();
+            &input[input.len() - 6..]
+        })
+        .collect();
+    assert_eq!(names, ["c.docx", "a.docx", "b.docx", "d.docx", "e.docx"]);
+    assert_eq!(files[0]["characters"], 3);
+    assert_eq!(files[0]["spaces_removed"], 1);
+    assert_eq!(files[2]["modified"], false);
+    assert!(files[4]["error"].is_string());
+
+    let totals = &batch["totals"];
+    assert_eq!(totals["processed"], 4);
+    assert_eq!(totals["already_clean"], 2);
+    assert_eq!(totals["failed"], 1);
+    assert_eq!(totals["characters"], 4);
+}
+
+// The broken document second of four, with --stop-on-error
+fn check_stopping(test: &str, args: &[&str]) -> (PathBuf, Output) {
+    let dir = folder(test);
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .args(["--check", "--stop-on-error"])
+        .args(args)
+        .args(["a.docx", "e.docx", "c.docx", "b.docx"].map(|name| dir.join(name)))
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    (dir, output)
+}
+
+#[test]
+fn stop_on_error_leaves_the_files_after_the_failure() {
+    let (dir, output) = check_stopping("stop", &[]);
+    assert_eq!(output.status.code(), Some(3), "{:?}", output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let summary = &stdout[stdout.find("Batch Summary:").unwrap()..];
+    assert!(
+        summary.contains("Stopped at the first failure; 2 more files were not processed\n"),
+        "{summary}"
+    );
+    assert!(
+        summary.contains("Files processed:          1\n"),
+        "{summary}"
+    );
+    assert!(
+        !stdout.contains(&dir.join("c.docx").display().to_string()),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn stop_on_error_counts_the_files_left_in_the_json() {
+    let (_, output) = check_stopping("stop-json", &["-q", "--json=array"]);
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let objects = json.as_array().unwrap();
+    assert_eq!(objects.len(), 3);
+    let totals = &objects[2]["totals"];
+    assert_eq!(totals["processed"], 1);
+    assert_eq!(totals["failed"], 1);
+    assert_eq!(totals["not_processed"], 2);
+}
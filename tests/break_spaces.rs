@@ -0,0 +1,177 @@
+use docx_cleaner::{CleanReport, Cleaner, Config};
+use docx_rs::{BreakType, DocumentChild, Docx, Paragraph, ParagraphChild, Run, RunChild};
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+// The content of a run
+#[derive(Clone, Copy)]
+enum Piece {
+    Text(&'static str),
+    Break,
+    Tab,
+}
+
+use Piece::{Break, Tab, Text};
+
+mod support;
+
+fn package(runs: &[&[Piece]]) -> Vec<u8> {
+    let mut body = String::from("<w:p>");
+    for run in runs {
+        body.push_str("<w:r>");
+        for piece in *run {
+            match piece {
+                Text(text) => body.push_str(&format!("<w:t xml:space=\"preserve\">{}</w:t>", text)),
+                Break => body.push_str("<w:br/>"),
+                Tab => body.push_str("<w:tab/>"),
+            }
+        }
+        body.push_str("</w:r>");
+    }
+    body.push_str("</w:p>");
+    support::word_package(&body)
+}
+
+// The texts of the cleaned package in order, with | for a break and → for a tab
+fn clean_package(config: &Config, runs: &[&[Piece]]) -> (CleanReport, String) {
+    let input = package(runs);
+    let cleaned = Cleaner::new(config.clone()).clean_package(&input).unwrap();
+    let report = cleaned.report.clone();
+    let output = cleaned.into_bytes().unwrap();
+    let mut archive = ZipArchive::new(Cursor::new(output)).unwrap();
+    let mut xml = String::new();
+    archive
+        .by_name("word/document.xml")
+        .unwrap()
+        .read_to_string(&mut xml)
+        .unwrap();
+    let mut text = String::new();
+    for piece in xml.split('<').skip(1) {
+        if let Some(content) = piece.strip_prefix("w:t xml:space=\"preserve\">") {
+            text.push_str(&format!("[{}]", content));
+        } else if piece.starts_with("w:br/>") {
+            text.push('|');
+        } else if piece.starts_with("w:tab/>") {
+            text.push('\u{2192}');
+        }
+    }
+    (report, text)
+}
+
+// The same for the docx-rs model cleaned by clean_docx
+fn clean_model(config: &Config, runs: &[&[Piece]]) -> (CleanReport, String) {
+    let mut paragraph = Paragraph::new();
+    for pieces in runs {
+        let mut run = Run::new();
+        for piece in *pieces {
+            run = match piece {
+                Text(text) => run.add_text(*text),
+                Break => run.add_break(BreakType::TextWrapping),
+                Tab => run.add_tab(),
+            };
+        }
+        paragraph = paragraph.add_run(run);
+    }
+    let mut docx = Docx::new().add_paragraph(paragraph);
+    let report = Cleaner::new(config.clone()).clean_docx(&mut docx);
+    let mut text = String::new();
+    for child in &docx.document.children {
+        let DocumentChild::Paragraph(paragraph) = child else {
+            continue;
+        };
+        for child in &paragraph.children {
+            let ParagraphChild::Run(run) = child else {
+                continue;
+            };
+            for child in &run.children {
+                match child {
+                    RunChild::Text(t) => text.push_str(&format!("[{}]", t.text)),
+                    RunChild::Break(_) => text.push('|'),
+                    RunChild::Tab(_) => text.push('\u{2192}'),
+                    _ => {}
+                }
+            }
+        }
+    }
+    (report, text)
+}
+
+fn trimming() -> Config {
+    let mut config = Config::builtin();
+    config.trim_around_breaks = true;
+    config
+}
+
+// Each case in both engines: the runs, what the text becomes and how many spaces are trimmed
+fn check(config: &Config, cases: &[(&[&[Piece]], &str, usize)]) {
+    for &(runs, expected, trimmed) in cases {
+        for (engine, (report, text)) in [
+            ("package", clean_package(config, runs)),
+            ("docx-rs", clean_model(config, runs)),
+        ] {
+            assert_eq!(text, expected, "{engine}");
+            assert_eq!(report.break_spaces, trimmed, "{engine}: {expected}");
+        }
+    }
+}
+
+#[test]
+fn a_space_on_either_side_of_a_break_is_trimmed() {
+    check(
+        &trimming(),
+        &[
+            (&[&[Text("foo "), Break, Text(" bar")]], "[foo]|[bar]", 2),
+            (&[&[Text("foo "), Break, Text("bar")]], "[foo]|[bar]", 1),
+            (&[&[Text("foo"), Break, Text(" bar")]], "[foo]|[bar]", 1),
+            // A break between runs
+            (
+                &[&[Text("foo ")], &[Break], &[Text(" bar")]],
+                "[foo]|[bar]",
+                2,
+            ),
+            (&[&[Text("foo "), Break], &[Text(" bar")]], "[foo]|[bar]", 2),
+            // Only a single space goes
+            (&[&[Text("foo"), Break, Text("  bar")]], "[foo]|[ bar]", 1),
+            // A space alone between two breaks
+            (&[&[Break, Text(" "), Break]], "|[]|", 1),
+        ],
+    );
+}
+
+#[test]
+fn tabs_are_not_breaks() {
+    check(
+        &trimming(),
+        &[
+            (&[&[Text("foo "), Tab, Text(" bar")]], "[foo ]\u{2192}[ bar]", 0),
+            (&[&[Break, Tab, Text(" bar")]], "|\u{2192}[ bar]", 0),
+            (&[&[Text("foo "), Tab, Break]], "[foo ]\u{2192}|", 0),
+            (&[&[Tab, Text(" foo "), Break]], "\u{2192}[ foo]|", 1),
+        ],
+    );
+}
+
+#[test]
+fn spaces_are_not_collapsed_across_breaks_or_tabs() {
+    check(
+        &Config::builtin(),
+        &[
+            (&[&[Text("foo "), Break, Text(" bar")]], "[foo ]|[ bar]", 0),
+            (&[&[Text("foo "), Tab, Text(" bar")]], "[foo ]\u{2192}[ bar]", 0),
+            (
+                &[&[Text("foo ")], &[Break], &[Text(" bar")]],
+                "[foo ]|[ bar]",
+                0,
+            ),
+            (&[&[Text("foo ")], &[Text(" bar")]], "[foo ][bar]", 0),
+        ],
+    );
+}
+
+#[test]
+fn collapsing_comes_before_trimming() {
+    let (report, text) = clean_package(&trimming(), &[&[Text("foo   bar "), Break]]);
+    assert_eq!(text, "[foo bar]|");
+    assert_eq!(report.collapsed_spaces, 2);
+    assert_eq!(report.break_spaces, 1);
+}
@@ -0,0 +1,45 @@
+// Without a display there is no clipboard to clean, which is what these tests can rely on; a
+// clipboard with text on it would be replaced by running them elsewhere
+#![cfg(all(target_os = "linux", feature = "clipboard"))]
+
+use std::process::Command;
+
+fn clean(args: &[&str], dir: &std::path::Path) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("--clipboard")
+        .args(args)
+        .env_remove("DISPLAY")
+        .env_remove("WAYLAND_DISPLAY")
+        .current_dir(dir)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn without_a_clipboard_nothing_is_read_or_written() {
+    let dir = std::env::temp_dir().join(format!("clipboard-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let output = clean(&["--no-settings"], &dir);
+    let written = std::fs::read_dir(&dir).unwrap().count();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(output.status.code(), Some(3), "{:?}", output);
+    let error = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        error.contains("The clipboard could not be opened"),
+        "{error}"
+    );
+    assert!(output.stdout.is_empty(), "{:?}", output);
+    assert_eq!(written, 0);
+}
+
+#[test]
+fn the_clipboard_is_cleaned_instead_of_files() {
+    let dir = std::env::temp_dir();
+    for args in [&["Report.docx"][..], &["--stdin"], &["--check"], &["--log"]] {
+        let output = clean(args, &dir);
+        assert_eq!(output.status.code(), Some(2), "{:?}: {:?}", args, output);
+        let error = String::from_utf8_lossy(&output.stderr);
+        assert!(error.contains("cannot be used with"), "{error}");
+    }
+}
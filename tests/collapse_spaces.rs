@@ -0,0 +1,71 @@
+use docx_cleaner::{Cleaner, Config};
+use std::process::Command;
+
+mod support;
+
+// The text of the cleaned copy of a document of a single run of `text`, cleaned with the given
+// arguments
+fn clean_text(test: &str, text: &str, args: &[&str]) -> String {
+    let dir = std::env::temp_dir().join(format!("collapse-spaces-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("Report.docx");
+    std::fs::write(&input, support::text_package(text)).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("-q")
+        .args(args)
+        .arg(&input)
+        .output()
+        .unwrap();
+    let cleaned = std::fs::read(dir.join("Report_cleaned.docx"));
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    support::read_part(&cleaned.unwrap(), "word/document.xml")
+}
+
+#[test]
+fn runs_of_spaces_are_collapsed_by_default() {
+    let xml = clean_text("default", "a   b  c\u{200B}", &[]);
+    assert!(xml.contains("<w:t>a b c</w:t>"), "{xml}");
+}
+
+#[test]
+fn no_collapse_spaces_leaves_runs_of_spaces_alone() {
+    let xml = clean_text("off", "a   b  c\u{200B}", &["--no-collapse-spaces"]);
+    assert!(xml.contains("<w:t>a   b  c</w:t>"), "{xml}");
+}
+
+#[test]
+fn a_double_space_across_runs_loses_one_space_and_keeps_the_formatting() {
+    let body = "<w:p><w:r><w:rPr><w:b/></w:rPr><w:t xml:space=\"preserve\">bold </w:t></w:r>\
+                <w:r><w:rPr><w:i/></w:rPr><w:t xml:space=\"preserve\"> italic</w:t></w:r></w:p>";
+    let input = support::word_package(body);
+    let cleaned = Cleaner::new(Config::builtin())
+        .clean_package(&input)
+        .unwrap();
+    assert_eq!(cleaned.report.collapsed_spaces, 1);
+    let xml = support::read_part(&cleaned.into_bytes().unwrap(), "word/document.xml");
+    assert!(
+        xml.contains(
+            "<w:r><w:rPr><w:b/></w:rPr><w:t xml:space=\"preserve\">bold </w:t></w:r>\
+             <w:r><w:rPr><w:i/></w:rPr><w:t xml:space=\"preserve\">italic</w:t></w:r>"
+        ),
+        "{xml}"
+    );
+}
+
+#[test]
+fn code_and_preformatted_paragraphs_keep_their_spaces() {
+    // The style ids of "Code" and "HTML Preformatted", and a paragraph of the default style
+    let body = "<w:p><w:pPr><w:pStyle w:val=\"Code\"/></w:pPr><w:r><w:t>if  (x)   y</w:t></w:r></w:p>\
+                <w:p><w:pPr><w:pStyle w:val=\"HTMLPreformatted\"/></w:pPr><w:r><w:t>a    b</w:t></w:r></w:p>\
+                <w:p><w:r><w:t>c  d</w:t></w:r></w:p>";
+    let input = support::word_package(body);
+    let cleaned = Cleaner::new(Config::builtin())
+        .clean_package(&input)
+        .unwrap();
+    assert_eq!(cleaned.report.collapsed_spaces, 1);
+    let xml = support::read_part(&cleaned.into_bytes().unwrap(), "word/document.xml");
+    assert!(xml.contains("<w:t>if  (x)   y</w:t>"), "{xml}");
+    assert!(xml.contains("<w:t>a    b</w:t>"), "{xml}");
+    assert!(xml.contains("<w:t>c d</w:t>"), "{xml}");
+}
@@ -0,0 +1,124 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+mod support;
+
+// A document with a paragraph for each text
+fn package(paragraphs: &[&str]) -> Vec<u8> {
+    let body: String = paragraphs
+        .iter()
+        .map(|text| format!("<w:p><w:r><w:t>{}</w:t></w:r></w:p>", text))
+        .collect();
+    support::word_package(&body)
+}
+
+// A folder of its own for each test, since the tests run in parallel, with the flagged version
+// of a document: two zero width spaces in the first paragraph and a word joiner in the second
+fn folder(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("compare-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("flagged.docx"),
+        package(&["in&#x200B;vi&#x200B;sible", "word&#x2060;joiner"]),
+    )
+    .unwrap();
+    dir
+}
+
+fn compare(dir: &Path, resubmitted: &[&str]) -> Output {
+    std::fs::write(dir.join("resubmitted.docx"), package(resubmitted)).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .args(["compare", "flagged.docx", "resubmitted.docx"])
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(dir).unwrap();
+    output
+}
+
+#[test]
+fn a_partial_fix_is_counted_per_character_and_its_leftovers_listed() {
+    let dir = folder("partial");
+    let output = compare(&dir, &["in&#x200B;visible", "word joiner"]);
+    let comparison = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert!(
+        comparison.starts_with("A: flagged.docx\nB: resubmitted.docx\n"),
+        "{comparison}"
+    );
+    // A, B and the change of each character, the total and the part
+    let counts: Vec<Vec<&str>> = comparison
+        .lines()
+        .filter(|line| {
+            ["U+", "Total", "word/"]
+                .iter()
+                .any(|row| line.starts_with(row))
+        })
+        .map(|line| {
+            let cells: Vec<&str> = line.split_whitespace().collect();
+            cells[cells.len() - 3..].to_vec()
+        })
+        .collect();
+    assert_eq!(
+        counts,
+        [
+            ["2", "1", "-1"],
+            ["1", "0", "-1"],
+            ["3", "1", "-2"],
+            ["3", "1", "-2"]
+        ],
+        "{comparison}"
+    );
+    // The word joiner and one of the two zero width spaces
+    let only_a = comparison.split("Only in A (2):\n").nth(1).unwrap();
+    assert!(only_a.contains("paragraph 2"), "{comparison}");
+    assert!(comparison.ends_with("Only in B: none\n"), "{comparison}");
+}
+
+#[test]
+fn a_full_fix_exits_with_0() {
+    let dir = folder("fixed");
+    let output = compare(&dir, &["invisible", "word joiner"]);
+    let comparison = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+    assert!(comparison.contains("Only in A (3):\n"), "{comparison}");
+}
+
+#[test]
+fn characters_added_in_b_are_listed_as_only_in_b() {
+    let dir = folder("added");
+    let output = compare(
+        &dir,
+        &[
+            "in&#x200B;vi&#x200B;sible",
+            "word&#x2060;joiner",
+            "new&#xFEFF;",
+        ],
+    );
+    let comparison = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert!(comparison.contains("Only in A: none\n"), "{comparison}");
+    assert!(comparison.contains("Only in B (1):\n"), "{comparison}");
+    assert!(comparison.contains("(U+FEFF)"), "{comparison}");
+}
+
+#[test]
+fn a_missing_document_is_named() {
+    let dir = folder("missing");
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .args(["compare", "flagged.docx", "resubmitted.docx"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(output.status.code(), Some(3), "{:?}", output);
+    let errors = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        errors.contains("resubmitted.docx: The file does not exist"),
+        "{errors}"
+    );
+}
@@ -0,0 +1,135 @@
+use docx_cleaner::Config;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn problem(json: &str) -> String {
+    Config::from_json(json).err().unwrap()
+}
+
+// Lists the characters with a user config holding the given JSON
+fn list_chars(test: &str, json: &str) -> Output {
+    let path: PathBuf = std::env::temp_dir().join(format!(
+        "config-validation-{}-{}.json",
+        test,
+        std::process::id()
+    ));
+    std::fs::write(&path, json).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("list-chars")
+        .arg("--config")
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    output
+}
+
+#[test]
+fn keys_that_are_not_one_character_or_a_range_are_rejected() {
+    for (key, error) in [
+        (
+            "ab",
+            "key \"ab\" must be a single character, U+XXXX or U+XXXX..U+YYYY",
+        ),
+        ("U+ZZZZ", "key \"U+ZZZZ\" must be a single character"),
+        ("U+110000", "key \"U+110000\" is beyond U+10FFFF"),
+        ("U+D800", "key \"U+D800\" includes surrogate code points"),
+        (
+            "U+2010..U+2000",
+            "range \"U+2010..U+2000\" ends before it starts",
+        ),
+    ] {
+        let message = problem(&format!(r#"{{"{}": ["NAME", ""]}}"#, key));
+        assert!(message.contains(error), "{message}");
+    }
+}
+
+#[test]
+fn a_problem_names_the_key_as_it_is_written() {
+    let message = problem(r#"{"U+0041": "keep"}"#);
+    assert_eq!(
+        message,
+        "Invalid config: key \"U+0041\" is set to \"keep\" but is not in the built-in list"
+    );
+    let message = problem(r#"{"U+200B": "remove"}"#);
+    assert!(
+        message.contains("entry \"U+200B\" must be a list of strings"),
+        "{message}"
+    );
+}
+
+#[test]
+fn every_problem_is_reported_at_once() {
+    let message =
+        problem(r#"{"ab": ["NAME", ""], "nbsp": "sometimes", "U+200B": 1, "U+0041": "report"}"#);
+    assert!(
+        message.starts_with("Invalid config has 4 problems:\n"),
+        "{message}"
+    );
+    for error in [
+        "key \"ab\" must be a single character",
+        "setting \"nbsp\" must be",
+        "entry \"U+200B\" must be a list of strings",
+        "key \"U+0041\" is flagged but is not in the built-in list",
+    ] {
+        assert!(message.contains(&format!("\n  {}", error)), "{message}");
+    }
+}
+
+#[test]
+fn a_replacement_may_not_hold_a_removed_character() {
+    let message = problem(r#"{"U+200B": ["ZERO WIDTH SPACE", "\u200C"]}"#);
+    assert!(
+        message.contains(
+            "the replacement \"\\u{200c}\" of key \"U+200B\" contains U+200C ZERO WIDTH \
+             NON-JOINER, which is removed itself"
+        ),
+        "{message}"
+    );
+    // A built-in replacement the user config removes
+    let message = problem(r#"{"-": ["HYPHEN-MINUS", ""]}"#);
+    assert!(
+        message.contains("key \"-\" removes U+002D, which the replacement of U+2013 EN DASH"),
+        "{message}"
+    );
+    // Characters that are only reported stay in the text anyway
+    assert!(
+        Config::from_json(r#"{"U+200B": ["ZERO WIDTH SPACE", "\u200C"], "U+200C": "report"}"#)
+            .is_ok()
+    );
+}
+
+#[test]
+fn a_duplicate_key_only_warns_and_the_last_one_wins() {
+    let config = Config::from_json(
+        r#"{"U+200B": ["FIRST", "x"], "nbsp": "keep", "U+200B": ["SECOND", "y"], "nbsp": "replace"}"#,
+    )
+    .unwrap();
+    assert_eq!(config.name('\u{200B}'), "SECOND");
+    assert_eq!(config.replacement('\u{200B}'), "y");
+
+    let output = list_chars(
+        "duplicate",
+        r#"{"U+200B": ["FIRST", "x"], "U+200B": ["SECOND", "y"]}"#,
+    );
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+    let warnings = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        warnings.contains("key \"U+200B\" appears more than once"),
+        "{warnings}"
+    );
+}
+
+#[test]
+fn the_command_line_prints_every_problem_of_the_config_file() {
+    let output = list_chars(
+        "problems",
+        r#"{"ab": ["NAME", ""], "collapse_spaces": "yes"}"#,
+    );
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+    let error = String::from_utf8_lossy(&output.stderr);
+    assert!(error.contains("config-validation-problems"), "{error}");
+    assert!(error.contains("has 2 problems"), "{error}");
+    assert!(error.contains("key \"ab\""), "{error}");
+    assert!(error.contains("setting \"collapse_spaces\""), "{error}");
+}
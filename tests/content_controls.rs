@@ -0,0 +1,42 @@
+use docx_cleaner::{Cleaner, Config};
+
+mod support;
+
+const PROPERTIES: &str = "<w:sdtPr><w:alias w:val=\"Client\"/><w:tag w:val=\"client\"/>\
+<w:id w:val=\"42\"/><w:placeholder><w:docPart w:val=\"DefaultPlaceholder\"/></w:placeholder></w:sdtPr>";
+
+// Fill-in fields of a template: a block-level content control, an inline one and one in a table
+// cell, each with a zero width space
+fn body() -> String {
+    format!(
+        "<w:sdt>{p}<w:sdtContent><w:p><w:r><w:t>Acme\u{200B} Ltd</w:t></w:r></w:p></w:sdtContent></w:sdt>\
+         <w:p><w:r><w:t xml:space=\"preserve\">Dear </w:t></w:r><w:sdt>{p}<w:sdtContent>\
+         <w:r><w:t>Ms\u{200B} Smith</w:t></w:r></w:sdtContent></w:sdt></w:p>\
+         <w:tbl><w:tr><w:tc><w:sdt>{p}<w:sdtContent><w:p><w:r><w:t>4\u{200B}2</w:t></w:r></w:p>\
+         </w:sdtContent></w:sdt></w:tc></w:tr></w:tbl>",
+        p = PROPERTIES
+    )
+}
+
+#[test]
+fn text_in_content_controls_is_cleaned_and_counted() {
+    let input = support::word_package(&body());
+    let cleaned = Cleaner::new(Config::builtin())
+        .clean_package(&input)
+        .unwrap();
+    assert_eq!(cleaned.report.part_chars["body"][&'\u{200B}'], 3);
+    let xml = support::read_part(&cleaned.into_bytes().unwrap(), "word/document.xml");
+    assert!(xml.contains("<w:t>Acme Ltd</w:t>"), "{xml}");
+    assert!(xml.contains("<w:t>Ms Smith</w:t>"), "{xml}");
+    assert!(xml.contains("<w:t>42</w:t>"), "{xml}");
+}
+
+#[test]
+fn the_properties_of_content_controls_are_kept() {
+    let input = support::word_package(&body());
+    let cleaned = Cleaner::new(Config::builtin())
+        .clean_package(&input)
+        .unwrap();
+    let xml = support::read_part(&cleaned.into_bytes().unwrap(), "word/document.xml");
+    assert_eq!(xml.matches(PROPERTIES).count(), 3, "{xml}");
+}
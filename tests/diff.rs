@@ -0,0 +1,114 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+mod support;
+
+// A document with a paragraph for each text, split into runs at the |
+fn package(paragraphs: &[&str]) -> Vec<u8> {
+    let body: String = paragraphs
+        .iter()
+        .map(|text| {
+            let runs: String = text
+                .split('|')
+                .map(|run| format!("<w:r><w:t xml:space=\"preserve\">{}</w:t></w:r>", run))
+                .collect();
+            format!("<w:p>{}</w:p>", runs)
+        })
+        .collect();
+    support::word_package(&body)
+}
+
+// A folder of its own for each test, since the tests run in parallel
+fn folder(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("diff-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn only_the_changed_paragraphs_are_diffed_with_the_removed_characters_shown() {
+    let dir = folder("document");
+    std::fs::write(
+        dir.join("thesis.docx"),
+        package(&["Intro", "in&#x200B;vi|sible", "Unchanged", "two  spaces"]),
+    )
+    .unwrap();
+    let output = run(&dir, &["--diff", "--check", "-q", "thesis.docx"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "--- thesis.docx: word/document.xml (before)\n\
+         +++ thesis.docx: word/document.xml (after)\n\
+         @@ -2 +2 @@\n\
+         -in\u{27E8}U+200B\u{27E9}visible\n\
+         +invisible\n\
+         @@ -4 +4 @@\n\
+         -two  spaces\n\
+         +two spaces\n"
+    );
+}
+
+#[test]
+fn a_long_paragraph_is_cut_down_to_its_changes() {
+    let dir = folder("long");
+    let words = "word ".repeat(100);
+    std::fs::write(
+        dir.join("notes.txt"),
+        format!("{}in\u{200B}visible {}\n", words, words),
+    )
+    .unwrap();
+    let output = run(&dir, &["--diff", "--check", "-q", "notes.txt"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let diff = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = diff.lines().collect();
+    assert_eq!(lines.len(), 5, "{diff}");
+    assert!(lines[3].starts_with("-\u{2026}word "), "{diff}");
+    assert!(
+        lines[3].contains("in\u{27E8}U+200B\u{27E9}visible"),
+        "{diff}"
+    );
+    assert!(lines[3].ends_with('\u{2026}'), "{diff}");
+    assert!(lines[4].contains("invisible"), "{diff}");
+    assert!(lines[4].chars().count() < 100, "{diff}");
+}
+
+#[test]
+fn with_a_report_the_diff_goes_into_it() {
+    let dir = folder("report");
+    std::fs::write(dir.join("thesis.docx"), package(&["in&#x200B;visible"])).unwrap();
+    let output = run(
+        &dir,
+        &["--diff", "-q", "--report", "report.txt", "thesis.docx"],
+    );
+    let report = std::fs::read_to_string(dir.join("report.txt")).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(output.stdout.is_empty(), "{:?}", output);
+    assert!(
+        report.contains("\nDiff:\n--- thesis.docx: word/document.xml (before)\n"),
+        "{report}"
+    );
+    assert!(report.ends_with("+invisible\n"), "{report}");
+}
+
+#[test]
+fn nothing_is_printed_for_a_clean_document() {
+    let dir = folder("clean");
+    std::fs::write(dir.join("thesis.docx"), package(&["visible"])).unwrap();
+    let output = run(&dir, &["--diff", "--check", "-q", "thesis.docx"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+    assert!(output.stdout.is_empty(), "{:?}", output);
+}
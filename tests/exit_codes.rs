@@ -0,0 +1,77 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+mod support;
+
+// The exit code of a run over the given files, written to a directory of their own. The binary
+// is run with std::process::Command like in the other tests; assert_cmd would be a dev-dependency
+// for nothing but the exit code.
+fn exit_code(test: &str, files: &[(&str, &[u8])], args: &[&str]) -> Option<i32> {
+    let dir = std::env::temp_dir().join(format!("exit-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for (name, contents) in files {
+        std::fs::write(dir.join(name), contents).unwrap();
+        paths.push(dir.join(name));
+    }
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("-q")
+        .args(args)
+        .args(&paths)
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    output.status.code()
+}
+
+#[test]
+fn nothing_found_is_0() {
+    let clean = support::text_package("clean");
+    assert_eq!(exit_code("clean", &[("a.docx", &clean)], &[]), Some(0));
+    assert_eq!(exit_code("clean-check", &[("a.docx", &clean)], &["--check"]), Some(0));
+}
+
+#[test]
+fn characters_found_or_removed_is_1() {
+    let dirty = support::text_package("zero\u{200B}width");
+    assert_eq!(exit_code("removed", &[("a.docx", &dirty)], &[]), Some(1));
+    assert_eq!(exit_code("found", &[("a.docx", &dirty)], &["--check"]), Some(1));
+}
+
+#[test]
+fn exit_zero_keeps_the_old_behavior() {
+    let dirty = support::text_package("zero\u{200B}width");
+    let files = [("a.docx", &dirty[..])];
+    assert_eq!(exit_code("zero", &files, &["--exit-zero"]), Some(0));
+    assert_eq!(exit_code("zero-check", &files, &["--check", "--exit-zero"]), Some(0));
+}
+
+#[test]
+fn usage_errors_are_2() {
+    let clean = support::text_package("clean");
+    let files = [("a.docx", &clean[..])];
+    assert_eq!(exit_code("usage", &files, &["--no-such-flag"]), Some(2));
+    assert_eq!(exit_code("config", &files, &["--config", "missing.json"]), Some(2));
+}
+
+#[test]
+fn unreadable_input_is_3() {
+    assert_eq!(exit_code("invalid", &[("a.docx", b"not a zip")], &[]), Some(3));
+    assert_eq!(exit_code("empty", &[("a.docx", b"")], &["--check"]), Some(3));
+}
+
+#[test]
+fn unwritable_output_is_4() {
+    let dirty = support::text_package("zero\u{200B}width");
+    let args = ["--output", "missing/a.docx"];
+    assert_eq!(exit_code("output", &[("a.docx", &dirty)], &args), Some(4));
+}
+
+#[test]
+fn a_batch_exits_with_its_most_severe_code() {
+    let dirty = support::text_package("zero\u{200B}width");
+    let files = [("a.docx", &dirty[..]), ("b.docx", b"not a zip")];
+    assert_eq!(exit_code("batch", &files, &["--check"]), Some(3));
+    assert_eq!(exit_code("batch-zero", &files, &["--exit-zero"]), Some(3));
+}
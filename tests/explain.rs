@@ -0,0 +1,100 @@
+use docx_cleaner::{Config, Origin, RuleAction};
+use std::process::{Command, Output};
+
+fn explain(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("explain")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn each_rule_of_a_character_is_kept_with_its_source() {
+    let config = Config::from_json(r#"{"U+2002": "keep", "U+200B": ["ZWSP", "-"]}"#).unwrap();
+    assert_eq!(
+        config.history('\u{2002}'),
+        [
+            (Origin::Builtin, RuleAction::Replace),
+            (Origin::User, RuleAction::Keep)
+        ]
+    );
+    assert_eq!(
+        config.history('\u{200B}'),
+        [
+            (Origin::Builtin, RuleAction::Delete),
+            (Origin::User, RuleAction::Replace)
+        ]
+    );
+    assert!(config.history('a').is_empty());
+}
+
+#[test]
+fn a_listed_character_is_explained_with_an_example() {
+    let output = explain(&["U+2002"]);
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "U+2002 EN SPACE\n\
+         Category:  Zs\n\
+         Action:    replace with \" \"\n\
+         Rules:     replace (built-in list)\n\
+         Example:   \"one\\u{2002}two\" becomes \"one two\"\n"
+    );
+}
+
+#[test]
+fn the_rules_name_the_user_config_that_changed_them() {
+    let path = std::env::temp_dir().join(format!("explain-{}.json", std::process::id()));
+    std::fs::write(&path, r#"{"U+2002": "keep"}"#).unwrap();
+    let output = explain(&[
+        "\u{2002}",
+        "--config",
+        path.to_str().unwrap(),
+        "--categories",
+        "Zs",
+    ]);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+    let explanation = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        explanation.contains("Action:    keep, left alone even with --categories\n"),
+        "{explanation}"
+    );
+    assert!(
+        explanation.contains(&format!(
+            "Rules:     replace (built-in list), then keep (user config '{}')\n",
+            path.display()
+        )),
+        "{explanation}"
+    );
+    assert!(explanation.contains("is left as it is"), "{explanation}");
+}
+
+#[test]
+fn an_untargeted_character_shows_how_to_target_it() {
+    let output = explain(&["U+00E9"]);
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+    let explanation = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        explanation.contains("Action:    none, it is not targeted\n"),
+        "{explanation}"
+    );
+    assert!(!explanation.contains("Rules:"), "{explanation}");
+    assert!(
+        explanation.contains("\"U+00E9\": [\"NAME\", \"\"]"),
+        "{explanation}"
+    );
+    assert!(explanation.contains("--categories Ll"), "{explanation}");
+
+    let output = explain(&["U+12"]);
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+    let output = explain(&["ab"]);
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+    let error = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        error.contains("'ab' is neither a single character nor U+XXXX"),
+        "{error}"
+    );
+}
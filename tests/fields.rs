@@ -0,0 +1,59 @@
+use docx_cleaner::Config;
+
+mod support;
+
+// A REF field with a zero-width space pasted into its bookmark name, and a hyperlink to a
+// bookmark whose quoted name has a no-break space and a double space in it
+const BODY: &str = "<w:p><w:r><w:t xml:space=\"preserve\">See </w:t></w:r>\
+<w:r><w:fldChar w:fldCharType=\"begin\"/></w:r>\
+<w:r><w:instrText xml:space=\"preserve\"> REF _Ref\u{200B}123 \\h </w:instrText></w:r>\
+<w:r><w:fldChar w:fldCharType=\"separate\"/></w:r>\
+<w:r><w:t>Figure\u{200B} 1</w:t></w:r>\
+<w:r><w:fldChar w:fldCharType=\"end\"/></w:r></w:p>\
+<w:p><w:r><w:fldChar w:fldCharType=\"begin\"/></w:r>\
+<w:r><w:instrText xml:space=\"preserve\"> HYPERLINK \\l \"Part\u{A0}One  &amp; Two\" </w:instrText></w:r>\
+<w:r><w:fldChar w:fldCharType=\"separate\"/></w:r>\
+<w:r><w:t>Part One</w:t></w:r>\
+<w:r><w:fldChar w:fldCharType=\"end\"/></w:r></w:p>";
+
+#[test]
+fn zero_width_characters_are_removed_from_field_codes() {
+    let (report, body) = support::clean_body(Config::builtin(), BODY);
+    assert!(
+        body.contains("<w:instrText xml:space=\"preserve\"> REF _Ref123 \\h </w:instrText>"),
+        "{body}"
+    );
+    assert!(body.contains("<w:t>Figure 1</w:t>"), "{body}");
+    assert_eq!(report.parts["field"], 1);
+    assert_eq!(report.parts["body"], 1);
+    assert_eq!(report.chars[&'\u{200B}'], 2);
+}
+
+#[test]
+fn the_rest_of_a_field_is_left_as_it_is() {
+    let (report, body) = support::clean_body(Config::builtin(), BODY);
+    assert!(
+        body.contains(" HYPERLINK \\l \"Part\u{A0}One  &amp; Two\" </w:instrText>"),
+        "{body}"
+    );
+    for char_type in ["begin", "separate", "end"] {
+        let field_char = format!("<w:r><w:fldChar w:fldCharType=\"{}\"/></w:r>", char_type);
+        assert_eq!(body.matches(&field_char).count(), 2, "{body}");
+    }
+    assert_eq!(report.collapsed_spaces, 0);
+}
+
+#[test]
+fn field_codes_are_kept_in_tracked_changes() {
+    let mut config = Config::builtin();
+    config.track_changes = Some(docx_cleaner::Revision::now());
+    let (_, body) = support::clean_body(config, BODY);
+    assert!(
+        body.contains("<w:delInstrText xml:space=\"preserve\"> REF _Ref\u{200B}123 \\h </w:delInstrText>"),
+        "{body}"
+    );
+    assert!(
+        body.contains("<w:instrText xml:space=\"preserve\"> REF _Ref123 \\h </w:instrText>"),
+        "{body}"
+    );
+}
@@ -0,0 +1,127 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+mod support;
+
+// A folder of its own for each test, since the tests run in parallel, with a document to clean
+// under a name with a space and an umlaut, a clean one and a file of another type
+fn repository(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("files-from-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("my thesis ä.docx"),
+        support::text_package("in&#x200B;visible"),
+    )
+    .unwrap();
+    std::fs::write(dir.join("clean.docx"), support::text_package("visible")).unwrap();
+    std::fs::write(dir.join("build.rs"), "fn main() {}\n").unwrap();
+    dir
+}
+
+fn files(dir: &Path) -> Vec<String> {
+    let mut files: Vec<String> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    files.sort();
+    files
+}
+
+fn run(dir: &Path, args: &[&str], list: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(list.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn listed_files_are_checked_with_a_line_for_each_one_to_clean() {
+    let dir = repository("check");
+    let before = files(&dir);
+    let output = run(
+        &dir,
+        &["--files-from", "-"],
+        "my thesis ä.docx\r\nclean.docx\nbuild.rs\n\n",
+    );
+    let after = files(&dir);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "my thesis ä.docx: 1 characters (U+200B ×1) found\n"
+    );
+    assert_eq!(before, after);
+}
+
+#[test]
+fn a_missing_file_is_reported_without_stopping_the_others() {
+    let dir = repository("missing");
+    let output = run(
+        &dir,
+        &["--files-from", "-"],
+        "deleted.docx\nmy thesis ä.docx\n",
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(output.status.code(), Some(3), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("my thesis ä.docx: "));
+    let errors = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        errors.contains("deleted.docx: The file does not exist"),
+        "{errors}"
+    );
+}
+
+#[test]
+fn clean_files_pass_with_the_list_in_a_file_and_the_arguments() {
+    let dir = repository("clean");
+    std::fs::write(dir.join("staged.txt"), "clean.docx\n").unwrap();
+    let output = run(
+        &dir,
+        &["--files-from", "staged.txt", "build.rs", "clean.docx"],
+        "",
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+    assert!(output.stdout.is_empty(), "{:?}", output);
+}
+
+#[test]
+fn fix_cleans_in_place_and_backs_up_only_the_fixed_files() {
+    let dir = repository("fix");
+    let list = "my thesis ä.docx\nclean.docx\n";
+    let fixed = run(&dir, &["--files-from", "-", "--fix"], list);
+    let after = files(&dir);
+    let checked = run(&dir, &["--files-from", "-"], list);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(fixed.status.code(), Some(1), "{:?}", fixed);
+    assert_eq!(
+        String::from_utf8_lossy(&fixed.stdout),
+        "my thesis ä.docx: 1 characters (U+200B ×1) removed\n"
+    );
+    assert_eq!(
+        after,
+        [
+            "build.rs",
+            "clean.docx",
+            "my thesis ä.docx",
+            "my thesis ä.docx.bak"
+        ]
+    );
+    assert_eq!(checked.status.code(), Some(0), "{:?}", checked);
+}
@@ -0,0 +1,65 @@
+use docx_cleaner::{Cleaner, Config};
+
+mod support;
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/><Override PartName="/word/footnotes.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.footnotes+xml"/></Types>"#;
+
+const BODY: &str = "<w:p><w:r><w:t>See the note</w:t></w:r>\
+<w:r><w:footnoteReference w:id=\"1\"/></w:r><w:r><w:footnoteReference w:id=\"2\"/></w:r></w:p>";
+
+// The separator entries Word writes before the notes
+const SEPARATOR: &str = "<w:footnote w:type=\"separator\" w:id=\"-1\">\
+<w:p><w:pPr><w:spacing w:after=\"0\"/></w:pPr><w:r><w:separator/></w:r></w:p></w:footnote>";
+const CONTINUATION_SEPARATOR: &str = "<w:footnote w:type=\"continuationSeparator\" w:id=\"0\">\
+<w:p><w:pPr><w:spacing w:after=\"0\"/></w:pPr><w:r><w:continuationSeparator/></w:r></w:p>\
+</w:footnote>";
+// An empty note, and a citation pasted with a zero width joiner in footnote 2
+const EMPTY_NOTE: &str = "<w:footnote w:id=\"1\"><w:p/></w:footnote>";
+const NOTE: &str = "<w:footnote w:id=\"2\"><w:p><w:r><w:footnoteRef/></w:r>\
+<w:r><w:t xml:space=\"preserve\"> Smith\u{200D}, 2021</w:t></w:r></w:p></w:footnote>";
+
+fn package() -> Vec<u8> {
+    let footnotes = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <w:footnotes xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\">\
+         {}{}{}{}</w:footnotes>",
+        SEPARATOR, CONTINUATION_SEPARATOR, EMPTY_NOTE, NOTE
+    );
+    support::zip_package(&[
+        ("[Content_Types].xml", CONTENT_TYPES.as_bytes()),
+        ("_rels/.rels", support::PACKAGE_RELS.as_bytes()),
+        (
+            "word/document.xml",
+            support::document_xml("", BODY).as_bytes(),
+        ),
+        ("word/footnotes.xml", footnotes.as_bytes()),
+    ])
+}
+
+#[test]
+fn the_joiner_in_footnote_2_is_removed() {
+    let input = package();
+    let cleaned = Cleaner::new(Config::builtin())
+        .clean_package(&input)
+        .unwrap();
+    assert_eq!(cleaned.report.part_chars["footnote"][&'\u{200D}'], 1);
+    assert_eq!(cleaned.report.total_removed(), 1);
+    let footnotes = support::read_part(&cleaned.into_bytes().unwrap(), "word/footnotes.xml");
+    assert!(
+        footnotes.contains("<w:t xml:space=\"preserve\"> Smith, 2021</w:t>"),
+        "{footnotes}"
+    );
+}
+
+#[test]
+fn the_separators_and_empty_notes_survive() {
+    let input = package();
+    let cleaned = Cleaner::new(Config::builtin())
+        .clean_package(&input)
+        .unwrap();
+    let footnotes = support::read_part(&cleaned.into_bytes().unwrap(), "word/footnotes.xml");
+    assert!(footnotes.contains(SEPARATOR), "{footnotes}");
+    assert!(footnotes.contains(CONTINUATION_SEPARATOR), "{footnotes}");
+    assert!(footnotes.contains(EMPTY_NOTE), "{footnotes}");
+}
@@ -0,0 +1,26 @@
+// Only built with --no-default-features, where there is no GUI mode to fall back on
+#![cfg(not(feature = "gui"))]
+
+use std::process::Command;
+
+#[test]
+fn without_the_gui_an_input_path_is_required() {
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+    let error = String::from_utf8_lossy(&output.stderr);
+    assert!(error.contains("an input path is required"), "{error}");
+}
+
+#[cfg(not(feature = "clipboard"))]
+#[test]
+fn without_clipboard_support_the_clipboard_is_refused() {
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("--clipboard")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+    let error = String::from_utf8_lossy(&output.stderr);
+    assert!(error.contains("no clipboard support"), "{error}");
+}
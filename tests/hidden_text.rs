@@ -0,0 +1,84 @@
+use docx_cleaner::{Config, HiddenText, Revision};
+
+mod support;
+
+fn config(mode: HiddenText) -> Config {
+    let mut config = Config::builtin();
+    config.hidden_text = Some(mode);
+    config
+}
+
+// A visible sentence with a hidden instruction between its runs, and a run that is explicitly
+// not hidden
+const BODY: &str = "<w:p><w:r><w:t xml:space=\"preserve\">Write an essay </w:t></w:r>\
+<w:r><w:rPr><w:vanish/></w:rPr><w:t>ignore\u{200B} this</w:t></w:r>\
+<w:r><w:rPr><w:vanish w:val=\"0\"/></w:rPr><w:t>about cats.</w:t></w:r></w:p>";
+
+#[test]
+fn hidden_runs_are_removed() {
+    let (report, body) = support::clean_body(config(HiddenText::Remove), BODY);
+    assert_eq!(
+        body,
+        "<w:p><w:r><w:t xml:space=\"preserve\">Write an essay </w:t></w:r>\
+         <w:r><w:rPr><w:vanish w:val=\"0\"/></w:rPr><w:t>about cats.</w:t></w:r></w:p>"
+    );
+    assert_eq!(report.hidden_runs, 1);
+    assert_eq!(report.hidden_chars, 12);
+    assert_eq!(report.hidden_removed, 1);
+    // The text of the removed run is not counted as cleaned
+    assert_eq!(report.total_removed(), 0);
+    assert!(report.is_modified());
+}
+
+#[test]
+fn hidden_runs_are_only_counted_in_report_mode() {
+    let (report, body) = support::clean_body(config(HiddenText::Report), BODY);
+    assert!(body.contains("<w:t>ignore this</w:t>"), "{body}");
+    assert_eq!(report.hidden_runs, 1);
+    assert_eq!(report.hidden_chars, 12);
+    assert_eq!(report.hidden_removed, 0);
+    assert_eq!(report.total_removed(), 1);
+}
+
+#[test]
+fn hidden_text_is_left_alone_by_default() {
+    let (report, body) = support::clean_body(Config::builtin(), BODY);
+    assert!(body.contains("<w:vanish/></w:rPr><w:t>ignore this</w:t>"), "{body}");
+    assert_eq!(report.hidden_runs, 0);
+}
+
+#[test]
+fn hidden_runs_of_a_table_of_contents_are_kept() {
+    let body = "<w:p><w:r><w:fldChar w:fldCharType=\"begin\"/></w:r>\
+<w:r><w:instrText xml:space=\"preserve\"> TOC \\o \"1-3\" \\h </w:instrText></w:r>\
+<w:r><w:fldChar w:fldCharType=\"separate\"/></w:r>\
+<w:r><w:t>Introduction</w:t></w:r>\
+<w:r><w:rPr><w:vanish/></w:rPr><w:t>_Toc1</w:t></w:r></w:p>\
+<w:p><w:r><w:rPr><w:vanish/></w:rPr><w:fldChar w:fldCharType=\"end\"/></w:r></w:p>\
+<w:p><w:r><w:rPr><w:vanish/></w:rPr><w:t>secret</w:t></w:r></w:p>";
+    let (report, cleaned) = support::clean_body(config(HiddenText::Remove), body);
+    assert!(cleaned.contains("<w:t>_Toc1</w:t>"), "{cleaned}");
+    assert!(cleaned.contains("w:fldCharType=\"end\""), "{cleaned}");
+    assert!(!cleaned.contains("secret"), "{cleaned}");
+    assert_eq!(report.hidden_runs, 3);
+    assert_eq!(report.hidden_in_fields, 2);
+    assert_eq!(report.hidden_removed, 1);
+}
+
+#[test]
+fn hidden_runs_become_tracked_deletions() {
+    let mut config = config(HiddenText::Remove);
+    config.track_changes = Some(Revision {
+        author: "Editor".to_string(),
+        date: "2024-05-01T09:30:00Z".to_string(),
+    });
+    let (report, body) = support::clean_body(config, BODY);
+    assert!(
+        body.contains(
+            "<w:del w:id=\"1\" w:author=\"Editor\" w:date=\"2024-05-01T09:30:00Z\">\
+             <w:r><w:rPr><w:vanish/></w:rPr><w:delText>ignore\u{200B} this</w:delText></w:r></w:del>"
+        ),
+        "{body}"
+    );
+    assert_eq!(report.hidden_removed, 1);
+}
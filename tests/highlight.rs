@@ -0,0 +1,50 @@
+use docx_cleaner::Config;
+
+mod support;
+
+// The body of the cleaned document
+fn highlight(body: &str) -> String {
+    let mut config = Config::builtin();
+    config.highlight = Some("yellow".to_string());
+    support::clean_body(config, body).1
+}
+
+#[test]
+fn a_character_in_the_middle_splits_the_run_in_three() {
+    let body = highlight(
+        "<w:p><w:r><w:rPr><w:b/></w:rPr><w:t>foo\u{200B}bar</w:t></w:r></w:p>",
+    );
+    assert_eq!(
+        body,
+        "<w:p>\
+         <w:r><w:rPr><w:b/></w:rPr><w:t xml:space=\"preserve\">fo</w:t></w:r>\
+         <w:r><w:rPr><w:b/><w:highlight w:val=\"yellow\"/></w:rPr><w:t xml:space=\"preserve\">ob</w:t></w:r>\
+         <w:r><w:rPr><w:b/></w:rPr><w:t xml:space=\"preserve\">ar</w:t></w:r>\
+         </w:p>"
+    );
+}
+
+#[test]
+fn untouched_runs_are_not_highlighted() {
+    let untouched = "<w:r><w:rPr><w:i/></w:rPr><w:t xml:space=\"preserve\">plain </w:t></w:r>";
+    let body = highlight(&format!(
+        "<w:p>{}<w:r><w:t>ab\u{200B}</w:t></w:r></w:p><w:p>{}</w:p>",
+        untouched, untouched
+    ));
+    assert_eq!(body.matches("<w:highlight").count(), 1);
+    assert_eq!(body.matches(untouched).count(), 2);
+}
+
+#[test]
+fn the_highlight_goes_before_later_properties() {
+    let body = highlight(
+        "<w:p><w:r><w:rPr><w:highlight w:val=\"green\"/><w:u w:val=\"single\"/></w:rPr><w:t>x\u{200B}</w:t><w:tab/><w:t>y</w:t></w:r></w:p>",
+    );
+    assert_eq!(
+        body,
+        "<w:p>\
+         <w:r><w:rPr><w:highlight w:val=\"yellow\"/><w:u w:val=\"single\"/></w:rPr><w:t xml:space=\"preserve\">x</w:t></w:r>\
+         <w:r><w:rPr><w:highlight w:val=\"green\"/><w:u w:val=\"single\"/></w:rPr><w:t xml:space=\"preserve\"></w:t><w:tab/><w:t>y</w:t></w:r>\
+         </w:p>"
+    );
+}
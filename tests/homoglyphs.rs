@@ -0,0 +1,53 @@
+use docx_cleaner::{Config, Homoglyphs};
+
+mod support;
+
+// A Latin sentence with a Cyrillic "е" and "а" and a Greek "Ο", and a Russian one whose last word
+// only has letters that look Latin
+const BODY: &str = "<w:p><w:r><w:t>Th\u{435} c\u{430}t \u{39F}K</w:t></w:r></w:p>\
+<w:p><w:r><w:t>\u{41F}\u{440}\u{438}\u{432}\u{435}\u{442}, \u{441}\u{43E}\u{440}</w:t></w:r></w:p>";
+
+#[test]
+fn lookalikes_in_latin_words_become_latin() {
+    let mut config = Config::builtin();
+    config.homoglyphs = Some(Homoglyphs::builtin());
+    let (report, body) = support::clean_body(config, BODY);
+    assert!(body.contains("<w:t>The cat OK</w:t>"), "{body}");
+    assert!(body.contains("\u{41F}\u{440}\u{438}\u{432}\u{435}\u{442}, \u{441}\u{43E}\u{440}"));
+    assert_eq!(report.homoglyphs.len(), 3);
+    assert_eq!(report.homoglyphs[&'\u{430}'], 1);
+    assert_eq!(report.total_removed(), 0);
+    assert!(report.is_modified());
+}
+
+#[test]
+fn lookalikes_are_kept_by_default() {
+    let (report, body) = support::clean_body(Config::builtin(), BODY);
+    assert!(body.contains("Th\u{435} c\u{430}t \u{39F}K"));
+    assert!(report.homoglyphs.is_empty());
+    assert!(!report.is_modified());
+}
+
+#[test]
+fn the_user_table_extends_the_builtin_one() {
+    let path = std::env::temp_dir().join(format!("homoglyphs-{}.json", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"{"օ": ["ARMENIAN SMALL LETTER OH", "o"], "U+0430": "keep"}"#,
+    )
+    .unwrap();
+    let homoglyphs = Homoglyphs::load(Some(&path));
+    std::fs::remove_file(&path).unwrap();
+    let homoglyphs = homoglyphs.unwrap();
+    assert_eq!(homoglyphs.latin('\u{585}'), Some('o'));
+    assert_eq!(homoglyphs.latin('\u{430}'), None);
+
+    let mut config = Config::builtin();
+    config.homoglyphs = Some(homoglyphs);
+    let (report, body) = support::clean_body(
+        config,
+        "<w:p><w:r><w:t>g\u{585}\u{585}d c\u{430}t</w:t></w:r></w:p>",
+    );
+    assert!(body.contains("<w:t>good c\u{430}t</w:t>"), "{body}");
+    assert_eq!(report.homoglyphs[&'\u{585}'], 2);
+}
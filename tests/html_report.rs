@@ -0,0 +1,35 @@
+use std::process::Command;
+
+mod support;
+
+// Markup characters in the text, two characters close together and a Hebrew paragraph
+const DOCUMENT: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\"><w:body>\
+<w:p><w:r><w:t>if a &lt; b &amp;&amp; c\u{200B}d \"quoted\"</w:t></w:r></w:p>\
+<w:p><w:r><w:t>zero\u{200B}\u{2060}width</w:t></w:r></w:p>\
+<w:p><w:r><w:t>\u{5E9}\u{5DC}\u{5D5}\u{200D}\u{5DD}</w:t></w:r></w:p>\
+</w:body></w:document>";
+
+#[test]
+fn the_html_report_matches_the_golden_file() {
+    let dir = std::env::temp_dir().join(format!("html-report-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("Thesis <draft>.docx");
+    let report = dir.join("report.html");
+    std::fs::write(&input, support::document_package(DOCUMENT)).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .args(["-q", "--check", "--report"])
+        .arg(&report)
+        .arg(&input)
+        .output()
+        .unwrap();
+    let html = std::fs::read_to_string(&report).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+
+    // The parts that change from run to run
+    let html = html
+        .replace(&dir.display().to_string(), "DIR")
+        .replace(env!("CARGO_PKG_VERSION"), "VERSION");
+    assert_eq!(html, include_str!("golden/report.html"));
+}
@@ -0,0 +1,52 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+mod support;
+
+// A directory of its own for each test, since the tests run in parallel, with a document to
+// clean
+fn directory(test: &str) -> (PathBuf, PathBuf, Vec<u8>) {
+    let dir = std::env::temp_dir().join(format!("in-place-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("Report.docx");
+    let original = support::text_package("zero\u{200B}width");
+    std::fs::write(&input, &original).unwrap();
+    (dir, input, original)
+}
+
+fn clean(input: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("-q")
+        .arg("--in-place")
+        .args(args)
+        .arg(input)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn the_backup_is_byte_identical_to_the_original() {
+    let (dir, input, original) = directory("backup");
+    let output = clean(&input, &[]);
+    let backup = std::fs::read(dir.join("Report.docx.bak")).unwrap();
+    let cleaned = std::fs::read(&input).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert!(backup == original);
+    assert!(cleaned != original);
+    let xml = support::read_part(&cleaned, "word/document.xml");
+    assert!(xml.contains("<w:t>zerowidth</w:t>"), "{xml}");
+}
+
+#[test]
+fn the_backup_in_backup_dir_is_byte_identical_to_the_original() {
+    let (dir, input, original) = directory("backup-dir");
+    let backups = dir.join("backups");
+    let output = clean(&input, &["--backup-dir", backups.to_str().unwrap()]);
+    let backup = std::fs::read(backups.join("Report.docx.bak")).unwrap();
+    let next_to_input = dir.join("Report.docx.bak").exists();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert!(backup == original);
+    assert!(!next_to_input);
+}
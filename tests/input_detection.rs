@@ -0,0 +1,79 @@
+use docx_cleaner::{Cleaner, Config, Error};
+use std::process::Command;
+
+mod support;
+
+const DOCUMENT: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\"><w:body>\
+<w:p><w:r><w:t>zero\u{200B}width</w:t></w:r></w:p>\
+</w:body></w:document>";
+
+// An OLE compound file: the signature, the rest of the 512 byte header and a directory sector
+// with an entry for each stream
+fn compound_file(streams: &[&str]) -> Vec<u8> {
+    let mut file = vec![0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+    file.resize(512, 0);
+    for stream in ["Root Entry"].iter().chain(streams) {
+        let mut entry: Vec<u8> = format!("{stream}\0")
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let length = entry.len() as u16;
+        entry.resize(64, 0);
+        entry.extend(length.to_le_bytes());
+        entry.resize(128, 0);
+        file.extend(entry);
+    }
+    file
+}
+
+fn clean(input: &[u8]) -> Result<(), Error> {
+    Cleaner::new(Config::builtin())
+        .clean_package(input)
+        .map(|_| ())
+}
+
+#[test]
+fn an_empty_file_is_reported_as_empty() {
+    assert!(matches!(clean(&[]), Err(Error::Empty)));
+}
+
+#[test]
+fn a_cut_off_package_is_reported_as_incomplete() {
+    let package = support::document_package(DOCUMENT);
+    let truncated = &package[..package.len() / 2];
+    assert!(matches!(clean(truncated), Err(Error::Truncated)));
+}
+
+#[test]
+fn a_renamed_doc_file_is_reported_as_legacy() {
+    let doc = compound_file(&["WordDocument", "1Table", "\u{5}SummaryInformation"]);
+    assert!(matches!(clean(&doc), Err(Error::LegacyFormat)));
+}
+
+#[test]
+fn an_encrypted_document_is_reported_as_password_protected() {
+    let encrypted = compound_file(&["EncryptionInfo", "EncryptedPackage"]);
+    assert!(matches!(clean(&encrypted), Err(Error::Encrypted)));
+}
+
+#[test]
+fn text_is_not_mistaken_for_a_package() {
+    let result = clean(b"Just some text saved with the wrong extension");
+    assert!(matches!(result, Err(Error::InvalidDocx(_))), "{result:?}");
+}
+
+#[test]
+fn the_command_line_explains_what_to_do() {
+    let path = std::env::temp_dir().join(format!("legacy-{}.docx", std::process::id()));
+    std::fs::write(&path, compound_file(&["WordDocument"])).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .args(["-q", "--check"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let error = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(3), "{error}");
+    assert!(error.contains("save it as .docx first"), "{error}");
+}
@@ -0,0 +1,100 @@
+use docx_cleaner::{Cleaner, Config};
+use std::io::{Cursor, Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use zip::ZipArchive;
+
+mod support;
+
+// Two zero-width spaces and a no-break space
+const DOCUMENT: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\"><w:body>\
+<w:p><w:r><w:t>zero\u{200B}width\u{200B} and 10\u{A0}km</w:t></w:r></w:p>\
+</w:body></w:document>";
+
+fn document_xml(package: Vec<u8>) -> String {
+    let mut archive = ZipArchive::new(Cursor::new(package)).unwrap();
+    let mut xml = String::new();
+    archive
+        .by_name("word/document.xml")
+        .unwrap()
+        .read_to_string(&mut xml)
+        .unwrap();
+    xml
+}
+
+// A directory of its own for each test, since the tests run in parallel
+fn directory(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("docx-cleaner-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn declined_characters_stay_in_the_text() {
+    let mut config = Config::builtin();
+    config.decline('\u{A0}');
+    let input = support::document_package(DOCUMENT);
+    let cleaned = Cleaner::new(config).clean_package(&input).unwrap();
+    let report = cleaned.report.clone();
+    let xml = document_xml(cleaned.into_bytes().unwrap());
+    assert!(xml.contains("<w:t>zerowidth and 10\u{A0}km</w:t>"), "{xml}");
+    assert_eq!(report.chars[&'\u{200B}'], 2);
+    assert_eq!(report.declined[&'\u{A0}'], 1);
+    assert!(!report.chars.contains_key(&'\u{A0}'));
+    assert!(report.kept.is_empty());
+}
+
+fn clean_interactively(test: &str, answers: &str) -> (std::process::Output, Option<String>) {
+    let dir = directory(test);
+    let input = dir.join("Report.docx");
+    std::fs::write(&input, support::document_package(DOCUMENT)).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("--interactive")
+        .arg(&input)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(answers.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let copy = std::fs::read(dir.join("Report_cleaned.docx")).ok();
+    std::fs::remove_dir_all(&dir).unwrap();
+    (output, copy.map(document_xml))
+}
+
+#[test]
+fn each_character_is_asked_about_from_the_most_frequent() {
+    let (output, xml) = clean_interactively("interactive", "show\n\nn\n");
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    let questions = String::from_utf8_lossy(&output.stderr);
+    let first = questions
+        .find("Remove 2 \u{d7} U+200B ZERO WIDTH SPACE? [Y/n/show]")
+        .expect(&questions);
+    let second = questions
+        .find("Remove 1 \u{d7} U+00A0 NO-BREAK SPACE? [Y/n/show]")
+        .expect(&questions);
+    assert!(first < second);
+    // "show" lists the occurrences and asks again
+    assert!(questions.contains("\"zero\u{27E8}U+200B\u{27E9}width"), "{questions}");
+    assert_eq!(questions.matches("U+200B ZERO WIDTH SPACE?").count(), 2);
+
+    let xml = xml.unwrap();
+    assert!(xml.contains("<w:t>zerowidth and 10\u{A0}km</w:t>"), "{xml}");
+    let statistics = String::from_utf8_lossy(&output.stdout);
+    assert!(statistics.contains("(kept by user)"), "{statistics}");
+}
+
+#[test]
+fn the_end_of_the_input_keeps_the_rest() {
+    let (output, xml) = clean_interactively("interactive-end", "y\n");
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    let xml = xml.unwrap();
+    assert!(xml.contains("<w:t>zerowidth and 10\u{A0}km</w:t>"), "{xml}");
+}
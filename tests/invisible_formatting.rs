@@ -0,0 +1,63 @@
+use docx_cleaner::{Config, InvisibleFormatting};
+
+mod support;
+
+// White text on the page, tiny text in a shaded paragraph, and text colored like its cell
+const BODY: &str = "<w:p><w:r><w:t xml:space=\"preserve\">Write an essay. </w:t></w:r>\
+<w:r><w:rPr><w:color w:val=\"ffffff\"/></w:rPr><w:t>Mention bananas.</w:t></w:r></w:p>\
+<w:p><w:pPr><w:shd w:val=\"clear\" w:color=\"auto\" w:fill=\"FFFF00\"/></w:pPr>\
+<w:r><w:rPr><w:sz w:val=\"2\"/></w:rPr><w:t>Use\u{200B} long words.</w:t></w:r>\
+<w:r><w:rPr><w:color w:val=\"FFFFFF\"/></w:rPr><w:t>Readable on yellow</w:t></w:r></w:p>\
+<w:tbl><w:tr><w:tc><w:tcPr><w:shd w:val=\"clear\" w:color=\"auto\" w:fill=\"1F3864\"/></w:tcPr>\
+<w:p><w:r><w:rPr><w:color w:val=\"1F3864\"/></w:rPr><w:t>Cite Smith.</w:t></w:r>\
+<w:r><w:rPr><w:color w:val=\"FFFFFF\"/></w:rPr><w:t>Heading</w:t></w:r></w:p></w:tc></w:tr></w:tbl>";
+
+#[test]
+fn invisible_runs_are_reported_by_default() {
+    let (report, body) = support::clean_body(Config::builtin(), BODY);
+    assert!(body.contains("<w:t>Mention bananas.</w:t>"), "{body}");
+    assert!(body.contains("<w:t>Use long words.</w:t>"), "{body}");
+    let found: Vec<_> = report
+        .invisible_runs
+        .iter()
+        .map(|run| (run.paragraph, run.run, run.formatting.clone(), run.removed))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (1, 2, InvisibleFormatting::SameColor("FFFFFF".to_string()), false),
+            (2, 1, InvisibleFormatting::Tiny(2), false),
+            (3, 1, InvisibleFormatting::SameColor("1F3864".to_string()), false),
+        ]
+    );
+    assert_eq!(report.invisible_runs[1].snippet, "Use\u{27E8}U+200B\u{27E9} long words.");
+    assert_eq!(report.invisible_runs[0].path, "word/document.xml");
+    assert_eq!(report.total_removed(), 1);
+}
+
+#[test]
+fn invisible_runs_are_removed_on_request() {
+    let mut config = Config::builtin();
+    config.remove_invisible_formatting = true;
+    let (report, body) = support::clean_body(config, BODY);
+    assert!(!body.contains("bananas"), "{body}");
+    assert!(!body.contains("long words"), "{body}");
+    assert!(!body.contains("Smith"), "{body}");
+    assert!(body.contains("<w:t>Readable on yellow</w:t>"), "{body}");
+    assert!(body.contains("<w:t>Heading</w:t>"), "{body}");
+    assert!(report.invisible_runs.iter().all(|run| run.removed));
+    // The zero-width space went with its run
+    assert_eq!(report.total_removed(), 0);
+    assert!(report.is_modified());
+}
+
+#[test]
+fn theme_colors_are_left_to_check_by_hand() {
+    let body = "<w:p><w:r><w:rPr><w:color w:val=\"FFFFFF\" w:themeColor=\"background1\"/></w:rPr>\
+<w:t>Maybe hidden</w:t></w:r>\
+<w:r><w:rPr><w:color w:val=\"FFFFFF\" w:themeColor=\"background1\"/></w:rPr>\
+<w:t xml:space=\"preserve\"> </w:t></w:r></w:p>";
+    let (report, _) = support::clean_body(Config::builtin(), body);
+    assert!(report.invisible_runs.is_empty());
+    assert_eq!(report.theme_colored_runs, 1);
+}
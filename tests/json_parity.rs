@@ -0,0 +1,70 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::process::Command;
+
+mod support;
+
+// Zero width spaces, a word joiner, a no-break space, a double space and a left-to-right mark
+const TEXT: &str = "a\u{200B}b\u{200B}c\u{2060}d\u{00A0}e  f\u{200E}";
+
+// The standard output of a --check run with the given arguments
+fn check(test: &str, args: &[&str]) -> String {
+    let path = std::env::temp_dir().join(format!("parity-{}-{}.docx", test, std::process::id()));
+    std::fs::write(&path, support::text_package(TEXT)).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("--check")
+        .args(args)
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+// The number after the last ": " of the line starting with `label`
+fn count(console: &str, label: &str) -> u64 {
+    let line = console
+        .lines()
+        .find(|line| line.starts_with(label))
+        .unwrap();
+    let value = line.rsplit(": ").next().unwrap();
+    value.split(' ').next().unwrap().parse().unwrap()
+}
+
+#[test]
+fn the_console_and_json_counts_match() {
+    let console = check("console", &[]);
+    let json: Value = serde_json::from_str(&check("json", &["--json"])).unwrap();
+
+    // "  ZERO WIDTH SPACE (U+200B) - ...: 2" for each character of the statistics
+    let mut printed = BTreeMap::new();
+    for line in console.lines() {
+        if let (Some(start), Some(end)) = (line.find("(U+"), line.find(") - ")) {
+            let count: u64 = line.rsplit(": ").next().unwrap().parse().unwrap();
+            printed.insert(line[start + 1..end].to_string(), count);
+        }
+    }
+    let listed: BTreeMap<String, u64> = json["characters"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| {
+            let codepoint = c["codepoint"].as_str().unwrap().to_string();
+            (codepoint, c["count"].as_u64().unwrap())
+        })
+        .collect();
+    assert_eq!(printed, listed, "{console}");
+    assert_eq!(printed.len(), 3);
+
+    let total: u64 = listed.values().sum();
+    assert_eq!(count(&console, "Total characters found"), total);
+    assert_eq!(
+        count(&console, "No-break spaces found"),
+        json["nbsp_replaced"]
+    );
+    assert_eq!(
+        count(&console, "Multiple spaces collapsed"),
+        json["spaces_collapsed"]
+    );
+}
@@ -0,0 +1,55 @@
+use docx_cleaner::Config;
+use std::process::Command;
+
+const COMMENTED: &str = r#"{
+    // Pasted from the style guide of https://example.com/guide
+    "U+200B": ["ZERO WIDTH SPACE // not a comment", "/* nor this */"],
+    /* Curly quotes stay, the
+       translators rely on them */
+    "U+201C": "keep",
+    "U+201D": "keep", // trailing comma below
+    "nbsp_patterns": ["\\d \\d",],
+}"#;
+
+#[test]
+fn comments_and_trailing_commas_are_accepted() {
+    let config = Config::from_json(COMMENTED).unwrap();
+    assert_eq!(config.name('\u{200B}'), "ZERO WIDTH SPACE // not a comment");
+    assert_eq!(config.replacement('\u{200B}'), "/* nor this */");
+    assert!(!config.listed().contains(&'\u{201C}'));
+    assert!(!config.listed().contains(&'\u{201D}'));
+}
+
+#[test]
+fn errors_point_at_the_file_as_written() {
+    let json = "{\n    /* one\n       two */ // three\n    \"U+200B\": [\"A\" \"\"]\n}";
+    let message = Config::from_json(json).err().unwrap();
+    assert!(message.contains("at line 4 column 20"), "{message}");
+    // A comma alone is not a value
+    let message = Config::from_json("{\n    \"U+200B\": [,],\n}")
+        .err()
+        .unwrap();
+    assert!(message.contains("line 2"), "{message}");
+}
+
+#[test]
+fn the_effective_config_is_strict_json() {
+    let path = std::env::temp_dir().join(format!("jsonc-config-{}.json", std::process::id()));
+    std::fs::write(&path, COMMENTED).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("--print-effective-config")
+        .arg("--config")
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+    let printed = String::from_utf8(output.stdout).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&printed).unwrap();
+    assert_eq!(json["nbsp_patterns"], serde_json::json!(["\\d \\d"]));
+    assert_eq!(
+        json["\u{200B}"],
+        serde_json::json!(["ZERO WIDTH SPACE // not a comment", "/* nor this */"])
+    );
+    assert!(!printed.contains("\n    //"));
+}
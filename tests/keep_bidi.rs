@@ -0,0 +1,44 @@
+use std::process::Command;
+
+mod support;
+
+// Hebrew with a right-to-left mark and an embedding, next to a zero width space
+const TEXT: &str = "\u{202B}\u{05E9}\u{05DC}\u{05D5}\u{05DD}\u{200F}\u{202C} zero\u{200B}width";
+
+// The text of the cleaned copy of a document holding TEXT, cleaned with the given arguments
+fn clean(test: &str, args: &[&str]) -> String {
+    let dir = std::env::temp_dir().join(format!("keep-bidi-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("Report.docx");
+    std::fs::write(&input, support::text_package(TEXT)).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("-q")
+        .args(args)
+        .arg(&input)
+        .output()
+        .unwrap();
+    let cleaned = std::fs::read(dir.join("Report_cleaned.docx"));
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    support::read_part(&cleaned.unwrap(), "word/document.xml")
+}
+
+#[test]
+fn bidi_marks_are_kept_with_the_flag() {
+    let xml = clean("with", &["--keep-bidi"]);
+    assert!(
+        xml.contains(
+            "<w:t>\u{202B}\u{05E9}\u{05DC}\u{05D5}\u{05DD}\u{200F}\u{202C} zerowidth</w:t>"
+        ),
+        "{xml}"
+    );
+}
+
+#[test]
+fn bidi_marks_are_removed_without_it() {
+    let xml = clean("without", &[]);
+    assert!(
+        xml.contains("<w:t>\u{05E9}\u{05DC}\u{05D5}\u{05DD} zerowidth</w:t>"),
+        "{xml}"
+    );
+}
@@ -0,0 +1,101 @@
+use docx_cleaner::{CleanReport, Cleaner, Config};
+use std::process::Command;
+use support::Contamination;
+
+mod support;
+
+const PARAGRAPHS: usize = 5000;
+
+fn clean(contamination: Contamination) -> (Vec<u8>, CleanReport, Vec<u8>) {
+    let input = support::synthetic_document(PARAGRAPHS, contamination);
+    let cleaned = Cleaner::new(Config::builtin())
+        .clean_package(&input)
+        .unwrap();
+    let report = cleaned.report.clone();
+    let output = cleaned.into_bytes().unwrap();
+    (input, report, output)
+}
+
+fn count(report: &CleanReport, c: char) -> usize {
+    report.chars.get(&c).copied().unwrap_or(0)
+}
+
+#[test]
+fn a_document_with_nothing_to_clean_comes_back_as_it_was() {
+    let (input, report, output) = clean(Contamination::None);
+
+    assert_eq!(report.total_removed(), 0);
+    assert_eq!(report.collapsed_spaces, 0);
+    assert!(report.suspicious.is_empty());
+    assert_eq!(output, input);
+}
+
+// The 15,000 runs take turns at the four kinds of contamination
+#[test]
+fn every_contaminated_run_is_counted_once() {
+    for (contamination, runs) in [
+        (Contamination::OneIn(100), [38, 38, 37, 37]),
+        (Contamination::Every, [3750; 4]),
+    ] {
+        let (input, report, output) = clean(contamination);
+
+        assert_eq!(count(&report, '\u{200B}'), runs[0], "{:?}", contamination);
+        assert_eq!(count(&report, '\u{2060}'), runs[1], "{:?}", contamination);
+        assert_eq!(report.collapsed_spaces, runs[2], "{:?}", contamination);
+        assert_eq!(count(&report, '\u{FEFF}'), runs[3], "{:?}", contamination);
+        assert_ne!(output, input);
+    }
+}
+
+// A note on memory: clean_package holds the whole input and, from into_bytes, the whole output,
+// so a document costs about twice its size. clean_package_file only holds the XML of the text
+// parts, here about 2MB, and write_to copies everything else over from the file; with a few
+// hundred MB of video in the package, /usr/bin/time -v shows --stream at a few MB of resident
+// memory instead of twice the size of the document.
+#[test]
+fn a_document_cleaned_from_its_file_matches_one_cleaned_in_memory() {
+    let input = support::synthetic_document(PARAGRAPHS, Contamination::OneIn(100));
+    let path = std::env::temp_dir().join(format!("stream-{}.docx", std::process::id()));
+    std::fs::write(&path, &input).unwrap();
+    let cleaner = Cleaner::new(Config::builtin());
+
+    let in_memory = cleaner.clean_package(&input).unwrap();
+    let streamed = cleaner.clean_package_file(&path).unwrap();
+    let scanned = cleaner.scan_package_file(&path).unwrap();
+    assert_eq!(streamed.report.chars, in_memory.report.chars);
+    assert_eq!(streamed.report.collapsed_spaces, 37);
+    assert_eq!(scanned.chars, in_memory.report.chars);
+    let written = streamed
+        .write_to(std::io::Cursor::new(Vec::new()))
+        .unwrap()
+        .into_inner();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(written, in_memory.into_bytes().unwrap());
+}
+
+#[test]
+fn stream_writes_the_same_copy() {
+    let input = support::synthetic_document(PARAGRAPHS, Contamination::OneIn(100));
+    let dir = std::env::temp_dir().join(format!("stream-cli-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("thesis.docx");
+    std::fs::write(&path, &input).unwrap();
+    let run = |output: &str, stream: bool| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"));
+        command.arg(&path).arg("-q").arg("-o").arg(dir.join(output));
+        if stream {
+            command.arg("--stream");
+        }
+        let status = command.status().unwrap();
+        (status.code(), std::fs::read(dir.join(output)).unwrap())
+    };
+
+    let (code, streamed) = run("streamed.docx", true);
+    let (_, read) = run("read.docx", false);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(code, Some(1));
+    assert_eq!(streamed, read);
+    assert_ne!(streamed, input);
+}
@@ -0,0 +1,66 @@
+use docx_cleaner::{Cleaner, Config};
+use std::process::Command;
+
+mod support;
+
+// Three paragraphs of 100 characters, a long one with a character at its start and a short one
+// with a character at its end
+fn package() -> Vec<u8> {
+    let long = format!("  \u{200B}Chapter one{}", "x".repeat(86));
+    let short = format!("{}The end\u{2060}", "y".repeat(92));
+    let body = format!(
+        "<w:p><w:r><w:t xml:space=\"preserve\">{}</w:t></w:r></w:p>\
+         <w:p><w:r><w:t>{}</w:t></w:r></w:p>\
+         <w:p><w:r><w:t>{}</w:t></w:r></w:p>",
+        long,
+        "z".repeat(100),
+        short
+    );
+    support::word_package(&body)
+}
+
+#[test]
+fn replacements_know_their_paragraph_and_how_far_through_the_body_they_are() {
+    let report = Cleaner::new(Config::builtin())
+        .scan_package(&package())
+        .unwrap();
+    let [first, last] = &report.replacements[..] else {
+        panic!("{:?}", report.replacements);
+    };
+    assert_eq!(first.paragraph, Some(1));
+    assert_eq!(first.offset, 2);
+    assert_eq!(
+        first.snippet,
+        format!("\u{27E8}U+200B\u{27E9}Chapter one{}\u{2026}", "x".repeat(48))
+    );
+    assert!(first.position < 0.01, "{}", first.position);
+
+    assert_eq!(last.paragraph, Some(3));
+    assert_eq!(last.offset, 299);
+    assert!(last.snippet.starts_with("yyy"));
+    assert!(last.position > 0.99, "{}", last.position);
+}
+
+#[test]
+fn the_json_statistics_list_the_locations() {
+    let dir = std::env::temp_dir().join(format!("locations-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("Report.docx");
+    std::fs::write(&input, package()).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .args(["-q", "--check", "--json"])
+        .arg(&input)
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let locations = json["locations"].as_array().unwrap();
+    assert_eq!(locations.len(), 2, "{json}");
+    assert_eq!(locations[0]["paragraph"], 1);
+    assert_eq!(locations[0]["percent"], 1);
+    assert_eq!(locations[1]["percent"], 100);
+    assert!(locations[1]["context"]
+        .as_str()
+        .unwrap()
+        .ends_with("The end\u{27E8}U+2060\u{27E9}"));
+}
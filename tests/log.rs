@@ -0,0 +1,71 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+mod support;
+
+const DOCUMENT: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\"><w:body>\
+<w:p><w:r><w:t>zero\u{200B}width\u{200B}</w:t></w:r></w:p>\
+</w:body></w:document>";
+
+// A directory of its own for each test, with the document to clean
+fn directory(test: &str) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("docx-cleaner-log-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("Report.docx");
+    std::fs::write(&input, support::document_package(DOCUMENT)).unwrap();
+    (dir, input)
+}
+
+fn clean(input: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("-q")
+        .args(args)
+        .arg(input)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn each_cleaned_file_gets_a_line() {
+    let (dir, input) = directory("line");
+    let log = dir.join("cleaner.log");
+    let log_arg = log.to_str().unwrap();
+    let cleaned = clean(&input, &["--log", "--log-file", log_arg]);
+    let checked = clean(&input, &["--check", "--log", "--log-file", log_arg]);
+    let contents = std::fs::read_to_string(&log).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(cleaned.status.code(), Some(1), "{:?}", cleaned);
+    assert_eq!(checked.status.code(), Some(1), "{:?}", checked);
+    let lines: Vec<_> = contents.lines().collect();
+    assert_eq!(lines.len(), 2, "{contents}");
+    assert!(lines[0].contains("Report.docx  2 characters removed  "), "{contents}");
+    assert!(lines[0].ends_with("Report_cleaned.docx"), "{contents}");
+    assert!(lines[1].ends_with("2 characters found  no copy written"), "{contents}");
+}
+
+#[test]
+fn nothing_is_logged_without_log() {
+    let (dir, input) = directory("quiet");
+    let log = dir.join("cleaner.log");
+    let output = clean(&input, &["--log-file", log.to_str().unwrap()]);
+    let written = log.exists();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert!(!written);
+}
+
+#[test]
+fn a_full_log_is_moved_aside() {
+    let (dir, input) = directory("rotation");
+    let log = dir.join("cleaner.log");
+    std::fs::write(&log, vec![b'.'; 1024 * 1024 + 1]).unwrap();
+    let output = clean(&input, &["--log", "--log-file", log.to_str().unwrap()]);
+    let contents = std::fs::read_to_string(&log).unwrap();
+    let rotated = std::fs::metadata(dir.join("cleaner.log.1")).unwrap().len();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert_eq!(contents.lines().count(), 1);
+    assert_eq!(rotated, 1024 * 1024 + 1);
+}
@@ -0,0 +1,105 @@
+use docx_cleaner::{Cleaner, Config};
+use std::io::{Cursor, Read, Write};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+const PACKAGE_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/></Relationships>"#;
+
+const DOCUMENT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.microsoft.com/office/2006/relationships/vbaProject" Target="vbaProject.bin"/></Relationships>"#;
+
+const DOCUMENT: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\"><w:body>\
+<w:p><w:r><w:t>zero\u{200B}width</w:t></w:r></w:p>\
+</w:body></w:document>";
+
+// Not a real VBA project, only bytes that must come through untouched
+const VBA_PROJECT: &[u8] = b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1\0\0\0\0macros\x00\xff";
+
+fn content_types(main_type: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Default Extension="bin" ContentType="application/vnd.ms-office.vbaProject"/><Override PartName="/word/document.xml" ContentType="{}"/></Types>"#,
+        main_type
+    )
+}
+
+fn package(main_type: &str) -> Vec<u8> {
+    let content_types = content_types(main_type);
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let entries: [(&str, &[u8], CompressionMethod); 5] = [
+        (
+            "[Content_Types].xml",
+            content_types.as_bytes(),
+            CompressionMethod::Deflated,
+        ),
+        (
+            "_rels/.rels",
+            PACKAGE_RELS.as_bytes(),
+            CompressionMethod::Deflated,
+        ),
+        (
+            "word/_rels/document.xml.rels",
+            DOCUMENT_RELS.as_bytes(),
+            CompressionMethod::Deflated,
+        ),
+        (
+            "word/document.xml",
+            DOCUMENT.as_bytes(),
+            CompressionMethod::Deflated,
+        ),
+        ("word/vbaProject.bin", VBA_PROJECT, CompressionMethod::Stored),
+    ];
+    for (name, data, method) in entries {
+        zip.start_file(name, FileOptions::default().compression_method(method))
+            .unwrap();
+        zip.write_all(data).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+fn entry(buf: &[u8], name: &str) -> Vec<u8> {
+    let mut archive = ZipArchive::new(Cursor::new(buf)).unwrap();
+    let mut data = Vec::new();
+    archive
+        .by_name(name)
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    data
+}
+
+#[test]
+fn the_macros_and_content_types_of_a_docm_survive() {
+    let main_type = "application/vnd.ms-word.document.macroEnabled.main+xml";
+    let input = package(main_type);
+    let cleaned = Cleaner::new(Config::builtin())
+        .clean_package(&input)
+        .unwrap();
+    assert_eq!(cleaned.report.total_removed(), 1);
+    let output = cleaned.into_bytes().unwrap();
+
+    assert_eq!(entry(&output, "word/vbaProject.bin"), VBA_PROJECT);
+    assert_eq!(
+        entry(&output, "[Content_Types].xml"),
+        content_types(main_type).as_bytes()
+    );
+    assert_eq!(
+        entry(&output, "word/_rels/document.xml.rels"),
+        DOCUMENT_RELS.as_bytes()
+    );
+}
+
+#[test]
+fn the_body_of_a_macro_enabled_template_is_cleaned() {
+    let input = package("application/vnd.ms-word.template.macroEnabledTemplate.main+xml");
+    let output = Cleaner::new(Config::builtin())
+        .clean_package(&input)
+        .unwrap()
+        .into_bytes()
+        .unwrap();
+    let document = String::from_utf8(entry(&output, "word/document.xml")).unwrap();
+    assert_eq!(document, DOCUMENT.replace('\u{200B}', ""));
+    assert_eq!(entry(&output, "word/vbaProject.bin"), VBA_PROJECT);
+}
@@ -0,0 +1,81 @@
+use docx_cleaner::{CleanReport, Config};
+use serde_json::Value;
+use std::process::Command;
+
+mod support;
+
+// An equation pasted from a web page: sin applied to x times y, with a zero width space and a
+// right-to-left mark picked up on the way, after a sentence with a zero width space of its own
+const BODY: &str = "<w:p><w:r><w:t xml:space=\"preserve\">Let\u{200B} </w:t></w:r>\
+<m:oMath><m:func><m:fName><m:r><m:rPr><m:sty m:val=\"p\"/></m:rPr><m:t>sin</m:t></m:r></m:fName>\
+<m:e><m:r><m:t>\u{2061}x\u{2062}y\u{200B}</m:t></m:r></m:e></m:func>\
+<m:r><m:t>\u{200F}=0</m:t></m:r></m:oMath></w:p>";
+
+fn package() -> Vec<u8> {
+    support::document_package(&support::document_xml(
+        "xmlns:m=\"http://schemas.openxmlformats.org/officeDocument/2006/math\"",
+        BODY,
+    ))
+}
+
+// The report and the body of the cleaned document
+fn clean(config: Config) -> (CleanReport, String) {
+    let (report, xml) = support::clean_package(config, &package());
+    (report, support::between(&xml, "<w:body>", "</w:body>"))
+}
+
+#[test]
+fn junk_is_removed_from_equations_and_the_operators_are_flagged() {
+    let (report, body) = clean(Config::builtin());
+    let expected = BODY
+        .replace("Let\u{200B}", "Let")
+        .replace("y\u{200B}", "y")
+        .replace("\u{200F}=0", "=0");
+    assert_eq!(body, expected);
+    assert_eq!(report.chars[&'\u{200B}'], 2);
+    assert_eq!(report.chars[&'\u{200F}'], 1);
+    assert_eq!(report.parts["body"], 1);
+    assert_eq!(report.parts["math"], 2);
+    assert_eq!(report.kept[&'\u{2061}'], 1);
+    assert_eq!(report.kept[&'\u{2062}'], 1);
+}
+
+#[test]
+fn math_strict_removes_the_operators_too() {
+    let mut config = Config::builtin();
+    config.math_strict = true;
+    let (report, body) = clean(config);
+    assert!(
+        body.contains("<m:e><m:r><m:t>xy</m:t></m:r></m:e>"),
+        "{body}"
+    );
+    assert!(body.contains("<m:sty m:val=\"p\"/>"), "{body}");
+    assert_eq!(report.parts["math"], 4);
+    assert!(report.kept.is_empty());
+}
+
+// The JSON of a --check run with the given arguments
+fn check(test: &str, args: &[&str]) -> Value {
+    let path = std::env::temp_dir().join(format!("math-{}-{}.docx", test, std::process::id()));
+    std::fs::write(&path, package()).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .args(["--check", "-q", "--json"])
+        .args(args)
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn the_math_part_is_reported_apart_from_the_body() {
+    let json = check("default", &[]);
+    let parts = json["parts"].as_object().unwrap();
+    assert_eq!(parts.keys().collect::<Vec<_>>(), ["body", "math"]);
+    assert_eq!(parts["math"]["total"], 2);
+
+    let json = check("strict", &["--math-strict"]);
+    assert_eq!(json["parts"]["math"]["total"], 4);
+}
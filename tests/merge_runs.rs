@@ -0,0 +1,127 @@
+use docx_cleaner::{Cleaner, Config};
+use docx_rs::{DocumentChild, Docx, Paragraph, ParagraphChild, Run, RunChild};
+
+mod support;
+
+fn merging() -> Config {
+    let mut config = Config::builtin();
+    config.merge_runs = true;
+    config
+}
+
+const BOLD: &str = "<w:rPr><w:b/></w:rPr>";
+
+// A sentence pasted with a zero-width space after every word, each word in a run of its own
+fn fragmented() -> String {
+    let words = "The quick brown fox jumps over the lazy dog and runs off into the woods";
+    let runs: String = words
+        .split(' ')
+        .map(|word| {
+            format!(
+                "<w:r w:rsidR=\"00A1\">{}<w:t xml:space=\"preserve\">{}\u{200B} </w:t></w:r>",
+                BOLD, word
+            )
+        })
+        .collect();
+    format!("<w:p>{}</w:p>", runs)
+}
+
+#[test]
+fn runs_of_the_same_formatting_become_one() {
+    let (report, body) = support::clean_body(merging(), &fragmented());
+    assert_eq!(
+        body,
+        format!(
+            "<w:p><w:r w:rsidR=\"00A1\">{}<w:t xml:space=\"preserve\">The quick brown fox jumps \
+             over the lazy dog and runs off into the woods </w:t></w:r></w:p>",
+            BOLD
+        )
+    );
+    assert_eq!(report.merged_runs, 14);
+    assert_eq!(report.total_removed(), 15);
+}
+
+#[test]
+fn runs_are_only_merged_with_the_option() {
+    let (report, body) = support::clean_body(Config::builtin(), &fragmented());
+    assert_eq!(body.matches("<w:r ").count(), 15);
+    assert_eq!(report.merged_runs, 0);
+}
+
+#[test]
+fn formatting_and_other_content_keep_runs_apart() {
+    let body = format!(
+        "<w:p><w:r><w:t>one</w:t></w:r><w:r>{bold}<w:t>two</w:t></w:r>\
+         <w:r>{bold}<w:t>three</w:t><w:tab/></w:r><w:r>{bold}<w:t>four</w:t></w:r>\
+         <w:bookmarkStart w:id=\"0\" w:name=\"here\"/><w:r>{bold}<w:t>five</w:t></w:r>\
+         <w:r>{bold}<w:fldChar w:fldCharType=\"begin\"/></w:r><w:r>{bold}<w:t>six</w:t></w:r></w:p>",
+        bold = BOLD
+    );
+    let (report, cleaned) = support::clean_body(merging(), &body);
+    assert_eq!(cleaned, body);
+    assert_eq!(report.merged_runs, 0);
+}
+
+#[test]
+fn text_without_preserved_spaces_loses_them_as_in_word() {
+    let (report, body) = support::clean_body(
+        merging(),
+        "<w:p><w:r><w:t>one </w:t></w:r><w:r><w:t xml:space=\"preserve\"> two</w:t></w:r>\
+         <w:r><w:t>&amp;\u{200B}</w:t></w:r><w:r><w:t/></w:r></w:p>",
+    );
+    assert_eq!(body, "<w:p><w:r><w:t>one two&amp;</w:t></w:r></w:p>");
+    assert_eq!(report.merged_runs, 3);
+}
+
+#[test]
+fn runs_in_text_boxes_are_merged_on_their_own() {
+    let body = format!(
+        "<w:p><w:r><w:t>before</w:t></w:r><w:r><w:pict><w:txbxContent><w:p>\
+         <w:r>{bold}<w:t>in</w:t></w:r><w:r>{bold}<w:t>side</w:t></w:r>\
+         </w:p></w:txbxContent></w:pict></w:r><w:r><w:t>after</w:t></w:r></w:p>",
+        bold = BOLD
+    );
+    let (report, cleaned) = support::clean_body(merging(), &body);
+    assert_eq!(
+        cleaned,
+        body.replace(
+            "<w:t>in</w:t></w:r><w:r><w:rPr><w:b/></w:rPr><w:t>side</w:t>",
+            "<w:t>inside</w:t>"
+        )
+    );
+    assert_eq!(report.merged_runs, 1);
+}
+
+#[test]
+fn the_model_merges_runs_like_the_package() {
+    let bold = || Run::new().bold();
+    let paragraph = Paragraph::new()
+        .add_run(bold().add_text("zero\u{200B}"))
+        .add_run(bold().add_text("width"))
+        .add_run(Run::new().add_text(" plain"))
+        .add_run(Run::new().add_text(" text").add_tab())
+        .add_run(Run::new().add_text("tabbed"));
+    let mut docx = Docx::new().add_paragraph(paragraph);
+    let report = Cleaner::new(merging()).clean_docx(&mut docx);
+    let DocumentChild::Paragraph(paragraph) = &docx.document.children[0] else {
+        panic!("no paragraph");
+    };
+    let texts: Vec<String> = paragraph
+        .children
+        .iter()
+        .map(|child| {
+            let ParagraphChild::Run(run) = child else {
+                panic!("not a run");
+            };
+            run.children
+                .iter()
+                .map(|child| match child {
+                    RunChild::Text(t) => t.text.clone(),
+                    _ => "|".to_string(),
+                })
+                .collect()
+        })
+        .collect();
+    assert_eq!(texts, ["zerowidth", " plain", " text|", "tabbed"]);
+    assert_eq!(report.merged_runs, 1);
+}
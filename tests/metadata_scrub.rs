@@ -0,0 +1,123 @@
+use docx_cleaner::{parse_metadata_scrub, CleanReport, Cleaner, Config, MetadataScrub};
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+mod support;
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/><Override PartName="/word/settings.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.settings+xml"/><Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/><Override PartName="/docProps/app.xml" ContentType="application/vnd.openxmlformats-officedocument.extended-properties+xml"/><Override PartName="/docProps/thumbnail.jpeg" ContentType="image/jpeg"/></Types>"#;
+
+const PACKAGE_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/><Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/extended-properties" Target="docProps/app.xml"/><Relationship Id="rId4" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/thumbnail" Target="docProps/thumbnail.jpeg"/></Relationships>"#;
+
+const DOCUMENT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/settings" Target="settings.xml"/></Relationships>"#;
+
+// No characters to replace, so only the rsids change the document
+const DOCUMENT: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body><w:p w:rsidR="00A1" w:rsidRDefault="00B2" w14:paraId="1A2B"><w:r w:rsidRPr="00C3"><w:t>Plain text</w:t></w:r></w:p><w:sectPr w:rsidR="00A1"/></w:body></w:document>"#;
+
+const SETTINGS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:settings xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:zoom w:percent="100"/><w:rsids><w:rsidRoot w:val="00A1"/><w:rsid w:val="00A1"/><w:rsid w:val="00B2"/></w:rsids><w:compat/></w:settings>"#;
+
+const CORE: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/"><dc:title>Essay</dc:title><dc:creator>Jane Doe</dc:creator><cp:lastModifiedBy>John Roe</cp:lastModifiedBy><cp:revision>17</cp:revision></cp:coreProperties>"#;
+
+const APP: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Properties xmlns="http://schemas.openxmlformats.org/officeDocument/2006/extended-properties"><Template>C:\Users\jane\Templates\Essay.dotx</Template><TotalTime>342</TotalTime><Company>Acme</Company></Properties>"#;
+
+fn package() -> Vec<u8> {
+    support::zip_package(&[
+        ("[Content_Types].xml", CONTENT_TYPES.as_bytes()),
+        ("_rels/.rels", PACKAGE_RELS.as_bytes()),
+        ("word/document.xml", DOCUMENT.as_bytes()),
+        ("word/_rels/document.xml.rels", DOCUMENT_RELS.as_bytes()),
+        ("word/settings.xml", SETTINGS.as_bytes()),
+        ("docProps/core.xml", CORE.as_bytes()),
+        ("docProps/app.xml", APP.as_bytes()),
+        ("docProps/thumbnail.jpeg", &[0xFF, 0xD8, 0xFF, 0xD9]),
+    ])
+}
+
+// The report and the cleaned package
+fn scrub(scrub: MetadataScrub) -> (CleanReport, ZipArchive<Cursor<Vec<u8>>>) {
+    let mut config = Config::builtin();
+    config.scrub_metadata = Some(scrub);
+    let input = package();
+    let cleaned = Cleaner::new(config).clean_package(&input).unwrap();
+    let report = cleaned.report.clone();
+    let output = cleaned.into_bytes().unwrap();
+    (report, ZipArchive::new(Cursor::new(output)).unwrap())
+}
+
+fn read(archive: &mut ZipArchive<Cursor<Vec<u8>>>, name: &str) -> String {
+    let mut xml = String::new();
+    archive
+        .by_name(name)
+        .unwrap()
+        .read_to_string(&mut xml)
+        .unwrap();
+    xml
+}
+
+#[test]
+fn all_metadata_is_scrubbed() {
+    let (report, mut archive) = scrub(MetadataScrub::all());
+    let scrubbed: Vec<(&str, &str)> = report
+        .scrubbed
+        .iter()
+        .map(|(property, value)| (property.as_str(), value.as_str()))
+        .collect();
+    assert_eq!(
+        scrubbed,
+        [
+            ("dc:creator", "Jane Doe"),
+            ("cp:lastModifiedBy", "John Roe"),
+            ("cp:revision", "17"),
+            ("Template", r"C:\Users\jane\Templates\Essay.dotx"),
+            ("TotalTime", "342"),
+            ("Company", "Acme"),
+            ("thumbnail", "docProps/thumbnail.jpeg"),
+        ]
+    );
+    // Four attributes and three sessions in the settings
+    assert_eq!(report.rsids_removed, 7);
+    assert!(report.is_modified());
+
+    let document = read(&mut archive, "word/document.xml");
+    assert!(document.contains("<w:p w14:paraId=\"1A2B\"><w:r><w:t>Plain text</w:t></w:r></w:p><w:sectPr/>"), "{document}");
+    let settings = read(&mut archive, "word/settings.xml");
+    assert!(settings.contains("<w:zoom w:percent=\"100\"/><w:compat/>"), "{settings}");
+    let core = read(&mut archive, "docProps/core.xml");
+    assert!(core.contains("<dc:title>Essay</dc:title><dc:creator></dc:creator><cp:lastModifiedBy></cp:lastModifiedBy><cp:revision>1</cp:revision>"), "{core}");
+    let app = read(&mut archive, "docProps/app.xml");
+    assert!(app.contains("<Template></Template><TotalTime>0</TotalTime><Company></Company>"), "{app}");
+
+    assert!(archive.by_name("docProps/thumbnail.jpeg").is_err());
+    assert!(!read(&mut archive, "_rels/.rels").contains("thumbnail"));
+    assert!(!read(&mut archive, "[Content_Types].xml").contains("thumbnail"));
+}
+
+#[test]
+fn only_the_named_kinds_are_scrubbed() {
+    let (report, mut archive) = scrub(parse_metadata_scrub("author,rsids").unwrap());
+    assert_eq!(report.scrubbed.len(), 2);
+    assert_eq!(report.rsids_removed, 7);
+    assert!(read(&mut archive, "docProps/app.xml").contains("<Company>Acme</Company>"));
+    assert!(read(&mut archive, "docProps/core.xml").contains("<cp:revision>17</cp:revision>"));
+    assert!(archive.by_name("docProps/thumbnail.jpeg").is_ok());
+}
+
+#[test]
+fn untouched_parts_stay_as_they_were() {
+    let (_, mut archive) = scrub(parse_metadata_scrub("company").unwrap());
+    assert_eq!(read(&mut archive, "word/document.xml"), DOCUMENT);
+    assert_eq!(read(&mut archive, "docProps/core.xml"), CORE);
+}
+
+#[test]
+fn unknown_kinds_are_rejected() {
+    let error = parse_metadata_scrub("author,gps").unwrap_err();
+    assert!(error.contains("'gps'"), "{error}");
+    assert_eq!(parse_metadata_scrub("all"), Ok(MetadataScrub::all()));
+}
@@ -0,0 +1,45 @@
+use docx_cleaner::{Config, NbspMode, NO_BREAK_SPACE};
+
+mod support;
+
+#[test]
+fn a_replaced_no_break_space_is_collapsed_with_the_spaces_around_it() {
+    let (report, body) = support::clean_body(
+        Config::builtin(),
+        "<w:p><w:r><w:t>word \u{A0}word</w:t></w:r></w:p>\
+         <w:p><w:r><w:t xml:space=\"preserve\">last </w:t></w:r><w:r><w:t>\u{A0}run</w:t></w:r></w:p>",
+    );
+    assert!(body.contains("<w:t>word word</w:t>"), "{body}");
+    assert!(
+        body.contains("<w:t xml:space=\"preserve\">last </w:t></w:r><w:r><w:t>run</w:t>"),
+        "{body}"
+    );
+    assert_eq!(report.chars[&NO_BREAK_SPACE], 2);
+    assert_eq!(report.collapsed_stretches, 2);
+}
+
+#[test]
+fn kept_no_break_spaces_are_not_counted() {
+    let mut config = Config::builtin();
+    config.nbsp = NbspMode::Keep;
+    let (report, body) = support::clean_body(config, "<w:p><w:r><w:t>10\u{A0}kg</w:t></w:r></w:p>");
+    assert!(body.contains("<w:t>10\u{A0}kg</w:t>"));
+    assert!(!report.is_modified());
+    assert_eq!(report.nbsp_kept, 0);
+}
+
+#[test]
+fn patterns_keep_no_break_spaces_before_units_and_french_punctuation() {
+    let mut config = Config::builtin();
+    config.nbsp = NbspMode::Patterns;
+    let (report, body) = support::clean_body(
+        config,
+        "<w:p><w:r><w:t>10\u{A0}kg, \u{A7}\u{A0}5, \u{AB}\u{A0}oui\u{A0}\u{BB} et\u{A0}non\u{A0}!</w:t></w:r></w:p>",
+    );
+    assert!(
+        body.contains("<w:t>10\u{A0}kg, \u{A7}\u{A0}5, \u{AB}\u{A0}oui\u{A0}\u{BB} et non\u{A0}!</w:t>"),
+        "{body}"
+    );
+    assert_eq!(report.chars[&NO_BREAK_SPACE], 1);
+    assert_eq!(report.nbsp_kept, 5);
+}
@@ -0,0 +1,51 @@
+use docx_cleaner::{Cleaner, Config, Normalization};
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+mod support;
+
+// "naïve" with a combining diaeresis, the "fi" ligature, and a field code with a decomposed letter
+const DOCUMENT: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\"><w:body>\
+<w:p><w:r><w:t>nai\u{308}ve</w:t></w:r><w:r><w:t xml:space=\"preserve\"> \u{FB01}le</w:t></w:r></w:p>\
+<w:p><w:r><w:instrText xml:space=\"preserve\"> REF nai\u{308}ve </w:instrText></w:r></w:p>\
+</w:body></w:document>";
+
+fn normalize(form: Normalization) -> (String, usize) {
+    let mut config = Config::builtin();
+    config.normalize = Some(form);
+    let input = support::document_package(DOCUMENT);
+    let cleaned = Cleaner::new(config).clean_package(&input).unwrap();
+    assert_eq!(cleaned.report.total_removed(), 0);
+    let normalized = cleaned.report.normalized_runs;
+    let output = cleaned.into_bytes().unwrap();
+    let mut archive = ZipArchive::new(Cursor::new(output)).unwrap();
+    let mut xml = String::new();
+    archive
+        .by_name("word/document.xml")
+        .unwrap()
+        .read_to_string(&mut xml)
+        .unwrap();
+    (xml, normalized)
+}
+
+#[test]
+fn nfc_composes_accents_and_keeps_ligatures() {
+    let (xml, normalized) = normalize(Normalization::Nfc);
+    assert_eq!(normalized, 1);
+    assert!(xml.contains("<w:t>na\u{EF}ve</w:t>"));
+    assert!(xml.contains(" \u{FB01}le</w:t>"));
+}
+
+#[test]
+fn nfkc_also_folds_ligatures() {
+    let (xml, normalized) = normalize(Normalization::Nfkc);
+    assert_eq!(normalized, 2);
+    assert!(xml.contains(" file</w:t>"));
+}
+
+#[test]
+fn field_codes_are_not_normalized() {
+    let (xml, _) = normalize(Normalization::Nfkc);
+    assert!(xml.contains(" REF nai\u{308}ve </w:instrText>"));
+}
@@ -0,0 +1,86 @@
+use docx_cleaner::{Cleaner, Config};
+use std::io::{Cursor, Read, Write};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+const MIMETYPE: &str = "application/vnd.oasis.opendocument.text";
+
+const MANIFEST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest:manifest xmlns:manifest="urn:oasis:names:tc:opendocument:xmlns:manifest:1.0" manifest:version="1.3"><manifest:file-entry manifest:full-path="/" manifest:media-type="application/vnd.oasis.opendocument.text"/><manifest:file-entry manifest:full-path="content.xml" manifest:media-type="text/xml"/><manifest:file-entry manifest:full-path="styles.xml" manifest:media-type="text/xml"/></manifest:manifest>"#;
+
+// A heading, a list and a table, each with a zero width space, and a run of spaces
+const CONTENT: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+<office:document-content xmlns:office=\"urn:oasis:names:tc:opendocument:xmlns:office:1.0\" xmlns:text=\"urn:oasis:names:tc:opendocument:xmlns:text:1.0\" xmlns:table=\"urn:oasis:names:tc:opendocument:xmlns:table:1.0\"><office:body><office:text>\
+<text:h text:outline-level=\"1\">Intro\u{200B}duction</text:h>\
+<text:list><text:list-item><text:p>first\u{200B} item</text:p></text:list-item></text:list>\
+<table:table table:name=\"T\"><table:table-row><table:table-cell><text:p><text:span>ce\u{200B}ll</text:span></text:p></table:table-cell></table:table-row></table:table>\
+<text:p>two <text:s text:c=\"2\"/>spaces</text:p>\
+</office:text></office:body></office:document-content>";
+
+const STYLES: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+<office:document-styles xmlns:office=\"urn:oasis:names:tc:opendocument:xmlns:office:1.0\" xmlns:style=\"urn:oasis:names:tc:opendocument:xmlns:style:1.0\" xmlns:text=\"urn:oasis:names:tc:opendocument:xmlns:text:1.0\"><office:master-styles><style:master-page style:name=\"Standard\"><style:header><text:p>Head\u{200B}er</text:p></style:header></style:master-page></office:master-styles></office:document-styles>";
+
+fn package() -> Vec<u8> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let entries: [(&str, &str, CompressionMethod); 4] = [
+        ("mimetype", MIMETYPE, CompressionMethod::Stored),
+        ("content.xml", CONTENT, CompressionMethod::Deflated),
+        ("styles.xml", STYLES, CompressionMethod::Deflated),
+        ("META-INF/manifest.xml", MANIFEST, CompressionMethod::Deflated),
+    ];
+    for (name, data, method) in entries {
+        zip.start_file(name, FileOptions::default().compression_method(method))
+            .unwrap();
+        zip.write_all(data.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+fn entry(archive: &mut ZipArchive<Cursor<Vec<u8>>>, name: &str) -> String {
+    let mut xml = String::new();
+    archive
+        .by_name(name)
+        .unwrap()
+        .read_to_string(&mut xml)
+        .unwrap();
+    xml
+}
+
+#[test]
+fn headings_lists_and_tables_come_out_clean() {
+    let input = package();
+    let cleaned = Cleaner::new(Config::builtin())
+        .clean_package(&input)
+        .unwrap();
+    assert_eq!(cleaned.report.parts["body"], 3);
+    assert_eq!(cleaned.report.parts["styles"], 1);
+    assert_eq!(cleaned.report.collapsed_spaces, 2);
+    let mut archive = ZipArchive::new(Cursor::new(cleaned.into_bytes().unwrap())).unwrap();
+
+    let content = entry(&mut archive, "content.xml");
+    assert!(!content.contains('\u{200B}'));
+    assert!(content.contains("<text:h text:outline-level=\"1\">Introduction</text:h>"));
+    assert!(content.contains("<text:p>two spaces</text:p>"));
+    assert_eq!(
+        entry(&mut archive, "styles.xml"),
+        STYLES.replace('\u{200B}', "")
+    );
+    assert_eq!(entry(&mut archive, "META-INF/manifest.xml"), MANIFEST);
+}
+
+#[test]
+fn the_mimetype_stays_the_first_stored_entry() {
+    let input = package();
+    let output = Cleaner::new(Config::builtin())
+        .clean_package(&input)
+        .unwrap()
+        .into_bytes()
+        .unwrap();
+    let mut archive = ZipArchive::new(Cursor::new(output)).unwrap();
+    let mut mimetype = archive.by_index(0).unwrap();
+    assert_eq!(mimetype.name(), "mimetype");
+    assert_eq!(mimetype.compression(), CompressionMethod::Stored);
+    let mut data = String::new();
+    mimetype.read_to_string(&mut data).unwrap();
+    assert_eq!(data, MIMETYPE);
+}
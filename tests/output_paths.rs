@@ -0,0 +1,164 @@
+use docx_cleaner::{cleaned_copy_path, numbered_copy_path};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+mod support;
+
+const DOCUMENT: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\"><w:body>\
+<w:p><w:r><w:t>zero\u{200B}width</w:t></w:r></w:p>\
+</w:body></w:document>";
+
+fn copy_path(input: &str) -> Option<PathBuf> {
+    cleaned_copy_path(Path::new(input))
+}
+
+#[test]
+fn a_file_without_an_extension_gets_docx() {
+    assert_eq!(
+        copy_path("Thesis Final"),
+        Some(PathBuf::from("Thesis Final_cleaned.docx"))
+    );
+    assert_eq!(copy_path("Draft."), Some(PathBuf::from("Draft_cleaned.docx")));
+}
+
+#[test]
+fn the_case_of_the_extension_is_kept() {
+    assert_eq!(
+        copy_path("Report.DOCX"),
+        Some(PathBuf::from("Report_cleaned.DOCX"))
+    );
+}
+
+#[test]
+fn a_dotfile_name_is_kept_whole() {
+    assert_eq!(
+        copy_path("notes/.docx"),
+        Some(PathBuf::from("notes/.docx_cleaned.docx"))
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn files_at_the_root_stay_there() {
+    assert_eq!(
+        copy_path("/Thesis Final"),
+        Some(PathBuf::from("/Thesis Final_cleaned.docx"))
+    );
+    assert_eq!(copy_path("/"), None);
+}
+
+#[cfg(windows)]
+#[test]
+fn files_at_the_root_stay_there() {
+    assert_eq!(
+        copy_path(r"C:\Thesis Final"),
+        Some(PathBuf::from(r"C:\Thesis Final_cleaned.docx"))
+    );
+    assert_eq!(copy_path(r"C:\"), None);
+    assert_eq!(copy_path(r"\\server\share\"), None);
+}
+
+// A directory of its own for each test, since the tests run in parallel
+fn directory(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("docx-cleaner-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn clean(input: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("-q")
+        .args(args)
+        .arg(input)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn the_command_line_names_the_copy_of_a_file_without_an_extension() {
+    let dir = directory("no-extension");
+    let input = dir.join("Thesis Final");
+    std::fs::write(&input, support::document_package(DOCUMENT)).unwrap();
+    let output = clean(&input, &[]);
+    let written = dir.join("Thesis Final_cleaned.docx").exists();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert!(written);
+}
+
+#[test]
+fn numbered_copies_take_the_first_free_number() {
+    let dir = directory("numbered");
+    let copy = dir.join("Report_cleaned.docx");
+    let first = numbered_copy_path(&copy);
+    for name in ["Report_cleaned.docx", "Report_cleaned_2.docx", "Report_cleaned_4.docx"] {
+        std::fs::write(dir.join(name), "").unwrap();
+    }
+    let next = numbered_copy_path(&copy);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(first, dir.join("Report_cleaned_2.docx"));
+    assert_eq!(next, dir.join("Report_cleaned_3.docx"));
+}
+
+#[test]
+fn an_earlier_copy_is_only_replaced_with_force() {
+    let dir = directory("earlier-copy");
+    let input = dir.join("Report.docx");
+    let copy = dir.join("Report_cleaned.docx");
+    std::fs::write(&input, support::document_package(DOCUMENT)).unwrap();
+    std::fs::write(&copy, "edited by hand").unwrap();
+
+    let refused = clean(&input, &[]);
+    let kept = std::fs::read(&copy).unwrap();
+    let forced = clean(&input, &["--force"]);
+    let replaced = std::fs::read(&copy).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(refused.status.code(), Some(4));
+    let error = String::from_utf8_lossy(&refused.stderr);
+    assert!(error.contains("already exists; pass --force"), "{error}");
+    assert_eq!(kept, b"edited by hand");
+    assert_eq!(forced.status.code(), Some(1), "{:?}", forced);
+    assert!(replaced.starts_with(b"PK"));
+}
+
+#[test]
+fn an_earlier_copy_is_found_before_the_document_is_read() {
+    let dir = directory("before-reading");
+    let input = dir.join("Broken.docx");
+    std::fs::write(&input, "not a zip file").unwrap();
+    std::fs::write(dir.join("Broken_cleaned.docx"), "").unwrap();
+    let output = clean(&input, &[]);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(output.status.code(), Some(4), "{:?}", output);
+}
+
+#[test]
+fn only_the_finished_copy_is_left_in_the_folder() {
+    let dir = directory("atomic");
+    let input = dir.join("Report.docx");
+    std::fs::write(&input, support::document_package(DOCUMENT)).unwrap();
+    let output = clean(&input, &[]);
+    let mut names: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    names.sort();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert_eq!(names, ["Report.docx", "Report_cleaned.docx"]);
+}
+
+#[test]
+fn a_failed_write_is_reported_without_a_panic() {
+    let dir = directory("failed-write");
+    let input = dir.join("Report.docx");
+    std::fs::write(&input, support::document_package(DOCUMENT)).unwrap();
+    let target = dir.join("missing").join("Report.docx");
+    let output = clean(&input, &["--output", target.to_str().unwrap()]);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(output.status.code(), Some(4), "{:?}", output);
+    let error = String::from_utf8_lossy(&output.stderr);
+    assert!(error.contains("Could not write output"), "{error}");
+}
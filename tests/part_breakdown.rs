@@ -0,0 +1,83 @@
+use docx_cleaner::{Cleaner, Config};
+use serde_json::Value;
+use std::process::Command;
+
+mod support;
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/><Override PartName="/word/footer1.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.footer+xml"/></Types>"#;
+
+const DOCUMENT: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\"><w:body>\
+<w:p><w:r><w:t>zero\u{200B}width\u{200B} word\u{2060}joiner</w:t></w:r></w:p>\
+</w:body></w:document>";
+
+// A footer nobody looked at, with a character of its own
+const FOOTER: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<w:ftr xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\">\
+<w:p><w:r><w:t>page\u{FEFF}</w:t></w:r></w:p></w:ftr>";
+
+fn package() -> Vec<u8> {
+    support::zip_package(&[
+        ("[Content_Types].xml", CONTENT_TYPES.as_bytes()),
+        ("_rels/.rels", support::PACKAGE_RELS.as_bytes()),
+        ("word/document.xml", DOCUMENT.as_bytes()),
+        ("word/footer1.xml", FOOTER.as_bytes()),
+    ])
+}
+
+#[test]
+fn each_character_is_counted_in_its_part() {
+    let input = package();
+    let cleaned = Cleaner::new(Config::builtin()).clean_package(&input).unwrap();
+    let report = &cleaned.report;
+    assert_eq!(report.part_chars["body"][&'\u{200B}'], 2);
+    assert_eq!(report.part_chars["body"][&'\u{2060}'], 1);
+    assert_eq!(report.part_chars["footer"][&'\u{FEFF}'], 1);
+    assert!(!report.part_chars["footer"].contains_key(&'\u{200B}'));
+
+    let mut batch = report.clone();
+    batch.merge(report.clone());
+    assert_eq!(batch.part_chars["body"][&'\u{200B}'], 4);
+}
+
+// The standard output of a --check run with the given arguments
+fn check(test: &str, args: &[&str]) -> String {
+    let path = std::env::temp_dir().join(format!("parts-{}-{}.docx", test, std::process::id()));
+    std::fs::write(&path, package()).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("--check")
+        .args(args)
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn the_statistics_are_grouped_by_part() {
+    let statistics = check("console", &[]);
+    let expected = "body:\n  \
+        ZERO WIDTH SPACE (U+200B) - \u{200B}: 2\n  \
+        WORD JOINER (U+2060) - \u{2060}: 1\n  \
+        Subtotal: 3\n\
+        footer:\n  \
+        ZERO WIDTH NO-BREAK SPACE (U+FEFF) - \u{FEFF}: 1\n  \
+        Subtotal: 1\n\
+        \n\
+        Total characters found: 4\n";
+    assert!(statistics.contains(expected), "{statistics}");
+}
+
+#[test]
+fn the_json_nests_the_characters_in_their_part() {
+    let json: Value = serde_json::from_str(&check("json", &["-q", "--json"])).unwrap();
+    let parts = json["parts"].as_object().unwrap();
+    assert_eq!(parts.keys().collect::<Vec<_>>(), ["body", "footer"]);
+    assert_eq!(parts["body"]["total"], 3);
+    assert_eq!(parts["body"]["characters"][0]["codepoint"], "U+200B");
+    assert_eq!(parts["body"]["characters"][0]["count"], 2);
+    assert_eq!(parts["footer"]["characters"][0]["codepoint"], "U+FEFF");
+}
@@ -0,0 +1,100 @@
+use docx_cleaner::{Cleaner, Config};
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+mod support;
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Default Extension="png" ContentType="image/png"/><Override PartName="/ppt/presentation.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.presentation.main+xml"/><Override PartName="/ppt/slides/slide1.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slide+xml"/><Override PartName="/ppt/slides/slide2.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slide+xml"/><Override PartName="/ppt/notesSlides/notesSlide1.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.notesSlide+xml"/><Override PartName="/ppt/theme/theme1.xml" ContentType="application/vnd.openxmlformats-officedocument.theme+xml"/></Types>"#;
+
+const PACKAGE_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="ppt/presentation.xml"/></Relationships>"#;
+
+// slide2.xml is shown first
+const PRESENTATION: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<p:presentation xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><p:sldIdLst><p:sldId id="256" r:id="rId3"/><p:sldId id="257" r:id="rId2"/></p:sldIdLst></p:presentation>"#;
+
+const PRESENTATION_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/theme" Target="theme/theme1.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slide" Target="slides/slide1.xml"/><Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slide" Target="slides/slide2.xml"/></Relationships>"#;
+
+const SLIDE1_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/notesSlide" Target="../notesSlides/notesSlide1.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="../media/image1.png"/></Relationships>"#;
+
+fn slide(text: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <p:sld xmlns:a=\"http://schemas.openxmlformats.org/drawingml/2006/main\" xmlns:p=\"http://schemas.openxmlformats.org/presentationml/2006/main\"><p:cSld><p:spTree><p:sp><p:txBody><a:bodyPr/><a:p><a:r><a:rPr lang=\"en-US\"/><a:t>{}</a:t></a:r></a:p></p:txBody></p:sp></p:spTree></p:cSld></p:sld>",
+        text
+    )
+}
+
+const THEME: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<a:theme xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" name="Office Theme"><a:themeElements><a:fontScheme name="Office"><a:majorFont><a:latin typeface="Calibri&#x200B;"/></a:majorFont></a:fontScheme></a:themeElements></a:theme>"#;
+
+const IMAGE: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01";
+
+fn package() -> Vec<u8> {
+    let slide1 = slide("first\u{200B}shown second");
+    let slide2 = slide("shown\u{200B} first\u{200B}");
+    let notes = slide("notes\u{200D}");
+    support::zip_package(&[
+        ("[Content_Types].xml", CONTENT_TYPES.as_bytes()),
+        ("_rels/.rels", PACKAGE_RELS.as_bytes()),
+        ("ppt/presentation.xml", PRESENTATION.as_bytes()),
+        ("ppt/_rels/presentation.xml.rels", PRESENTATION_RELS.as_bytes()),
+        ("ppt/slides/slide1.xml", slide1.as_bytes()),
+        ("ppt/slides/slide2.xml", slide2.as_bytes()),
+        ("ppt/slides/_rels/slide1.xml.rels", SLIDE1_RELS.as_bytes()),
+        ("ppt/notesSlides/notesSlide1.xml", notes.as_bytes()),
+        ("ppt/theme/theme1.xml", THEME.as_bytes()),
+        ("ppt/media/image1.png", IMAGE),
+    ])
+}
+
+fn entry(buf: &[u8], name: &str) -> Vec<u8> {
+    let mut archive = ZipArchive::new(Cursor::new(buf)).unwrap();
+    let mut data = Vec::new();
+    archive
+        .by_name(name)
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    data
+}
+
+#[test]
+fn slide_and_notes_text_is_cleaned() {
+    let input = package();
+    let cleaned = Cleaner::new(Config::builtin())
+        .clean_package(&input)
+        .unwrap();
+    assert_eq!(cleaned.report.parts["slide"], 3);
+    assert_eq!(cleaned.report.parts["notes"], 1);
+    let output = cleaned.into_bytes().unwrap();
+
+    assert_eq!(
+        entry(&output, "ppt/slides/slide1.xml"),
+        slide("firstshown second").as_bytes()
+    );
+    assert_eq!(
+        entry(&output, "ppt/slides/slide2.xml"),
+        slide("shown first").as_bytes()
+    );
+    assert_eq!(
+        entry(&output, "ppt/notesSlides/notesSlide1.xml"),
+        slide("notes").as_bytes()
+    );
+    assert_eq!(entry(&output, "ppt/theme/theme1.xml"), THEME.as_bytes());
+    assert_eq!(entry(&output, "ppt/media/image1.png"), IMAGE);
+}
+
+#[test]
+fn counts_are_per_slide_in_the_order_shown() {
+    let input = package();
+    let report = Cleaner::new(Config::builtin())
+        .scan_package(&input)
+        .unwrap();
+    // slide2.xml is slide 1, and slide1.xml with its notes is slide 2
+    let slides: Vec<(usize, usize)> = report.slides.into_iter().collect();
+    assert_eq!(slides, [(1, 2), (2, 2)]);
+}
@@ -0,0 +1,122 @@
+use docx_cleaner::{Cleaner, Config};
+use std::io::{Cursor, Read, Write};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+mod support;
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Default Extension="png" ContentType="image/png"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/><Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/><Override PartName="/word/charts/chart1.xml" ContentType="application/vnd.openxmlformats-officedocument.drawingml.chart+xml"/></Types>"#;
+
+const DOCUMENT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="media/image1.png"/><Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/chart" Target="charts/chart1.xml"/></Relationships>"#;
+
+const DOCUMENT: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\"><w:body>\
+<w:p><w:pPr><w:pStyle w:val=\"Thesis\"/></w:pPr><w:r><w:t>zero\u{200B}width</w:t></w:r></w:p>\
+</w:body></w:document>";
+
+const STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:w14="http://schemas.microsoft.com/office/word/2010/wordml"><w:style w:type="paragraph" w:customStyle="1" w:styleId="Thesis"><w:name w:val="Thesis"/><w:rPr><w14:ligatures w14:val="standard"/><w:spacing w:val="-3"/></w:rPr></w:style></w:styles>"#;
+
+const CHART: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<c:chartSpace xmlns:c="http://schemas.openxmlformats.org/drawingml/2006/chart"><c:chart><c:plotArea><c:barChart><c:ser><c:val><c:numLit><c:ptCount val="1"/><c:pt idx="0"><c:v>42</c:v></c:pt></c:numLit></c:val></c:ser></c:barChart></c:plotArea></c:chart></c:chartSpace>"#;
+
+// Not a valid PNG, only bytes that would not survive being decoded and re-encoded
+const IMAGE: &[u8] =
+    b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01\0\0\0\x01\x08\x06\0\0\0\x1f\x15\xc4\x89";
+
+fn package() -> Vec<u8> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let entries: [(&str, &[u8], CompressionMethod); 7] = [
+        (
+            "[Content_Types].xml",
+            CONTENT_TYPES.as_bytes(),
+            CompressionMethod::Deflated,
+        ),
+        (
+            "_rels/.rels",
+            support::PACKAGE_RELS.as_bytes(),
+            CompressionMethod::Deflated,
+        ),
+        (
+            "word/_rels/document.xml.rels",
+            DOCUMENT_RELS.as_bytes(),
+            CompressionMethod::Deflated,
+        ),
+        (
+            "word/document.xml",
+            DOCUMENT.as_bytes(),
+            CompressionMethod::Deflated,
+        ),
+        (
+            "word/styles.xml",
+            STYLES.as_bytes(),
+            CompressionMethod::Deflated,
+        ),
+        (
+            "word/charts/chart1.xml",
+            CHART.as_bytes(),
+            CompressionMethod::Deflated,
+        ),
+        ("word/media/image1.png", IMAGE, CompressionMethod::Stored),
+    ];
+    for (name, data, method) in entries {
+        zip.start_file(name, FileOptions::default().compression_method(method))
+            .unwrap();
+        zip.write_all(data).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+fn entries(buf: &[u8]) -> Vec<(String, Vec<u8>)> {
+    let mut archive = ZipArchive::new(Cursor::new(buf)).unwrap();
+    (0..archive.len())
+        .map(|i| {
+            let mut file = archive.by_index(i).unwrap();
+            let mut data = Vec::new();
+            file.read_to_end(&mut data).unwrap();
+            (file.name().to_string(), data)
+        })
+        .collect()
+}
+
+#[test]
+fn only_the_cleaned_part_is_rewritten() {
+    let input = package();
+    let cleaned = Cleaner::new(Config::builtin())
+        .clean_package(&input)
+        .unwrap();
+    assert_eq!(cleaned.report.total_removed(), 1);
+    let output = cleaned.into_bytes().unwrap();
+
+    let before = entries(&input);
+    let after = entries(&output);
+    assert_eq!(
+        before.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+        after.iter().map(|(name, _)| name).collect::<Vec<_>>()
+    );
+    for ((name, old), (_, new)) in before.iter().zip(&after) {
+        if name == "word/document.xml" {
+            let new = String::from_utf8(new.clone()).unwrap();
+            assert_eq!(new, DOCUMENT.replace('\u{200B}', ""));
+        } else {
+            assert_eq!(old, new, "{name} changed");
+        }
+    }
+}
+
+#[test]
+fn clean_document_is_copied_unchanged() {
+    let input = package();
+    let first = Cleaner::new(Config::builtin())
+        .clean_package(&input)
+        .unwrap()
+        .into_bytes()
+        .unwrap();
+    let cleaned = Cleaner::new(Config::builtin())
+        .clean_package(&first)
+        .unwrap();
+    assert_eq!(cleaned.report.total_removed(), 0);
+    assert_eq!(cleaned.into_bytes().unwrap(), first);
+}
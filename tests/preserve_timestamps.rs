@@ -0,0 +1,96 @@
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::time::{Duration, SystemTime};
+
+mod support;
+
+const DOCUMENT: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body><w:p><w:r><w:t>in&#x200B;visible</w:t></w:r></w:p></w:body></w:document>"#;
+
+// 2020-09-13, in whole seconds so that every filesystem can hold it exactly
+fn long_ago() -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000)
+}
+
+// A document last modified long ago, in a directory of its own since the tests run in parallel
+fn old_document(test: &str) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join(format!(
+        "preserve-timestamps-{}-{}",
+        test,
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("Report.docx");
+    std::fs::write(&input, support::document_package(DOCUMENT)).unwrap();
+    let times = std::fs::FileTimes::new().set_modified(long_ago());
+    std::fs::File::options()
+        .write(true)
+        .open(&input)
+        .unwrap()
+        .set_times(times)
+        .unwrap();
+    (dir, input)
+}
+
+fn clean(input: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("-q")
+        .args(args)
+        .arg(input)
+        .output()
+        .unwrap()
+}
+
+fn modified(path: &Path) -> SystemTime {
+    std::fs::metadata(path).unwrap().modified().unwrap()
+}
+
+#[test]
+fn the_copy_gets_the_modified_time_of_the_input() {
+    let (dir, input) = old_document("copy");
+    let preserved = clean(&input, &["--preserve-timestamps"]);
+    let copy = dir.join("Report_cleaned.docx");
+    let preserved_time = modified(&copy);
+    std::fs::remove_file(&copy).unwrap();
+    let plain = clean(&input, &[]);
+    let plain_time = modified(&copy);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(preserved.status.code(), Some(1), "{:?}", preserved);
+    assert_eq!(preserved_time, long_ago());
+    assert_eq!(plain.status.code(), Some(1), "{:?}", plain);
+    assert!(plain_time > long_ago());
+}
+
+#[test]
+fn a_read_only_original_stays_read_only_in_place() {
+    let (dir, input) = old_document("read-only");
+    let mut permissions = std::fs::metadata(&input).unwrap().permissions();
+    permissions.set_readonly(true);
+    std::fs::set_permissions(&input, permissions.clone()).unwrap();
+    let output = clean(&input, &["--in-place", "--preserve-timestamps"]);
+    let cleaned = std::fs::read(&input).unwrap();
+    let metadata = std::fs::metadata(&input).unwrap();
+    let backup_exists = dir.join("Report.docx.bak").exists();
+    // Windows cannot remove read-only files
+    for path in [&input, &dir.join("Report.docx.bak")] {
+        let mut writable = std::fs::metadata(path).unwrap().permissions();
+        #[allow(clippy::permissions_set_readonly_false)]
+        writable.set_readonly(false);
+        std::fs::set_permissions(path, writable).unwrap();
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert!(metadata.permissions().readonly());
+    assert_eq!(metadata.permissions(), permissions);
+    assert_eq!(metadata.modified().unwrap(), long_ago());
+    assert!(backup_exists);
+    let document = zip::ZipArchive::new(Cursor::new(cleaned))
+        .unwrap()
+        .by_name("word/document.xml")
+        .map(|file| std::io::read_to_string(file).unwrap())
+        .unwrap();
+    assert!(document.contains("<w:t>invisible</w:t>"), "{document}");
+}
@@ -0,0 +1,146 @@
+use docx_cleaner::{CleanReport, Config, Origin};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+mod support;
+
+// A zero-width space, an em dash, curly quotes, a Cyrillic "а" and an en space before a no-break
+// space
+const BODY: &str = "<w:p><w:r><w:t>zero\u{200B}width \u{2014} \u{201C}quoted\u{201D} c\u{430}t a\u{2002}\u{A0}b</w:t></w:r></w:p>";
+
+// The report and the text of the cleaned document
+fn clean(config: Config) -> (CleanReport, String) {
+    let (report, xml) = support::clean_package(config, &support::word_package(BODY));
+    (report, support::between(&xml, "<w:t>", "</w:t>"))
+}
+
+// A user config of its own for each test, so that the one of the platform is not picked up
+fn user_config(test: &str, json: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("profiles-{}-{}.json", test, std::process::id()));
+    std::fs::write(&path, json).unwrap();
+    path
+}
+
+fn load(path: &Path, profile: &str) -> Result<Config, String> {
+    Config::load_profile(Some(path), None, profile, &[])
+}
+
+#[test]
+fn the_builtin_profiles_differ_in_strictness() {
+    let path = user_config("builtin", "{}");
+    let minimal = load(&path, "minimal").unwrap();
+    let standard = load(&path, "standard").unwrap();
+    let aggressive = load(&path, "aggressive").unwrap();
+    let default = Config::load(Some(&path), &[]).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(default.profile(), "standard");
+    assert_eq!(minimal.profile(), "minimal");
+    assert_eq!(clean(default).1, "zerowidth - \"quoted\" c\u{430}t a b");
+    assert_eq!(clean(standard).1, "zerowidth - \"quoted\" c\u{430}t a b");
+    assert_eq!(
+        clean(minimal).1,
+        "zerowidth \u{2014} \u{201C}quoted\u{201D} c\u{430}t a\u{2002}\u{A0}b"
+    );
+    let (report, text) = clean(aggressive);
+    assert_eq!(text, "zerowidth -- \"quoted\" cat a b");
+    assert_eq!(report.homoglyphs[&'\u{430}'], 1);
+}
+
+#[test]
+fn the_user_config_goes_on_top_of_its_own_profiles() {
+    let path = user_config(
+        "user",
+        r#"{
+            "—": ["EM DASH", "-"],
+            "profiles": {
+                "minimal": {"description": "Mine", "U+2010..U+2029": "keep"},
+                "quotes": {"description": "Quotes only", "typography": true}
+            }
+        }"#,
+    );
+    let profiles = Config::profiles(Some(&path), None).unwrap();
+    let minimal = load(&path, "minimal").unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["minimal", "standard", "aggressive", "quotes"]);
+    assert_eq!(profiles[0].description(), "Mine");
+    assert_eq!(profiles[3].contents(), ["ASCII punctuation"]);
+    assert_eq!(minimal.origin('\u{2014}'), Origin::User);
+    assert_eq!(
+        clean(minimal).1,
+        "zerowidth - \u{201C}quoted\u{201D} c\u{430}t a b"
+    );
+}
+
+#[test]
+fn profiles_are_checked_like_the_rest_of_the_config() {
+    let path = user_config("unknown", "{}");
+    let unknown = load(&path, "strict");
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(
+        unknown.err().unwrap(),
+        "Unknown profile 'strict'; the profiles are minimal, standard, aggressive"
+    );
+
+    for (json, error) in [
+        (
+            r#"{"typography": true}"#,
+            "\"typography\" is only allowed in a profile",
+        ),
+        (
+            r#"{"profiles": {"a": {"profiles": {}}}}"#,
+            "profiles cannot have \"profiles\" of their own",
+        ),
+        (
+            r#"{"profiles": {"a": "keep"}}"#,
+            "profile \"a\" must be an object",
+        ),
+        (
+            r#"{"profiles": {"a": {"homoglyphs": "yes"}}}"#,
+            "profile \"a\": setting \"homoglyphs\" must be true or false",
+        ),
+    ] {
+        let message = Config::from_json(json).err().unwrap();
+        assert!(message.contains(error), "{message}");
+    }
+}
+
+#[test]
+fn the_command_line_lists_and_records_the_profile() {
+    let dir = std::env::temp_dir().join(format!("profiles-cli-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config = dir.join("config.json");
+    std::fs::write(&config, "{}").unwrap();
+    let input = dir.join("Report.docx");
+    std::fs::write(&input, support::word_package(BODY)).unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+            .args(args)
+            .arg("--config")
+            .arg(&config)
+            .output()
+            .unwrap()
+    };
+    let listing = run(&["list-profiles"]);
+    let mut args = vec!["-q", "--check", "--json", "--profile", "minimal"];
+    let input_arg = input.to_str().unwrap();
+    args.push(input_arg);
+    let checked = run(&args);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let listing = String::from_utf8(listing.stdout).unwrap();
+    assert!(listing.starts_with("minimal "), "{listing}");
+    assert!(
+        listing.contains("keeps 139 built-in characters"),
+        "{listing}"
+    );
+    assert!(
+        listing.contains("The built-in list as it is (default)"),
+        "{listing}"
+    );
+    let json: serde_json::Value = serde_json::from_slice(&checked.stdout).unwrap();
+    assert_eq!(json["profile"], "minimal");
+    assert_eq!(json["total"], 1);
+}
@@ -0,0 +1,78 @@
+use docx_cleaner::{Config, Revision};
+
+mod support;
+
+fn pruning() -> Config {
+    let mut config = Config::builtin();
+    config.prune_empty = true;
+    config
+}
+
+// A paragraph keeping its text next to a run of nothing but zero-width spaces, a paragraph of
+// them alone, and a paragraph that was empty already
+const BODY: &str = "<w:p><w:r><w:t>keep</w:t></w:r><w:r><w:rPr><w:b/></w:rPr><w:t>\u{200B}\u{200B}</w:t></w:r></w:p>\
+<w:p><w:pPr><w:jc w:val=\"center\"/></w:pPr><w:r><w:t>\u{200B}</w:t></w:r><w:proofErr w:type=\"spellStart\"/></w:p>\
+<w:p><w:pPr><w:jc w:val=\"center\"/></w:pPr></w:p>\
+<w:p><w:r><w:t>after</w:t></w:r></w:p>";
+
+#[test]
+fn emptied_runs_and_paragraphs_are_removed() {
+    let (report, body) = support::clean_body(pruning(), BODY);
+    assert_eq!(
+        body,
+        "<w:p><w:r><w:t>keep</w:t></w:r></w:p>\
+         <w:p><w:pPr><w:jc w:val=\"center\"/></w:pPr></w:p>\
+         <w:p><w:r><w:t>after</w:t></w:r></w:p>"
+    );
+    assert_eq!(report.pruned_runs, 2);
+    assert_eq!(report.pruned_paragraphs, 1);
+    assert_eq!(report.total_removed(), 3);
+}
+
+#[test]
+fn nothing_is_pruned_by_default() {
+    let (report, body) = support::clean_body(Config::builtin(), BODY);
+    assert!(
+        body.contains("<w:r><w:rPr><w:b/></w:rPr><w:t></w:t></w:r>"),
+        "{body}"
+    );
+    assert_eq!(report.pruned_runs + report.pruned_paragraphs, 0);
+}
+
+#[test]
+fn paragraphs_holding_more_than_text_are_kept() {
+    let body = "<w:p><w:r><w:t>\u{200B}</w:t><w:br/></w:r></w:p>\
+<w:p><w:pPr><w:sectPr/></w:pPr><w:r><w:t>\u{200B}</w:t></w:r></w:p>\
+<w:p><w:bookmarkStart w:id=\"0\" w:name=\"here\"/><w:r><w:t>\u{200B}</w:t></w:r><w:bookmarkEnd w:id=\"0\"/></w:p>\
+<w:tbl><w:tr><w:tc><w:p><w:r><w:t>\u{200B}</w:t></w:r></w:p></w:tc></w:tr></w:tbl>";
+    let (report, cleaned) = support::clean_body(pruning(), body);
+    assert_eq!(
+        cleaned,
+        "<w:p><w:r><w:t></w:t><w:br/></w:r></w:p>\
+         <w:p><w:pPr><w:sectPr/></w:pPr></w:p>\
+         <w:p><w:bookmarkStart w:id=\"0\" w:name=\"here\"/><w:bookmarkEnd w:id=\"0\"/></w:p>\
+         <w:tbl><w:tr><w:tc><w:p></w:p></w:tc></w:tr></w:tbl>"
+    );
+    assert_eq!(report.pruned_runs, 3);
+    assert_eq!(report.pruned_paragraphs, 0);
+}
+
+#[test]
+fn tracked_changes_delete_the_run_and_keep_the_paragraph() {
+    let mut config = pruning();
+    config.track_changes = Some(Revision {
+        author: "docx-cleaner".to_string(),
+        date: "2024-05-01T09:30:00Z".to_string(),
+    });
+    let (report, body) = support::clean_body(
+        config,
+        "<w:p><w:r><w:t>\u{200B}</w:t></w:r></w:p><w:p><w:r><w:t>after</w:t></w:r></w:p>",
+    );
+    assert!(body.starts_with("<w:p><w:del "), "{body}");
+    assert!(
+        body.contains("<w:r><w:delText>\u{200B}</w:delText></w:r></w:del></w:p>"),
+        "{body}"
+    );
+    assert_eq!(report.pruned_runs, 1);
+    assert_eq!(report.pruned_paragraphs, 0);
+}
@@ -0,0 +1,59 @@
+use docx_cleaner::{CleanReport, Cleaner, Config};
+
+mod support;
+
+// One character of each kind of replacement: deleted, a space, another character and a string
+const CONFIG: &str = r#"{
+    "U+200B": ["ZERO WIDTH SPACE", ""],
+    "U+2002": ["EN SPACE", " "],
+    "U+2011": ["NON-BREAKING HYPHEN", "-"],
+    "U+2026": ["HORIZONTAL ELLIPSIS", "..."]
+}"#;
+
+// The report and the text of the only run of the cleaned document
+fn clean(text: &str) -> (CleanReport, String) {
+    let config = Config::from_json(CONFIG).unwrap();
+    let input = support::text_package(text);
+    let cleaned = Cleaner::new(config).clean_package(&input).unwrap();
+    let report = cleaned.report.clone();
+    let xml = support::read_part(&cleaned.into_bytes().unwrap(), "word/document.xml");
+    let start = xml.find("<w:t>").unwrap() + "<w:t>".len();
+    let end = xml.find("</w:t>").unwrap();
+    (report, xml[start..end].to_string())
+}
+
+#[test]
+fn deleted_characters_leave_nothing() {
+    let (report, text) = clean("zero\u{200B}width");
+    assert_eq!(text, "zerowidth");
+    assert_eq!(report.chars[&'\u{200B}'], 1);
+}
+
+#[test]
+fn replace_with_space_gives_a_space() {
+    let (report, text) = clean("en\u{2002}space");
+    assert_eq!(text, "en space");
+    assert_eq!(report.chars[&'\u{2002}'], 1);
+}
+
+#[test]
+fn replace_with_a_character_gives_that_character() {
+    let (report, text) = clean("pages 4\u{2011}7");
+    assert_eq!(text, "pages 4-7");
+    assert_eq!(report.chars[&'\u{2011}'], 1);
+}
+
+#[test]
+fn replace_with_a_string_gives_the_whole_string() {
+    let (report, text) = clean("wait\u{2026}");
+    assert_eq!(text, "wait...");
+    assert_eq!(report.chars[&'\u{2026}'], 1);
+}
+
+#[test]
+fn every_kind_in_one_run_counts_once_per_character() {
+    let (report, text) = clean("a\u{200B}b\u{2002}c\u{2011}d\u{2026}\u{2026}");
+    assert_eq!(text, "ab c-d......");
+    assert_eq!(report.chars[&'\u{2026}'], 2);
+    assert_eq!(report.total_removed(), 5);
+}
@@ -0,0 +1,119 @@
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+mod support;
+
+// A directory of its own for each test, since the tests run in parallel
+fn directory(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "reproducible-output-{}-{}",
+        test,
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn clean(input: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .env("SOURCE_DATE_EPOCH", "1700000000")
+        .args(args)
+        .arg(input)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn two_runs_write_the_same_bytes() {
+    let dir = directory("same-bytes");
+    let input = dir.join("Report.docx");
+    std::fs::write(&input, support::text_package("in\u{200B}visible  text")).unwrap();
+    let mut copies = Vec::new();
+    for args in [&[][..], &["--annotate", "--highlight", "yellow"]] {
+        for run in ["first", "second"] {
+            let output = dir.join(format!("{}-{}.docx", run, args.len()));
+            let mut args = args.to_vec();
+            args.extend(["-q", "-o", output.to_str().unwrap()]);
+            let result = clean(&input, &args);
+            assert_eq!(result.status.code(), Some(1), "{:?}", result);
+            copies.push(std::fs::read(&output).unwrap());
+            // The comments are dated to the second, so the runs are a second apart to catch a
+            // date taken from the clock
+            std::thread::sleep(std::time::Duration::from_millis(1100));
+        }
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(copies[0] == copies[1], "the plain copies differ");
+    assert!(copies[2] == copies[3], "the annotated copies differ");
+    let comments = zip::ZipArchive::new(Cursor::new(&copies[2]))
+        .unwrap()
+        .by_name("word/comments.xml")
+        .map(|file| file.last_modified())
+        .unwrap();
+    assert_eq!(
+        (comments.year(), comments.month(), comments.day()),
+        (1980, 1, 1)
+    );
+}
+
+#[test]
+fn a_file_with_nothing_to_clean_can_be_skipped() {
+    let dir = directory("skip");
+    let input = dir.join("Clean.docx");
+    std::fs::write(&input, support::text_package("nothing to see")).unwrap();
+    let copy = clean(&input, &["--no-change-skip"]);
+    let in_place = clean(&input, &["--no-change-skip", "--in-place"]);
+    let mut written: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    written.sort();
+    // A file with something to clean is still written
+    let dirty = dir.join("Dirty.docx");
+    std::fs::write(&dirty, support::text_package("in\u{200B}visible")).unwrap();
+    let cleaned = clean(&dirty, &["-q", "--no-change-skip"]);
+    let cleaned_exists = dir.join("Dirty_cleaned.docx").exists();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(copy.status.code(), Some(0), "{:?}", copy);
+    let statistics = String::from_utf8_lossy(&copy.stdout);
+    assert!(
+        statistics.contains("Nothing to clean, nothing written\n"),
+        "{statistics}"
+    );
+    assert!(!statistics.contains("Saved as"), "{statistics}");
+    assert_eq!(in_place.status.code(), Some(0), "{:?}", in_place);
+    assert_eq!(written, ["Clean.docx"]);
+    assert_eq!(cleaned.status.code(), Some(1), "{:?}", cleaned);
+    assert!(cleaned_exists);
+}
+
+// Standard input is always written back to standard output
+#[test]
+fn the_stdin_filter_does_not_say_nothing_was_written() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("--stdin")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all("in\u{200B}visible\n".as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert_eq!(output.stdout, b"invisible\n");
+    let statistics = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        statistics.contains("Total characters removed: 1"),
+        "{statistics}"
+    );
+    assert!(!statistics.contains("nothing written"), "{statistics}");
+}
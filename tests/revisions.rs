@@ -0,0 +1,42 @@
+use docx_cleaner::{Cleaner, Config};
+
+mod support;
+
+// A reviewer's insertion full of left-to-right marks, and a deletion with one of its own
+const BODY: &str = "<w:p><w:r><w:t xml:space=\"preserve\">Kept </w:t></w:r>\
+<w:ins w:id=\"1\" w:author=\"Reviewer\" w:date=\"2024-01-01T00:00:00Z\">\
+<w:r><w:t>in\u{200E}ser\u{200E}ted</w:t></w:r></w:ins>\
+<w:del w:id=\"2\" w:author=\"Reviewer\" w:date=\"2024-01-01T00:00:00Z\">\
+<w:r><w:delText>de\u{200E}leted</w:delText></w:r></w:del></w:p>";
+
+fn clean() -> (usize, String) {
+    let input = support::word_package(BODY);
+    let cleaned = Cleaner::new(Config::builtin())
+        .clean_package(&input)
+        .unwrap();
+    let removed = cleaned.report.total_removed();
+    let xml = support::read_part(&cleaned.into_bytes().unwrap(), "word/document.xml");
+    (removed, xml)
+}
+
+#[test]
+fn inserted_runs_are_cleaned_like_normal_runs() {
+    let (_, xml) = clean();
+    assert!(
+        xml.contains(
+            "<w:ins w:id=\"1\" w:author=\"Reviewer\" w:date=\"2024-01-01T00:00:00Z\">\
+             <w:r><w:t>inserted</w:t></w:r></w:ins>"
+        ),
+        "{xml}"
+    );
+}
+
+#[test]
+fn deleted_text_is_left_alone_and_not_counted() {
+    let (removed, xml) = clean();
+    assert!(
+        xml.contains("<w:r><w:delText>de\u{200E}leted</w:delText></w:r></w:del>"),
+        "{xml}"
+    );
+    assert_eq!(removed, 2);
+}
@@ -0,0 +1,57 @@
+use docx_cleaner::Config;
+
+mod support;
+
+// Furigana over a word, with a zero width space in both the reading and the base, followed by
+// text with an ideographic space
+const FURIGANA: &str = "<w:p><w:r><w:ruby><w:rubyPr><w:rubyAlign w:val=\"distributeSpace\"/>\
+<w:hps w:val=\"10\"/><w:hpsRaise w:val=\"18\"/><w:hpsBaseText w:val=\"21\"/><w:lid w:val=\"ja-JP\"/></w:rubyPr>\
+<w:rt><w:r><w:t>\u{304B}\u{3093}\u{200B}\u{3058}</w:t></w:r></w:rt>\
+<w:rubyBase><w:r><w:t>\u{6F22}\u{200B}\u{5B57}</w:t></w:r></w:rubyBase></w:ruby></w:r>\
+<w:r><w:t>\u{3067}\u{3059}\u{3000}\u{306D}</w:t></w:r></w:p>";
+
+#[test]
+fn furigana_is_cleaned_without_breaking_the_ruby() {
+    let (report, body) = support::clean_body(Config::builtin(), FURIGANA);
+    assert!(!body.contains('\u{200B}'), "{body}");
+    assert!(
+        body.contains("<w:rt><w:r><w:t>\u{304B}\u{3093}\u{3058}</w:t></w:r></w:rt>"),
+        "{body}"
+    );
+    assert!(
+        body.contains("<w:rubyBase><w:r><w:t>\u{6F22}\u{5B57}</w:t></w:r></w:rubyBase>"),
+        "{body}"
+    );
+    assert!(body.contains("<w:hpsBaseText w:val=\"21\"/>"), "{body}");
+    assert_eq!(report.chars[&'\u{200B}'], 2);
+    assert_eq!(report.parts["body"], 2);
+}
+
+#[test]
+fn the_ideographic_space_is_only_flagged() {
+    let (report, body) = support::clean_body(Config::builtin(), FURIGANA);
+    assert!(body.contains("\u{3067}\u{3059}\u{3000}\u{306D}"), "{body}");
+    assert!(!report.chars.contains_key(&'\u{3000}'));
+    assert_eq!(report.kept[&'\u{3000}'], 1);
+}
+
+#[test]
+fn the_ideographic_space_can_be_replaced() {
+    let config = Config::from_json(r#"{"\u3000": ["IDEOGRAPHIC SPACE", " "]}"#).unwrap();
+    let (report, body) = support::clean_body(config, FURIGANA);
+    assert!(body.contains("\u{3067}\u{3059} \u{306D}"), "{body}");
+    assert_eq!(report.chars[&'\u{3000}'], 1);
+}
+
+// The reading is set above the base, so a space at the end of one does not make the space at
+// the start of the other a second one
+#[test]
+fn the_spaces_of_the_reading_and_the_base_are_collapsed_apart() {
+    let body = "<w:p><w:r><w:ruby><w:rubyPr/>\
+<w:rt><w:r><w:t xml:space=\"preserve\">\u{304B}\u{3093}\u{3058} </w:t></w:r></w:rt>\
+<w:rubyBase><w:r><w:t xml:space=\"preserve\"> \u{6F22}\u{5B57}</w:t></w:r></w:rubyBase></w:ruby></w:r></w:p>";
+    let (report, body) = support::clean_body(Config::builtin(), body);
+    assert!(body.contains(">\u{304B}\u{3093}\u{3058} </w:t>"), "{body}");
+    assert!(body.contains("> \u{6F22}\u{5B57}</w:t>"), "{body}");
+    assert_eq!(report.collapsed_spaces, 0);
+}
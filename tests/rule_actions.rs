@@ -0,0 +1,140 @@
+use docx_cleaner::{Cleaner, Config, GeneralCategory, RuleAction};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+mod support;
+
+const CONFIG: &str = r#"{
+    "U+200B": {"name": "ZERO WIDTH SPACE", "action": "delete"},
+    "U+2002": {"action": "replace", "replacement": "_"},
+    "U+200D": {"action": "flag"},
+    "U+00A0": {"name": "NO-BREAK SPACE", "action": "flag"},
+    "U+2060": "flag",
+    "U+200C": {"action": "keep"}
+}"#;
+
+// A directory of its own for each test, since the tests run in parallel
+fn directory(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rule-actions-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn check(dir: &Path, text: &str, args: &[&str]) -> Output {
+    let config = dir.join("config.json");
+    std::fs::write(&config, CONFIG).unwrap();
+    let input = dir.join("Report.docx");
+    std::fs::write(&input, support::text_package(text)).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("--check")
+        .arg("--config")
+        .arg(&config)
+        .args(args)
+        .arg(&input)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn each_entry_can_spell_out_its_action() {
+    let config = Config::from_json(CONFIG).unwrap();
+    assert_eq!(config.action('\u{200B}'), Some(RuleAction::Delete));
+    assert_eq!(config.action('\u{2002}'), Some(RuleAction::Replace));
+    assert_eq!(config.replacement('\u{2002}'), "_");
+    assert_eq!(config.action('\u{200D}'), Some(RuleAction::Flag));
+    assert_eq!(config.action('\u{2060}'), Some(RuleAction::Flag));
+    assert_eq!(config.action('\u{200C}'), Some(RuleAction::Keep));
+    // A flagged character with a name is added to the list
+    assert_eq!(config.action('\u{00A0}'), Some(RuleAction::Flag));
+    assert_eq!(config.name('\u{00A0}'), "NO-BREAK SPACE");
+    assert_eq!(config.action('a'), None);
+
+    let input = support::text_package("a\u{200B}b\u{2002}c\u{00A0}d\u{00A0}e\u{200D}");
+    let cleaned = Cleaner::new(config).clean_package(&input).unwrap();
+    let report = &cleaned.report;
+    assert_eq!(report.chars[&'\u{200B}'], 1);
+    assert_eq!(report.chars[&'\u{2002}'], 1);
+    assert!(!report.chars.contains_key(&'\u{00A0}'));
+    assert_eq!(report.kept[&'\u{00A0}'], 2);
+    assert_eq!(report.kept[&'\u{200D}'], 1);
+
+    let message = Config::from_json(r#"{"U+200B": {"action": "flag", "replacement": " "}}"#)
+        .err()
+        .unwrap();
+    assert!(
+        message.contains("\"U+200B\" is set to \"flag\" and cannot have a replacement"),
+        "{message}"
+    );
+}
+
+#[test]
+fn keep_takes_characters_out_of_the_categories() {
+    let dir = directory("categories");
+    let config = dir.join("config.json");
+    std::fs::write(&config, r#"{"U+00AD": "keep"}"#).unwrap();
+    let with_categories =
+        Config::load_profile(Some(&config), None, "standard", &[GeneralCategory::Format]);
+    let without = Config::load_profile(Some(&config), None, "standard", &[]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let with_categories = with_categories.unwrap();
+    assert_eq!(with_categories.action('\u{00AD}'), Some(RuleAction::Keep));
+    assert_eq!(with_categories.action('\u{2061}'), Some(RuleAction::Delete));
+    // Without the categories there is nothing to keep
+    let message = without.err().unwrap();
+    assert!(message.contains("is not in the built-in list"), "{message}");
+}
+
+#[test]
+fn the_statistics_separate_deleted_replaced_and_flagged() {
+    let dir = directory("statistics");
+    let output = check(&dir, "a\u{200B}b\u{2002}c\u{00A0}d\u{00A0}e", &[]);
+    let json = check(
+        &dir,
+        "a\u{200B}b\u{2002}c\u{00A0}d\u{00A0}e",
+        &["-q", "--json"],
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    let statistics = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        statistics.contains("Deleted: 1, replaced: 1\n"),
+        "{statistics}"
+    );
+    assert!(
+        statistics.contains(
+            "Flagged (left in the text):\nNO-BREAK SPACE (U+00A0) - \u{00A0}: 2\n\
+             Total characters flagged: 2\n"
+        ),
+        "{statistics}"
+    );
+    let json: Value = serde_json::from_slice(&json.stdout).unwrap();
+    assert_eq!(json["deleted"], 1);
+    assert_eq!(json["replaced"], 1);
+    assert_eq!(json["flagged"], 2);
+}
+
+#[test]
+fn flagged_characters_only_fail_the_check_with_strict_flags() {
+    let dir = directory("strict-flags");
+    let lenient = check(&dir, "a\u{00A0}b", &["-q"]);
+    let strict = check(&dir, "a\u{00A0}b", &["-q", "--strict-flags"]);
+    let listing = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .args(["list-chars", "--config"])
+        .arg(dir.join("config.json"))
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(lenient.status.code(), Some(0), "{:?}", lenient);
+    assert_eq!(strict.status.code(), Some(1), "{:?}", strict);
+    let listing = String::from_utf8_lossy(&listing.stdout);
+    assert!(
+        listing
+            .lines()
+            .any(|line| line.starts_with("U+00A0") && line.contains("<flag>")),
+        "{listing}"
+    );
+}
@@ -0,0 +1,124 @@
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+mod support;
+
+const DOCUMENT: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\"><w:body>\
+<w:p><w:r><w:t>clean</w:t></w:r></w:p>\
+<w:p><w:r><w:t>first</w:t></w:r><w:r><w:t>zero\u{200B}width</w:t></w:r></w:p>\
+</w:body></w:document>";
+
+// A directory of its own for each test, with a document and a text file to check
+fn directory(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sarif-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("My Thesis.docx"),
+        support::document_package(DOCUMENT),
+    )
+    .unwrap();
+    std::fs::write(dir.join("notes.txt"), "line one\nline\u{FEFF} two\n").unwrap();
+    dir
+}
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .args(args)
+        .arg(dir.join("My Thesis.docx"))
+        .arg(dir.join("notes.txt"))
+        .output()
+        .unwrap()
+}
+
+fn object<'a>(value: &'a Value, property: &str) -> &'a serde_json::Map<String, Value> {
+    value[property]
+        .as_object()
+        .unwrap_or_else(|| panic!("{property} must be an object"))
+}
+
+fn array<'a>(value: &'a Value, property: &str) -> &'a Vec<Value> {
+    value[property]
+        .as_array()
+        .unwrap_or_else(|| panic!("{property} must be an array"))
+}
+
+// The required properties and types of the SARIF 2.1.0 schema for the objects written, since no
+// schema validator is at hand
+fn validate(log: &Value) {
+    assert_eq!(log["version"], "2.1.0");
+    for run in array(log, "runs") {
+        let driver = &run["tool"]["driver"];
+        assert!(driver["name"].is_string());
+        let rules = array(driver, "rules");
+        for rule in rules {
+            assert!(rule["id"].is_string());
+            assert!(object(rule, "fullDescription")["text"].is_string());
+            assert!(object(rule, "shortDescription")["text"].is_string());
+            let level = &rule["defaultConfiguration"]["level"];
+            assert!(["none", "note", "warning", "error"].contains(&level.as_str().unwrap()));
+        }
+        for result in array(run, "results") {
+            assert!(object(result, "message")["text"].is_string());
+            let index = result["ruleIndex"].as_u64().unwrap() as usize;
+            assert_eq!(rules[index]["id"], result["ruleId"]);
+            for location in array(result, "locations") {
+                let physical = object(location, "physicalLocation");
+                assert!(physical["artifactLocation"]["uri"].is_string());
+                if let Some(region) = physical.get("region") {
+                    assert!(region["startLine"].as_u64().unwrap() >= 1);
+                }
+                if let Some(logical) = location.get("logicalLocations") {
+                    for logical in logical.as_array().unwrap() {
+                        assert!(logical["fullyQualifiedName"].is_string());
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn scan_writes_a_sarif_log() {
+    let dir = directory("scan");
+    let output = run(&dir, &["scan", "--format", "sarif"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    let log: Value = serde_json::from_slice(&output.stdout).unwrap();
+    validate(&log);
+
+    let run = &log["runs"][0];
+    let rules = array(&run["tool"]["driver"], "rules");
+    let rule = rules.iter().find(|rule| rule["id"] == "U+200B").unwrap();
+    assert_eq!(rule["fullDescription"]["text"], "ZERO WIDTH SPACE (U+200B) is removed");
+
+    let results = array(run, "results");
+    assert_eq!(results.len(), 2);
+    let location = &results[0]["locations"][0];
+    assert_eq!(results[0]["ruleId"], "U+200B");
+    assert!(location["physicalLocation"]["artifactLocation"]["uri"]
+        .as_str()
+        .unwrap()
+        .ends_with("/My%20Thesis.docx"));
+    assert_eq!(
+        location["logicalLocations"][0]["fullyQualifiedName"],
+        "body/paragraph 2/run 2"
+    );
+    let location = &results[1]["locations"][0];
+    assert_eq!(results[1]["ruleId"], "U+FEFF");
+    assert_eq!(location["physicalLocation"]["region"]["startLine"], 2);
+}
+
+#[test]
+fn check_writes_the_same_log() {
+    let dir = directory("check");
+    let scanned = run(&dir, &["scan", "--format", "sarif"]);
+    let checked = run(&dir, &["--check", "--format", "sarif"]);
+    let cleaned = run(&dir, &["--format", "sarif"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(checked.status.code(), Some(1), "{:?}", checked);
+    assert_eq!(checked.stdout, scanned.stdout);
+    // Cleaning would not find everything that was there, so it is refused
+    assert_eq!(cleaned.status.code(), Some(2), "{:?}", cleaned);
+}
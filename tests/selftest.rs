@@ -0,0 +1,42 @@
+use docx_cleaner::builtin_problems;
+use std::process::Command;
+
+#[test]
+fn the_embedded_config_has_no_problems() {
+    assert!(builtin_problems().is_empty(), "{:?}", builtin_problems());
+}
+
+#[test]
+fn the_selftest_passes_every_stage_without_writing_anything() {
+    let dir = std::env::temp_dir().join(format!("selftest-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("selftest")
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let written = std::fs::read_dir(&dir).unwrap().count();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+    let stages = String::from_utf8_lossy(&output.stdout);
+    let passed: Vec<&str> = stages
+        .lines()
+        .filter_map(|line| line.strip_prefix("PASS  "))
+        .filter_map(|line| line.split(':').next())
+        .collect();
+    assert_eq!(
+        passed,
+        [
+            "Embedded config",
+            "Test document",
+            "Clean",
+            "Output",
+            "Counters"
+        ],
+        "{stages}"
+    );
+    assert!(!stages.contains("FAIL"), "{stages}");
+    assert!(stages.ends_with("Self-test passed\n"), "{stages}");
+    assert_eq!(written, 0);
+}
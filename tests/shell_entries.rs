@@ -0,0 +1,18 @@
+// On Windows the subcommands change the registry of the user running the tests
+#![cfg(not(windows))]
+
+use std::process::Command;
+
+#[test]
+fn the_explorer_entries_are_only_changed_on_windows() {
+    for subcommand in ["register-shell", "unregister-shell"] {
+        let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+            .arg(subcommand)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(0), "{:?}", output);
+        let message = String::from_utf8_lossy(&output.stdout);
+        assert!(message.contains("only"), "{message}");
+        assert!(message.contains("nothing was changed"), "{message}");
+    }
+}
@@ -0,0 +1,53 @@
+use docx_cleaner::Config;
+
+mod support;
+
+// A word hyphenated by hand with a w:softHyphen element between its halves, which Word breaks
+// the line at, one with the character itself, and a number range with a w:noBreakHyphen
+const BODY: &str = "<w:p><w:r><w:t>docu</w:t><w:softHyphen/><w:t>ment</w:t></w:r>\
+<w:r><w:t xml:space=\"preserve\"> hyphen\u{AD}ation </w:t></w:r>\
+<w:r><w:t>pages 4</w:t><w:noBreakHyphen/><w:t>7</w:t></w:r></w:p>";
+
+fn config(json: &str) -> Config {
+    let mut config = Config::from_json(json).unwrap();
+    config.record_locations = true;
+    config
+}
+
+#[test]
+fn deleted_soft_hyphens_join_the_word() {
+    let (report, body) = support::clean_body(config(r#"{"\u00AD": ["SOFT HYPHEN", ""]}"#), BODY);
+    assert!(
+        body.contains("<w:r><w:t>docu</w:t><w:t>ment</w:t></w:r>"),
+        "{body}"
+    );
+    assert!(body.contains("> hyphenation </w:t>"), "{body}");
+    assert!(!body.contains("softHyphen"), "{body}");
+    assert_eq!(report.chars[&'\u{AD}'], 2);
+    let first = &report.replacements[0];
+    assert_eq!(first.character, '\u{AD}');
+    assert_eq!((first.paragraph, first.run), (Some(1), Some(1)));
+    assert_eq!(first.context(), "docu\u{27E8}U+00AD\u{27E9}ment hyphen\u{27E8}U+00AD\u{27E9}ation pa");
+}
+
+#[test]
+fn no_break_hyphens_become_the_replacement() {
+    let (report, body) = support::clean_body(Config::builtin(), BODY);
+    assert!(
+        body.contains("<w:t>pages 4</w:t><w:t xml:space=\"preserve\">-</w:t><w:t>7</w:t>"),
+        "{body}"
+    );
+    assert_eq!(report.chars[&'\u{2011}'], 1);
+    // Soft hyphens are not in the built-in list
+    assert!(body.contains("<w:softHyphen/>"), "{body}");
+    assert!(!report.chars.contains_key(&'\u{AD}'));
+}
+
+#[test]
+fn reported_hyphens_stay() {
+    let (report, body) = support::clean_body(config(r#"{"\u2011": "report"}"#), BODY);
+    assert!(body.contains("<w:softHyphen/>"), "{body}");
+    assert!(body.contains("<w:noBreakHyphen/>"), "{body}");
+    assert_eq!(report.kept[&'\u{2011}'], 1);
+    assert!(!report.is_modified());
+}
@@ -0,0 +1,201 @@
+// Packages for the tests, shared with `mod support;` from a test and
+// `#[path = "../tests/support/mod.rs"]` from benches/clean.rs: small hand-written ones around a
+// given body, and documents generated with docx-rs for the benchmarks and the tests that need a
+// realistic amount of text. Not every includer uses every generator.
+#![allow(dead_code)]
+
+use docx_cleaner::{CleanReport, Cleaner, Config};
+use docx_rs::*;
+use std::io::{Cursor, Read, Write};
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+pub const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/></Types>"#;
+
+pub const PACKAGE_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/></Relationships>"#;
+
+/// A zip package of the given parts, in this order
+pub fn zip_package(parts: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, data) in parts {
+        zip.start_file(*name, FileOptions::default()).unwrap();
+        zip.write_all(data).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+/// word/document.xml around the given body, declaring the given namespaces besides `w:`, e.g.
+/// `xmlns:m="…"`
+pub fn document_xml(namespaces: &str, body: &str) -> String {
+    let namespaces = if namespaces.is_empty() {
+        String::new()
+    } else {
+        format!(" {}", namespaces)
+    };
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\"{}>\
+         <w:body>{}</w:body></w:document>",
+        namespaces, body
+    )
+}
+
+/// A .docx package of nothing but the given word/document.xml
+pub fn document_package(document: &str) -> Vec<u8> {
+    zip_package(&[
+        ("[Content_Types].xml", CONTENT_TYPES.as_bytes()),
+        ("_rels/.rels", PACKAGE_RELS.as_bytes()),
+        ("word/document.xml", document.as_bytes()),
+    ])
+}
+
+/// A .docx package of nothing but word/document.xml around the given body
+pub fn word_package(body: &str) -> Vec<u8> {
+    document_package(&document_xml("", body))
+}
+
+/// The text of the part of a package named `name`
+pub fn read_part(buf: &[u8], name: &str) -> String {
+    let mut archive = zip::ZipArchive::new(Cursor::new(buf)).unwrap();
+    let mut text = String::new();
+    archive
+        .by_name(name)
+        .unwrap()
+        .read_to_string(&mut text)
+        .unwrap();
+    text
+}
+
+/// The report of cleaning `input` with `config`, and word/document.xml of the cleaned package
+pub fn clean_package(config: Config, input: &[u8]) -> (CleanReport, String) {
+    let cleaned = Cleaner::new(config).clean_package(input).unwrap();
+    let report = cleaned.report.clone();
+    let xml = read_part(&cleaned.into_bytes().unwrap(), "word/document.xml");
+    (report, xml)
+}
+
+/// The text of `xml` between the first `start` and the `end` after it
+pub fn between(xml: &str, start: &str, end: &str) -> String {
+    let start = xml.find(start).unwrap() + start.len();
+    let end = start + xml[start..].find(end).unwrap();
+    xml[start..end].to_string()
+}
+
+/// The report of cleaning a word_package around `body`, and the body of the cleaned document
+pub fn clean_body(config: Config, body: &str) -> (CleanReport, String) {
+    let (report, xml) = clean_package(config, &word_package(body));
+    (report, between(&xml, "<w:body>", "</w:body>"))
+}
+
+/// A .docx package of a single paragraph of a single run of the given text
+pub fn text_package(text: &str) -> Vec<u8> {
+    word_package(&format!("<w:p><w:r><w:t>{}</w:t></w:r></w:p>", text))
+}
+
+pub const TEXT: &str = "The quick brown fox jumps over the lazy dog while the results are \
+                        discussed in more detail in the following chapter.";
+
+/// Which runs of a synthetic document have characters to clean
+#[derive(Clone, Copy, Debug)]
+pub enum Contamination {
+    /// Nothing to clean anywhere, the common case
+    None,
+    /// One run in this many, e.g. 100 for 1% of them
+    OneIn(usize),
+    /// Every run, e.g. text pasted from a PDF
+    Every,
+}
+
+// Cycles through a zero width space, a word joiner, a double space and a byte order mark
+fn contaminated(i: usize) -> String {
+    match i % 4 {
+        0 => TEXT.replacen("fox", "fo\u{200B}x", 1),
+        1 => TEXT.replacen("results", "re\u{2060}sults", 1),
+        2 => TEXT.replacen(" over", "  over", 1),
+        _ => TEXT.replacen("chapter", "chap\u{FEFF}ter", 1),
+    }
+}
+
+/// A .docx package of paragraphs of three runs each, contaminated as given
+pub fn synthetic_document(paragraphs: usize, contamination: Contamination) -> Vec<u8> {
+    let mut docx = Docx::new();
+    for i in 0..paragraphs {
+        let mut paragraph = Paragraph::new();
+        for j in 0..3 {
+            let run = 3 * i + j;
+            let text = match contamination {
+                Contamination::OneIn(n) if run % n == 0 => contaminated(run / n),
+                Contamination::Every => contaminated(run),
+                _ => TEXT.to_string(),
+            };
+            paragraph = paragraph.add_run(Run::new().add_text(text));
+        }
+        docx = docx.add_paragraph(paragraph);
+    }
+    pack(docx)
+}
+
+/// Roughly a 400 page dissertation: paragraphs of a few runs, a table every few pages, and a zero
+/// width space, word joiner or double space here and there
+pub fn dissertation() -> Vec<u8> {
+    const PARAGRAPHS: usize = 8000;
+    const TABLE_EVERY: usize = 40;
+
+    let mut docx = Docx::new()
+        .header(Header::new().add_paragraph(
+            Paragraph::new().add_run(Run::new().add_text("Chapter\u{200B} header")),
+        ));
+    for i in 0..PARAGRAPHS {
+        let marked = match i % 5 {
+            0 => TEXT.replacen("fox", "fo\u{200B}x", 1),
+            1 => TEXT.replacen("results", "re\u{2060}sults", 1),
+            2 => TEXT.replacen(" over", "  over", 1),
+            _ => TEXT.to_string(),
+        };
+        let mut paragraph = Paragraph::new();
+        for _ in 0..3 {
+            paragraph = paragraph.add_run(Run::new().add_text(&marked));
+        }
+        docx = docx.add_paragraph(paragraph);
+
+        if i % TABLE_EVERY == 0 {
+            let rows = (0..10)
+                .map(|_| {
+                    TableRow::new(
+                        (0..4)
+                            .map(|_| {
+                                TableCell::new().add_paragraph(
+                                    Paragraph::new()
+                                        .add_run(Run::new().add_text("cell\u{FEFF} 42")),
+                                )
+                            })
+                            .collect(),
+                    )
+                })
+                .collect();
+            docx = docx.add_table(Table::new(rows));
+        }
+    }
+    pack(docx)
+}
+
+/// About this many bytes of text with nothing to clean, as a document model
+pub fn clean_model(text_bytes: usize) -> Docx {
+    let mut docx = Docx::new();
+    for _ in 0..text_bytes / (3 * TEXT.len()) {
+        let mut paragraph = Paragraph::new();
+        for _ in 0..3 {
+            paragraph = paragraph.add_run(Run::new().add_text(TEXT));
+        }
+        docx = docx.add_paragraph(paragraph);
+    }
+    docx
+}
+
+fn pack(docx: Docx) -> Vec<u8> {
+    let mut packed = Cursor::new(Vec::new());
+    docx.build().pack(&mut packed).unwrap();
+    packed.into_inner()
+}
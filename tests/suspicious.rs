@@ -0,0 +1,69 @@
+use docx_cleaner::{unlisted_name, Cleaner, Config};
+use std::process::{Command, Output};
+
+mod support;
+
+// Hidden ASCII in tag characters, a private use character and an unassigned code point, none of
+// which the built-in list has
+const DOCUMENT: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\"><w:body>\
+<w:p><w:r><w:t>hi\u{E0061}\u{E0061}\u{E0062} there</w:t></w:r></w:p>\
+<w:p><w:r><w:t>logo \u{E000} and \u{378}</w:t></w:r></w:p>\
+</w:body></w:document>";
+
+fn check(test: &str, args: &[&str]) -> Output {
+    let dir = std::env::temp_dir().join(format!("suspicious-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("Report.docx");
+    std::fs::write(&input, support::document_package(DOCUMENT)).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("--check")
+        .args(args)
+        .arg(&input)
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    output
+}
+
+#[test]
+fn unlisted_format_private_and_unassigned_characters_are_reported_and_kept() {
+    let report = Cleaner::new(Config::builtin())
+        .scan_package(&support::document_package(DOCUMENT))
+        .unwrap();
+    assert_eq!(report.total_removed(), 0);
+    assert_eq!(report.suspicious.len(), 4, "{:?}", report.suspicious);
+    assert_eq!(report.suspicious[&'\u{E0061}'], 2);
+    assert_eq!(report.suspicious[&'\u{E000}'], 1);
+    assert_eq!(report.suspicious[&'\u{378}'], 1);
+    assert_eq!(
+        report.suspicious_contexts[&'\u{E0061}'],
+        "hi\u{27E8}U+E0061\u{27E9}\u{27E8}U+E0061\u{27E9}\u{27E8}U+E0062\u{27E9} there"
+    );
+    assert_eq!(unlisted_name('\u{E0061}'), "TAG LATIN SMALL LETTER A");
+    assert_eq!(unlisted_name('\u{E000}'), "<private use>");
+    assert_eq!(unlisted_name('\u{378}'), "<unassigned>");
+}
+
+#[test]
+fn the_statistics_list_them_apart_from_the_removed_characters() {
+    let output = check("statistics", &[]);
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+    let statistics = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        statistics.contains(
+            "Found but not configured (left in the text):\nU+E0061 TAG LATIN SMALL LETTER A: 2"
+        ),
+        "{statistics}"
+    );
+    assert!(
+        statistics.contains("Total suspicious characters: 5"),
+        "{statistics}"
+    );
+}
+
+#[test]
+fn suspicious_as_error_makes_the_check_fail() {
+    let output = check("error", &["-q", "--suspicious-as-error", "--exit-zero"]);
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+}
@@ -0,0 +1,45 @@
+use docx_cleaner::{CleanReport, Config};
+
+mod support;
+
+const NAMESPACES: &str =
+    "xmlns:wp=\"http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing\" \
+xmlns:a=\"http://schemas.openxmlformats.org/drawingml/2006/main\" \
+xmlns:wps=\"http://schemas.microsoft.com/office/word/2010/wordprocessingShape\"";
+
+// A shape with a text box, as Word 2010 and later write it, hyphenated by hand with U+00AD
+const SHAPE: &str = "<w:r><w:drawing><wp:anchor distT=\"0\" distB=\"0\" distL=\"0\" distR=\"0\">\
+<wp:extent cx=\"1828800\" cy=\"457200\"/><wp:docPr id=\"1\" name=\"Text Box 1\"/>\
+<a:graphic><a:graphicData uri=\"http://schemas.microsoft.com/office/word/2010/wordprocessingShape\">\
+<wps:wsp><wps:spPr><a:prstGeom prst=\"rect\"/></wps:spPr>\
+<wps:txbx><w:txbxContent><w:p><w:r><w:rPr><w:b/></w:rPr><w:t>Spring cata\u{AD}logue</w:t></w:r></w:p>\
+</w:txbxContent></wps:txbx><wps:bodyPr/></wps:wsp></a:graphicData></a:graphic></wp:anchor>\
+</w:drawing></w:r>";
+
+// The report and word/document.xml of the cleaned document
+fn clean(config: Config) -> (CleanReport, String) {
+    let body = format!(
+        "<w:p><w:r><w:t xml:space=\"preserve\">See </w:t></w:r>{}</w:p>",
+        SHAPE
+    );
+    let input = support::document_package(&support::document_xml(NAMESPACES, &body));
+    support::clean_package(config, &input)
+}
+
+#[test]
+fn soft_hyphens_in_a_shape_are_removed_when_listed() {
+    let config = Config::from_json(r#"{"U+00AD": ["SOFT HYPHEN", ""]}"#).unwrap();
+    let (report, xml) = clean(config);
+    assert_eq!(report.part_chars["textbox"][&'\u{AD}'], 1);
+    assert_eq!(report.total_removed(), 1);
+    // Only the text changes, the markup of the shape around it stays as it was
+    let expected = SHAPE.replace("cata\u{AD}logue", "catalogue");
+    assert!(xml.contains(&expected), "{xml}");
+}
+
+#[test]
+fn soft_hyphens_are_kept_by_the_builtin_list() {
+    let (report, xml) = clean(Config::builtin());
+    assert_eq!(report.total_removed(), 0);
+    assert!(xml.contains(SHAPE), "{xml}");
+}
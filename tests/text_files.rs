@@ -0,0 +1,34 @@
+use docx_cleaner::{Cleaner, Config, Error};
+
+fn clean(text: &[u8], markdown: bool) -> (String, usize) {
+    let cleaned = Cleaner::new(Config::builtin())
+        .clean_text(text, markdown)
+        .unwrap();
+    let removed = cleaned.report.total_removed();
+    (String::from_utf8(cleaned.into_bytes()).unwrap(), removed)
+}
+
+#[test]
+fn the_byte_order_mark_is_removed_and_line_endings_kept() {
+    let (text, removed) = clean(
+        "\u{FEFF}zero\u{200B}width  text\r\n  indented\nlast".as_bytes(),
+        false,
+    );
+    assert_eq!(text, "zerowidth text\r\n  indented\nlast");
+    assert_eq!(removed, 2);
+}
+
+#[test]
+fn spaces_in_markdown_code_blocks_are_kept() {
+    let (text, _) = clean(
+        "a  b\n```\nlet  x\u{200B} = 1;\n```\nc  d\n".as_bytes(),
+        true,
+    );
+    assert_eq!(text, "a b\n```\nlet  x = 1;\n```\nc d\n");
+}
+
+#[test]
+fn other_encodings_are_rejected() {
+    let result = Cleaner::new(Config::builtin()).clean_text(b"caf\xe9\n", false);
+    assert!(matches!(result, Err(Error::InvalidText(_))));
+}
@@ -0,0 +1,98 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+mod support;
+
+// A folder of documents with a different number of zero width spaces each, the larger ones
+// first so that they tend to finish last, and a broken one in the middle
+fn folder(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("threads-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for i in 0..24 {
+        let text = format!(
+            "{}{}",
+            "text ".repeat((24 - i) * 2000),
+            "x\u{200B}".repeat(i)
+        );
+        std::fs::write(
+            dir.join(format!("{:02}.docx", i)),
+            support::text_package(&text),
+        )
+        .unwrap();
+    }
+    std::fs::write(dir.join("12b.docx"), b"not a document").unwrap();
+    dir
+}
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .args(args)
+        .arg(dir)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn the_output_does_not_depend_on_the_threads() {
+    let dir = folder("order");
+    let serial = run(&dir, &["--check", "--threads", "1"]);
+    let parallel = run(&dir, &["--check", "--threads", "8"]);
+    let json = run(&dir, &["--check", "-q", "--json=array", "--threads", "8"]);
+    let serial_json = run(&dir, &["--check", "-q", "--json=array", "--threads", "1"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(serial.status.code(), Some(3), "{:?}", serial);
+    assert_eq!(parallel.status.code(), serial.status.code());
+    assert_eq!(
+        String::from_utf8_lossy(&parallel.stdout),
+        String::from_utf8_lossy(&serial.stdout)
+    );
+    assert_eq!(json.stdout, serial_json.stdout);
+    let stdout = String::from_utf8(serial.stdout).unwrap();
+    assert!(
+        stdout.contains("Total characters found:   276\n"),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn every_copy_is_written_with_several_threads() {
+    let dir = folder("copies");
+    let output = dir.join("out");
+    std::fs::create_dir_all(&output).unwrap();
+    let result = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .args(["-q", "--threads", "4", "-o"])
+        .arg(&output)
+        .args((0..24).map(|i| dir.join(format!("{:02}.docx", i))))
+        .output()
+        .unwrap();
+    let written = std::fs::read_dir(&output).unwrap().count();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(result.status.code(), Some(1), "{:?}", result);
+    assert_eq!(written, 24);
+}
+
+#[test]
+fn inputs_written_to_the_same_copy_are_refused_up_front() {
+    let dir = std::env::temp_dir().join(format!("threads-collision-{}", std::process::id()));
+    for sub in ["one", "two", "out"] {
+        std::fs::create_dir_all(dir.join(sub)).unwrap();
+    }
+    std::fs::write(dir.join("one/a.docx"), support::text_package("one\u{200B}")).unwrap();
+    std::fs::write(dir.join("two/a.docx"), support::text_package("two\u{200B}")).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("-o")
+        .arg(dir.join("out"))
+        .arg(dir.join("one/a.docx"))
+        .arg(dir.join("two/a.docx"))
+        .output()
+        .unwrap();
+    let written = std::fs::read_dir(dir.join("out")).unwrap().count();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+    let error = String::from_utf8_lossy(&output.stderr);
+    assert!(error.contains("would both be written to"), "{error}");
+    assert_eq!(written, 0);
+}
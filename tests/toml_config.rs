@@ -0,0 +1,114 @@
+use docx_cleaner::{Config, ConfigFormat};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/configs")
+        .join(name)
+}
+
+fn load(path: &Path, format: Option<ConfigFormat>, profile: &str) -> Result<Config, String> {
+    Config::load_profile(Some(path), format, profile, &[])
+}
+
+// Everything the rules decide, in a form that can be compared
+fn rules(config: &Config) -> (String, Vec<(char, String, String, bool)>) {
+    let listed = config
+        .listed()
+        .into_iter()
+        .map(|c| {
+            (
+                c,
+                config.name(c).to_string(),
+                config.replacement(c).to_string(),
+                config.is_reported(c),
+            )
+        })
+        .collect();
+    (config.to_json(), listed)
+}
+
+#[test]
+fn json_and_toml_give_the_same_rules() {
+    for profile in ["standard", "house"] {
+        let json = load(&fixture("rules.json"), None, profile).unwrap();
+        let toml = load(&fixture("rules.toml"), None, profile).unwrap();
+        assert_eq!(rules(&json), rules(&toml), "profile {profile}");
+        assert_eq!(toml.replacement('\u{200B}'), "-");
+        assert_eq!(toml.name('\u{E005}'), "PRIVATE USE");
+        assert!(toml.is_reported('\u{200C}'));
+        assert!(!toml.listed().contains(&'\u{2011}'));
+    }
+    let house = load(&fixture("rules.toml"), None, "house").unwrap();
+    assert_eq!(house.replacement('\u{2014}'), "--");
+    assert_eq!(house.replacement('\u{2019}'), "'");
+}
+
+#[test]
+fn the_format_can_be_given_whatever_the_extension() {
+    let path = std::env::temp_dir().join(format!("toml-config-{}.conf", std::process::id()));
+    std::fs::copy(fixture("rules.toml"), &path).unwrap();
+    let as_json = load(&path, None, "standard");
+    let as_toml = load(&path, Some(ConfigFormat::Toml), "standard");
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .args(["list-chars", "--config-format", "toml", "--config"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(as_json.is_err());
+    assert_eq!(as_toml.unwrap().replacement('\u{200B}'), "-");
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+    let listing = String::from_utf8_lossy(&output.stdout);
+    assert!(listing.contains("PRIVATE USE"), "{listing}");
+}
+
+#[test]
+fn every_rule_problem_is_reported_with_its_rule() {
+    let path = std::env::temp_dir().join(format!("toml-problems-{}.toml", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"
+nbsp = "sometimes"
+
+[[rule]]
+name = "NO CHARACTER"
+
+[[rule]]
+char = "U+200B"
+colour = "red"
+
+[[rule]]
+char = "U+200C"
+action = "delete"
+replacement = " "
+
+[[rule]]
+char = "U+200D"
+action = "strip"
+
+[[rule]]
+char = "ab"
+"#,
+    )
+    .unwrap();
+    let message = load(&path, None, "standard").err().unwrap();
+    std::fs::write(&path, "[[rule]]\nchar = \"U+200B\"\nname = ZERO\n").unwrap();
+    let syntax = load(&path, None, "standard").err().unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(message.contains("has 6 problems"), "{message}");
+    for error in [
+        "rule 1: \"char\" must be a character",
+        "unknown field \"colour\" in \"U+200B\"",
+        "\"U+200C\" is deleted and cannot have a replacement",
+        "action \"strip\" of \"U+200D\" must be",
+        "key \"ab\" must be a single character",
+        "setting \"nbsp\" must be",
+    ] {
+        assert!(message.contains(error), "{message}");
+    }
+    assert!(syntax.contains("line 3"), "{syntax}");
+}
@@ -0,0 +1,69 @@
+use docx_cleaner::{Cleaner, Config, Revision};
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+mod support;
+
+// A changed run, an unchanged one, a changed run with a tab in it, and an insertion that is already
+// tracked with the bookmark id 7 before it
+const DOCUMENT: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\"><w:body>\
+<w:p><w:r><w:rPr><w:b/></w:rPr><w:t>zero\u{200B}width</w:t></w:r><w:r><w:t xml:space=\"preserve\"> plain</w:t></w:r></w:p>\
+<w:p><w:bookmarkStart w:id=\"7\" w:name=\"mark\"/><w:r><w:t>direction\u{200E}</w:t><w:tab/><w:t>mark</w:t></w:r><w:bookmarkEnd w:id=\"7\"/></w:p>\
+<w:p><w:ins w:id=\"3\" w:author=\"Reviewer\" w:date=\"2024-01-01T00:00:00Z\"><w:r><w:t>in\u{200B}serted</w:t></w:r></w:ins></w:p>\
+</w:body></w:document>";
+
+fn document_xml(buf: &[u8]) -> String {
+    let mut archive = ZipArchive::new(Cursor::new(buf)).unwrap();
+    let mut xml = String::new();
+    archive
+        .by_name("word/document.xml")
+        .unwrap()
+        .read_to_string(&mut xml)
+        .unwrap();
+    xml
+}
+
+fn clean_with_tracked_changes() -> String {
+    let mut config = Config::builtin();
+    config.track_changes = Some(Revision {
+        author: "docx-cleaner".to_string(),
+        date: "2024-05-01T09:30:00Z".to_string(),
+    });
+    let input = support::document_package(DOCUMENT);
+    let cleaned = Cleaner::new(config).clean_package(&input).unwrap();
+    assert_eq!(cleaned.report.total_removed(), 3);
+    document_xml(&cleaned.into_bytes().unwrap())
+}
+
+#[test]
+fn changed_runs_become_deletions_and_insertions() {
+    let xml = clean_with_tracked_changes();
+    let revision = r#"w:author="docx-cleaner" w:date="2024-05-01T09:30:00Z""#;
+    let first = format!(
+        "<w:del w:id=\"8\" {revision}><w:r><w:rPr><w:b/></w:rPr><w:delText>zero\u{200B}width</w:delText></w:r></w:del>\
+         <w:ins w:id=\"9\" {revision}><w:r><w:rPr><w:b/></w:rPr><w:t>zerowidth</w:t></w:r></w:ins>"
+    );
+    let second = format!(
+        "<w:del w:id=\"10\" {revision}><w:r><w:delText>direction\u{200E}</w:delText><w:tab/><w:delText>mark</w:delText></w:r></w:del>\
+         <w:ins w:id=\"11\" {revision}><w:r><w:t>direction</w:t><w:tab/><w:t>mark</w:t></w:r></w:ins>"
+    );
+    assert!(xml.contains(&first), "{xml}");
+    assert!(xml.contains(&second), "{xml}");
+}
+
+#[test]
+fn unchanged_runs_are_not_wrapped() {
+    let xml = clean_with_tracked_changes();
+    assert!(xml.contains(r#"</w:ins><w:r><w:t xml:space="preserve"> plain</w:t></w:r></w:p>"#));
+    assert_eq!(xml.matches("<w:del ").count(), 2);
+    assert_eq!(xml.matches("<w:ins ").count(), 3);
+}
+
+#[test]
+fn runs_in_tracked_insertions_are_cleaned_in_place() {
+    let xml = clean_with_tracked_changes();
+    assert!(xml.contains(
+        r#"<w:ins w:id="3" w:author="Reviewer" w:date="2024-01-01T00:00:00Z"><w:r><w:t>inserted</w:t></w:r></w:ins>"#
+    ));
+}
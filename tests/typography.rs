@@ -0,0 +1,43 @@
+use docx_cleaner::{Config, Origin};
+
+mod support;
+
+const BODY: &str = "<w:p><w:r><w:t>\u{201C}Don\u{2019}t\u{201D} \u{2013} it\u{2019}s 9\u{2013}5\u{2014}or so\u{2026}</w:t></w:r></w:p>";
+
+#[test]
+fn punctuation_becomes_ascii() {
+    let mut config = Config::builtin();
+    config.add_typography();
+    assert_eq!(config.origin('\u{2019}'), Origin::Typography);
+    let (report, body) = support::clean_body(config, BODY);
+    assert!(
+        body.contains("<w:t>\"Don't\" - it's 9-5--or so...</w:t>"),
+        "{body}"
+    );
+    assert_eq!(report.chars[&'\u{2019}'], 2);
+    assert_eq!(report.chars[&'\u{2013}'], 2);
+    assert_eq!(report.total_removed(), 8);
+}
+
+#[test]
+fn only_the_builtin_punctuation_is_replaced_by_default() {
+    let (report, body) = support::clean_body(Config::builtin(), BODY);
+    assert!(
+        body.contains("<w:t>\"Don\u{2019}t\" - it\u{2019}s 9-5-or so\u{2026}</w:t>"),
+        "{body}"
+    );
+    assert!(!report.chars.contains_key(&'\u{2019}'));
+}
+
+#[test]
+fn the_user_config_takes_precedence() {
+    let path = std::env::temp_dir().join(format!("typography-{}.json", std::process::id()));
+    std::fs::write(&path, r#"{"\u2014": ["EM DASH", "-"], "\u201C": "keep"}"#).unwrap();
+    let config = Config::load(Some(&path), &[]);
+    std::fs::remove_file(&path).unwrap();
+    let mut config = config.unwrap();
+    config.add_typography();
+    assert_eq!(config.origin('\u{2014}'), Origin::User);
+    let (_, body) = support::clean_body(config, BODY);
+    assert!(body.contains("<w:t>\u{201C}Don't\" - it's 9-5-or so...</w:t>"), "{body}");
+}
@@ -0,0 +1,52 @@
+use docx_cleaner::{unlisted_name, Config, GeneralCategory};
+use std::process::Command;
+
+#[test]
+fn characters_without_a_config_name_get_their_unicode_name() {
+    let config = Config::load(None, &[GeneralCategory::Format]).unwrap();
+    assert_eq!(config.name('\u{00AD}'), "SOFT HYPHEN");
+    assert_eq!(config.name('\u{0605}'), "ARABIC NUMBER MARK ABOVE");
+    // The name of the config comes first
+    assert_eq!(config.name('\u{200B}'), "ZERO WIDTH SPACE");
+    assert_eq!(config.name('\u{00E9}'), "LATIN SMALL LETTER E WITH ACUTE");
+
+    let config =
+        Config::from_json(r#"{"U+2060": {"action": "replace", "replacement": "-"}}"#).unwrap();
+    assert_eq!(config.name('\u{2060}'), "WORD JOINER");
+
+    assert_eq!(unlisted_name('\u{F0000}'), "<private use>");
+    assert_eq!(unlisted_name('\u{E0080}'), "<unassigned>");
+}
+
+#[test]
+fn the_effective_config_names_every_character() {
+    let config = Config::from_json(
+        r#"{"U+00A0": {"name": "NBSP", "action": "flag"}, "U+2060": {"action": "replace", "replacement": "-"}}"#,
+    )
+    .unwrap();
+    let json = config.to_json();
+    assert!(json.contains(r#""\u2060": ["WORD JOINER", "-"]"#), "{json}");
+    assert!(
+        json.contains(r#""\u00A0": {"name": "NBSP", "action": "flag"}"#),
+        "{json}"
+    );
+    // A flagged character outside the built-in list keeps the name that adds it
+    let again = Config::from_json(r#"{"\u00A0": {"name": "NBSP", "action": "flag"}}"#).unwrap();
+    assert_eq!(again.name('\u{00A0}'), "NBSP");
+}
+
+#[test]
+fn the_statistics_name_characters_removed_by_category() {
+    let path = std::env::temp_dir().join(format!("unicode-names-{}.txt", std::process::id()));
+    std::fs::write(&path, "co\u{00AD}operate\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .args(["--check", "--categories", "Cf"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    let statistics = String::from_utf8_lossy(&output.stdout);
+    assert!(statistics.contains("SOFT HYPHEN (U+00AD)"), "{statistics}");
+}
@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+mod support;
+
+const DOCUMENT: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\"><w:body>\
+<w:p><w:r><w:t>zero\u{200B}width</w:t></w:r><w:r><w:t>untouched</w:t></w:r></w:p>\
+</w:body></w:document>";
+
+// A document of its own for each test, since the tests run in parallel
+fn document(test: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("verbosity-{}-{}.docx", test, std::process::id()));
+    std::fs::write(&path, support::document_package(DOCUMENT)).unwrap();
+    path
+}
+
+// Standard error of a --check run
+fn check(test: &str, args: &[&str]) -> String {
+    let path = document(test);
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("--check")
+        .args(args)
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn nothing_is_logged_by_default() {
+    assert_eq!(check("default", &[]), "");
+}
+
+#[test]
+fn verbose_logs_each_part() {
+    let log = check("parts", &["-v"]);
+    assert_eq!(log, "Info: word/document.xml (body): 1 replaced\n");
+}
+
+#[test]
+fn more_verbose_logs_each_changed_run() {
+    let log = check("runs", &["-vv"]);
+    assert!(log.contains("Debug: body run with U+200B ZERO WIDTH SPACE\n"), "{log}");
+    assert!(!log.contains("Trace:"), "{log}");
+    assert!(!log.contains("untouched"), "{log}");
+
+    let log = check("texts", &["-vvv"]);
+    assert!(log.contains("Trace: \"zero\\u{200b}width\" -> \"zerowidth\"\n"), "{log}");
+}
+
+#[test]
+fn quiet_leaves_out_warnings() {
+    let warning = "Warning: --highlight is ignored with --check";
+    assert!(check("warning", &["--highlight", "yellow"]).contains(warning));
+    assert_eq!(check("quiet", &["-q", "--highlight", "yellow"]), "");
+}
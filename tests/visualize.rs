@@ -0,0 +1,63 @@
+use docx_cleaner::{CleanReport, Config, DEFAULT_VISUALIZE_FORMAT};
+use std::process::Command;
+
+mod support;
+
+// A zero width space, a left-to-right mark, a double space, an en space and a private use
+// character, which the built-in list removes but has no abbreviation for
+const TEXT: &str = "Pay\u{200B}ment due\u{200E}  on\u{2002}Friday\u{E000}";
+
+fn package() -> Vec<u8> {
+    support::word_package(&format!(
+        "<w:p><w:r><w:t xml:space=\"preserve\">{}</w:t></w:r></w:p>",
+        TEXT
+    ))
+}
+
+fn visualizing(format: &str) -> Config {
+    let mut config = Config::from_json(r#"{"U+E000": ["PRIVATE USE", ""]}"#).unwrap();
+    config.visualize = Some(format.to_string());
+    config
+}
+
+// The report and the text of the cleaned document
+fn clean(config: Config) -> (CleanReport, String) {
+    let (report, xml) = support::clean_package(config, &package());
+    (report, support::between(&xml, "preserve\">", "</w:t>"))
+}
+
+#[test]
+fn each_character_becomes_a_token_and_is_counted() {
+    let (report, text) = clean(visualizing(DEFAULT_VISUALIZE_FORMAT));
+
+    assert_eq!(
+        text, "Pay[ZWSP]ment due[LRM]  on[ENSP]Friday[U+E000]",
+        "the double space is left as it is"
+    );
+    assert_eq!(report.total_removed(), 4);
+    assert_eq!(report.chars[&'\u{2002}'], 1);
+    assert_eq!(report.collapsed_spaces, 0);
+}
+
+#[test]
+fn the_token_format_can_name_the_character() {
+    let (_, text) = clean(visualizing("({code} {name})"));
+
+    assert!(
+        text.starts_with("Pay(U+200B ZERO WIDTH SPACE)ment"),
+        "{text}"
+    );
+    assert!(text.ends_with("Friday(U+E000 PRIVATE USE)"), "{text}");
+}
+
+#[test]
+fn visualize_cannot_replace_the_original() {
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .args(["--visualize", "--in-place", "thesis.docx"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("cannot be used with"), "{stderr}");
+}
@@ -0,0 +1,48 @@
+// The browser entry point, called natively; build with --features wasm to run these
+#![cfg(feature = "wasm")]
+
+use docx_cleaner::wasm::clean_docx_bytes;
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+mod support;
+
+const DOCUMENT: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\"><w:body>\
+<w:p><w:r><w:t>zero\u{200B}width and soft\u{AD}hyphen</w:t></w:r></w:p>\
+</w:body></w:document>";
+
+fn body(bytes: Vec<u8>) -> String {
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+    let mut xml = String::new();
+    archive
+        .by_name("word/document.xml")
+        .unwrap()
+        .read_to_string(&mut xml)
+        .unwrap();
+    xml
+}
+
+#[test]
+fn the_bytes_are_cleaned_in_memory() {
+    let Ok(cleaned) = clean_docx_bytes(&support::document_package(DOCUMENT), None) else {
+        panic!("the fixture could not be cleaned");
+    };
+    assert!(body(cleaned.bytes()).contains("<w:t>zerowidth and soft\u{AD}hyphen</w:t>"));
+    let statistics: serde_json::Value = serde_json::from_str(&cleaned.statistics()).unwrap();
+    assert_eq!(statistics["total"], 1);
+    assert_eq!(statistics["characters"][0]["codepoint"], "U+200B");
+    assert_eq!(statistics["parts"]["body"], 1);
+}
+
+#[test]
+fn a_config_can_be_passed_as_json() {
+    let config = r#"{"\u00AD": ["SOFT HYPHEN", ""], "\u200B": "keep"}"#;
+    let Ok(cleaned) = clean_docx_bytes(
+        &support::document_package(DOCUMENT),
+        Some(config.to_string()),
+    ) else {
+        panic!("the fixture could not be cleaned");
+    };
+    assert!(body(cleaned.bytes()).contains("<w:t>zero\u{200B}width and softhyphen</w:t>"));
+}
@@ -0,0 +1,141 @@
+// Ctrl-C is sent with kill, so the tests only run where there is one
+#![cfg(all(unix, feature = "watch"))]
+
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+mod support;
+
+const DOCUMENT: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body><w:p><w:r><w:t>in&#x200B;visible</w:t></w:r></w:p></w:body></w:document>"#;
+
+// A folder of its own for each test, since the tests run in parallel
+fn folder(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("watch-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Returns once the folder is watched, with the rest of standard output still to be read
+fn watch(dir: &Path) -> (Child, BufReader<std::process::ChildStdout>) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("--watch")
+        .arg(dir)
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut first = String::new();
+    stdout.read_line(&mut first).unwrap();
+    assert!(first.starts_with("Watching "), "{first}");
+    (child, stdout)
+}
+
+fn wait_for(path: &Path) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(20) {
+        if path.exists() {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    false
+}
+
+// Ctrl-C, then the exit code and what was printed since watching began
+fn stop(mut child: Child, mut stdout: BufReader<std::process::ChildStdout>) -> (i32, String) {
+    Command::new("kill")
+        .arg("-INT")
+        .arg(child.id().to_string())
+        .status()
+        .unwrap();
+    let mut printed = String::new();
+    stdout.read_to_string(&mut printed).unwrap();
+    let status = child.wait().unwrap();
+    (status.code().unwrap(), printed)
+}
+
+#[test]
+fn new_documents_are_cleaned_until_ctrl_c() {
+    let dir = folder("new");
+    let (child, stdout) = watch(&dir);
+    // Word's lock file and a cleaned copy are not cleaned again
+    std::fs::write(
+        dir.join("~$Report.docx"),
+        support::document_package(DOCUMENT),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("Old_cleaned.docx"),
+        support::document_package(DOCUMENT),
+    )
+    .unwrap();
+    std::fs::write(dir.join("Report.docx"), support::document_package(DOCUMENT)).unwrap();
+    let cleaned = wait_for(&dir.join("Report_cleaned.docx"));
+    // Long enough for the other two to settle had they been picked up
+    std::thread::sleep(Duration::from_secs(1));
+    let (code, printed) = stop(child, stdout);
+    let mut files: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    files.sort();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(cleaned, "{printed}");
+    assert_eq!(
+        files,
+        [
+            "Old_cleaned.docx",
+            "Report.docx",
+            "Report_cleaned.docx",
+            "~$Report.docx"
+        ]
+    );
+    assert_eq!(code, 1, "{printed}");
+    assert!(
+        printed.contains("Report.docx  1 characters removed"),
+        "{printed}"
+    );
+    assert!(printed.contains("Files processed:          1"), "{printed}");
+    assert!(printed.contains("Total characters removed: 1"), "{printed}");
+}
+
+#[test]
+fn a_document_still_being_copied_is_tried_again() {
+    let dir = folder("partial");
+    let (child, stdout) = watch(&dir);
+    let document = support::document_package(DOCUMENT);
+    let input = dir.join("Report.docx");
+    std::fs::write(&input, &document[..document.len() / 2]).unwrap();
+    // Settled and failed to open at least once
+    std::thread::sleep(Duration::from_secs(2));
+    std::fs::write(&input, &document).unwrap();
+    let cleaned = wait_for(&dir.join("Report_cleaned.docx"));
+    let (code, printed) = stop(child, stdout);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(cleaned, "{printed}");
+    assert_eq!(code, 1, "{printed}");
+    assert!(printed.contains("Files failed:             0"), "{printed}");
+}
+
+#[test]
+fn only_an_existing_folder_is_watched() {
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .arg("--watch")
+        .arg(std::env::temp_dir().join("watch-missing-folder"))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3), "{:?}", output);
+    let error = String::from_utf8_lossy(&output.stderr);
+    assert!(error.contains("Could not watch"), "{error}");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_docx-cleaner"))
+        .args(["--watch", ".", "Report.docx"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+}
@@ -0,0 +1,119 @@
+use docx_cleaner::{Cleaner, Config};
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+mod support;
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/worksheets/sheet2.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/sharedStrings.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sharedStrings+xml"/><Override PartName="/xl/pivotCache/pivotCacheRecords1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.pivotCacheRecords+xml"/></Types>"#;
+
+const PACKAGE_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#;
+
+const WORKBOOK: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="Data" sheetId="1" r:id="rId1"/><sheet name="Prices" sheetId="2" r:id="rId2"/></sheets></workbook>"#;
+
+const WORKBOOK_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet2.xml"/><Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/sharedStrings" Target="sharedStrings.xml"/></Relationships>"#;
+
+// The first string is shown twice on Data, the third once on Prices
+const SHARED_STRINGS: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<sst xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\" count=\"4\" uniqueCount=\"3\">\
+<si><t>key\u{200B}</t></si>\
+<si><t xml:space=\"preserve\">two  spaces\nand a line</t></si>\
+<si><r><t>rich\u{200B}</t></r><rPh sb=\"0\" eb=\"1\"><t>\u{200B}</t></rPh></si>\
+</sst>";
+
+const SHEET1: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<worksheet xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\"><sheetData>\
+<row r=\"1\"><c r=\"A1\" t=\"s\"><v>0</v></c><c r=\"B1\" t=\"s\"><v>1</v></c></row>\
+<row r=\"2\"><c r=\"A2\" t=\"s\"><v>0</v></c><c r=\"B2\" t=\"str\"><f>\"a\u{200B}\"&amp;A1</f><v>a\u{200B}key</v></c></row>\
+<row r=\"3\"><c r=\"A3\" t=\"inlineStr\"><is><t>in\u{200B}line</t></is></c><c r=\"B3\"><v>42</v></c></row>\
+</sheetData></worksheet>";
+
+const SHEET2: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<worksheet xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\"><sheetData>\
+<row r=\"1\"><c r=\"A1\" t=\"s\"><v>2</v></c></row>\
+</sheetData></worksheet>";
+
+const PIVOT_RECORDS: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+<pivotCacheRecords xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\"><r><s v=\"key\u{200B}\"/></r></pivotCacheRecords>";
+
+fn package() -> Vec<u8> {
+    support::zip_package(&[
+        ("[Content_Types].xml", CONTENT_TYPES.as_bytes()),
+        ("_rels/.rels", PACKAGE_RELS.as_bytes()),
+        ("xl/workbook.xml", WORKBOOK.as_bytes()),
+        ("xl/_rels/workbook.xml.rels", WORKBOOK_RELS.as_bytes()),
+        ("xl/sharedStrings.xml", SHARED_STRINGS.as_bytes()),
+        ("xl/worksheets/sheet1.xml", SHEET1.as_bytes()),
+        ("xl/worksheets/sheet2.xml", SHEET2.as_bytes()),
+        (
+            "xl/pivotCache/pivotCacheRecords1.xml",
+            PIVOT_RECORDS.as_bytes(),
+        ),
+    ])
+}
+
+fn entry(buf: &[u8], name: &str) -> String {
+    let mut archive = ZipArchive::new(Cursor::new(buf)).unwrap();
+    let mut xml = String::new();
+    archive
+        .by_name(name)
+        .unwrap()
+        .read_to_string(&mut xml)
+        .unwrap();
+    xml
+}
+
+fn clean(config: Config) -> Vec<u8> {
+    let input = package();
+    Cleaner::new(config)
+        .clean_package(&input)
+        .unwrap()
+        .into_bytes()
+        .unwrap()
+}
+
+#[test]
+fn only_string_content_is_cleaned() {
+    let output = clean(Config::builtin());
+    let strings = entry(&output, "xl/sharedStrings.xml");
+    assert!(strings.contains("<si><t>key</t></si>"));
+    // Neither the spaces nor the line break in a cell are touched
+    assert!(strings.contains("two  spaces\nand a line"));
+    assert!(strings.contains("<r><t>rich</t></r><rPh sb=\"0\" eb=\"1\"><t>\u{200B}</t></rPh>"));
+
+    // The formula and its cached result stay as they are
+    assert_eq!(
+        entry(&output, "xl/worksheets/sheet1.xml"),
+        SHEET1.replace("in\u{200B}line", "inline")
+    );
+    assert_eq!(entry(&output, "xl/worksheets/sheet2.xml"), SHEET2);
+    assert_eq!(
+        entry(&output, "xl/pivotCache/pivotCacheRecords1.xml"),
+        PIVOT_RECORDS
+    );
+}
+
+#[test]
+fn cell_spaces_are_collapsed_on_request() {
+    let mut config = Config::builtin();
+    config.collapse_cell_spaces = true;
+    let strings = entry(&clean(config), "xl/sharedStrings.xml");
+    assert!(strings.contains("two spaces\nand a line"));
+}
+
+#[test]
+fn shared_strings_count_for_every_cell_showing_them() {
+    let input = package();
+    let report = Cleaner::new(Config::builtin())
+        .scan_package(&input)
+        .unwrap();
+    assert_eq!(report.parts["strings"], 2);
+    assert_eq!(report.parts["sheet"], 1);
+    assert_eq!(
+        report.sheets,
+        [("Data".to_string(), 3), ("Prices".to_string(), 1)]
+    );
+}